@@ -75,7 +75,7 @@ fn easy_fs_pack() -> std::io::Result<()> {
         inode.write_at(0, all_data.as_slice());
     }
     // list apps
-    for app in root_inode.ls() {
+    for app in root_inode.ls(false) {
         println!("{}", app);
     }
     Ok(())
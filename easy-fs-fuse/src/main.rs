@@ -27,6 +27,14 @@ impl BlockDevice for BlockFile {
             .expect("Error when seeking!");
         assert_eq!(file.write(buf).unwrap(), BLOCK_SZ, "Not a complete block!");
     }
+    /// Flush the file to the disk under it
+    fn flush(&self) {
+        self.0
+            .lock()
+            .unwrap()
+            .sync_data()
+            .expect("Error when flushing!");
+    }
 }
 
 fn main() {
@@ -85,29 +93,55 @@ fn easy_fs_pack() -> std::io::Result<()> {
         inode.write_at(0, all_data.as_slice());
     }
     // list apps
-    for app in root_inode.ls() {
+    for app in root_inode.ls(false) {
         println!("{}", app);
     }
     Ok(())
 }
 
+/// Tests touching easy-fs share the one global block cache and the one
+/// image file `target/fs.img`, so they must not run concurrently
+#[cfg(test)]
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Hold the test lock and open the test image, large enough for any test
+#[cfg(test)]
+fn test_image() -> std::io::Result<(std::sync::MutexGuard<'static, ()>, File)> {
+    let guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open("target/fs.img")?;
+    f.set_len((BLOCK_NUM * BLOCK_SZ) as u64)?;
+    Ok((guard, f))
+}
+
+/// The test lock, the test image and a fresh easy-fs on it
+#[cfg(test)]
+type TestFs = (
+    std::sync::MutexGuard<'static, ()>,
+    Arc<BlockFile>,
+    Arc<spin::Mutex<EasyFileSystem>>,
+);
+
+/// Create a fresh easy-fs of `blocks` blocks on the test image, holding the test lock
+#[cfg(test)]
+fn test_fs(blocks: u32) -> std::io::Result<TestFs> {
+    let (guard, f) = test_image()?;
+    let block_file = Arc::new(BlockFile(Mutex::new(f)));
+    let efs = EasyFileSystem::create(block_file.clone(), blocks, 1);
+    Ok((guard, block_file, efs))
+}
+
 #[test]
 fn efs_test() -> std::io::Result<()> {
-    let block_file = Arc::new(BlockFile(Mutex::new({
-        let f = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open("target/fs.img")?;
-        f.set_len(BLOCK_NUM * BLOCK_SZ).unwrap();
-        f
-    })));
-    EasyFileSystem::create(block_file.clone(), 4096, 1);
-    let efs = EasyFileSystem::open(block_file.clone());
+    let (_guard, block_file, _) = test_fs(4096)?;
+    let efs = EasyFileSystem::open(block_file);
     let root_inode = EasyFileSystem::root_inode(&efs);
     root_inode.create("filea");
     root_inode.create("fileb");
-    for name in root_inode.ls() {
+    for name in root_inode.ls(false) {
         println!("{}", name);
     }
     let filea = root_inode.find("filea").unwrap();
@@ -153,3 +187,1405 @@ fn efs_test() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn efs_fadvise_test() -> std::io::Result<()> {
+    use easy_fs::{block_cache_stats, FileAdvice};
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("scan").unwrap();
+    let data: Vec<u8> = (0..16 * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    file.write_at(0, &data);
+    // start from a cold cache for the file data
+    file.dont_need(0, data.len());
+    let mut buffer = [0u8; BLOCK_SZ];
+
+    // sequential: reading block 0 brings the following blocks in
+    file.set_advice(FileAdvice::Sequential);
+    file.read_at(0, &mut buffer);
+    let (_, misses, _) = block_cache_stats();
+    // stop reading ahead so that only demand loads are counted
+    file.set_advice(FileAdvice::Random);
+    for i in 1..5 {
+        file.read_at(i * BLOCK_SZ, &mut buffer);
+        assert_eq!(&buffer[..], &data[i * BLOCK_SZ..(i + 1) * BLOCK_SZ]);
+    }
+    assert_eq!(block_cache_stats().1, misses);
+
+    // dontneed: the range has to be loaded again
+    file.dont_need(0, 4 * BLOCK_SZ);
+    let (_, misses, _) = block_cache_stats();
+    for i in 0..4 {
+        file.read_at(i * BLOCK_SZ, &mut buffer);
+    }
+    assert_eq!(block_cache_stats().1, misses + 4);
+
+    // random: no read-ahead, while willneed prefetches explicitly
+    file.dont_need(0, data.len());
+    file.read_at(8 * BLOCK_SZ, &mut buffer);
+    let (_, misses, _) = block_cache_stats();
+    file.read_at(9 * BLOCK_SZ, &mut buffer);
+    assert_eq!(block_cache_stats().1, misses + 1);
+    file.will_need(10 * BLOCK_SZ, 2 * BLOCK_SZ);
+    let (_, misses, _) = block_cache_stats();
+    file.read_at(10 * BLOCK_SZ, &mut buffer);
+    file.read_at(11 * BLOCK_SZ, &mut buffer);
+    assert_eq!(block_cache_stats().1, misses);
+
+    // the hint belongs to the file, whichever handle set it
+    let other = root_inode.find("scan").unwrap();
+    file.set_advice(FileAdvice::Sequential);
+    assert_eq!(other.advice(), FileAdvice::Sequential);
+    other.set_advice(FileAdvice::Random);
+    assert_eq!(file.advice(), FileAdvice::Random);
+    // and goes with it
+    assert_eq!(root_inode.unlinkat("scan"), 0);
+    let fresh = (0..64)
+        .map(|i| root_inode.create(&format!("fresh{}", i)).unwrap())
+        .find(|fresh| fresh.stat().ino == file.stat().ino)
+        .unwrap();
+    assert_eq!(fresh.advice(), FileAdvice::Normal);
+    Ok(())
+}
+
+#[test]
+fn efs_inode_reuse_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let gone = root_inode.create("gone").unwrap().stat().ino;
+    // a file with another link keeps its inode
+    assert_eq!(root_inode.linkat("gone", "kept"), 0);
+    assert_eq!(root_inode.unlinkat("gone"), 0);
+    assert_eq!(root_inode.find("kept").unwrap().stat().ino, gone);
+    assert!((0..8).all(|i| root_inode.create(&format!("a{}", i)).unwrap().stat().ino != gone));
+    // the last link frees the inode for the files created after it
+    assert_eq!(root_inode.unlinkat("kept"), 0);
+    assert!((0..64).any(|i| root_inode.create(&format!("b{}", i)).unwrap().stat().ino == gone));
+    Ok(())
+}
+
+#[test]
+fn efs_rename_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let target = root_inode.create("target").unwrap();
+    target.write_at(0, "old contents".as_bytes());
+    let target_ino = target.stat().ino;
+    let tmp = root_inode.create("tmp").unwrap();
+    tmp.write_at(0, "new contents".as_bytes());
+    let tmp_ino = tmp.stat().ino;
+
+    assert_eq!(root_inode.rename("tmp", "target", true), 0);
+    assert!(root_inode.find("tmp").is_none());
+    assert_eq!(root_inode.ls(false), vec![String::from("target")]);
+    let target = root_inode.find("target").unwrap();
+    assert_eq!(target.stat().ino, tmp_ino);
+    let mut buffer = [0u8; 32];
+    let len = target.read_at(0, &mut buffer);
+    assert_eq!(&buffer[..len], "new contents".as_bytes());
+    // the replaced inode has been freed and is handed out again
+    assert_eq!(root_inode.create("other").unwrap().stat().ino, target_ino);
+
+    // renaming to a fresh name keeps the inode
+    assert_eq!(root_inode.rename("target", "moved", false), 0);
+    assert_eq!(root_inode.find("moved").unwrap().stat().ino, tmp_ino);
+    assert_eq!(root_inode.rename("target", "moved", false), -1);
+    Ok(())
+}
+
+#[test]
+fn efs_for_each_entry_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let mut inode_ids = Vec::new();
+    for i in 0..5 {
+        let file = root_inode.create(&format!("f{}", i)).unwrap();
+        inode_ids.push(file.stat().ino as u32);
+    }
+    assert_eq!(root_inode.unlinkat("f2"), 0);
+    // the freed entry in the middle is skipped, `.` and `..` are not
+    let mut count = 0;
+    let mut found = None;
+    root_inode.for_each_entry(|dirent| {
+        count += 1;
+        if dirent.name() == "f3" {
+            found = Some(dirent.inode_number());
+        }
+    });
+    assert_eq!(count, 6);
+    assert_eq!(found, Some(inode_ids[3]));
+    assert_eq!(root_inode.ls(false), vec!["f0", "f1", "f3", "f4"]);
+    Ok(())
+}
+
+#[test]
+fn efs_case_insensitive_test() -> std::io::Result<()> {
+    let (_guard, block_file, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    // names are case-sensitive unless asked otherwise
+    assert!(root_inode.create("Exact").is_some());
+    assert!(root_inode.create("exact").is_some());
+    assert!(root_inode.find("EXACT").is_none());
+    drop((root_inode, efs));
+
+    let efs = EasyFileSystem::create(block_file, 4096, 1);
+    efs.lock().set_case_insensitive(true);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let ino = root_inode.create("File").unwrap().stat().ino;
+    assert!(root_inode.create("file").is_none());
+    assert!(root_inode.create_dir("FILE").is_none());
+    assert_eq!(root_inode.find("fIlE").unwrap().stat().ino, ino);
+    assert_eq!(root_inode.linkat("file", "Alias"), 0);
+    assert_eq!(root_inode.linkat("FILE", "alias"), -1);
+    assert_eq!(root_inode.rename_noreplace("ALIAS", "file", false), -1);
+    // changing only the case renames the entry itself
+    assert_eq!(root_inode.rename("file", "FILE", false), 0);
+    assert_eq!(root_inode.ls(false), vec!["FILE", "Alias"]);
+    assert_eq!(root_inode.unlinkat("alias"), 0);
+    assert_eq!(root_inode.unlinkat("file"), 0);
+    assert!(root_inode.ls(false).is_empty());
+    Ok(())
+}
+
+#[test]
+fn efs_name_length_test() -> std::io::Result<()> {
+    use easy_fs::NAME_LENGTH_LIMIT;
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let longest = "n".repeat(NAME_LENGTH_LIMIT);
+    let too_long = "n".repeat(NAME_LENGTH_LIMIT + 1);
+    assert!(root_inode.create(&longest).is_some());
+    let size = root_inode.stat().size;
+    // the truncated name would collide with the existing entry
+    assert!(root_inode.create(&too_long).is_none());
+    assert!(root_inode.create_dir(&too_long).is_none());
+    assert_eq!(root_inode.linkat(&longest, &too_long), -1);
+    assert_eq!(root_inode.rename(&longest, &too_long, false), -1);
+    assert_eq!(root_inode.stat().size, size);
+    assert_eq!(root_inode.ls(false), vec![longest.clone()]);
+    assert!(root_inode.find(&longest).is_some());
+    Ok(())
+}
+
+#[test]
+fn efs_rename_noreplace_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("old").unwrap();
+    file.write_at(0, b"data");
+    let ino = file.stat().ino;
+    let size = root_inode.size();
+    assert_eq!(root_inode.rename_noreplace("old", "new", false), 0);
+    // the entry is renamed in place with no link taken or dropped
+    assert_eq!(root_inode.ls(false), vec![String::from("new")]);
+    assert_eq!(root_inode.size(), size);
+    let file = root_inode.find("new").unwrap();
+    assert_eq!(file.stat().ino, ino);
+    assert_eq!(file.stat().nlink, 1);
+    // an existing target is left alone
+    let other = root_inode.create("other").unwrap().stat().ino;
+    assert_eq!(root_inode.rename_noreplace("new", "other", true), -1);
+    assert_eq!(root_inode.find("new").unwrap().stat().ino, ino);
+    assert_eq!(root_inode.find("other").unwrap().stat().ino, other);
+    assert_eq!(root_inode.linkat("new", "alias"), 0);
+    assert_eq!(root_inode.rename_noreplace("new", "alias", false), -1);
+    assert_eq!(root_inode.rename_noreplace("gone", "fresh", false), -1);
+    assert_eq!(root_inode.ls(false).len(), 3);
+    Ok(())
+}
+
+#[test]
+fn efs_statfs_test() -> std::io::Result<()> {
+    let (_guard, block_file, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let empty = root_inode.statfs();
+    assert_eq!(empty.block_size, BLOCK_SZ as u64);
+    // one bitmap block of inodes, of which the root takes one
+    assert_eq!((empty.inodes, empty.free_inodes), (4096, 4095));
+    // the root directory holds its `.` and `..` in one block
+    assert_eq!(empty.free_blocks, empty.blocks - 1);
+    let file = root_inode.create("file").unwrap();
+    file.write_at(0, &[1u8; 4 * BLOCK_SZ]);
+    let used = root_inode.statfs();
+    assert_eq!(used.free_inodes, 4094);
+    // the data and the first block of the root directory
+    assert_eq!(used.free_blocks, empty.blocks - 5);
+    // the totals come back the same from the super block
+    drop((file, root_inode, efs));
+    let efs = EasyFileSystem::open(block_file);
+    assert_eq!(EasyFileSystem::root_inode(&efs).statfs(), used);
+    assert_eq!(efs.lock().total_data_blocks() as u64, empty.blocks);
+    assert_eq!(efs.lock().total_inodes(), 4096);
+    Ok(())
+}
+
+#[test]
+fn efs_fsync_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    // large enough to need the indirect2 blocks
+    let data: Vec<u8> = (0..80 * 1024).map(|i| (i % 251) as u8).collect();
+    let file = root_inode.create("file").unwrap();
+    assert_eq!(file.write_at(0, &data), data.len());
+    file.fsync();
+    // a second handle on the image shares no cached block with the first
+    let block_file = Arc::new(BlockFile(Mutex::new(
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("target/fs.img")?,
+    )));
+    let remounted = EasyFileSystem::root_inode(&EasyFileSystem::open(block_file));
+    let file = remounted.find("file").unwrap();
+    assert_eq!(file.size(), data.len());
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert!(buffer == data);
+    Ok(())
+}
+
+#[test]
+fn efs_batched_io_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("batched").unwrap();
+    // 64 KiB spread over pages, like a UserBuffer
+    let data: Vec<u8> = (0..64 * 1024).map(|i| (i % 253) as u8).collect();
+    let pages: Vec<&[u8]> = data.chunks(4096).collect();
+    let before = easy_fs::fs_lock_count();
+    assert_eq!(file.write_all_at(0, &pages), Some(data.len()));
+    let batched_write = easy_fs::fs_lock_count() - before;
+    let before = easy_fs::fs_lock_count();
+    for (i, page) in pages.iter().enumerate() {
+        assert_eq!(file.write_at(i * 4096, page), page.len());
+    }
+    let sliced_write = easy_fs::fs_lock_count() - before;
+    assert_eq!(batched_write, 1);
+    assert_eq!(sliced_write, pages.len());
+
+    let mut buffer = vec![0u8; data.len() + 100];
+    let mut pages: Vec<&mut [u8]> = buffer.chunks_mut(4096).collect();
+    let before = easy_fs::fs_lock_count();
+    assert_eq!(file.try_read_all_at(0, &mut pages), Some(data.len()));
+    assert_eq!(easy_fs::fs_lock_count() - before, 1);
+    assert!(buffer[..data.len()] == data[..]);
+    // reading from the middle stops at the end of the file
+    let mut pages: Vec<&mut [u8]> = buffer.chunks_mut(1000).collect();
+    assert_eq!(file.try_read_all_at(60 * 1024, &mut pages), Some(4096));
+    assert!(buffer[..4096] == data[60 * 1024..]);
+    Ok(())
+}
+
+#[test]
+fn efs_unlink_open_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let data: Vec<u8> = (0..20 * BLOCK_SZ).map(|i| (i % 249) as u8).collect();
+    let free = root_inode.statfs().free_blocks;
+    let file = root_inode.create("open").unwrap();
+    assert_eq!(file.write_at(0, &data), data.len());
+    let used = free - root_inode.statfs().free_blocks;
+    file.open();
+    file.open();
+    assert_eq!(root_inode.unlinkat("open"), 0);
+    assert!(root_inode.find("open").is_none());
+    // the name is gone but the open file still reads its data
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert!(buffer == data);
+    assert_eq!(free - root_inode.statfs().free_blocks, used);
+    file.close();
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    // the last close frees the blocks and the inode
+    let free_inodes = root_inode.statfs().free_inodes;
+    file.close();
+    assert_eq!(root_inode.statfs().free_blocks, free);
+    assert_eq!(root_inode.statfs().free_inodes, free_inodes + 1);
+
+    // a file that is still linked survives being closed
+    let file = root_inode.create("linked").unwrap();
+    assert_eq!(file.write_at(0, b"kept"), 4);
+    file.open();
+    file.close();
+    assert_eq!(root_inode.find("linked").unwrap().size(), 4);
+    Ok(())
+}
+
+#[test]
+fn efs_nlink_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("first").unwrap();
+    file.open();
+    // another handle on the same inode, like a second open fd
+    let other = root_inode.find("first").unwrap();
+    other.open();
+    assert_eq!(file.stat().nlink, 1);
+    assert_eq!(root_inode.linkat("first", "second"), 0);
+    assert_eq!(root_inode.linkat("second", "third"), 0);
+    assert_eq!(file.stat().nlink, 3);
+    assert_eq!(other.stat().nlink, 3);
+    // no name can be taken twice, nor a missing one linked
+    assert_eq!(root_inode.linkat("first", "third"), -1);
+    assert_eq!(root_inode.linkat("missing", "fourth"), -1);
+    assert_eq!(file.stat().nlink, 3);
+    assert_eq!(root_inode.unlinkat("second"), 0);
+    assert_eq!(root_inode.unlinkat("second"), -1);
+    assert_eq!(file.stat().nlink, 2);
+    assert_eq!(other.stat().nlink, 2);
+    assert_eq!(
+        root_inode.find("third").unwrap().stat().ino,
+        file.stat().ino
+    );
+    // every name gone, the open handles still stat the inode
+    assert_eq!(root_inode.unlinkat("first"), 0);
+    assert_eq!(root_inode.unlinkat("third"), 0);
+    assert_eq!(file.stat().nlink, 0);
+    let free_inodes = root_inode.statfs().free_inodes;
+    file.close();
+    assert_eq!(other.stat().nlink, 0);
+    other.close();
+    assert_eq!(root_inode.statfs().free_inodes, free_inodes + 1);
+    Ok(())
+}
+
+#[test]
+fn efs_hole_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let secret = vec![0x5au8; 8 * BLOCK_SZ];
+    let file = root_inode.create("secret").unwrap();
+    assert_eq!(file.write_at(0, &secret), secret.len());
+    drop(file);
+    assert_eq!(root_inode.unlinkat("secret"), 0);
+    // writing past the end leaves a hole over the blocks just freed
+    let file = root_inode.create("sparse").unwrap();
+    assert_eq!(file.write_at(6 * BLOCK_SZ + 100, b"end"), 3);
+    let mut buffer = vec![0xffu8; 6 * BLOCK_SZ + 100];
+    assert_eq!(file.read_at(0, &mut buffer), buffer.len());
+    assert!(buffer.iter().all(|&b| b == 0));
+    Ok(())
+}
+
+#[test]
+fn efs_stale_image_test() -> std::io::Result<()> {
+    let _guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    // an image full of what some earlier filesystem left behind
+    let mut f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open("target/stale.img")?;
+    f.write_all(&vec![0xaau8; 4096 * BLOCK_SZ])?;
+    let block_file = Arc::new(BlockFile(Mutex::new(f)));
+    EasyFileSystem::create(block_file.clone(), 4096, 1);
+    let root_inode = EasyFileSystem::root_inode(&EasyFileSystem::open(block_file));
+    let file = root_inode.create("sparse").unwrap();
+    assert_eq!(file.write_at(10 * BLOCK_SZ, b"end"), 3);
+    let mut buffer = vec![0xffu8; 10 * BLOCK_SZ + 3];
+    assert_eq!(file.read_at(0, &mut buffer), buffer.len());
+    assert!(buffer[..10 * BLOCK_SZ].iter().all(|&b| b == 0));
+    assert_eq!(&buffer[10 * BLOCK_SZ..], b"end");
+    Ok(())
+}
+
+#[test]
+fn efs_append_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    root_inode.create("log").unwrap();
+    // two handles on the file append side by side
+    let writers: Vec<_> = [b'a', b'b']
+        .iter()
+        .map(|&tag| {
+            let file = root_inode.find("log").unwrap();
+            std::thread::spawn(move || {
+                for i in 0..100u8 {
+                    let record = [tag, i, tag, i];
+                    let (offset, len) = file.append_all(&[&record[..2], &record[2..]]).unwrap();
+                    assert_eq!(len, 4);
+                    assert_eq!(offset % 4, 0);
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let file = root_inode.find("log").unwrap();
+    assert_eq!(file.size(), 800);
+    let mut buffer = vec![0u8; 800];
+    assert_eq!(file.read_at(0, &mut buffer), 800);
+    // no record was overwritten or torn, and each writer's stayed in order
+    let mut next = [0u8; 2];
+    for record in buffer.chunks(4) {
+        assert_eq!(record[0], record[2]);
+        assert_eq!(record[1], record[3]);
+        let writer = (record[0] - b'a') as usize;
+        assert_eq!(record[1], next[writer]);
+        next[writer] += 1;
+    }
+    assert_eq!(next, [100, 100]);
+    Ok(())
+}
+
+/// A test image counting how many times it was flushed
+#[cfg(test)]
+struct CountingFile(BlockFile, std::sync::atomic::AtomicUsize);
+
+#[cfg(test)]
+impl CountingFile {
+    fn flushes(&self) -> usize {
+        self.1.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+impl BlockDevice for CountingFile {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.0.read_block(block_id, buf)
+    }
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.0.write_block(block_id, buf)
+    }
+    fn flush(&self) {
+        self.0.flush();
+        self.1.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn efs_flush_test() -> std::io::Result<()> {
+    let (_guard, f) = test_image()?;
+    let device = Arc::new(CountingFile(
+        BlockFile(Mutex::new(f)),
+        std::sync::atomic::AtomicUsize::new(0),
+    ));
+    let efs = EasyFileSystem::create(device.clone(), 4096, 1);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("file").unwrap();
+    // each sync flushes the device once, however many blocks it wrote
+    let flushes = device.flushes();
+    file.chown(Some(1), None);
+    assert_eq!(device.flushes(), flushes + 1);
+    assert_eq!(file.write_at(0, &[1u8; 8 * BLOCK_SZ]), 8 * BLOCK_SZ);
+    assert_eq!(device.flushes(), flushes + 2);
+    file.fsync();
+    assert_eq!(device.flushes(), flushes + 3);
+    efs.lock().flush();
+    assert_eq!(device.flushes(), flushes + 4);
+    Ok(())
+}
+
+#[test]
+fn efs_check_test() -> std::io::Result<()> {
+    use easy_fs::FsError;
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let dir = root_inode.create_dir("dir").unwrap();
+    let file = dir.create("file").unwrap();
+    // large enough for indirect2 blocks
+    file.write_at(0, &[3u8; 200 * BLOCK_SZ]);
+    assert_eq!(dir.linkat("file", "again"), 0);
+    let sealed = root_inode.create("sealed").unwrap();
+    sealed.write_at(0, &[5u8; 3 * BLOCK_SZ]);
+    assert!(sealed.enable_verity());
+    root_inode.create("gone").unwrap().write_at(0, b"gone");
+    assert_eq!(root_inode.unlinkat("gone"), 0);
+    assert_eq!(root_inode.rename("sealed", "dir/sealed", false), 0);
+    assert_eq!(efs.lock().check(), vec![]);
+
+    let ino = file.stat().ino as u32;
+    let (block_id, block_offset) = efs.lock().get_disk_inode_pos(ino);
+    efs.lock().flush();
+    std::fs::copy("target/fs.img", "target/fsck.img")?;
+    let mut f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("target/fsck.img")?;
+    // nlink follows the size, 19 direct and 2 indirect block ids
+    let nlink_pos = block_id as usize * BLOCK_SZ + block_offset + 4 * (1 + 19 + 2);
+    f.seek(SeekFrom::Start(nlink_pos as u64))?;
+    f.write_all(&5u32.to_le_bytes())?;
+    let clone = EasyFileSystem::open(Arc::new(BlockFile(Mutex::new(f))));
+    assert_eq!(
+        clone.lock().check(),
+        vec![FsError::LinkCount {
+            inode: ino,
+            nlink: 5,
+            entries: 2,
+        }]
+    );
+    // the original image is untouched
+    assert_eq!(efs.lock().check(), vec![]);
+    Ok(())
+}
+
+#[test]
+fn efs_two_devices_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let block_file = Arc::new(BlockFile(Mutex::new({
+        let f = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open("target/fs2.img")?;
+        f.set_len((BLOCK_NUM * BLOCK_SZ) as u64).unwrap();
+        f
+    })));
+    EasyFileSystem::create(block_file.clone(), 4096, 1);
+    let other_root = EasyFileSystem::root_inode(&EasyFileSystem::open(block_file.clone()));
+    assert!(!root_inode.is_same(&other_root));
+
+    // both filesystems use the same block ids, the cache must keep them apart
+    let mnt = root_inode.create_dir("mnt").unwrap();
+    assert!(mnt.is_dir());
+    assert!(root_inode.find("mnt").unwrap().is_same(&mnt));
+    assert!(root_inode.create_dir("mnt").is_none());
+    root_inode
+        .create("file")
+        .unwrap()
+        .write_at(0, "first".as_bytes());
+    other_root
+        .create("file")
+        .unwrap()
+        .write_at(0, "second".as_bytes());
+    let mut buffer = [0u8; 16];
+    let len = root_inode.find("file").unwrap().read_at(0, &mut buffer);
+    assert_eq!(&buffer[..len], "first".as_bytes());
+    let len = other_root.find("file").unwrap().read_at(0, &mut buffer);
+    assert_eq!(&buffer[..len], "second".as_bytes());
+    assert_eq!(other_root.ls(false), vec![String::from("file")]);
+
+    // only the root inode is left once the file inodes are gone
+    assert_eq!(other_root.fs_refs(), 1);
+    other_root.flush_fs();
+    let efs = EasyFileSystem::open(block_file);
+    let len = EasyFileSystem::root_inode(&efs)
+        .find("file")
+        .unwrap()
+        .read_at(0, &mut buffer);
+    assert_eq!(&buffer[..len], "second".as_bytes());
+    Ok(())
+}
+
+#[test]
+fn efs_cache_size_test() -> std::io::Result<()> {
+    use easy_fs::{block_cache_stats, BLOCK_CACHE_SIZE};
+    let (_guard, block_file, _) = test_fs(4096)?;
+    let efs = EasyFileSystem::open_with_cache_size(block_file.clone(), 8);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("scan").unwrap();
+    let data = vec![7u8; 64 * BLOCK_SZ];
+    assert_eq!(file.write_at(0, &data), data.len());
+
+    // a scan longer than the cache keeps pushing blocks out
+    let (_, _, evictions) = block_cache_stats();
+    let mut buffer = [0u8; BLOCK_SZ];
+    for i in 0..64 {
+        assert_eq!(file.read_at(i * BLOCK_SZ, &mut buffer), BLOCK_SZ);
+    }
+    assert!(block_cache_stats().2 >= evictions + 64 - 8);
+    // the block just read is still there
+    let (hits, misses, _) = block_cache_stats();
+    assert_eq!(file.read_at(63 * BLOCK_SZ, &mut buffer), BLOCK_SZ);
+    assert_eq!(block_cache_stats().1, misses);
+    assert!(block_cache_stats().0 > hits);
+    drop((file, root_inode, efs));
+    EasyFileSystem::open_with_cache_size(block_file, BLOCK_CACHE_SIZE);
+    Ok(())
+}
+
+#[test]
+fn efs_readahead_test() -> std::io::Result<()> {
+    use easy_fs::block_cache_stats;
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("ahead").unwrap();
+    let data: Vec<u8> = (0..8 * BLOCK_SZ).map(|i| (i % 253) as u8).collect();
+    file.write_at(0, &data);
+    file.dont_need(0, data.len());
+    let mut buffer = [0u8; BLOCK_SZ];
+
+    // blocks 2..6 are loaded, later reads of them are all hits
+    file.will_need(2 * BLOCK_SZ + 100, 3 * BLOCK_SZ);
+    let (hits, misses, _) = block_cache_stats();
+    for i in 2..6 {
+        assert_eq!(file.read_at(i * BLOCK_SZ, &mut buffer), BLOCK_SZ);
+        assert_eq!(&buffer[..], &data[i * BLOCK_SZ..(i + 1) * BLOCK_SZ]);
+    }
+    assert_eq!(block_cache_stats().1, misses);
+    assert!(block_cache_stats().0 >= hits + 4);
+
+    // cached blocks are skipped and the range is clamped at the end of file
+    let (_, misses, _) = block_cache_stats();
+    file.will_need(4 * BLOCK_SZ, usize::MAX);
+    assert_eq!(block_cache_stats().1, misses + 2);
+    file.will_need(100 * BLOCK_SZ, BLOCK_SZ);
+    assert_eq!(block_cache_stats().1, misses + 2);
+    Ok(())
+}
+
+#[test]
+fn efs_enospc_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let log = root_inode.create("log").unwrap();
+    assert_eq!(log.write_at(0, &[1u8; BLOCK_SZ]), BLOCK_SZ);
+    let spare = root_inode.create("spare").unwrap();
+    assert_eq!(spare.write_at(0, &[2u8; 4 * BLOCK_SZ]), 4 * BLOCK_SZ);
+    // fill the disk up completely
+    let filler = root_inode.create("filler").unwrap();
+    let mut size = 0;
+    for chunk in [64 * BLOCK_SZ, BLOCK_SZ] {
+        let data = vec![3u8; chunk];
+        loop {
+            let len = filler.write_at(size, &data);
+            if len == 0 {
+                break;
+            }
+            assert_eq!(len, chunk);
+            size += len;
+        }
+    }
+    // leave room for only half a record
+    assert_eq!(root_inode.unlinkat("spare"), 0);
+    let record = [4u8; 8 * BLOCK_SZ];
+    assert_eq!(log.write_at(BLOCK_SZ, &record), 0);
+    assert_eq!(log.size(), BLOCK_SZ);
+    let mut buffer = [0u8; 2 * BLOCK_SZ];
+    assert_eq!(log.read_at(0, &mut buffer), BLOCK_SZ);
+    assert!(buffer[..BLOCK_SZ].iter().all(|&byte| byte == 1));
+    // the reservation covers all buffers of a write
+    assert_eq!(
+        log.write_all_at(
+            BLOCK_SZ,
+            &[&record[..3 * BLOCK_SZ], &record[..3 * BLOCK_SZ]]
+        ),
+        None
+    );
+    assert_eq!(log.size(), BLOCK_SZ);
+    // while the half record still fits
+    assert_eq!(
+        log.write_at(BLOCK_SZ, &record[..4 * BLOCK_SZ]),
+        4 * BLOCK_SZ
+    );
+    assert_eq!(log.size(), 5 * BLOCK_SZ);
+    Ok(())
+}
+
+#[test]
+fn efs_full_disk_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("full").unwrap();
+    let chunk = [5u8; BLOCK_SZ];
+    while file.write_at(file.size(), &chunk) == BLOCK_SZ {}
+    let size = file.size();
+    assert_eq!(efs.lock().free_data_blocks(), 0);
+    // nothing is handed out of a full bitmap
+    assert_eq!(efs.lock().alloc_data(), None);
+    assert_eq!(efs.lock().alloc_data_blocks(1), None);
+    let data = vec![6u8; 64 * BLOCK_SZ];
+    assert_eq!(file.write_at(size, &data), 0);
+    assert_eq!(file.size(), size);
+    // the blocks written before are intact
+    let mut buffer = vec![0u8; size];
+    assert_eq!(file.read_at(0, &mut buffer), size);
+    assert!(buffer.iter().all(|&byte| byte == 5));
+    // and freeing some makes room again
+    assert!(file.truncate(size - 64 * BLOCK_SZ));
+    assert_eq!(file.write_at(file.size(), &data), data.len());
+    assert_eq!(file.size(), size);
+    Ok(())
+}
+
+#[test]
+fn efs_bitmap_tail_test() -> std::io::Result<()> {
+    // two data bitmap blocks, the second of them only partly in use
+    let (_guard, _, efs) = test_fs(8192)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let chunk = [5u8; BLOCK_SZ];
+    let first = root_inode.create("first").unwrap();
+    assert_eq!(first.write_at(0, &[5u8; 256 * BLOCK_SZ]), 256 * BLOCK_SZ);
+    let rest = root_inode.create("rest").unwrap();
+    while rest.write_at(rest.size(), &chunk) == BLOCK_SZ {}
+    assert_eq!(efs.lock().free_data_blocks(), 0);
+    // the blocks freed lie in the first bitmap block, while the search
+    // starts in the last one, past whose bits in use there is nothing
+    assert_eq!(root_inode.unlinkat("first"), 0);
+    let freed = efs.lock().free_data_blocks();
+    assert!(freed > 0);
+    while rest.write_at(rest.size(), &chunk) == BLOCK_SZ {}
+    assert_eq!(efs.lock().free_data_blocks(), 0);
+    assert_eq!(efs.lock().alloc_data_blocks(1), None);
+    assert_eq!(efs.lock().check(), vec![]);
+    Ok(())
+}
+
+#[test]
+fn efs_create_full_disk_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("full").unwrap();
+    let chunk = [5u8; BLOCK_SZ];
+    while file.write_at(file.size(), &chunk) == BLOCK_SZ {}
+    assert_eq!(root_inode.statfs().free_blocks, 0);
+    // names fit in the free slots of the root's block until it runs out
+    let created = (0..)
+        .map(|i| format!("file{}", i))
+        .take_while(|name| root_inode.create(name).is_some())
+        .count();
+    assert_eq!(created, BLOCK_SZ / easy_fs::DIRENT_SZ - 3);
+    let free_inodes = root_inode.statfs().free_inodes;
+    assert!(root_inode.create("more").is_none());
+    assert!(root_inode.create_symlink("more", "full").is_none());
+    assert_eq!(root_inode.linkat("full", "more"), -1);
+    // a failed create hands its inode back
+    assert_eq!(root_inode.statfs().free_inodes, free_inodes);
+    assert!(root_inode.find("more").is_none());
+    // a free slot is not enough for a directory, which needs a block
+    assert_eq!(root_inode.unlinkat("file0"), 0);
+    assert!(root_inode.create_dir("dir").is_none());
+    assert_eq!(root_inode.statfs().free_inodes, free_inodes + 1);
+    assert_eq!(root_inode.stat().nlink, 2);
+    assert!(root_inode.create("file0").is_some());
+    // freeing a block makes room again
+    assert_eq!(root_inode.unlinkat("file1"), 0);
+    assert!(file.truncate(file.size() - BLOCK_SZ));
+    let dir = root_inode.create_dir("dir").unwrap();
+    assert!(dir.find("..").is_some());
+    assert_eq!(root_inode.stat().nlink, 3);
+    Ok(())
+}
+
+#[test]
+fn efs_zero_range_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("zeros").unwrap();
+    let data = vec![7u8; 40 * BLOCK_SZ];
+    file.write_at(0, &data);
+    let blocks = file.stat().blocks;
+    // spans a partial block, whole blocks and the indirect block boundary
+    assert!(file.zero_range(BLOCK_SZ + 10, 30 * BLOCK_SZ));
+    assert_eq!(file.stat().blocks, blocks);
+    assert_eq!(file.size(), data.len());
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert!(buffer[..BLOCK_SZ + 10].iter().all(|&byte| byte == 7));
+    assert!(buffer[BLOCK_SZ + 10..31 * BLOCK_SZ + 10]
+        .iter()
+        .all(|&byte| byte == 0));
+    assert!(buffer[31 * BLOCK_SZ + 10..].iter().all(|&byte| byte == 7));
+
+    // plain allocation grows the file without touching the data
+    assert!(file.allocate(data.len(), 2 * BLOCK_SZ));
+    assert_eq!(file.size(), data.len() + 2 * BLOCK_SZ);
+    assert!(file.stat().blocks > blocks);
+    assert!(!file.zero_range(usize::MAX, 2));
+    Ok(())
+}
+
+#[test]
+fn efs_create_dir_test() -> std::io::Result<()> {
+    use easy_fs::StatMode;
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let root_links = root_inode.stat().nlink;
+    let a = root_inode.create_dir("a").unwrap();
+    assert_eq!(root_inode.stat().nlink, root_links + 1);
+    assert!(a.stat().mode.contains(StatMode::DIR));
+    assert!(a.ls(false).is_empty());
+    let b = a.create_dir("b").unwrap();
+    assert_eq!(a.stat().nlink, 3);
+    let c = b.create("c").unwrap();
+    c.write_at(0, b"deep");
+    assert_eq!(c.stat().mode, StatMode::FILE);
+    assert_eq!(b.stat().nlink, 2);
+    // the tree can be walked down from the root again
+    let found = root_inode
+        .find("a")
+        .and_then(|a| a.find("b"))
+        .and_then(|b| b.find("c"))
+        .unwrap();
+    let mut buffer = [0u8; 8];
+    assert_eq!(found.read_at(0, &mut buffer), 4);
+    assert_eq!(&buffer[..4], b"deep");
+    assert_eq!(a.ls(false), vec![String::from("b")]);
+    assert!(a.create_dir("b").is_none());
+    // removing a directory drops its link to the parent
+    assert_eq!(b.unlinkat("c"), 0);
+    assert_eq!(a.unlinkat("b"), 0);
+    assert_eq!(a.stat().nlink, 2);
+    assert_eq!(root_inode.unlinkat("a"), 0);
+    assert_eq!(root_inode.stat().nlink, root_links);
+    Ok(())
+}
+
+#[test]
+fn efs_dot_entries_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let root_ino = root_inode.stat().ino;
+    // the root is its own parent
+    assert_eq!(root_inode.ls(true), vec![".", ".."]);
+    assert_eq!(root_inode.find("..").unwrap().stat().ino, root_ino);
+    assert_eq!(root_inode.stat().nlink, 2);
+    let a = root_inode.create_dir("a").unwrap();
+    let b = a.create_dir("b").unwrap();
+    let a_ino = a.stat().ino;
+    assert_eq!(b.find("..").unwrap().stat().ino, a_ino);
+    assert_eq!(b.find(".").unwrap().stat().ino, b.stat().ino);
+    assert_eq!(a.find("..").unwrap().stat().ino, root_ino);
+    assert_eq!(
+        root_inode.find_path("a/b/../..").unwrap().stat().ino,
+        root_ino
+    );
+    assert_eq!(a.ls(true), vec![".", "..", "b"]);
+    assert_eq!(a.ls(false), vec!["b"]);
+    // the entries can be neither replaced nor removed
+    assert!(a.create(".").is_none());
+    assert!(a.create_dir("..").is_none());
+    assert_eq!(a.unlinkat("."), -1);
+    assert_eq!(a.unlinkat(".."), -1);
+    assert_eq!(a.rename("..", "up", false), -1);
+    assert_eq!(a.linkat(".", "self"), -1);
+    assert_eq!(a.ls(true), vec![".", "..", "b"]);
+    // removing an empty directory frees its inode
+    let free_inodes = root_inode.statfs().free_inodes;
+    assert_eq!(a.unlinkat("b"), 0);
+    assert_eq!(root_inode.statfs().free_inodes, free_inodes + 1);
+    Ok(())
+}
+
+#[test]
+fn efs_rmdir_not_empty_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let root_links = root_inode.stat().nlink;
+    let a = root_inode.create_dir("a").unwrap();
+    let b = a.create_dir("b").unwrap();
+    b.create("file").unwrap();
+    // a directory goes only once it holds nothing but its dots
+    assert!(!a.is_empty_dir());
+    assert_eq!(root_inode.unlinkat("a"), -1);
+    assert_eq!(a.unlinkat("b"), -1);
+    assert!(root_inode.find_path("a/b/file").is_some());
+    assert_eq!((a.stat().nlink, b.stat().nlink), (3, 2));
+    assert_eq!(b.unlinkat("file"), 0);
+    assert!(b.is_empty_dir());
+    assert_eq!(a.unlinkat("b"), 0);
+    assert_eq!(root_inode.unlinkat("a"), 0);
+    assert_eq!(root_inode.stat().nlink, root_links);
+    // a file is never an empty directory
+    let file = root_inode.create("file").unwrap();
+    assert!(!file.is_empty_dir());
+    Ok(())
+}
+
+#[test]
+fn efs_rename_mismatch_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let full = root_inode.create_dir("full").unwrap();
+    full.create("child").unwrap();
+    root_inode.create_dir("empty").unwrap();
+    root_inode.create_dir("dir").unwrap();
+    root_inode.create("file").unwrap();
+    let root_links = root_inode.stat().nlink;
+    // a file can't replace a directory, nor a directory a file
+    assert_eq!(root_inode.rename("file", "empty", false), -1);
+    assert_eq!(root_inode.rename("dir", "file", false), -1);
+    // nor a directory one that isn't empty
+    assert_eq!(root_inode.rename("dir", "full", false), -1);
+    assert!(root_inode.find_path("full/child").is_some());
+    assert!(root_inode.find("dir").unwrap().is_dir());
+    assert!(!root_inode.find("file").unwrap().is_dir());
+    assert_eq!(root_inode.stat().nlink, root_links);
+    // an empty directory is replaced, giving up its link to the root
+    assert_eq!(root_inode.rename("dir", "empty", false), 0);
+    assert!(root_inode.find("dir").is_none());
+    assert!(root_inode.find("empty").unwrap().is_dir());
+    assert_eq!(root_inode.stat().nlink, root_links - 1);
+    Ok(())
+}
+
+#[test]
+fn efs_link_dir_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let a = root_inode.create_dir("a").unwrap();
+    let b = a.create_dir("b").unwrap();
+    let file = b.create("file").unwrap();
+    let nlink = a.stat().nlink;
+    // neither a directory nor its parent may get a second name
+    assert_eq!(root_inode.linkat("a", "a2"), -1);
+    assert_eq!(a.linkat("b", "b2"), -1);
+    assert!(root_inode.find("a2").is_none());
+    assert!(a.find("b2").is_none());
+    assert_eq!(a.stat().nlink, nlink);
+    assert_eq!(b.stat().nlink, 2);
+    // files still can
+    assert_eq!(b.linkat("file", "file2"), 0);
+    assert_eq!(file.stat().nlink, 2);
+    assert_eq!(
+        root_inode.find_path("a/b/file2").unwrap().stat().ino,
+        file.stat().ino
+    );
+    Ok(())
+}
+
+#[test]
+fn efs_find_path_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let bin = root_inode
+        .create_dir("usr")
+        .and_then(|usr| usr.create_dir("bin"))
+        .unwrap();
+    bin.create("app").unwrap().write_at(0, b"app");
+    let mut buffer = [0u8; 8];
+    let app = root_inode.find_path("/usr/bin/app").unwrap();
+    assert_eq!(app.read_at(0, &mut buffer), 3);
+    assert_eq!(&buffer[..3], b"app");
+    // empty components are skipped
+    assert!(root_inode.find_path("usr//bin/app/").is_some());
+    assert!(root_inode.find_path("/").unwrap().is_dir());
+    // a single name still works
+    assert!(root_inode.find_path("usr").unwrap().is_dir());
+    assert!(root_inode.find_path("/usr/lib/app").is_none());
+    // only directories can be walked through
+    assert!(root_inode.find_path("/usr/bin/app/more").is_none());
+    Ok(())
+}
+
+#[test]
+fn efs_symlink_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let bin = root_inode
+        .create_dir("usr")
+        .and_then(|usr| usr.create_dir("bin"))
+        .unwrap();
+    bin.create("app").unwrap().write_at(0, b"app");
+    // links to a file and to a directory, relative and absolute
+    let link = bin.create_symlink("relative", "app").unwrap();
+    assert_eq!(link.readlink().unwrap(), "app");
+    assert_eq!(link.stat().mode, easy_fs::StatMode::LINK);
+    root_inode.create_symlink("bin", "/usr/bin").unwrap();
+    root_inode
+        .create_symlink("up", "usr/bin/../bin/relative")
+        .unwrap();
+    let app = root_inode.find_path("usr/bin/app").unwrap();
+    for path in ["usr/bin/relative", "bin/app", "bin/relative", "up"].iter() {
+        let found = root_inode.find_path_follow(path).unwrap();
+        assert_eq!(found.stat().ino, app.stat().ino);
+    }
+    let mut buffer = [0u8; 8];
+    let found = root_inode.find_path_follow("/bin/relative").unwrap();
+    assert_eq!(found.read_at(0, &mut buffer), 3);
+    assert_eq!(&buffer[..3], b"app");
+    // without following, the link itself is found
+    assert!(root_inode.find_path("bin").unwrap().readlink().is_some());
+    assert!(root_inode.find_path("bin/app").is_none());
+    assert!(app.readlink().is_none());
+    // and only the last component may be left unfollowed
+    let root = root_inode.find_path("").unwrap();
+    let resolve = |path| easy_fs::resolve_path(root.clone(), &root, path, false, |inode| inode);
+    assert_eq!(resolve("/bin").unwrap().readlink().unwrap(), "/usr/bin");
+    assert_eq!(resolve("bin/relative").unwrap().readlink().unwrap(), "app");
+    assert!(resolve("bin/app").unwrap().readlink().is_none());
+    // directory entries tell links apart
+    let mut offset = 0;
+    while let Some(entry) = bin.read_dirent(offset) {
+        assert_eq!(entry.is_symlink, entry.name == "relative");
+        offset = entry.offset + easy_fs::DIRENT_SZ;
+    }
+
+    // a dangling link still reads, but doesn't resolve
+    let dangling = root_inode.create_symlink("dangling", "nowhere").unwrap();
+    assert_eq!(dangling.readlink().unwrap(), "nowhere");
+    assert!(root_inode.find_path_follow("dangling").is_none());
+    root_inode.create("nowhere").unwrap();
+    assert!(root_inode.find_path_follow("dangling").is_some());
+    assert!(root_inode.create_symlink("empty", "").is_none());
+    assert!(root_inode.create_symlink("dangling", "app").is_none());
+
+    // a loop gives up at the depth cap
+    root_inode.create_symlink("self", "self").unwrap();
+    assert!(root_inode.find_path_follow("self").is_none());
+    root_inode.create_symlink("ping", "pong").unwrap();
+    root_inode.create_symlink("pong", "ping").unwrap();
+    assert!(root_inode.find_path_follow("ping/app").is_none());
+    // a chain just as long as the cap still resolves
+    let chain = |i: usize| format!("chain{}", i);
+    for i in 0..easy_fs::SYMLOOP_MAX {
+        root_inode.create_symlink(&chain(i), &chain(i + 1)).unwrap();
+    }
+    root_inode.create(&chain(easy_fs::SYMLOOP_MAX)).unwrap();
+    assert!(root_inode.find_path_follow(&chain(0)).is_some());
+    root_inode
+        .create_symlink("chain_too_long", &chain(0))
+        .unwrap();
+    assert!(root_inode.find_path_follow("chain_too_long").is_none());
+
+    // unlinking a link leaves what it points to alone
+    assert_eq!(bin.unlinkat("relative"), 0);
+    assert!(bin.find("app").is_some());
+    assert!(root_inode.find_path_follow("up").is_none());
+    Ok(())
+}
+
+#[test]
+fn efs_large_file_test() -> std::io::Result<()> {
+    use easy_fs::MAX_FILE_SIZE;
+    // room for a file of the largest size
+    let (_guard, _, efs) = test_fs(32768)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("large").unwrap();
+    // far into the indirect2 blocks
+    let data: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    assert_eq!(file.write_at(0, &data), data.len());
+    assert_eq!(file.size(), data.len());
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert!(buffer == data);
+    // nothing is written past what the indirect2 block can hold
+    assert_eq!(file.write_at(MAX_FILE_SIZE - 1, b"xy"), 0);
+    assert_eq!(file.size(), data.len());
+    assert_eq!(file.write_at(MAX_FILE_SIZE - 1, b"x"), 1);
+    assert_eq!(file.size(), MAX_FILE_SIZE);
+    assert!(!file.truncate(MAX_FILE_SIZE + 1));
+    file.clear();
+    assert_eq!(file.stat().blocks, 0);
+    Ok(())
+}
+
+#[test]
+fn efs_truncate_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("cut").unwrap();
+    let data = vec![7u8; 40 * BLOCK_SZ];
+    file.write_at(0, &data);
+    let blocks = file.stat().blocks;
+    // down past the indirect block boundary and into a block
+    assert!(file.truncate(3 * BLOCK_SZ + 10));
+    assert_eq!(file.size(), 3 * BLOCK_SZ + 10);
+    assert!(file.stat().blocks < blocks);
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), 3 * BLOCK_SZ + 10);
+    assert!(buffer[..3 * BLOCK_SZ + 10].iter().all(|&byte| byte == 7));
+    // the cut away part comes back as zeros
+    assert!(file.truncate(data.len()));
+    assert_eq!(file.size(), data.len());
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert!(buffer[..3 * BLOCK_SZ + 10].iter().all(|&byte| byte == 7));
+    assert!(buffer[3 * BLOCK_SZ + 10..].iter().all(|&byte| byte == 0));
+    assert!(file.truncate(0));
+    assert_eq!(file.stat().blocks, 0);
+    Ok(())
+}
+
+#[test]
+fn efs_truncate_tail_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("tail").unwrap();
+    let free = efs.lock().free_data_blocks();
+    assert_eq!(file.write_at(0, &[1u8; 2000]), 2000);
+    assert!(file.truncate(500));
+    let mut buffer = [0u8; 2000];
+    assert_eq!(file.read_at(500, &mut buffer), 0);
+    assert_eq!(file.read_at(0, &mut buffer), 500);
+    assert!(buffer[..500].iter().all(|&byte| byte == 1));
+    // only the tail blocks went back to the disk
+    assert_eq!(efs.lock().free_data_blocks(), free - 1);
+    // and the cut off bytes of the last one don't come back
+    assert_eq!(file.write_at(1000, &[2u8]), 1);
+    assert_eq!(file.read_at(0, &mut buffer), 1001);
+    assert!(buffer[500..1000].iter().all(|&byte| byte == 0));
+    // shrinking out of the indirect2 and indirect1 blocks frees them too
+    let data: Vec<u8> = (0..200 * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    assert_eq!(file.write_at(0, &data), data.len());
+    for size in [
+        180 * BLOCK_SZ,
+        147 * BLOCK_SZ + 1,
+        100 * BLOCK_SZ,
+        19 * BLOCK_SZ,
+        7,
+    ] {
+        assert!(file.truncate(size));
+        let blocks = file.stat().blocks;
+        assert_eq!(efs.lock().free_data_blocks() as u64, free as u64 - blocks);
+        let mut buffer = vec![0u8; data.len()];
+        assert_eq!(file.read_at(0, &mut buffer), size);
+        assert_eq!(buffer[..size], data[..size]);
+        // growing back again reuses the freed index blocks
+        assert_eq!(file.write_at(size, &data[size..]), data.len() - size);
+        assert_eq!(file.read_at(0, &mut buffer), data.len());
+        assert!(buffer == data);
+        assert!(file.truncate(size));
+    }
+    assert!(file.truncate(0));
+    assert_eq!(efs.lock().free_data_blocks(), free);
+    Ok(())
+}
+
+#[test]
+fn efs_verity_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("sealed").unwrap();
+    let mut data = Vec::new();
+    for i in 0..200 {
+        let mut block = format!("verity block {:03}", i).into_bytes();
+        block.resize(BLOCK_SZ, b'.');
+        data.extend_from_slice(&block);
+    }
+    file.write_at(0, &data);
+    let blocks = file.stat().blocks;
+    assert!(file.enable_verity());
+    assert!(file.is_verity());
+    assert!(!file.enable_verity());
+    // an index block and two hash blocks
+    assert_eq!(file.stat().blocks, blocks + 3);
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.try_read_at(0, &mut buffer), Some(data.len()));
+    assert_eq!(buffer, data);
+    assert_eq!(file.write_at(0, b"tampered"), 0);
+    assert!(!file.allocate(0, data.len() + BLOCK_SZ));
+    assert!(!file.zero_range(0, BLOCK_SZ));
+    assert_eq!(file.size(), data.len());
+
+    // flip a byte of data block 150 behind the filesystem's back
+    file.flush_fs();
+    let mut image = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("target/fs.img")?;
+    let mut block = [0u8; BLOCK_SZ];
+    let mut block_id = 0;
+    loop {
+        image.read_exact(&mut block)?;
+        if block.starts_with(b"verity block 150") {
+            break;
+        }
+        block_id += 1;
+    }
+    block[BLOCK_SZ - 1] = b'!';
+    image.seek(SeekFrom::Start((block_id * BLOCK_SZ) as u64))?;
+    image.write_all(&block)?;
+    drop(image);
+
+    assert_eq!(
+        file.try_read_at(150 * BLOCK_SZ + 20, &mut buffer[..10]),
+        None
+    );
+    assert_eq!(
+        file.try_read_at(149 * BLOCK_SZ, &mut buffer[..2 * BLOCK_SZ]),
+        None
+    );
+    assert_eq!(file.read_at(150 * BLOCK_SZ, &mut buffer), 0);
+    // the other blocks still read fine
+    assert_eq!(
+        file.try_read_at(0, &mut buffer[..150 * BLOCK_SZ]),
+        Some(150 * BLOCK_SZ)
+    );
+    assert_eq!(&buffer[..150 * BLOCK_SZ], &data[..150 * BLOCK_SZ]);
+    assert_eq!(
+        file.try_read_at(151 * BLOCK_SZ, &mut buffer),
+        Some(49 * BLOCK_SZ)
+    );
+    assert_eq!(root_inode.unlinkat("sealed"), 0);
+    Ok(())
+}
+
+#[test]
+fn efs_usage_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("usage").unwrap();
+    assert_eq!(file.usage().total_blocks, 0);
+    file.write_at(0, &vec![1u8; 40 * BLOCK_SZ]);
+    let usage = file.usage();
+    // the data blocks and an indirect1 block, none of them shared
+    assert_eq!(usage.total_blocks, 41);
+    assert_eq!(usage.unique_blocks, usage.total_blocks);
+    assert_eq!(usage.total_blocks, file.stat().blocks);
+    // a hard link is the same inode rather than a sharing one
+    assert_eq!(root_inode.linkat("usage", "usage_link"), 0);
+    assert_eq!(root_inode.find("usage_link").unwrap().usage(), usage);
+    Ok(())
+}
+
+#[test]
+fn efs_times_test() -> std::io::Result<()> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    static TIME: AtomicU32 = AtomicU32::new(100);
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    easy_fs::set_clock(|| TIME.load(Ordering::SeqCst));
+    TIME.store(100, Ordering::SeqCst);
+    let file = root_inode.create("times").unwrap();
+    let created = file.stat();
+    assert_eq!(
+        (created.atime, created.mtime, created.ctime),
+        (100, 100, 100)
+    );
+    assert_eq!(root_inode.stat().mtime, 100);
+    TIME.store(105, Ordering::SeqCst);
+    file.write_at(0, b"first");
+    let written = file.stat();
+    assert_eq!(
+        (written.atime, written.mtime, written.ctime),
+        (100, 105, 105)
+    );
+    TIME.store(110, Ordering::SeqCst);
+    file.write_at(5, b"second");
+    assert!(file.stat().mtime > written.mtime);
+    TIME.store(120, Ordering::SeqCst);
+    let mut buffer = [0u8; 16];
+    assert_eq!(file.read_at(0, &mut buffer), 11);
+    let read = file.stat();
+    assert_eq!((read.atime, read.mtime, read.ctime), (120, 110, 110));
+    // a link changes the status of the file and the contents of the directory
+    TIME.store(130, Ordering::SeqCst);
+    assert_eq!(root_inode.linkat("times", "alias"), 0);
+    let linked = file.stat();
+    assert_eq!((linked.mtime, linked.ctime), (110, 130));
+    assert_eq!(root_inode.stat().mtime, 130);
+    TIME.store(140, Ordering::SeqCst);
+    assert!(file.truncate(3));
+    assert_eq!(file.stat().mtime, 140);
+    Ok(())
+}
+
+#[test]
+fn efs_chmod_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("perm").unwrap();
+    let dir = root_inode.create_dir("perm_dir").unwrap();
+    assert_eq!(file.stat().perm, 0o644);
+    assert_eq!(dir.stat().perm, 0o755);
+    file.chmod(0o100600);
+    assert_eq!(file.stat().perm, 0o600);
+    file.chown(Some(1000), None);
+    file.chown(None, Some(100));
+    let stat = root_inode.find("perm").unwrap().stat();
+    assert_eq!((stat.perm, stat.uid, stat.gid), (0o600, 1000, 100));
+    // the mode bits don't leak into the file type
+    assert_eq!(stat.mode.bits(), 0o100000);
+    Ok(())
+}
+
+#[test]
+fn efs_defrag_test() -> std::io::Result<()> {
+    let (_guard, _, efs) = test_fs(4096)?;
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    // grow two files a block at a time so their blocks interleave
+    let file = root_inode.create("scattered").unwrap();
+    let other = root_inode.create("other").unwrap();
+    let data: Vec<u8> = (0..30 * BLOCK_SZ).map(|i| (i % 251) as u8).collect();
+    for chunk in data.chunks(BLOCK_SZ) {
+        file.write_at(file.size(), chunk);
+        other.write_at(other.size(), chunk);
+    }
+    let blocks = file.block_ids();
+    assert!(blocks.windows(2).any(|pair| pair[1] != pair[0] + 1));
+    let free = efs.lock().free_data_blocks();
+    let inode_id = file.stat().ino as u32;
+
+    // refused while inodes are alive, unless access is exclusive
+    assert!(!EasyFileSystem::defrag_inode(&efs, inode_id, false));
+    assert_eq!(file.block_ids(), blocks);
+    assert!(EasyFileSystem::defrag_inode(&efs, inode_id, true));
+    let blocks = file.block_ids();
+    // the data blocks and the indirect1 block behind the direct ones form one run
+    assert!(blocks.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(blocks[29] - blocks[0] + 1, file.usage().total_blocks as u32);
+    assert_eq!(efs.lock().free_data_blocks(), free);
+    let mut buffer = vec![0u8; data.len()];
+    assert_eq!(file.read_at(0, &mut buffer), data.len());
+    assert_eq!(buffer, data);
+
+    // with every inode dropped the filesystem is offline
+    let inode_id = other.stat().ino as u32;
+    drop((file, other, root_inode));
+    assert!(EasyFileSystem::defrag_inode(&efs, inode_id, false));
+    let other = EasyFileSystem::root_inode(&efs).find("other").unwrap();
+    assert_eq!(other.read_at(0, &mut buffer), data.len());
+    assert_eq!(buffer, data);
+    Ok(())
+}
+
+/// Blocks of the image the deterministic allocation test builds
+#[cfg(test)]
+const GOLDEN_BLOCKS: usize = 16384;
+
+/// Build an image from zeros with a fixed sequence of operations, returning
+/// the first block given to the last file, the blocks of the one before it and
+/// a 64-bit FNV-1a hash of the image
+#[cfg(test)]
+fn golden_image(deterministic: bool) -> std::io::Result<(u32, Vec<u32>, u64)> {
+    let f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open("target/golden.img")?;
+    f.set_len(0)?;
+    f.set_len((GOLDEN_BLOCKS * BLOCK_SZ) as u64)?;
+    let block_file = Arc::new(BlockFile(Mutex::new(f)));
+    // keep the timestamps out of the hash
+    easy_fs::set_clock(|| 0);
+    let efs = EasyFileSystem::create(block_file, GOLDEN_BLOCKS as u32, 1);
+    efs.lock().set_deterministic(deterministic);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let small = root_inode.create("small").unwrap();
+    small.write_at(0, &[1u8; 3 * BLOCK_SZ]);
+    let freed = small.block_ids();
+    assert_eq!(freed.len(), 3);
+    drop(small);
+    // a file large enough to reach the second data bitmap block
+    let big = root_inode.create("big").unwrap();
+    let data: Vec<u8> = (0..4200 * BLOCK_SZ).map(|i| (i % 253) as u8).collect();
+    big.write_at(0, &data);
+    let big_blocks = big.block_ids();
+    drop(big);
+    assert_eq!(root_inode.unlinkat("small"), 0);
+    let last = root_inode.create("last").unwrap();
+    last.write_at(0, b"last");
+    let first = last.block_ids()[0];
+    drop((last, root_inode));
+    efs.lock().flush();
+    let mut image = Vec::new();
+    File::open("target/golden.img")?.read_to_end(&mut image)?;
+    let hash = image.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    if deterministic {
+        assert_eq!(first, freed[0]);
+    } else {
+        assert!(first > *big_blocks.iter().max().unwrap());
+    }
+    Ok((first, big_blocks, hash))
+}
+
+#[test]
+fn efs_deterministic_alloc_test() -> std::io::Result<()> {
+    let _guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    // searching on from the last allocation skips the blocks freed behind it
+    let (hinted_first, hinted_big, hinted_hash) = golden_image(false)?;
+    let (first, big, hash) = golden_image(true)?;
+    assert_eq!(big, hinted_big);
+    assert_ne!(first, hinted_first);
+    assert_ne!(hash, hinted_hash);
+    // the recorded layout of the sequence, which only changes with the format
+    assert_eq!(hash, 0x1f7c6535ee7beba4);
+    assert_eq!(golden_image(true)?.2, hash);
+    Ok(())
+}
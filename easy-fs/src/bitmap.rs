@@ -11,6 +11,10 @@ const BLOCK_BITS: usize = BLOCK_SZ * 8;
 pub struct Bitmap {
     start_block_id: usize,
     blocks: usize,
+    /// number of bits in use, the rest of the last block is never handed out
+    bits: usize,
+    /// bitmap block the last allocation was made in, where the next one starts looking
+    hint: usize,
 }
 
 /// Decompose bits into (block_pos, bits64_pos, inner_pos)
@@ -23,39 +27,99 @@ fn decomposition(mut bit: usize) -> (usize, usize, usize) {
 impl Bitmap {
     /// A new bitmap from start block id and number of blocks
     pub fn new(start_block_id: usize, blocks: usize) -> Self {
+        Self::with_bits(start_block_id, blocks, blocks * BLOCK_BITS)
+    }
+    /// A new bitmap of `blocks` blocks of which only the first `bits` bits
+    /// are in use
+    pub fn with_bits(start_block_id: usize, blocks: usize, bits: usize) -> Self {
+        assert!(bits <= blocks * BLOCK_BITS);
         Self {
             start_block_id,
             blocks,
+            bits,
+            hint: 0,
         }
     }
     /// Allocate a new block from a block device
-    pub fn alloc(&self, block_device: &Arc<dyn BlockDevice>) -> Option<usize> {
-        for block_id in 0..self.blocks {
-            let pos = get_block_cache(
-                block_id + self.start_block_id as usize,
-                Arc::clone(block_device),
-            )
-            .lock()
-            .modify(0, |bitmap_block: &mut BitmapBlock| {
-                if let Some((bits64_pos, inner_pos)) = bitmap_block
-                    .iter()
-                    .enumerate()
-                    .find(|(_, bits64)| **bits64 != u64::MAX)
-                    .map(|(bits64_pos, bits64)| (bits64_pos, bits64.trailing_ones() as usize))
-                {
-                    // modify cache
-                    bitmap_block[bits64_pos] |= 1u64 << inner_pos;
-                    Some(block_id * BLOCK_BITS + bits64_pos * 64 + inner_pos as usize)
-                } else {
-                    None
-                }
-            });
+    ///
+    /// The search starts from the bitmap block of the last allocation and wraps
+    /// around, so full blocks in front of it are not scanned again and again.
+    pub fn alloc(&mut self, block_device: &Arc<dyn BlockDevice>) -> Option<usize> {
+        let hint = self.hint;
+        for block_id in (hint..self.blocks).chain(0..hint) {
+            let pos = self.alloc_in(block_device, block_id);
             if pos.is_some() {
+                self.hint = block_id;
                 return pos;
             }
         }
         None
     }
+    /// Allocate the lowest free bit, wherever the last allocation was made
+    pub fn alloc_lowest(&self, block_device: &Arc<dyn BlockDevice>) -> Option<usize> {
+        (0..self.blocks).find_map(|block_id| self.alloc_in(block_device, block_id))
+    }
+    /// Allocate the lowest free bit in use of bitmap block `block_id`
+    fn alloc_in(&self, block_device: &Arc<dyn BlockDevice>, block_id: usize) -> Option<usize> {
+        get_block_cache(
+            block_id + self.start_block_id as usize,
+            Arc::clone(block_device),
+        )
+        .lock()
+        .modify(0, |bitmap_block: &mut BitmapBlock| {
+            if let Some((bits64_pos, inner_pos)) = bitmap_block
+                .iter()
+                .enumerate()
+                .find(|(_, bits64)| **bits64 != u64::MAX)
+                .map(|(bits64_pos, bits64)| (bits64_pos, bits64.trailing_ones() as usize))
+                .filter(|(bits64_pos, inner_pos)| {
+                    block_id * BLOCK_BITS + bits64_pos * 64 + inner_pos < self.bits
+                })
+            {
+                // modify cache
+                bitmap_block[bits64_pos] |= 1u64 << inner_pos;
+                Some(block_id * BLOCK_BITS + bits64_pos * 64 + inner_pos as usize)
+            } else {
+                None
+            }
+        })
+    }
+    /// Allocate `count` consecutive bits below `limit`, returning the first of them
+    pub fn alloc_run(
+        &self,
+        block_device: &Arc<dyn BlockDevice>,
+        count: usize,
+        limit: usize,
+    ) -> Option<usize> {
+        let mut start = 0;
+        let mut len = 0;
+        for bit in 0..limit.min(self.maximum()) {
+            let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
+            let used = get_block_cache(block_pos + self.start_block_id, Arc::clone(block_device))
+                .lock()
+                .read(0, |bitmap_block: &BitmapBlock| {
+                    bitmap_block[bits64_pos] & (1u64 << inner_pos) > 0
+                });
+            if used {
+                start = bit + 1;
+                len = 0;
+                continue;
+            }
+            len += 1;
+            if len == count {
+                for bit in start..start + count {
+                    let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
+                    get_block_cache(block_pos + self.start_block_id, Arc::clone(block_device))
+                        .lock()
+                        .modify(0, |bitmap_block: &mut BitmapBlock| {
+                            bitmap_block[bits64_pos] |= 1u64 << inner_pos;
+                        });
+                }
+                return Some(start);
+            }
+        }
+        None
+    }
     /// Deallocate a block
     pub fn dealloc(&self, block_device: &Arc<dyn BlockDevice>, bit: usize) {
         let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
@@ -66,8 +130,32 @@ impl Bitmap {
                 bitmap_block[bits64_pos] -= 1u64 << inner_pos;
             });
     }
+    /// Whether `bit` is allocated
+    pub fn is_allocated(&self, block_device: &Arc<dyn BlockDevice>, bit: usize) -> bool {
+        let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
+        get_block_cache(block_pos + self.start_block_id, Arc::clone(block_device))
+            .lock()
+            .read(0, |bitmap_block: &BitmapBlock| {
+                bitmap_block[bits64_pos] & (1u64 << inner_pos) != 0
+            })
+    }
+    /// Count the allocated blocks
+    pub fn allocated(&self, block_device: &Arc<dyn BlockDevice>) -> usize {
+        (0..self.blocks)
+            .map(|block_id| {
+                get_block_cache(block_id + self.start_block_id, Arc::clone(block_device))
+                    .lock()
+                    .read(0, |bitmap_block: &BitmapBlock| {
+                        bitmap_block
+                            .iter()
+                            .map(|bits64| bits64.count_ones() as usize)
+                            .sum::<usize>()
+                    })
+            })
+            .sum()
+    }
     /// Get the max number of allocatable blocks
     pub fn maximum(&self) -> usize {
-        self.blocks * BLOCK_BITS
+        self.bits
     }
 }
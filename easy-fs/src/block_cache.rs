@@ -1,6 +1,7 @@
 use super::{BlockDevice, BLOCK_SZ};
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
 use spin::Mutex;
 
@@ -76,50 +77,175 @@ impl Drop for BlockCache {
     }
 }
 
-/// Use a block cache of 16 blocks
-const BLOCK_CACHE_SIZE: usize = 16;
+/// Use a block cache of 16 blocks unless told otherwise at mount
+pub const BLOCK_CACHE_SIZE: usize = 16;
+
+/// Identify a block device by the address of the object behind it
+fn device_id(block_device: &Arc<dyn BlockDevice>) -> usize {
+    Arc::as_ptr(block_device) as *const () as usize
+}
 
 pub struct BlockCacheManager {
-    queue: VecDeque<(usize, Arc<Mutex<BlockCache>>)>,
+    /// (device id, block id, cache) of every resident block
+    queue: VecDeque<(usize, usize, Arc<Mutex<BlockCache>>)>,
+    /// number of lookups served from the cache
+    hits: usize,
+    /// number of lookups that had to load the block from disk
+    misses: usize,
+    /// number of blocks pushed out to make room for another
+    evictions: usize,
+    /// how many blocks the cache holds at most
+    capacity: usize,
 }
 
 impl BlockCacheManager {
     pub fn new() -> Self {
         Self {
             queue: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            capacity: BLOCK_CACHE_SIZE,
         }
     }
 
+    /// Hold at most `capacity` blocks, pushing out unused ones if there are
+    /// more already
+    pub fn set_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "The block cache needs room for a block!");
+        self.capacity = capacity;
+        while self.queue.len() > self.capacity && self.make_room() {}
+    }
+
     pub fn get_block_cache(
         &mut self,
         block_id: usize,
         block_device: Arc<dyn BlockDevice>,
     ) -> Arc<Mutex<BlockCache>> {
-        if let Some(pair) = self.queue.iter().find(|pair| pair.0 == block_id) {
-            Arc::clone(&pair.1)
+        let dev = device_id(&block_device);
+        if let Some(pair) = self
+            .queue
+            .iter()
+            .find(|pair| pair.0 == dev && pair.1 == block_id)
+        {
+            self.hits += 1;
+            Arc::clone(&pair.2)
+        } else {
+            self.misses += 1;
+            self.load(block_id, block_device)
+        }
+    }
+
+    /// Whether the block is currently resident in the cache
+    pub fn contains(&self, block_id: usize, block_device: &Arc<dyn BlockDevice>) -> bool {
+        let dev = device_id(block_device);
+        self.queue
+            .iter()
+            .any(|pair| pair.0 == dev && pair.1 == block_id)
+    }
+
+    /// Load a block into the cache ahead of use, without touching the hit counter.
+    /// Blocks that are already cached are left alone.
+    pub fn prefetch(&mut self, block_id: usize, block_device: Arc<dyn BlockDevice>) {
+        if !self.contains(block_id, &block_device) {
+            self.misses += 1;
+            self.load(block_id, block_device);
+        }
+    }
+
+    /// Evict a clean block which is not in use by anyone else.
+    /// Returns whether the block was evicted.
+    pub fn evict(&mut self, block_id: usize, block_device: &Arc<dyn BlockDevice>) -> bool {
+        let dev = device_id(block_device);
+        if let Some(idx) = self.queue.iter().position(|pair| {
+            pair.0 == dev
+                && pair.1 == block_id
+                && Arc::strong_count(&pair.2) == 1
+                && !pair.2.lock().modified
+        }) {
+            self.queue.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Write the block back if it is cached and dirty
+    pub fn sync_block(&self, block_id: usize, block_device: &Arc<dyn BlockDevice>) {
+        let dev = device_id(block_device);
+        if let Some(pair) = self
+            .queue
+            .iter()
+            .find(|pair| pair.0 == dev && pair.1 == block_id)
+        {
+            pair.2.lock().sync();
+        }
+    }
+
+    /// Write back and drop a block which is not in use by anyone else.
+    /// Returns whether the block was dropped.
+    pub fn drop_block(&mut self, block_id: usize, block_device: &Arc<dyn BlockDevice>) -> bool {
+        let dev = device_id(block_device);
+        if let Some(idx) = self.queue.iter().position(|pair| {
+            pair.0 == dev && pair.1 == block_id && Arc::strong_count(&pair.2) == 1
+        }) {
+            // written back as it is dropped
+            self.queue.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Write back and drop every cached block of a device
+    pub fn drop_device(&mut self, block_device: &Arc<dyn BlockDevice>) {
+        let dev = device_id(block_device);
+        self.queue.retain(|pair| pair.0 != dev);
+    }
+
+    /// Get (hits, misses, evictions) of the cache
+    pub fn stats(&self) -> (usize, usize, usize) {
+        (self.hits, self.misses, self.evictions)
+    }
+
+    /// Push out the least recently loaded unused block, returning whether
+    /// there was one
+    fn make_room(&mut self) -> bool {
+        // from front to tail
+        if let Some((idx, _)) = self
+            .queue
+            .iter()
+            .enumerate()
+            .find(|(_, pair)| Arc::strong_count(&pair.2) == 1)
+        {
+            self.queue.drain(idx..=idx);
+            self.evictions += 1;
+            true
         } else {
-            // substitute
-            if self.queue.len() == BLOCK_CACHE_SIZE {
-                // from front to tail
-                if let Some((idx, _)) = self
-                    .queue
-                    .iter()
-                    .enumerate()
-                    .find(|(_, pair)| Arc::strong_count(&pair.1) == 1)
-                {
-                    self.queue.drain(idx..=idx);
-                } else {
-                    panic!("Run out of BlockCache!");
-                }
+            false
+        }
+    }
+
+    /// Load a block which is not cached yet, substituting an unused one if full
+    fn load(
+        &mut self,
+        block_id: usize,
+        block_device: Arc<dyn BlockDevice>,
+    ) -> Arc<Mutex<BlockCache>> {
+        // substitute
+        while self.queue.len() >= self.capacity {
+            if !self.make_room() {
+                panic!("Run out of BlockCache!");
             }
-            // load block into mem and push back
-            let block_cache = Arc::new(Mutex::new(BlockCache::new(
-                block_id,
-                Arc::clone(&block_device),
-            )));
-            self.queue.push_back((block_id, Arc::clone(&block_cache)));
-            block_cache
         }
+        // load block into mem and push back
+        let block_cache = Arc::new(Mutex::new(BlockCache::new(
+            block_id,
+            Arc::clone(&block_device),
+        )));
+        self.queue
+            .push_back((device_id(&block_device), block_id, Arc::clone(&block_cache)));
+        block_cache
     }
 }
 
@@ -140,10 +266,60 @@ pub fn get_block_cache(
         .get_block_cache(block_id, block_device)
 }
 
-/// Sync all block cache to block device
+/// Load the given block into the cache if it is not resident yet
+pub fn block_cache_prefetch(block_id: usize, block_device: Arc<dyn BlockDevice>) {
+    BLOCK_CACHE_MANAGER.lock().prefetch(block_id, block_device)
+}
+
+/// Whether the given block is resident in the cache, without loading it
+pub fn block_cache_contains(block_id: usize, block_device: &Arc<dyn BlockDevice>) -> bool {
+    BLOCK_CACHE_MANAGER.lock().contains(block_id, block_device)
+}
+
+/// Drop the given block from the cache if it is clean and unused
+pub fn block_cache_evict(block_id: usize, block_device: &Arc<dyn BlockDevice>) -> bool {
+    BLOCK_CACHE_MANAGER.lock().evict(block_id, block_device)
+}
+
+/// Write back and drop the given block from the cache if it is unused
+pub fn block_cache_drop(block_id: usize, block_device: &Arc<dyn BlockDevice>) -> bool {
+    BLOCK_CACHE_MANAGER.lock().drop_block(block_id, block_device)
+}
+
+/// Write the given block back if it is cached and dirty
+pub fn block_cache_sync(block_id: usize, block_device: &Arc<dyn BlockDevice>) {
+    BLOCK_CACHE_MANAGER.lock().sync_block(block_id, block_device)
+}
+
+/// Write back and drop every cached block of the given block device
+pub fn block_cache_drop_device(block_device: &Arc<dyn BlockDevice>) {
+    BLOCK_CACHE_MANAGER.lock().drop_device(block_device)
+}
+
+/// Get (hits, misses, evictions) statistics of the global block cache
+pub fn block_cache_stats() -> (usize, usize, usize) {
+    BLOCK_CACHE_MANAGER.lock().stats()
+}
+
+/// Let the global block cache hold at most `capacity` blocks
+pub fn block_cache_set_capacity(capacity: usize) {
+    BLOCK_CACHE_MANAGER.lock().set_capacity(capacity)
+}
+
+/// Sync all block cache to block device, then flush every device with
+/// blocks in the cache once
 pub fn block_cache_sync_all() {
     let manager = BLOCK_CACHE_MANAGER.lock();
-    for (_, cache) in manager.queue.iter() {
-        cache.lock().sync();
+    let mut devices: Vec<(usize, Arc<dyn BlockDevice>)> = Vec::new();
+    for (dev, _, cache) in manager.queue.iter() {
+        let mut cache = cache.lock();
+        cache.sync();
+        if !devices.iter().any(|(id, _)| id == dev) {
+            devices.push((*dev, Arc::clone(&cache.block_device)));
+        }
+    }
+    drop(manager);
+    for (_, device) in devices {
+        device.flush();
     }
 }
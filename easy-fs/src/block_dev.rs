@@ -5,4 +5,6 @@ use core::any::Any;
 pub trait BlockDevice: Send + Sync + Any {
     fn read_block(&self, block_id: usize, buf: &mut [u8]);
     fn write_block(&self, block_id: usize, buf: &[u8]);
+    /// Push the blocks written so far out of the buffers of the device itself
+    fn flush(&self) {}
 }
@@ -1,9 +1,13 @@
 use super::{
-    block_cache_sync_all, get_block_cache, Bitmap, BlockDevice, DiskInode, DiskInodeType, Inode,
-    SuperBlock,
+    block_cache_drop_device, block_cache_set_capacity, block_cache_sync_all, get_block_cache,
+    Bitmap, BlockDevice, DirEntry, DiskInode, DiskInodeType, FileAdvice, Inode, SuperBlock,
+    DIRENT_SZ,
 };
 use crate::BLOCK_SZ;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use spin::Mutex;
 
 /// An easy fs over a block device
@@ -13,11 +17,47 @@ pub struct EasyFileSystem {
     pub data_bitmap: Bitmap,
     inode_area_start_block: u32,
     data_area_start_block: u32,
+    /// number of inodes, fixed when the filesystem is made
+    total_inodes: u32,
+    /// number of data blocks, fixed when the filesystem is made
+    total_data_blocks: u32,
+    /// number of data blocks neither allocated nor reserved
+    free_data_blocks: u32,
+    /// number of data blocks set aside for writes in progress
+    reserved_data_blocks: u32,
+    /// always allocate the lowest free inode and data block
+    deterministic: bool,
+    /// compare names with ASCII case folded
+    case_insensitive: bool,
+    /// open files of each inode that has any, which keep it from being
+    /// released when its last link goes
+    open_files: BTreeMap<u32, usize>,
+    /// access pattern hint of each inode given one other than Normal
+    advice: BTreeMap<u32, FileAdvice>,
 }
 
 /// A data block of block size
 type DataBlock = [u8; BLOCK_SZ];
 
+/// An inconsistency found by [`EasyFileSystem::check`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsError {
+    /// A data block allocated in the bitmap that no inode holds
+    LeakedBlock(u32),
+    /// A block an inode holds that is free in the bitmap or not a data block
+    FreeBlockInUse { inode: u32, block_id: u32 },
+    /// A block held twice, by two inodes or by the same one
+    SharedBlock { block_id: u32, inodes: (u32, u32) },
+    /// An inode whose nlink differs from the number of entries naming it
+    LinkCount {
+        inode: u32,
+        nlink: u32,
+        entries: u32,
+    },
+    /// A directory entry naming an inode that is not allocated
+    DanglingEntry { dir: u32, name: String, inode: u32 },
+}
+
 impl EasyFileSystem {
     /// Create a filesystem from a block device
     pub fn create(
@@ -34,9 +74,10 @@ impl EasyFileSystem {
         let data_total_blocks = total_blocks - 1 - inode_total_blocks;
         let data_bitmap_blocks = (data_total_blocks + 4096) / 4097;
         let data_area_blocks = data_total_blocks - data_bitmap_blocks;
-        let data_bitmap = Bitmap::new(
+        let data_bitmap = Bitmap::with_bits(
             (1 + inode_bitmap_blocks + inode_area_blocks) as usize,
             data_bitmap_blocks as usize,
+            data_area_blocks as usize,
         );
         let mut efs = Self {
             block_device: Arc::clone(&block_device),
@@ -44,6 +85,14 @@ impl EasyFileSystem {
             data_bitmap,
             inode_area_start_block: 1 + inode_bitmap_blocks,
             data_area_start_block: 1 + inode_total_blocks + data_bitmap_blocks,
+            total_inodes: inode_num as u32,
+            total_data_blocks: data_area_blocks,
+            free_data_blocks: data_area_blocks,
+            reserved_data_blocks: 0,
+            deterministic: false,
+            case_insensitive: false,
+            open_files: BTreeMap::new(),
+            advice: BTreeMap::new(),
         };
         // clear inode bitmap
         let start = 1;
@@ -84,15 +133,21 @@ impl EasyFileSystem {
         );
         // write back immediately
         // create a inode for root node "/"
-        assert_eq!(efs.alloc_inode(), 0);
+        assert_eq!(efs.alloc_inode(), Some(0));
         let (root_inode_block_id, root_inode_offset) = efs.get_disk_inode_pos(0);
         get_block_cache(root_inode_block_id as usize, Arc::clone(&block_device))
             .lock()
             .modify(root_inode_offset, |disk_inode: &mut DiskInode| {
                 disk_inode.initialize(DiskInodeType::Directory);
             });
+        let efs = Arc::new(Mutex::new(efs));
+        // the root is its own parent
+        assert!(
+            Self::root_inode(&efs).add_dots(&mut efs.lock(), 0),
+            "no room for the root directory"
+        );
         block_cache_sync_all();
-        Arc::new(Mutex::new(efs))
+        efs
     }
     /// Open a block device as a filesystem
     pub fn open(block_device: Arc<dyn BlockDevice>) -> Arc<Mutex<Self>> {
@@ -103,19 +158,147 @@ impl EasyFileSystem {
                 assert!(super_block.is_valid(), "Error loading EFS!");
                 let inode_total_blocks =
                     super_block.inode_bitmap_blocks + super_block.inode_area_blocks;
-                let efs = Self {
+                let inode_bitmap = Bitmap::new(1, super_block.inode_bitmap_blocks as usize);
+                let mut efs = Self {
                     block_device,
-                    inode_bitmap: Bitmap::new(1, super_block.inode_bitmap_blocks as usize),
-                    data_bitmap: Bitmap::new(
+                    total_inodes: inode_bitmap.maximum() as u32,
+                    inode_bitmap,
+                    data_bitmap: Bitmap::with_bits(
                         (1 + inode_total_blocks) as usize,
                         super_block.data_bitmap_blocks as usize,
+                        super_block.data_area_blocks as usize,
                     ),
                     inode_area_start_block: 1 + super_block.inode_bitmap_blocks,
                     data_area_start_block: 1 + inode_total_blocks + super_block.data_bitmap_blocks,
+                    total_data_blocks: super_block.data_area_blocks,
+                    free_data_blocks: 0,
+                    reserved_data_blocks: 0,
+                    deterministic: false,
+                    case_insensitive: false,
+                    open_files: BTreeMap::new(),
+                    advice: BTreeMap::new(),
                 };
+                efs.free_data_blocks = super_block.data_area_blocks
+                    - efs.data_bitmap.allocated(&efs.block_device) as u32;
                 Arc::new(Mutex::new(efs))
             })
     }
+    /// Open a block device as a filesystem, letting the block cache shared by
+    /// all filesystems hold `cache_blocks` blocks from now on
+    pub fn open_with_cache_size(
+        block_device: Arc<dyn BlockDevice>,
+        cache_blocks: usize,
+    ) -> Arc<Mutex<Self>> {
+        block_cache_set_capacity(cache_blocks);
+        Self::open(block_device)
+    }
+    /// Look for inconsistencies between the bitmaps, the inodes and the
+    /// directory entries, changing nothing
+    ///
+    /// Every allocated data block must be held by exactly one inode, every
+    /// entry must name an allocated inode and the nlink of an inode must count
+    /// the entries naming it, `.` and `..` included.
+    pub fn check(&self) -> Vec<FsError> {
+        let mut errors = Vec::new();
+        let mut owners: BTreeMap<u32, u32> = BTreeMap::new();
+        let mut entries: BTreeMap<u32, u32> = BTreeMap::new();
+        let mut nlinks = Vec::new();
+        let data_area =
+            self.data_area_start_block..self.data_area_start_block + self.total_data_blocks;
+        for inode_id in 0..self.total_inodes {
+            if !self
+                .inode_bitmap
+                .is_allocated(&self.block_device, inode_id as usize)
+            {
+                continue;
+            }
+            let (block_id, block_offset) = self.get_disk_inode_pos(inode_id);
+            let (nlink, block_ids, dirents) =
+                get_block_cache(block_id as usize, Arc::clone(&self.block_device))
+                    .lock()
+                    .read(block_offset, |disk_inode: &DiskInode| {
+                        (
+                            disk_inode.nlink,
+                            disk_inode.all_block_ids(&self.block_device),
+                            self.dir_entries(disk_inode),
+                        )
+                    });
+            nlinks.push((inode_id, nlink));
+            for block_id in block_ids {
+                if !data_area.contains(&block_id)
+                    || !self.data_bitmap.is_allocated(
+                        &self.block_device,
+                        (block_id - self.data_area_start_block) as usize,
+                    )
+                {
+                    errors.push(FsError::FreeBlockInUse {
+                        inode: inode_id,
+                        block_id,
+                    });
+                }
+                if let Some(owner) = owners.insert(block_id, inode_id) {
+                    errors.push(FsError::SharedBlock {
+                        block_id,
+                        inodes: (owner, inode_id),
+                    });
+                }
+            }
+            for dirent in dirents {
+                let target = dirent.inode_number();
+                if target >= self.total_inodes
+                    || !self
+                        .inode_bitmap
+                        .is_allocated(&self.block_device, target as usize)
+                {
+                    errors.push(FsError::DanglingEntry {
+                        dir: inode_id,
+                        name: dirent.name().to_string(),
+                        inode: target,
+                    });
+                } else {
+                    *entries.entry(target).or_insert(0) += 1;
+                }
+            }
+        }
+        for block_id in data_area {
+            let bit = (block_id - self.data_area_start_block) as usize;
+            if self.data_bitmap.is_allocated(&self.block_device, bit)
+                && !owners.contains_key(&block_id)
+            {
+                errors.push(FsError::LeakedBlock(block_id));
+            }
+        }
+        for (inode_id, nlink) in nlinks {
+            let named = entries.get(&inode_id).copied().unwrap_or(0);
+            if nlink != named {
+                errors.push(FsError::LinkCount {
+                    inode: inode_id,
+                    nlink,
+                    entries: named,
+                });
+            }
+        }
+        errors
+    }
+    /// The used entries of a directory, none if the inode is a file
+    fn dir_entries(&self, disk_inode: &DiskInode) -> Vec<DirEntry> {
+        if !disk_inode.is_dir() {
+            return Vec::new();
+        }
+        (0..disk_inode.size as usize / DIRENT_SZ)
+            .map(|index| {
+                let mut dirent = DirEntry::empty();
+                disk_inode.read_at(index * DIRENT_SZ, dirent.as_bytes_mut(), &self.block_device);
+                dirent
+            })
+            .filter(|dirent| dirent.is_used())
+            .collect()
+    }
+    /// Write back the cached blocks of the filesystem and release them
+    pub fn flush(&self) {
+        block_cache_drop_device(&self.block_device);
+        self.block_device.flush();
+    }
     /// Get the root inode of the filesystem
     pub fn root_inode(efs: &Arc<Mutex<Self>>) -> Inode {
         let block_device = Arc::clone(&efs.lock().block_device);
@@ -144,30 +327,234 @@ impl EasyFileSystem {
     pub fn get_data_block_id(&self, data_block_id: u32) -> u32 {
         self.data_area_start_block + data_block_id
     }
-    /// Allocate a new inode
-    pub fn alloc_inode(&mut self) -> u32 {
-        self.inode_bitmap.alloc(&self.block_device).unwrap() as u32
+    /// Allocate a new inode, or None if all of them are taken
+    pub fn alloc_inode(&mut self) -> Option<u32> {
+        let inode_id = if self.deterministic {
+            self.inode_bitmap.alloc_lowest(&self.block_device)
+        } else {
+            self.inode_bitmap.alloc(&self.block_device)
+        };
+        inode_id.map(|inode_id| inode_id as u32)
+    }
+    /// Allocate the lowest free inode and data block from now on, rather than
+    /// searching on from the last allocation. The same operations on the same
+    /// image then always give the same bytes, whatever the search optimizations.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+    /// Look names up with ASCII case folded, so that names differing only by
+    /// case are the same name. Nothing about it is stored on disk: set it right
+    /// after create or open, before the first lookup, on every open alike.
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+    /// Whether the names `a` and `b` refer to the same entry
+    pub fn names_match(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+    /// Note a file of inode `inode_id` being opened
+    pub fn open_inode(&mut self, inode_id: u32) {
+        *self.open_files.entry(inode_id).or_insert(0) += 1;
+    }
+    /// Note a file of inode `inode_id` being closed, returning whether it
+    /// was the last one open
+    pub fn close_inode(&mut self, inode_id: u32) -> bool {
+        let count = self.open_files.get_mut(&inode_id).unwrap();
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        self.open_files.remove(&inode_id);
+        true
+    }
+    /// Whether any file of inode `inode_id` is open
+    pub fn inode_open(&self, inode_id: u32) -> bool {
+        self.open_files.contains_key(&inode_id)
     }
     /// Deallocate an inode
     pub fn dealloc_inode(&mut self, inode_id: u32) {
+        // a file made later in its place starts with no hint
+        self.advice.remove(&inode_id);
         self.inode_bitmap.dealloc(&self.block_device, inode_id as usize)
     }
-    /// Allocate a data block
-    pub fn alloc_data(&mut self) -> u32 {
-        self.data_bitmap.alloc(&self.block_device).unwrap() as u32 + self.data_area_start_block
+    /// Set the access pattern hint of inode `inode_id`, shared by every
+    /// handle and file of it
+    pub fn set_advice(&mut self, inode_id: u32, advice: FileAdvice) {
+        if advice == FileAdvice::Normal {
+            self.advice.remove(&inode_id);
+        } else {
+            self.advice.insert(inode_id, advice);
+        }
+    }
+    /// Get the access pattern hint of inode `inode_id`
+    pub fn advice(&self, inode_id: u32) -> FileAdvice {
+        self.advice
+            .get(&inode_id)
+            .copied()
+            .unwrap_or(FileAdvice::Normal)
     }
-    /// Deallocate a data block
-    pub fn dealloc_data(&mut self, block_id: u32) {
-        get_block_cache(block_id as usize, Arc::clone(&self.block_device))
+    /// Number of data blocks that are neither allocated nor reserved
+    pub fn free_data_blocks(&self) -> u32 {
+        self.free_data_blocks
+    }
+    /// Number of data blocks the filesystem has
+    pub fn total_data_blocks(&self) -> u32 {
+        self.total_data_blocks
+    }
+    /// Number of inodes not allocated, counted from the inode bitmap
+    pub fn free_inodes(&self) -> u32 {
+        self.total_inodes - self.inode_bitmap.allocated(&self.block_device) as u32
+    }
+    /// Number of inodes the filesystem has
+    pub fn total_inodes(&self) -> u32 {
+        self.total_inodes
+    }
+    /// Set aside `blocks` free data blocks for an upcoming allocation.
+    /// Returns false if there are not that many left.
+    pub fn reserve_data(&mut self, blocks: u32) -> bool {
+        if blocks > self.free_data_blocks {
+            return false;
+        }
+        self.free_data_blocks -= blocks;
+        self.reserved_data_blocks += blocks;
+        true
+    }
+    /// Give back `blocks` reserved data blocks that won't be allocated
+    pub fn cancel_reservation(&mut self, blocks: u32) {
+        self.reserved_data_blocks -= blocks;
+        self.free_data_blocks += blocks;
+    }
+    /// Allocate a data block out of a reservation.
+    /// Returns None, keeping the reservation, if there is none or the bitmap is full.
+    pub fn alloc_data(&mut self) -> Option<u32> {
+        if self.reserved_data_blocks == 0 {
+            return None;
+        }
+        let bit = if self.deterministic {
+            self.data_bitmap.alloc_lowest(&self.block_device)
+        } else {
+            self.data_bitmap.alloc(&self.block_device)
+        }?;
+        self.reserved_data_blocks -= 1;
+        let block_id = bit as u32 + self.data_area_start_block;
+        // dealloc_data cleared it already unless it was never in use, but
+        // create leaves the data area of a reused image as it was
+        self.zero_data(block_id);
+        Some(block_id)
+    }
+    /// Reserve and allocate `count` data blocks at once.
+    /// Returns None, with nothing allocated, if they are not all there.
+    pub fn alloc_data_blocks(&mut self, count: u32) -> Option<Vec<u32>> {
+        if !self.reserve_data(count) {
+            return None;
+        }
+        let mut blocks = Vec::new();
+        for allocated in 0..count {
+            match self.alloc_data() {
+                Some(block_id) => blocks.push(block_id),
+                None => {
+                    self.cancel_reservation(count - allocated);
+                    for block_id in blocks {
+                        self.dealloc_data(block_id);
+                    }
+                    return None;
+                }
+            }
+        }
+        Some(blocks)
+    }
+    /// Allocate `count` consecutive data blocks, returning the first of them
+    pub fn alloc_data_run(&mut self, count: u32) -> Option<u32> {
+        if !self.reserve_data(count) {
+            return None;
+        }
+        let data_area_blocks = get_block_cache(0, Arc::clone(&self.block_device))
+            .lock()
+            .read(0, |super_block: &SuperBlock| super_block.data_area_blocks);
+        self.reserved_data_blocks -= count;
+        match self.data_bitmap.alloc_run(
+            &self.block_device,
+            count as usize,
+            data_area_blocks as usize,
+        ) {
+            Some(start) => {
+                let start = start as u32 + self.data_area_start_block;
+                for block_id in start..start + count {
+                    self.zero_data(block_id);
+                }
+                Some(start)
+            }
+            None => {
+                // the free blocks are too scattered, give them back
+                self.free_data_blocks += count;
+                None
+            }
+        }
+    }
+    /// Move the blocks of an inode into one contiguous run, in the order they are read.
+    /// Returns false if the inode is busy or there is no run long enough.
+    ///
+    /// The copy is written back before the inode switches to it, and the switch
+    /// rewrites the single block holding the inode, so a crash leaves either the
+    /// old or the new layout, at worst leaking the blocks of an unfinished copy.
+    /// Open files are not tracked, so unless the caller vouches for `exclusive`
+    /// access the inode counts as busy while any inode of the filesystem is alive.
+    pub fn defrag_inode(efs: &Arc<Mutex<Self>>, inode_id: u32, exclusive: bool) -> bool {
+        if !exclusive && Arc::strong_count(efs) > 1 {
+            return false;
+        }
+        let mut fs = efs.lock();
+        let block_device = Arc::clone(&fs.block_device);
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let inode_block = get_block_cache(block_id as usize, Arc::clone(&block_device));
+        let blocks = inode_block
             .lock()
-            .modify(0, |data_block: &mut DataBlock| {
-                data_block.iter_mut().for_each(|p| {
-                    *p = 0;
-                })
+            .read(block_offset, |disk_inode: &DiskInode| {
+                DiskInode::total_blocks(disk_inode.size)
             });
+        if blocks == 0 {
+            return true;
+        }
+        let start = match fs.alloc_data_run(blocks) {
+            Some(start) => start,
+            None => return false,
+        };
+        let copy = inode_block
+            .lock()
+            .read(block_offset, |disk_inode: &DiskInode| {
+                disk_inode.relocated((start..start + blocks).collect(), &block_device)
+            });
+        block_cache_sync_all();
+        let old_blocks = inode_block
+            .lock()
+            .modify(block_offset, |disk_inode: &mut DiskInode| {
+                disk_inode.adopt_blocks(copy, &block_device)
+            });
+        block_cache_sync_all();
+        for block_id in old_blocks {
+            fs.dealloc_data(block_id);
+        }
+        block_cache_sync_all();
+        true
+    }
+    /// Deallocate a data block
+    pub fn dealloc_data(&mut self, block_id: u32) {
+        self.zero_data(block_id);
         self.data_bitmap.dealloc(
             &self.block_device,
             (block_id - self.data_area_start_block) as usize,
-        )
+        );
+        self.free_data_blocks += 1;
+    }
+    /// Fill a data block with zeros, so that no file can read what another
+    /// one left in it
+    fn zero_data(&self, block_id: u32) {
+        get_block_cache(block_id as usize, Arc::clone(&self.block_device))
+            .lock()
+            .modify(0, |data_block: &mut DataBlock| data_block.fill(0));
     }
 }
@@ -1,4 +1,4 @@
-use super::{get_block_cache, BlockDevice, BLOCK_SZ};
+use super::{block_cache_contains, get_block_cache, BlockDevice, BLOCK_SZ};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter, Result};
@@ -6,9 +6,9 @@ use core::fmt::{Debug, Formatter, Result};
 /// Magic number for sanity check
 const EFS_MAGIC: u32 = 0x3b800001;
 /// The max number of direct inodes
-const INODE_DIRECT_COUNT: usize = 27;
+const INODE_DIRECT_COUNT: usize = 19;
 /// The max length of inode name
-const NAME_LENGTH_LIMIT: usize = 27;
+pub const NAME_LENGTH_LIMIT: usize = 27;
 /// The max number of indirect1 inodes
 const INODE_INDIRECT1_COUNT: usize = BLOCK_SZ / 4;
 /// The max number of indirect2 inodes
@@ -18,8 +18,29 @@ const DIRECT_BOUND: usize = INODE_DIRECT_COUNT;
 /// The upper bound of indirect1 inode index
 const INDIRECT1_BOUND: usize = DIRECT_BOUND + INODE_INDIRECT1_COUNT;
 /// The upper bound of indirect2 inode index
-#[allow(unused)]
 const INDIRECT2_BOUND: usize = INDIRECT1_BOUND + INODE_INDIRECT2_COUNT;
+/// The max size of a file, with every indirect2 block in use
+pub const MAX_FILE_SIZE: usize = INDIRECT2_BOUND * BLOCK_SZ;
+/// The max number of hash blocks under a verity index block, which holds
+/// their ids in the first half and their hashes in the second
+const VERITY_HASH_BLOCKS: usize = INODE_INDIRECT1_COUNT / 2;
+/// The max number of data blocks of a verity file
+const VERITY_DATA_BLOCKS: usize = VERITY_HASH_BLOCKS * INODE_INDIRECT1_COUNT;
+
+/// Read entry `idx` of an indirect block, from the cache if it is there
+/// and from disk otherwise
+fn peek_indirect(block_id: u32, idx: usize, block_device: &Arc<dyn BlockDevice>) -> u32 {
+    if block_cache_contains(block_id as usize, block_device) {
+        return get_block_cache(block_id as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |indirect_block: &IndirectBlock| indirect_block[idx]);
+    }
+    let mut buf = [0u8; BLOCK_SZ];
+    block_device.read_block(block_id as usize, &mut buf);
+    let mut entry = [0u8; 4];
+    entry.copy_from_slice(&buf[idx * 4..idx * 4 + 4]);
+    u32::from_ne_bytes(entry)
+}
 
 /// Super block of a filesystem
 #[repr(C)]
@@ -70,10 +91,12 @@ impl SuperBlock {
 }
 
 /// Type of a disk inode
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum DiskInodeType {
     File,
     Directory,
+    /// A symbolic link, whose data is the path it points to
+    SymLink,
 }
 
 /// A indirect block
@@ -89,9 +112,47 @@ pub struct DiskInode {
     pub indirect1: u32,
     pub indirect2: u32,
     pub nlink: u32,
+    /// verity index block, 0 if the file is not sealed
+    pub verity: u32,
+    /// hash of the verity index block
+    pub verity_root: u32,
+    /// permission bits
+    pub mode: u32,
+    /// owner user id
+    pub uid: u32,
+    /// owner group id
+    pub gid: u32,
+    /// last access time in seconds
+    pub atime: u32,
+    /// last modification time in seconds
+    pub mtime: u32,
+    /// last status change time in seconds
+    pub ctime: u32,
     type_: DiskInodeType,
 }
 
+// The verity block and root, the mode, the owner and the three timestamps
+// took the place of eight direct blocks, so that a disk inode still fills
+// exactly a quarter of a block
+const _: () = assert!(core::mem::size_of::<DiskInode>() * 4 == BLOCK_SZ);
+
+/// 32-bit FNV-1a hash of a buffer.
+///
+/// It is not cryptographic: it catches corruption and careless tampering,
+/// not someone who forges blocks with a matching hash.
+fn fnv_hash(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c9dc5, |hash, &byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+/// Hash of a whole block on the device
+fn block_hash(block_id: u32, block_device: &Arc<dyn BlockDevice>) -> u32 {
+    get_block_cache(block_id as usize, Arc::clone(block_device))
+        .lock()
+        .read(0, |data_block: &DataBlock| fnv_hash(data_block))
+}
+
 impl DiskInode {
     /// Initialize a disk inode, as well as all direct inodes under it
     /// indirect1 and indirect2 block are allocated only when they are needed
@@ -101,8 +162,25 @@ impl DiskInode {
         self.indirect1 = 0;
         self.indirect2 = 0;
         self.nlink = 1;
+        self.verity = 0;
+        self.verity_root = 0;
+        self.mode = match type_ {
+            DiskInodeType::File => 0o644,
+            DiskInodeType::Directory => 0o755,
+            DiskInodeType::SymLink => 0o777,
+        };
+        self.uid = 0;
+        self.gid = 0;
+        self.atime = 0;
+        self.mtime = 0;
+        self.ctime = 0;
         self.type_ = type_;
     }
+    /// Record a change of the contents at time `now`
+    pub fn touch(&mut self, now: u32) {
+        self.mtime = now;
+        self.ctime = now;
+    }
     /// Whether this inode is a directory
     pub fn is_dir(&self) -> bool {
         self.type_ == DiskInodeType::Directory
@@ -111,6 +189,32 @@ impl DiskInode {
     pub fn is_file(&self) -> bool {
         self.type_ == DiskInodeType::File
     }
+    /// Whether this inode is a symbolic link
+    pub fn is_symlink(&self) -> bool {
+        self.type_ == DiskInodeType::SymLink
+    }
+    /// Whether this inode is sealed by verity
+    pub fn is_verity(&self) -> bool {
+        self.verity != 0
+    }
+    /// Get the number of blocks verity needs to seal this inode,
+    /// or None if it is too large
+    pub fn verity_blocks_needed(&self) -> Option<u32> {
+        let data_blocks = self.data_blocks() as usize;
+        if data_blocks > VERITY_DATA_BLOCKS {
+            return None;
+        }
+        let hash_blocks = (data_blocks + INODE_INDIRECT1_COUNT - 1) / INODE_INDIRECT1_COUNT;
+        Some(1 + hash_blocks as u32)
+    }
+    /// Get the number of blocks verity uses for this inode
+    pub fn verity_blocks(&self) -> u32 {
+        if self.is_verity() {
+            self.verity_blocks_needed().unwrap()
+        } else {
+            0
+        }
+    }
     /// Get the number of data blocks corresponding to size
     pub fn data_blocks(&self) -> u32 {
         Self::_data_blocks(self.size)
@@ -165,6 +269,21 @@ impl DiskInode {
                 })
         }
     }
+    /// Get id of block given inner id like `get_block_id`, but reading the
+    /// indirect blocks not in the cache from disk instead of caching them
+    pub fn peek_block_id(&self, inner_id: u32, block_device: &Arc<dyn BlockDevice>) -> u32 {
+        let inner_id = inner_id as usize;
+        if inner_id < INODE_DIRECT_COUNT {
+            self.direct[inner_id]
+        } else if inner_id < INDIRECT1_BOUND {
+            peek_indirect(self.indirect1, inner_id - INODE_DIRECT_COUNT, block_device)
+        } else {
+            let last = inner_id - INDIRECT1_BOUND;
+            let indirect1 =
+                peek_indirect(self.indirect2, last / INODE_INDIRECT1_COUNT, block_device);
+            peek_indirect(indirect1, last % INODE_INDIRECT1_COUNT, block_device)
+        }
+    }
     /// Inncrease the size of current disk inode
     pub fn increase_size(
         &mut self,
@@ -238,6 +357,74 @@ impl DiskInode {
                 }
             });
     }
+    /// Ids of the indirect blocks indexing the data blocks in use
+    pub fn index_block_ids(&self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        let data_blocks = self.data_blocks() as usize;
+        let mut v = Vec::new();
+        if data_blocks > INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+        }
+        if data_blocks > INDIRECT1_BOUND {
+            v.push(self.indirect2);
+            let used =
+                (data_blocks - INDIRECT1_BOUND + INODE_INDIRECT1_COUNT - 1) / INODE_INDIRECT1_COUNT;
+            get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    v.extend_from_slice(&indirect2[..used])
+                });
+        }
+        v
+    }
+    /// Decrease the size of current disk inode and return the blocks past the
+    /// new end, data and index blocks alike, that should be deallocated.
+    /// The rest of the new last data block is cleared to zero.
+    pub fn decrease_size(
+        &mut self,
+        new_size: u32,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Vec<u32> {
+        assert!(new_size <= self.size);
+        let current_blocks = self.data_blocks() as usize;
+        let total_blocks = Self::_data_blocks(new_size) as usize;
+        let tail = new_size as usize % BLOCK_SZ;
+        if tail != 0 {
+            let block_id = self.get_block_id(total_blocks as u32 - 1, block_device);
+            get_block_cache(block_id as usize, Arc::clone(block_device))
+                .lock()
+                .modify(0, |data_block: &mut DataBlock| data_block[tail..].fill(0));
+        }
+        let mut v: Vec<u32> = (total_blocks..current_blocks)
+            .map(|inner_id| self.get_block_id(inner_id as u32, block_device))
+            .collect();
+        // indirect1 block
+        if total_blocks <= INODE_DIRECT_COUNT && current_blocks > INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+            self.indirect1 = 0;
+        }
+        // low-level indirect1 blocks and the indirect2 block
+        if current_blocks > INDIRECT1_BOUND {
+            let indirect1_blocks = |data_blocks: usize| {
+                (data_blocks.max(INDIRECT1_BOUND) - INDIRECT1_BOUND + INODE_INDIRECT1_COUNT - 1)
+                    / INODE_INDIRECT1_COUNT
+            };
+            let (kept, used) = (
+                indirect1_blocks(total_blocks),
+                indirect1_blocks(current_blocks),
+            );
+            get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    v.extend_from_slice(&indirect2[kept..used])
+                });
+            if total_blocks <= INDIRECT1_BOUND {
+                v.push(self.indirect2);
+                self.indirect2 = 0;
+            }
+        }
+        self.size = new_size;
+        v
+    }
     /// Clear size to zero and return blocks that should be deallocated
     /// and clear the block contents to zero later
     pub fn clear_size(&mut self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
@@ -314,6 +501,161 @@ impl DiskInode {
         self.indirect2 = 0;
         v
     }
+    /// A copy of current disk inode pointing at the same blocks
+    fn shallow_copy(&self) -> DiskInode {
+        DiskInode {
+            size: self.size,
+            direct: self.direct,
+            indirect1: self.indirect1,
+            indirect2: self.indirect2,
+            nlink: self.nlink,
+            verity: self.verity,
+            verity_root: self.verity_root,
+            mode: self.mode,
+            uid: self.uid,
+            gid: self.gid,
+            atime: self.atime,
+            mtime: self.mtime,
+            ctime: self.ctime,
+            type_: self.type_,
+        }
+    }
+    /// Copy the data of current disk inode into `new_blocks`, which are used
+    /// in the order `increase_size` takes them, and return a disk inode
+    /// pointing at the copy. Current disk inode is left untouched.
+    pub fn relocated(
+        &self,
+        new_blocks: Vec<u32>,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> DiskInode {
+        let mut copy = self.shallow_copy();
+        copy.size = 0;
+        copy.direct = [0; INODE_DIRECT_COUNT];
+        copy.indirect1 = 0;
+        copy.indirect2 = 0;
+        copy.increase_size(self.size, new_blocks, block_device);
+        let mut data = [0u8; BLOCK_SZ];
+        for inner_id in 0..self.data_blocks() {
+            get_block_cache(
+                self.get_block_id(inner_id, block_device) as usize,
+                Arc::clone(block_device),
+            )
+            .lock()
+            .read(0, |data_block: &DataBlock| data.copy_from_slice(data_block));
+            get_block_cache(
+                copy.get_block_id(inner_id, block_device) as usize,
+                Arc::clone(block_device),
+            )
+            .lock()
+            .modify(0, |data_block: &mut DataBlock| {
+                data_block.copy_from_slice(&data)
+            });
+        }
+        copy
+    }
+    /// Switch to the blocks of `other`, a relocated copy of current disk inode,
+    /// and return the blocks used so far
+    pub fn adopt_blocks(
+        &mut self,
+        other: DiskInode,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Vec<u32> {
+        assert_eq!(self.size, other.size);
+        let old_blocks = self.shallow_copy().clear_size(block_device);
+        self.direct = other.direct;
+        self.indirect1 = other.indirect1;
+        self.indirect2 = other.indirect2;
+        old_blocks
+    }
+    /// Seal current disk inode with verity.
+    ///
+    /// `new_blocks` holds the index block followed by the hash blocks, as
+    /// many as `verity_blocks_needed` tells. Every data block is hashed into a
+    /// hash block, every hash block into the index block, and the hash of the
+    /// index block is kept in the inode.
+    pub fn enable_verity(&mut self, new_blocks: Vec<u32>, block_device: &Arc<dyn BlockDevice>) {
+        assert_eq!(Some(new_blocks.len() as u32), self.verity_blocks_needed());
+        let data_blocks = self.data_blocks();
+        let mut index: IndirectBlock = [0; INODE_INDIRECT1_COUNT];
+        for (i, &hash_block_id) in new_blocks[1..].iter().enumerate() {
+            let mut hashes: IndirectBlock = [0; INODE_INDIRECT1_COUNT];
+            for (j, hash) in hashes.iter_mut().enumerate() {
+                let inner_id = (i * INODE_INDIRECT1_COUNT + j) as u32;
+                if inner_id < data_blocks {
+                    *hash = block_hash(self.get_block_id(inner_id, block_device), block_device);
+                }
+            }
+            get_block_cache(hash_block_id as usize, Arc::clone(block_device))
+                .lock()
+                .modify(0, |hash_block: &mut IndirectBlock| *hash_block = hashes);
+            index[i] = hash_block_id;
+            index[VERITY_HASH_BLOCKS + i] = block_hash(hash_block_id, block_device);
+        }
+        get_block_cache(new_blocks[0] as usize, Arc::clone(block_device))
+            .lock()
+            .modify(0, |index_block: &mut IndirectBlock| *index_block = index);
+        self.verity = new_blocks[0];
+        self.verity_root = block_hash(self.verity, block_device);
+    }
+    /// Check a data block of a sealed inode against the hashes verity keeps
+    pub fn verify_block(&self, inner_id: u32, block_device: &Arc<dyn BlockDevice>) -> bool {
+        if block_hash(self.verity, block_device) != self.verity_root {
+            return false;
+        }
+        let inner_id = inner_id as usize;
+        let i = inner_id / INODE_INDIRECT1_COUNT;
+        let (hash_block_id, hash_block_hash) =
+            get_block_cache(self.verity as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |index_block: &IndirectBlock| {
+                    (index_block[i], index_block[VERITY_HASH_BLOCKS + i])
+                });
+        if block_hash(hash_block_id, block_device) != hash_block_hash {
+            return false;
+        }
+        let hash = get_block_cache(hash_block_id as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |hash_block: &IndirectBlock| {
+                hash_block[inner_id % INODE_INDIRECT1_COUNT]
+            });
+        let block_id = self.get_block_id(inner_id as u32, block_device);
+        block_hash(block_id, block_device) == hash
+    }
+    /// Ids of every block current disk inode holds: its data blocks, the
+    /// indirect blocks indexing them and its verity blocks
+    pub fn all_block_ids(&self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        let mut v: Vec<u32> = (0..self.data_blocks())
+            .map(|inner_id| self.get_block_id(inner_id, block_device))
+            .collect();
+        v.extend(self.index_block_ids(block_device));
+        if self.is_verity() {
+            let hash_blocks = self.verity_blocks() as usize - 1;
+            v.push(self.verity);
+            get_block_cache(self.verity as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |index_block: &IndirectBlock| {
+                    v.extend_from_slice(&index_block[..hash_blocks]);
+                });
+        }
+        v
+    }
+    /// Drop the verity seal and return the blocks it used
+    pub fn clear_verity(&mut self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        let mut v: Vec<u32> = Vec::new();
+        if !self.is_verity() {
+            return v;
+        }
+        let hash_blocks = self.verity_blocks() as usize - 1;
+        v.push(self.verity);
+        get_block_cache(self.verity as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |index_block: &IndirectBlock| {
+                v.extend_from_slice(&index_block[..hash_blocks]);
+            });
+        self.verity = 0;
+        self.verity_root = 0;
+        v
+    }
     /// Read data from current disk inode
     pub fn read_at(
         &self,
@@ -413,8 +755,10 @@ impl DirEntry {
             inode_number: 0,
         }
     }
-    /// Crate a directory entry from name and inode number
+    /// Crate a directory entry from name and inode number.
+    /// The name must be at most `NAME_LENGTH_LIMIT` bytes.
     pub fn new(name: &str, inode_number: u32) -> Self {
+        assert!(name.len() <= NAME_LENGTH_LIMIT);
         let mut bytes = [0u8; NAME_LENGTH_LIMIT + 1];
         bytes[..name.len()].copy_from_slice(name.as_bytes());
         Self {
@@ -435,6 +779,11 @@ impl DirEntry {
         let len = (0usize..).find(|i| self.name[*i] == 0).unwrap();
         core::str::from_utf8(&self.name[..len]).unwrap()
     }
+    /// Whether the entry is in use. Free slots are zeroed, and going by the
+    /// name rather than the inode number lets `..` refer to the root.
+    pub fn is_used(&self) -> bool {
+        self.name[0] != 0
+    }
     /// Get inode number of the entry
     pub fn inode_number(&self) -> u32 {
         self.inode_number
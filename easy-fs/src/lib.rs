@@ -15,8 +15,17 @@ mod vfs;
 /// Use a block size of 512 bytes
 pub const BLOCK_SZ: usize = 512;
 use bitmap::Bitmap;
-use block_cache::{block_cache_sync_all, get_block_cache};
+pub use block_cache::{block_cache_stats, BLOCK_CACHE_SIZE};
+use block_cache::{
+    block_cache_contains, block_cache_drop, block_cache_drop_device, block_cache_evict,
+    block_cache_prefetch, block_cache_set_capacity, block_cache_sync, block_cache_sync_all,
+    get_block_cache,
+};
 pub use block_dev::BlockDevice;
-pub use efs::EasyFileSystem;
+pub use efs::{EasyFileSystem, FsError};
 use layout::*;
-pub use vfs::{Inode, Stat};
+pub use layout::{DirEntry, DIRENT_SZ, MAX_FILE_SIZE, NAME_LENGTH_LIMIT};
+pub use vfs::{
+    fs_lock_count, resolve_path, set_clock, DirEntryInfo, DiskUsage, FileAdvice, Inode, Stat,
+    StatFs, StatMode, SYMLOOP_MAX,
+};
@@ -1,10 +1,14 @@
 use super::{
-    block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode, DiskInodeType,
-    EasyFileSystem, DIRENT_SZ,
+    block_cache_contains, block_cache_drop, block_cache_evict, block_cache_prefetch,
+    block_cache_sync, block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode,
+    DiskInodeType, EasyFileSystem, BLOCK_SZ, DIRENT_SZ, MAX_FILE_SIZE, NAME_LENGTH_LIMIT,
 };
 use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
 use spin::{Mutex, MutexGuard};
 
 /// Virtual filesystem layer over easy-fs
@@ -15,6 +19,130 @@ pub struct Inode {
     block_device: Arc<dyn BlockDevice>,
 }
 
+lazy_static! {
+    /// Where the time inodes are stamped with comes from
+    static ref CLOCK: Mutex<Option<fn() -> u32>> = Mutex::new(None);
+}
+
+/// Stamp inodes with the time in seconds `clock` gives from now on.
+/// Without a clock every timestamp is 0.
+pub fn set_clock(clock: fn() -> u32) {
+    *CLOCK.lock() = Some(clock);
+}
+
+/// Current time in seconds by the clock set, if any
+fn now() -> u32 {
+    CLOCK.lock().map_or(0, |clock| clock())
+}
+
+/// Times an inode took the filesystem lock
+static FS_LOCKS: AtomicUsize = AtomicUsize::new(0);
+
+/// How many times inodes have taken the filesystem lock so far, which tells
+/// how well their IO is batched
+pub fn fs_lock_count() -> usize {
+    FS_LOCKS.load(Ordering::Relaxed)
+}
+
+/// Number of blocks read ahead after a sequential read
+const READAHEAD_BLOCKS: usize = 4;
+
+/// Access pattern hint of an inode
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FileAdvice {
+    /// No special treatment
+    Normal,
+    /// Expect sequential access, read ahead aggressively
+    Sequential,
+    /// Expect random access, never read ahead
+    Random,
+}
+
+/// Most symbolic links followed while resolving one path, beyond which it is
+/// taken to loop
+pub const SYMLOOP_MAX: usize = 8;
+
+/// Whether `name` is one of the entries every directory starts with
+fn is_dot(name: &str) -> bool {
+    name == "." || name == ".."
+}
+
+/// Whether `name` can be given to a new dirent. An empty name marks a free
+/// slot, and a longer one would not fit.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !is_dot(name) && name.len() <= NAME_LENGTH_LIMIT
+}
+
+/// Stack the components of `path` on `names`, so that they pop off in order
+fn push_names(names: &mut Vec<String>, path: &str) {
+    names.extend(
+        path.rsplit('/')
+            .filter(|name| !name.is_empty())
+            .map(String::from),
+    );
+}
+
+/// Find an inode by `path` from the directory `start`, or from `root` if the
+/// path is absolute, following the symbolic links met on the way. A relative
+/// link target goes on from the directory holding the link, an absolute one
+/// from `root`. The last component is only followed with `follow_last`.
+///
+/// `..` goes back to the directory the walk came from, never above `start`,
+/// and `enter` may swap each inode found for another, such as the root of a
+/// filesystem mounted on it. Returns None for a dangling link, or once more
+/// than `SYMLOOP_MAX` links were followed, which a loop of links always
+/// gets to.
+pub fn resolve_path(
+    start: Arc<Inode>,
+    root: &Arc<Inode>,
+    path: &str,
+    follow_last: bool,
+    enter: impl Fn(Arc<Inode>) -> Arc<Inode>,
+) -> Option<Arc<Inode>> {
+    let mut walked = vec![if path.starts_with('/') {
+        root.clone()
+    } else {
+        start
+    }];
+    let mut names: Vec<String> = Vec::new();
+    push_names(&mut names, path);
+    let mut links = 0;
+    while let Some(name) = names.pop() {
+        match name.as_str() {
+            "." => {}
+            ".." => {
+                if walked.len() > 1 {
+                    walked.pop();
+                }
+            }
+            _ => {
+                let dir = walked.last().unwrap();
+                if !dir.is_dir() {
+                    return None;
+                }
+                let inode = dir.find(&name)?;
+                let target = match inode.readlink() {
+                    Some(target) if follow_last || !names.is_empty() => target,
+                    _ => {
+                        walked.push(enter(inode));
+                        continue;
+                    }
+                };
+                links += 1;
+                if links > SYMLOOP_MAX {
+                    return None;
+                }
+                if target.starts_with('/') {
+                    walked.truncate(1);
+                    walked[0] = root.clone();
+                }
+                push_names(&mut names, &target);
+            }
+        }
+    }
+    walked.pop()
+}
+
 impl Inode {
     /// Create a vfs inode
     pub fn new(
@@ -30,6 +158,11 @@ impl Inode {
             block_device,
         }
     }
+    /// Take the lock of the filesystem holding current inode
+    fn lock_fs(&self) -> MutexGuard<EasyFileSystem> {
+        FS_LOCKS.fetch_add(1, Ordering::Relaxed);
+        self.fs.lock()
+    }
     /// Call a function over a disk inode to read it
     fn read_disk_inode<V>(&self, f: impl FnOnce(&DiskInode) -> V) -> V {
         get_block_cache(self.block_id, Arc::clone(&self.block_device))
@@ -43,7 +176,12 @@ impl Inode {
             .modify(self.block_offset, f)
     }
     /// Find inode under a disk inode by name
-    fn find_inode_id(&self, name: &str, disk_inode: &DiskInode) -> Option<u32> {
+    fn find_inode_id(
+        &self,
+        name: &str,
+        disk_inode: &DiskInode,
+        fs: &EasyFileSystem,
+    ) -> Option<u32> {
         // assert it is a directory
         assert!(disk_inode.is_dir());
         let file_count = (disk_inode.size as usize) / DIRENT_SZ;
@@ -53,7 +191,7 @@ impl Inode {
                 disk_inode.read_at(DIRENT_SZ * i, dirent.as_bytes_mut(), &self.block_device,),
                 DIRENT_SZ,
             );
-            if dirent.name() == name {
+            if dirent.is_used() && fs.names_match(dirent.name(), name) {
                 return Some(dirent.inode_number() as u32);
             }
         }
@@ -61,9 +199,9 @@ impl Inode {
     }
     /// Find inode under current inode by name
     pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
-        let fs = self.fs.lock();
+        let fs = self.lock_fs();
         self.read_disk_inode(|disk_inode| {
-            self.find_inode_id(name, disk_inode).map(|inode_id| {
+            self.find_inode_id(name, disk_inode, &fs).map(|inode_id| {
                 let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
                 Arc::new(Self::new(
                     block_id,
@@ -74,24 +212,61 @@ impl Inode {
             })
         })
     }
-    /// Increase the size of a disk inode
+    /// Find inode under current inode by a `/` separated path.
+    /// Empty components are skipped, and every component but the last
+    /// must name a directory.
+    pub fn find_path(&self, path: &str) -> Option<Arc<Inode>> {
+        let mut inode: Option<Arc<Inode>> = None;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let dir = inode.as_deref().unwrap_or(self);
+            if !dir.is_dir() {
+                return None;
+            }
+            inode = Some(dir.find(name)?);
+        }
+        // an empty path names this inode itself
+        inode.or_else(|| {
+            Some(Arc::new(Self::new(
+                self.block_id as u32,
+                self.block_offset,
+                self.fs.clone(),
+                self.block_device.clone(),
+            )))
+        })
+    }
+    /// Like `find_path`, but following the symbolic links met on the way,
+    /// the last component included, as `resolve_path` does. Absolute link
+    /// targets start from the root of the filesystem.
+    pub fn find_path_follow(&self, path: &str) -> Option<Arc<Inode>> {
+        let root = Arc::new(EasyFileSystem::root_inode(&self.fs));
+        resolve_path(self.find_path("")?, &root, path, true, |inode| inode)
+    }
+    /// Increase the size of a disk inode.
+    /// Returns false and leaves the inode alone if the data blocks don't fit
+    /// or the file would outgrow the indirect2 block.
     fn increase_size(
         &self,
         new_size: u32,
         disk_inode: &mut DiskInode,
         fs: &mut MutexGuard<EasyFileSystem>,
-    ) {
+    ) -> bool {
         if new_size < disk_inode.size {
-            return;
+            return true;
         }
-        let blocks_needed = disk_inode.blocks_num_needed(new_size);
-        let mut v: Vec<u32> = Vec::new();
-        for _ in 0..blocks_needed {
-            v.push(fs.alloc_data());
+        if new_size as usize > MAX_FILE_SIZE {
+            return false;
         }
+        let blocks_needed = disk_inode.blocks_num_needed(new_size);
+        let v = match fs.alloc_data_blocks(blocks_needed) {
+            Some(v) => v,
+            None => return false,
+        };
         disk_inode.increase_size(new_size, v, &self.block_device);
+        true
     }
-    fn add_new_entry(&self, fs: &mut MutexGuard<EasyFileSystem>) -> usize {
+    /// Find a free slot for a dirent under current inode, growing the
+    /// directory by one if there is none. Returns None if the disk is full.
+    fn add_new_entry(&self, fs: &mut MutexGuard<EasyFileSystem>) -> Option<usize> {
         self.modify_disk_inode(|root_inode| {
             let file_count = (root_inode.size as usize) / DIRENT_SZ;
             let mut dirent = DirEntry::empty();
@@ -100,25 +275,60 @@ impl Inode {
                     root_inode.read_at(DIRENT_SZ * i, dirent.as_bytes_mut(), &self.block_device,),
                     DIRENT_SZ,
                 );
-                if dirent.inode_number() == 0 {
-                    return i;
+                if !dirent.is_used() {
+                    return Some(i);
                 }
             }
             let new_size = (file_count + 1) * DIRENT_SZ;
             // increase size
-            self.increase_size(new_size as u32, root_inode, fs);
-            file_count
+            if !self.increase_size(new_size as u32, root_inode, fs) {
+                return None;
+            }
+            Some(file_count)
         })
     }
     /// Create inode under current inode by name
     pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
-        let mut fs = self.fs.lock();
+        self.create_inode(name, DiskInodeType::File)
+    }
+    /// Create a directory under current inode by name
+    pub fn create_dir(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Directory)
+    }
+    /// Create a symbolic link to `target` under current inode by name.
+    /// The target is stored as it is and need not exist.
+    pub fn create_symlink(&self, name: &str, target: &str) -> Option<Arc<Inode>> {
+        if target.is_empty() {
+            return None;
+        }
+        let inode = self.create_inode(name, DiskInodeType::SymLink)?;
+        if inode.write_at(0, target.as_bytes()) != target.len() {
+            self.unlinkat(name);
+            return None;
+        }
+        Some(inode)
+    }
+    /// The path current inode points to, or None if it is not a symbolic link
+    pub fn readlink(&self) -> Option<String> {
+        let fs = self.lock_fs();
+        let size = self.read_disk_inode(|disk_inode| {
+            disk_inode.is_symlink().then_some(disk_inode.size as usize)
+        })?;
+        let mut target = vec![0u8; size];
+        self.read_locked(&fs, 0, &mut target)?;
+        String::from_utf8(target).ok()
+    }
+    fn create_inode(&self, name: &str, type_: DiskInodeType) -> Option<Arc<Inode>> {
+        if !valid_name(name) {
+            return None;
+        }
+        let mut fs = self.lock_fs();
         if self
             .modify_disk_inode(|root_inode| {
                 // assert it is a directory
                 assert!(root_inode.is_dir());
                 // has the file been created?
-                self.find_inode_id(name, root_inode)
+                self.find_inode_id(name, root_inode, &fs)
             })
             .is_some()
         {
@@ -126,69 +336,440 @@ impl Inode {
         }
         // create a new file
         // alloc a inode with an indirect block
-        let new_inode_id = fs.alloc_inode();
+        let new_inode_id = fs.alloc_inode()?;
         // initialize inode
         let (new_inode_block_id, new_inode_block_offset) = fs.get_disk_inode_pos(new_inode_id);
         get_block_cache(new_inode_block_id as usize, Arc::clone(&self.block_device))
             .lock()
             .modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
-                new_inode.initialize(DiskInodeType::File);
+                new_inode.initialize(type_);
+                let now = now();
+                new_inode.atime = now;
+                new_inode.touch(now);
             });
-        let index = self.add_new_entry(&mut fs);
+        let inode = Self::new(
+            new_inode_block_id,
+            new_inode_block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        );
+        // each directory links back to its parent
+        let is_dir = type_ == DiskInodeType::Directory;
+        if is_dir {
+            let parent_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+            if !inode.add_dots(&mut fs, parent_id) {
+                inode.release(&mut fs, new_inode_id);
+                block_cache_sync_all();
+                return None;
+            }
+        }
+        let index = match self.add_new_entry(&mut fs) {
+            Some(index) => index,
+            None => {
+                // hand back the inode and the block its dots took
+                inode.release(&mut fs, new_inode_id);
+                block_cache_sync_all();
+                return None;
+            }
+        };
         self.modify_disk_inode(|root_inode| {
             // write dirent
             let dirent = DirEntry::new(name, new_inode_id);
             root_inode.write_at(index * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+            root_inode.touch(now());
         });
-
-        let (block_id, block_offset) = fs.get_disk_inode_pos(new_inode_id);
+        if is_dir {
+            self.link();
+        }
         block_cache_sync_all();
         // return inode
-        Some(Arc::new(Self::new(
-            block_id,
-            block_offset,
-            self.fs.clone(),
-            self.block_device.clone(),
-        )))
+        Some(Arc::new(inode))
         // release efs lock automatically by compiler
     }
-    /// List inodes under current inode
-    pub fn ls(&self) -> Vec<String> {
-        let _fs = self.fs.lock();
+    /// Write the `.` and `..` entries of a fresh directory, `.` being one
+    /// more link to the directory itself. Returns false if the disk is full.
+    pub(crate) fn add_dots(&self, fs: &mut MutexGuard<EasyFileSystem>, parent_id: u32) -> bool {
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        for (name, id) in [(".", inode_id), ("..", parent_id)] {
+            let index = match self.add_new_entry(fs) {
+                Some(index) => index,
+                None => return false,
+            };
+            self.modify_disk_inode(|disk_inode| {
+                let dirent = DirEntry::new(name, id);
+                disk_inode.write_at(index * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+            });
+        }
+        self.link();
+        true
+    }
+    /// Whether two vfs inodes refer to the same disk inode
+    pub fn is_same(&self, other: &Inode) -> bool {
+        Arc::ptr_eq(&self.fs, &other.fs)
+            && self.block_id == other.block_id
+            && self.block_offset == other.block_offset
+    }
+    /// Number of references to the filesystem, one held by each live inode
+    pub fn fs_refs(&self) -> usize {
+        Arc::strong_count(&self.fs)
+    }
+    /// Write back and release the cached blocks of the whole filesystem
+    pub fn flush_fs(&self) {
+        self.lock_fs().flush();
+    }
+    /// How full the filesystem holding current inode is
+    pub fn statfs(&self) -> StatFs {
+        let fs = self.lock_fs();
+        StatFs {
+            block_size: BLOCK_SZ as u64,
+            blocks: fs.total_data_blocks() as u64,
+            free_blocks: fs.free_data_blocks() as u64,
+            inodes: fs.total_inodes() as u64,
+            free_inodes: fs.free_inodes() as u64,
+        }
+    }
+    /// Get the size in bytes of current inode
+    pub fn size(&self) -> usize {
+        self.read_disk_inode(|disk_inode| disk_inode.size as usize)
+    }
+    /// Whether current inode is a directory
+    pub fn is_dir(&self) -> bool {
+        self.read_disk_inode(|disk_inode| disk_inode.is_dir())
+    }
+    /// Whether current inode is a directory holding nothing but `.` and `..`
+    pub fn is_empty_dir(&self) -> bool {
+        let _fs = self.lock_fs();
+        self.holds_only_dots()
+    }
+    /// Like `is_empty_dir`, for callers already holding the fs lock
+    fn holds_only_dots(&self) -> bool {
         self.read_disk_inode(|disk_inode| {
             let file_count = (disk_inode.size as usize) / DIRENT_SZ;
-            let mut v: Vec<String> = Vec::new();
+            let mut dirent = DirEntry::empty();
+            disk_inode.is_dir()
+                && (0..file_count).all(|i| {
+                    assert_eq!(
+                        disk_inode.read_at(
+                            i * DIRENT_SZ,
+                            dirent.as_bytes_mut(),
+                            &self.block_device
+                        ),
+                        DIRENT_SZ,
+                    );
+                    !dirent.is_used() || is_dot(dirent.name())
+                })
+        })
+    }
+    /// Read the first used dirent of current directory at or after byte `offset`
+    pub fn read_dirent(&self, offset: usize) -> Option<DirEntryInfo> {
+        let fs = self.lock_fs();
+        let (index, inode_id, name) = self.read_disk_inode(|disk_inode| {
+            let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+            let mut dirent = DirEntry::empty();
+            for i in (offset + DIRENT_SZ - 1) / DIRENT_SZ..file_count {
+                assert_eq!(
+                    disk_inode.read_at(DIRENT_SZ * i, dirent.as_bytes_mut(), &self.block_device,),
+                    DIRENT_SZ,
+                );
+                if dirent.is_used() {
+                    return Some((i, dirent.inode_number(), String::from(dirent.name())));
+                }
+            }
+            None
+        })?;
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let (is_dir, is_symlink) =
+            get_block_cache(block_id as usize, Arc::clone(&self.block_device))
+                .lock()
+                .read(block_offset, |disk_inode: &DiskInode| {
+                    (disk_inode.is_dir(), disk_inode.is_symlink())
+                });
+        Some(DirEntryInfo {
+            offset: index * DIRENT_SZ,
+            inode_id,
+            name,
+            is_dir,
+            is_symlink,
+        })
+    }
+    /// Call `f` on every used entry under current inode in directory order,
+    /// reading one entry at a time. `f` runs under the fs lock, so it must
+    /// not call back into the filesystem.
+    pub fn for_each_entry(&self, mut f: impl FnMut(&DirEntry)) {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+            let mut dirent = DirEntry::empty();
             for i in 0..file_count {
-                let mut dirent = DirEntry::empty();
                 assert_eq!(
                     disk_inode.read_at(i * DIRENT_SZ, dirent.as_bytes_mut(), &self.block_device,),
                     DIRENT_SZ,
                 );
-                if dirent.inode_number() != 0 {
-                    v.push(String::from(dirent.name()));
+                if dirent.is_used() {
+                    f(&dirent);
                 }
             }
-            v
         })
     }
-    /// Read data from current inode
+    /// List inodes under current inode, `.` and `..` only if `dots` is set
+    pub fn ls(&self, dots: bool) -> Vec<String> {
+        let mut v: Vec<String> = Vec::new();
+        self.for_each_entry(|dirent| {
+            if dots || !is_dot(dirent.name()) {
+                v.push(String::from(dirent.name()));
+            }
+        });
+        v
+    }
+    /// Read data from current inode, nothing at all if it fails verity
     pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
-        let _fs = self.fs.lock();
-        self.read_disk_inode(|disk_inode| disk_inode.read_at(offset, buf, &self.block_device))
+        self.try_read_at(offset, buf).unwrap_or(0)
+    }
+    /// Read data from current inode.
+    ///
+    /// On a verity file every block touched is checked first, and None is
+    /// returned without reading anything if one doesn't match its hash.
+    pub fn try_read_at(&self, offset: usize, buf: &mut [u8]) -> Option<usize> {
+        let fs = self.lock_fs();
+        let size = self.read_locked(&fs, offset, buf)?;
+        self.touch_atime();
+        Some(size)
+    }
+    /// Read into the buffers back to back starting at `offset`, taking the
+    /// filesystem lock only once.
+    ///
+    /// Reading stops at the end of the file. On a verity file it also stops
+    /// before a block that doesn't match its hash, returning None only if
+    /// nothing was read before it.
+    pub fn try_read_all_at(&self, offset: usize, bufs: &mut [&mut [u8]]) -> Option<usize> {
+        let fs = self.lock_fs();
+        let mut pos = offset;
+        for buf in bufs.iter_mut() {
+            match self.read_locked(&fs, pos, buf) {
+                Some(0) => break,
+                Some(size) => pos += size,
+                None if pos > offset => break,
+                None => return None,
+            }
+        }
+        self.touch_atime();
+        Some(pos - offset)
+    }
+    /// Read from current inode with the filesystem lock held
+    fn read_locked(&self, fs: &EasyFileSystem, offset: usize, buf: &mut [u8]) -> Option<usize> {
+        let advice = fs.advice(fs.get_inode_id(self.block_id as u32, self.block_offset));
+        self.read_disk_inode(|disk_inode| {
+            if disk_inode.is_verity() {
+                let (start, end) = Self::block_range(disk_inode, offset, buf.len());
+                if !(start..end)
+                    .all(|inner_id| disk_inode.verify_block(inner_id as u32, &self.block_device))
+                {
+                    return None;
+                }
+            }
+            let size = disk_inode.read_at(offset, buf, &self.block_device);
+            if size > 0 && advice == FileAdvice::Sequential {
+                let next = (offset + size + BLOCK_SZ - 1) / BLOCK_SZ;
+                self.prefetch_blocks(disk_inode, next, next + READAHEAD_BLOCKS);
+            }
+            Some(size)
+        })
+    }
+    /// Stamp current inode as read now
+    fn touch_atime(&self) {
+        // leave the inode block clean unless the time moved on
+        let now = now();
+        if self.read_disk_inode(|disk_inode| disk_inode.atime) != now {
+            self.modify_disk_inode(|disk_inode| disk_inode.atime = now);
+        }
+    }
+    /// Whether current inode is sealed by verity and thus immutable
+    pub fn is_verity(&self) -> bool {
+        self.read_disk_inode(|disk_inode| disk_inode.is_verity())
+    }
+    /// Seal current file with verity, after which it can't be written and
+    /// reads check its blocks against hashes taken now.
+    ///
+    /// Returns false if it is not a file, is sealed already, is too large
+    /// for verity or the hash blocks don't fit on disk.
+    pub fn enable_verity(&self) -> bool {
+        let mut fs = self.lock_fs();
+        let enabled = self.modify_disk_inode(|disk_inode| {
+            if !disk_inode.is_file() || disk_inode.is_verity() {
+                return false;
+            }
+            let blocks_needed = match disk_inode.verity_blocks_needed() {
+                Some(blocks_needed) => blocks_needed,
+                None => return false,
+            };
+            let v = match fs.alloc_data_blocks(blocks_needed) {
+                Some(v) => v,
+                None => return false,
+            };
+            disk_inode.enable_verity(v, &self.block_device);
+            true
+        });
+        block_cache_sync_all();
+        enabled
+    }
+    /// Load data blocks `[start, end)` of a disk inode into the block cache
+    fn prefetch_blocks(&self, disk_inode: &DiskInode, start: usize, end: usize) {
+        let end = end.min(disk_inode.data_blocks() as usize);
+        for inner_id in start..end {
+            let block_id = disk_inode.get_block_id(inner_id as u32, &self.block_device);
+            block_cache_prefetch(block_id as usize, Arc::clone(&self.block_device));
+        }
+    }
+    /// Set the access pattern hint of current inode, which every handle to
+    /// it shares
+    pub fn set_advice(&self, advice: FileAdvice) {
+        let mut fs = self.lock_fs();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        fs.set_advice(inode_id, advice);
+    }
+    /// Get the access pattern hint of current inode
+    pub fn advice(&self) -> FileAdvice {
+        let fs = self.lock_fs();
+        fs.advice(fs.get_inode_id(self.block_id as u32, self.block_offset))
+    }
+    /// Inner block ids covering `[offset, offset + len)`, clamped to the file
+    fn block_range(disk_inode: &DiskInode, offset: usize, len: usize) -> (usize, usize) {
+        let start = offset / BLOCK_SZ;
+        let end = offset.saturating_add(len).saturating_add(BLOCK_SZ - 1) / BLOCK_SZ;
+        (start, end.min(disk_inode.data_blocks() as usize))
+    }
+    /// Prefetch the data blocks covering `[offset, offset + len)`
+    pub fn will_need(&self, offset: usize, len: usize) {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            let (start, end) = Self::block_range(disk_inode, offset, len);
+            self.prefetch_blocks(disk_inode, start, end);
+        })
+    }
+    /// Whether each data block covering `[offset, offset + len)` is in the
+    /// block cache, found out without loading any of them
+    pub fn cached_blocks(&self, offset: usize, len: usize) -> Vec<bool> {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            let (start, end) = Self::block_range(disk_inode, offset, len);
+            (start..end)
+                .map(|inner_id| {
+                    let block_id = disk_inode.peek_block_id(inner_id as u32, &self.block_device);
+                    block_cache_contains(block_id as usize, &self.block_device)
+                })
+                .collect()
+        })
+    }
+    /// Write back and drop every cached data block of current inode, leaving
+    /// those of other files alone. Returns how many were dropped.
+    pub fn drop_cache(&self) -> usize {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            (0..disk_inode.data_blocks())
+                .filter(|&inner_id| {
+                    let block_id = disk_inode.peek_block_id(inner_id, &self.block_device);
+                    block_cache_drop(block_id as usize, &self.block_device)
+                })
+                .count()
+        })
+    }
+    /// Drop the clean cached data blocks covering `[offset, offset + len)`
+    pub fn dont_need(&self, offset: usize, len: usize) {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            let (start, end) = Self::block_range(disk_inode, offset, len);
+            for inner_id in start..end {
+                let block_id = disk_inode.get_block_id(inner_id as u32, &self.block_device);
+                block_cache_evict(block_id as usize, &self.block_device);
+            }
+        })
+    }
+    /// Allocate the blocks backing `[offset, offset + len)`, growing the file
+    /// if the range ends past it. Returns false if they don't fit on disk.
+    pub fn allocate(&self, offset: usize, len: usize) -> bool {
+        let mut fs = self.lock_fs();
+        let end = match offset.checked_add(len) {
+            Some(end) if end <= u32::MAX as usize => end,
+            _ => return false,
+        };
+        let allocated = self.modify_disk_inode(|disk_inode| {
+            !disk_inode.is_verity() && self.increase_size(end as u32, disk_inode, &mut fs)
+        });
+        block_cache_sync_all();
+        allocated
     }
-    /// Write data to current inode
+    /// Make `[offset, offset + len)` read back as zeros while keeping its
+    /// blocks allocated, growing the file if the range ends past it.
+    /// Returns false if the file cannot grow that far.
+    pub fn zero_range(&self, offset: usize, len: usize) -> bool {
+        let mut fs = self.lock_fs();
+        let end = match offset.checked_add(len) {
+            Some(end) if end <= u32::MAX as usize => end,
+            _ => return false,
+        };
+        let zeroed = self.modify_disk_inode(|disk_inode| {
+            if disk_inode.is_verity() || !self.increase_size(end as u32, disk_inode, &mut fs) {
+                return false;
+            }
+            let zeros = [0u8; BLOCK_SZ];
+            let mut pos = offset;
+            while pos < end {
+                // up to the end of the block holding `pos`
+                let chunk = (end - pos).min(BLOCK_SZ - pos % BLOCK_SZ);
+                disk_inode.write_at(pos, &zeros[..chunk], &self.block_device);
+                pos += chunk;
+            }
+            disk_inode.touch(now());
+            true
+        });
+        block_cache_sync_all();
+        zeroed
+    }
+    /// Write data to current inode, nothing at all if it doesn't fit on disk
     pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
-        let mut fs = self.fs.lock();
-        let size = self.modify_disk_inode(|disk_inode| {
-            self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs);
-            disk_inode.write_at(offset, buf, &self.block_device)
+        self.write_all_at(offset, &[buf]).unwrap_or(0)
+    }
+    /// Write the buffers back to back starting at `offset`, taking the
+    /// filesystem lock only once.
+    ///
+    /// The data blocks for the whole write are reserved before any byte is
+    /// written, so it either happens completely or, returning None, not at all.
+    /// A verity file is never written.
+    pub fn write_all_at(&self, offset: usize, bufs: &[&[u8]]) -> Option<usize> {
+        self.write_all(Some(offset), bufs).map(|(_, size)| size)
+    }
+    /// Write the buffers back to back at the end of current inode, returning
+    /// the offset they start at and their total length.
+    ///
+    /// The end is looked up under the same lock as the write, so appends
+    /// racing each other never overwrite one another.
+    pub fn append_all(&self, bufs: &[&[u8]]) -> Option<(usize, usize)> {
+        self.write_all(None, bufs)
+    }
+    fn write_all(&self, offset: Option<usize>, bufs: &[&[u8]]) -> Option<(usize, usize)> {
+        let mut fs = self.lock_fs();
+        let len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let written = self.modify_disk_inode(|disk_inode| {
+            let offset = offset.unwrap_or(disk_inode.size as usize);
+            if disk_inode.is_verity()
+                || offset + len > MAX_FILE_SIZE
+                || !self.increase_size((offset + len) as u32, disk_inode, &mut fs)
+            {
+                return None;
+            }
+            let mut pos = offset;
+            for buf in bufs {
+                pos += disk_inode.write_at(pos, buf, &self.block_device);
+            }
+            disk_inode.touch(now());
+            Some((offset, pos - offset))
         });
         block_cache_sync_all();
-        size
+        written
     }
     /// Clear the data in current inode
     pub fn clear(&self) {
-        let mut fs = self.fs.lock();
+        let mut fs = self.lock_fs();
         self.modify_disk_inode(|disk_inode| {
             let size = disk_inode.size;
             let data_blocks_dealloc = disk_inode.clear_size(&self.block_device);
@@ -196,11 +777,36 @@ impl Inode {
             for data_block in data_blocks_dealloc.into_iter() {
                 fs.dealloc_data(data_block);
             }
+            for verity_block in disk_inode.clear_verity(&self.block_device) {
+                fs.dealloc_data(verity_block);
+            }
         });
         block_cache_sync_all();
     }
+    /// Cut the file down or grow it to `size` bytes, what it grows by
+    /// reading as zeros. Returns false for a verity file or if it cannot
+    /// grow that far.
+    pub fn truncate(&self, size: usize) -> bool {
+        let old_size = self.size();
+        if size >= old_size {
+            return self.zero_range(old_size, size - old_size);
+        }
+        let mut fs = self.lock_fs();
+        let truncated = self.modify_disk_inode(|disk_inode| {
+            if disk_inode.is_verity() {
+                return false;
+            }
+            for data_block in disk_inode.decrease_size(size as u32, &self.block_device) {
+                fs.dealloc_data(data_block);
+            }
+            disk_inode.touch(now());
+            true
+        });
+        block_cache_sync_all();
+        truncated
+    }
     pub fn stat(&self) -> Stat {
-        let fs = self.fs.lock();
+        let fs = self.lock_fs();
         self.read_disk_inode(|disk_inode| Stat {
             dev: 0,
             ino: fs.get_inode_id(self.block_id as u32, self.block_offset) as u64,
@@ -209,28 +815,112 @@ impl Inode {
                     StatMode::DIR
                 } else if disk_inode.is_file() {
                     StatMode::FILE
+                } else if disk_inode.is_symlink() {
+                    StatMode::LINK
                 } else {
                     StatMode::NULL
                 }
             },
             nlink: disk_inode.nlink,
-            pad: [0; 7],
+            size: disk_inode.size as u64,
+            blocks: Self::allocated_blocks(disk_inode),
+            perm: disk_inode.mode,
+            uid: disk_inode.uid,
+            gid: disk_inode.gid,
+            pad: 0,
+            atime: disk_inode.atime as u64,
+            mtime: disk_inode.mtime as u64,
+            ctime: disk_inode.ctime as u64,
+        })
+    }
+    /// Set the permission bits of current inode
+    pub fn chmod(&self, mode: u32) {
+        let _fs = self.lock_fs();
+        self.modify_disk_inode(|disk_inode| disk_inode.mode = mode & 0o7777);
+        block_cache_sync_all();
+    }
+    /// Set the owner of current inode, keeping the ids given as None
+    pub fn chown(&self, uid: Option<u32>, gid: Option<u32>) {
+        let _fs = self.lock_fs();
+        self.modify_disk_inode(|disk_inode| {
+            if let Some(uid) = uid {
+                disk_inode.uid = uid;
+            }
+            if let Some(gid) = gid {
+                disk_inode.gid = gid;
+            }
+        });
+        block_cache_sync_all();
+    }
+    /// Write back the cached blocks of current inode, leaving those of the
+    /// rest of the filesystem alone
+    pub fn fsync(&self) {
+        let _fs = self.lock_fs();
+        let block_ids = self.read_disk_inode(|disk_inode| {
+            let mut block_ids: Vec<u32> = (0..disk_inode.data_blocks())
+                .map(|inner_id| disk_inode.get_block_id(inner_id, &self.block_device))
+                .collect();
+            block_ids.extend(disk_inode.index_block_ids(&self.block_device));
+            block_ids
+        });
+        for block_id in block_ids {
+            block_cache_sync(block_id as usize, &self.block_device);
+        }
+        block_cache_sync(self.block_id, &self.block_device);
+        self.block_device.flush();
+    }
+    /// Ids of the blocks holding the data of current inode, in file order
+    pub fn block_ids(&self) -> Vec<u32> {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            (0..disk_inode.data_blocks())
+                .map(|inner_id| disk_inode.get_block_id(inner_id, &self.block_device))
+                .collect()
+        })
+    }
+    /// Blocks held by a disk inode, including index and verity blocks
+    fn allocated_blocks(disk_inode: &DiskInode) -> u64 {
+        (DiskInode::total_blocks(disk_inode.size) + disk_inode.verity_blocks()) as u64
+    }
+    /// Blocks held by current inode, and how many of them it owns alone
+    pub fn usage(&self) -> DiskUsage {
+        let _fs = self.lock_fs();
+        self.read_disk_inode(|disk_inode| {
+            let total_blocks = Self::allocated_blocks(disk_inode);
+            // easy-fs has no reflinks, so no block is shared between inodes
+            DiskUsage {
+                total_blocks,
+                unique_blocks: total_blocks,
+            }
         })
     }
     fn link(&self) {
         self.modify_disk_inode(|disk_inode| {
             disk_inode.nlink += 1;
+            disk_inode.ctime = now();
         });
     }
     fn unlink(&self) -> u32 {
         self.modify_disk_inode(|disk_inode| {
             disk_inode.nlink -= 1;
+            disk_inode.ctime = now();
             disk_inode.nlink
         })
     }
     pub fn linkat(&self, old_name: &str, new_name: &str) -> isize {
-        let mut fs = self.fs.lock();
-        let id = self.read_disk_inode(|disk_inode| self.find_inode_id(old_name, disk_inode));
+        if is_dot(old_name) || !valid_name(new_name) {
+            return -1;
+        }
+        let mut fs = self.lock_fs();
+        let (id, taken) = self.read_disk_inode(|disk_inode| {
+            (
+                self.find_inode_id(old_name, disk_inode, &fs),
+                self.find_dirent(new_name, disk_inode, &fs).is_some(),
+            )
+        });
+        if taken {
+            return -1;
+        }
         if let Some(inode_id) = id {
             let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
             let inode = Arc::new(Self::new(
@@ -239,10 +929,19 @@ impl Inode {
                 self.fs.clone(),
                 self.block_device.clone(),
             ));
-            let index = self.add_new_entry(&mut fs);
+            // a second name for a directory, its parent included, could
+            // make a cycle out of the tree
+            if inode.is_dir() {
+                return -1;
+            }
+            let index = match self.add_new_entry(&mut fs) {
+                Some(index) => index,
+                None => return -1,
+            };
             self.modify_disk_inode(|root_inode| {
                 let dirent = DirEntry::new(new_name, inode_id);
                 root_inode.write_at(index * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+                root_inode.touch(now());
             });
             inode.link();
             block_cache_sync_all();
@@ -252,8 +951,11 @@ impl Inode {
         }
     }
     pub fn unlinkat(&self, name: &str) -> isize {
-        let mut fs = self.fs.lock();
-        let id = self.read_disk_inode(|disk_inode| self.find_inode_id(name, disk_inode));
+        if is_dot(name) {
+            return -1;
+        }
+        let mut fs = self.lock_fs();
+        let id = self.read_disk_inode(|disk_inode| self.find_inode_id(name, disk_inode, &fs));
         if let Some(inode_id) = id {
             let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
             let inode = Arc::new(Self::new(
@@ -262,6 +964,10 @@ impl Inode {
                 self.fs.clone(),
                 self.block_device.clone(),
             ));
+            // the children of a directory would be left without a parent
+            if inode.is_dir() && !inode.holds_only_dots() {
+                return -1;
+            }
             self.modify_disk_inode(|root_inode| {
                 let file_count = (root_inode.size as usize) / DIRENT_SZ;
                 let mut dirent = DirEntry::empty();
@@ -274,21 +980,19 @@ impl Inode {
                         ),
                         DIRENT_SZ,
                     );
-                    if dirent.name() == name {
+                    if dirent.is_used() && fs.names_match(dirent.name(), name) {
                         root_inode.write_at(DIRENT_SZ * i, &[0; DIRENT_SZ], &self.block_device);
                     }
                 }
+                root_inode.touch(now());
             });
-            if inode.unlink() == 0 {
-                inode.modify_disk_inode(|disk_inode| {
-                    let size = disk_inode.size;
-                    let data_blocks_dealloc = disk_inode.clear_size(&self.block_device);
-                    assert!(data_blocks_dealloc.len() == DiskInode::total_blocks(size) as usize);
-                    for data_block in data_blocks_dealloc.into_iter() {
-                        fs.dealloc_data(data_block);
-                    }
-                });
-                fs.dealloc_inode(inode_id);
+            if inode.is_dir() {
+                self.unlink();
+                inode.unlink();
+            }
+            // an open file keeps the blocks until it is closed
+            if inode.unlink() == 0 && !fs.inode_open(inode_id) {
+                inode.release(&mut fs, inode_id);
             }
             block_cache_sync_all();
             0
@@ -296,6 +1000,194 @@ impl Inode {
             -1
         }
     }
+    /// Note current inode being opened as a file, which keeps it and its data
+    /// around after its last link goes until it is closed again
+    pub fn open(&self) {
+        let mut fs = self.lock_fs();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        fs.open_inode(inode_id);
+    }
+    /// Note a file of current inode being closed, which releases the inode if
+    /// it was the last file open and no link is left
+    pub fn close(&self) {
+        let mut fs = self.lock_fs();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        if fs.close_inode(inode_id) && self.read_disk_inode(|disk_inode| disk_inode.nlink) == 0 {
+            self.release(&mut fs, inode_id);
+            block_cache_sync_all();
+        }
+    }
+    /// Free the data blocks and the disk inode of an inode without links
+    fn release(&self, fs: &mut MutexGuard<EasyFileSystem>, inode_id: u32) {
+        self.modify_disk_inode(|disk_inode| {
+            let size = disk_inode.size;
+            let data_blocks_dealloc = disk_inode.clear_size(&self.block_device);
+            assert!(data_blocks_dealloc.len() == DiskInode::total_blocks(size) as usize);
+            for data_block in data_blocks_dealloc.into_iter() {
+                fs.dealloc_data(data_block);
+            }
+            for verity_block in disk_inode.clear_verity(&self.block_device) {
+                fs.dealloc_data(verity_block);
+            }
+        });
+        fs.dealloc_inode(inode_id);
+    }
+    /// Find the slot and inode id of a dirent under a disk inode by name
+    fn find_dirent(
+        &self,
+        name: &str,
+        disk_inode: &DiskInode,
+        fs: &EasyFileSystem,
+    ) -> Option<(usize, u32)> {
+        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let mut dirent = DirEntry::empty();
+        for i in 0..file_count {
+            assert_eq!(
+                disk_inode.read_at(DIRENT_SZ * i, dirent.as_bytes_mut(), &self.block_device,),
+                DIRENT_SZ,
+            );
+            if dirent.is_used() && fs.names_match(dirent.name(), name) {
+                return Some((i, dirent.inode_number()));
+            }
+        }
+        None
+    }
+    /// Rename an entry under current inode, replacing the target if it exists
+    ///
+    /// The target dirent is overwritten in place under the fs lock, so it
+    /// always names either the old or the new inode. With `sync` set, all
+    /// cached data is written back before the dirent is touched.
+    pub fn rename(&self, old_name: &str, new_name: &str, sync: bool) -> isize {
+        self.rename_entry(old_name, new_name, sync, true)
+    }
+    /// Rename an entry under current inode like `rename`, but fail if the
+    /// target exists. The check and the rename happen under one fs lock.
+    pub fn rename_noreplace(&self, old_name: &str, new_name: &str, sync: bool) -> isize {
+        self.rename_entry(old_name, new_name, sync, false)
+    }
+    fn rename_entry(&self, old_name: &str, new_name: &str, sync: bool, replace: bool) -> isize {
+        if is_dot(old_name) || !valid_name(new_name) {
+            return -1;
+        }
+        let mut fs = self.lock_fs();
+        let (old, new) = self.read_disk_inode(|root_inode| {
+            (
+                self.find_dirent(old_name, root_inode, &fs),
+                self.find_dirent(new_name, root_inode, &fs),
+            )
+        });
+        let (old_index, inode_id) = match old {
+            Some(old) => old,
+            None => return -1,
+        };
+        // names differing only by case find the same entry, which is renamed in place
+        let new = new.filter(|&(new_index, _)| new_index != old_index);
+        // the inode the new name refers to, which loses a link
+        let target = match new {
+            Some((_, target_id)) => {
+                if !replace {
+                    return -1;
+                }
+                // both names are links to the same inode
+                if target_id == inode_id {
+                    return 0;
+                }
+                let inode_at = |inode_id| {
+                    let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+                    Self::new(
+                        block_id,
+                        block_offset,
+                        self.fs.clone(),
+                        self.block_device.clone(),
+                    )
+                };
+                let (source, target) = (inode_at(inode_id), inode_at(target_id));
+                // a directory only replaces an empty directory, a file only a file
+                if source.is_dir() != target.is_dir()
+                    || (target.is_dir() && !target.holds_only_dots())
+                {
+                    return -1;
+                }
+                Some((target_id, target))
+            }
+            None => None,
+        };
+        if sync {
+            block_cache_sync_all();
+        }
+        self.modify_disk_inode(|root_inode| {
+            let dirent = DirEntry::new(new_name, inode_id);
+            match new {
+                Some((new_index, _)) => {
+                    root_inode.write_at(
+                        new_index * DIRENT_SZ,
+                        dirent.as_bytes(),
+                        &self.block_device,
+                    );
+                    root_inode.write_at(old_index * DIRENT_SZ, &[0; DIRENT_SZ], &self.block_device);
+                }
+                // a fresh name is written over the old one in place
+                None => {
+                    root_inode.write_at(
+                        old_index * DIRENT_SZ,
+                        dirent.as_bytes(),
+                        &self.block_device,
+                    );
+                }
+            }
+        });
+        if let Some((target_id, target)) = target {
+            if target.is_dir() {
+                self.unlink();
+                target.unlink();
+            }
+            if target.unlink() == 0 && !fs.inode_open(target_id) {
+                target.release(&mut fs, target_id);
+            }
+        }
+        block_cache_sync_all();
+        0
+    }
+}
+
+/// A used entry of a directory
+pub struct DirEntryInfo {
+    /// byte offset of the entry within the directory file
+    pub offset: usize,
+    /// inode id the entry refers to
+    pub inode_id: u32,
+    /// name of the entry
+    pub name: String,
+    /// whether the entry refers to a directory
+    pub is_dir: bool,
+    /// whether the entry refers to a symbolic link
+    pub is_symlink: bool,
+}
+
+/// Capacity of a filesystem and what of it is free
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct StatFs {
+    /// size of a block in bytes
+    pub block_size: u64,
+    /// number of data blocks
+    pub blocks: u64,
+    /// number of data blocks free for writes
+    pub free_blocks: u64,
+    /// number of inodes
+    pub inodes: u64,
+    /// number of inodes free
+    pub free_inodes: u64,
+}
+
+/// Disk usage of a inode
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// number of blocks held, including index and verity blocks
+    pub total_blocks: u64,
+    /// number of those blocks no other inode refers to
+    pub unique_blocks: u64,
 }
 
 /// The stat of a inode
@@ -310,18 +1202,58 @@ pub struct Stat {
     pub mode: StatMode,
     /// number of hard links
     pub nlink: u32,
+    /// size in bytes
+    pub size: u64,
+    /// number of blocks allocated, including index and verity blocks
+    pub blocks: u64,
+    /// permission bits
+    pub perm: u32,
+    /// owner user id
+    pub uid: u32,
+    /// owner group id
+    pub gid: u32,
     /// unused pad
-    pad: [u64; 7],
+    pad: u32,
+    /// last access time in seconds
+    pub atime: u64,
+    /// last modification time in seconds
+    pub mtime: u64,
+    /// last status change time in seconds
+    pub ctime: u64,
+}
+
+impl Stat {
+    /// The stat of an open file with no inode behind it, such as a pipe,
+    /// holding `size` bytes
+    pub fn anonymous(size: u64) -> Self {
+        Self {
+            dev: 0,
+            ino: 0,
+            mode: StatMode::NULL,
+            nlink: 1,
+            size,
+            blocks: 0,
+            perm: 0o600,
+            uid: 0,
+            gid: 0,
+            pad: 0,
+            atime: 0,
+            mtime: 0,
+            ctime: 0,
+        }
+    }
 }
 
 bitflags! {
     /// The mode of a inode
-    /// whether a directory or a file
+    /// whether a directory, a file or a symbolic link
     pub struct StatMode: u32 {
         const NULL  = 0;
         /// directory
         const DIR   = 0o040000;
         /// ordinary regular file
         const FILE  = 0o100000;
+        /// symbolic link
+        const LINK  = 0o120000;
     }
 }
@@ -3,12 +3,30 @@
 pub const USER_STACK_SIZE: usize = 4096 * 2;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 20;
 pub const KERNEL_HEAP_SIZE: usize = 0x20_0000;
+/// Kernel heap usage in bytes above which a warning is logged
+pub const KERNEL_HEAP_HIGH_WATER: usize = KERNEL_HEAP_SIZE / 4 * 3;
 pub const MEMORY_END: usize = 0x88000000;
+/// number of memory nodes the physical frames are split into
+pub const MEMORY_NODES: usize = 1;
+/// size of the ram disk in blocks
+pub const RAM_DISK_BLOCKS: usize = 4096;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
 pub const MAX_SYSCALL_NUM: usize = 500;
+/// number of recent syscalls kept in the trace of a task
+pub const SYSCALL_TRACE_LEN: usize = 16;
+/// number of fd slots a new process starts with
+pub const FD_TABLE_INIT_CAP: usize = 16;
+/// max number of fds a process may have open
+pub const RLIMIT_NOFILE: usize = 1024;
+/// max length of a path from user space, including the terminating NUL
+pub const PATH_MAX: usize = 4096;
+/// max bytes the arguments and environment of exec may take on the new user stack
+pub const ARG_MAX: usize = USER_STACK_SIZE / 2;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+/// number of harts the scheduler may dispatch tasks to
+pub const HART_NUM: usize = 1;
 pub const CLOCK_FREQ: usize = 12500000;
 pub const MMIO: &[(usize, usize)] = &[(0x10001000, 0x1000)];
@@ -1,12 +1,32 @@
+mod ram_disk;
 mod virtio_blk;
 
+use crate::config::RAM_DISK_BLOCKS;
 use alloc::sync::Arc;
-use easy_fs::BlockDevice;
+use easy_fs::{BlockDevice, EasyFileSystem};
 use lazy_static::*;
+use ram_disk::RamDisk;
 type BlockDeviceImpl = virtio_blk::VirtIOBlock;
 
 lazy_static! {
     pub static ref BLOCK_DEVICE: Arc<dyn BlockDevice> = Arc::new(BlockDeviceImpl::new());
+    /// A scratch device holding an empty easy-fs, formatted at first use
+    pub static ref RAM_DISK: Arc<dyn BlockDevice> = {
+        let ram_disk: Arc<dyn BlockDevice> = Arc::new(RamDisk::new(RAM_DISK_BLOCKS));
+        EasyFileSystem::create(ram_disk.clone(), RAM_DISK_BLOCKS as u32, 1);
+        ram_disk
+    };
+}
+
+/// Get a block device by its device number
+///
+/// Device 0 is the disk holding the root filesystem, device 1 is the ram disk.
+pub fn get_block_device(dev: usize) -> Option<Arc<dyn BlockDevice>> {
+    match dev {
+        0 => Some(BLOCK_DEVICE.clone()),
+        1 => Some(RAM_DISK.clone()),
+        _ => None,
+    }
 }
 
 #[allow(unused)]
@@ -0,0 +1,38 @@
+use super::BlockDevice;
+use crate::config::PAGE_SIZE;
+use crate::mm::{frame_alloc, FrameTracker};
+use alloc::vec::Vec;
+use easy_fs::BLOCK_SZ;
+
+/// Number of blocks stored in one frame
+const BLOCKS_PER_FRAME: usize = PAGE_SIZE / BLOCK_SZ;
+
+/// A block device backed by physical frames
+pub struct RamDisk {
+    frames: Vec<FrameTracker>,
+}
+
+impl RamDisk {
+    /// Create a zeroed ram disk of `blocks` blocks
+    pub fn new(blocks: usize) -> Self {
+        let frames = (0..(blocks + BLOCKS_PER_FRAME - 1) / BLOCKS_PER_FRAME)
+            .map(|_| frame_alloc().unwrap())
+            .collect();
+        Self { frames }
+    }
+    fn block(&self, block_id: usize) -> &'static mut [u8] {
+        let offset = (block_id % BLOCKS_PER_FRAME) * BLOCK_SZ;
+        &mut self.frames[block_id / BLOCKS_PER_FRAME]
+            .ppn
+            .get_bytes_array()[offset..offset + BLOCK_SZ]
+    }
+}
+
+impl BlockDevice for RamDisk {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        buf.copy_from_slice(self.block(block_id));
+    }
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.block(block_id).copy_from_slice(buf);
+    }
+}
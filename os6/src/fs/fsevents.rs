@@ -0,0 +1,159 @@
+//! A global stream of filesystem events, for watching the whole filesystem
+
+use super::{File, Stat};
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use crate::syscall::errno::EINVAL;
+use crate::task::current_task;
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use bitflags::*;
+use lazy_static::*;
+
+/// Most events a stream holds before it starts dropping them
+const FSEVENT_QUEUE_LEN: usize = 64;
+
+bitflags! {
+    /// Kinds of filesystem events
+    pub struct FsEventMask: u32 {
+        /// A file or directory was created
+        const CREATE = 1 << 0;
+        /// A name was removed
+        const UNLINK = 1 << 1;
+        /// A file was renamed
+        const RENAME = 1 << 2;
+        /// A file was written to
+        const WRITE = 1 << 3;
+        /// Events after the ones before this were dropped
+        const OVERFLOW = 1 << 31;
+    }
+}
+
+/// An event read from a stream
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FsEvent {
+    /// inode the event happened to, 0 for an overflow
+    pub ino: u64,
+    /// a single kind of event
+    pub mask: u32,
+    /// process that caused it, 0 for an overflow
+    pub pid: u32,
+}
+
+/// The queue of events in 'UPSafeCell'
+struct FsEventQueue {
+    events: VecDeque<FsEvent>,
+    /// some events didn't fit into the queue
+    overflowed: bool,
+}
+
+/// An open stream of every event of the kinds in `mask`
+pub struct FsEventStream {
+    mask: FsEventMask,
+    queue: UPSafeCell<FsEventQueue>,
+}
+
+lazy_static! {
+    /// Every open stream, dropped once its last fd is closed
+    static ref FSEVENT_STREAMS: UPSafeCell<Vec<Weak<FsEventStream>>> =
+        unsafe { UPSafeCell::new(Vec::new()) };
+}
+
+/// Open a stream receiving the events in `mask` from now on
+pub fn fsevents_open(mask: FsEventMask) -> Arc<FsEventStream> {
+    let stream = Arc::new(FsEventStream {
+        mask,
+        queue: unsafe {
+            UPSafeCell::new(FsEventQueue {
+                events: VecDeque::new(),
+                overflowed: false,
+            })
+        },
+    });
+    FSEVENT_STREAMS
+        .exclusive_access()
+        .push(Arc::downgrade(&stream));
+    stream
+}
+
+/// Report an event on inode `ino` by the current process to the streams watching for it
+pub fn fsevent(mask: FsEventMask, ino: u64) {
+    let mut streams = FSEVENT_STREAMS.exclusive_access();
+    streams.retain(|stream| stream.strong_count() > 0);
+    if streams.is_empty() {
+        return;
+    }
+    let pid = current_task().map_or(0, |task| task.getpid() as u32);
+    for stream in streams.iter().filter_map(|stream| stream.upgrade()) {
+        if !stream.mask.contains(mask) {
+            continue;
+        }
+        let mut queue = stream.queue.exclusive_access();
+        if queue.events.len() < FSEVENT_QUEUE_LEN {
+            queue.events.push_back(FsEvent {
+                ino,
+                mask: mask.bits(),
+                pid,
+            });
+        } else {
+            queue.overflowed = true;
+        }
+    }
+}
+
+impl File for FsEventStream {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    /// Read as many whole events as fit, or none if there are none queued
+    fn read(&self, buf: UserBuffer) -> isize {
+        let size = core::mem::size_of::<FsEvent>();
+        let mut queue = self.queue.exclusive_access();
+        if queue.events.is_empty() && !queue.overflowed {
+            return 0;
+        }
+        if buf.len() < size {
+            return -EINVAL;
+        }
+        let mut records: Vec<u8> = Vec::new();
+        while records.len() + size <= buf.len() {
+            // the overflow comes after everything that made it into the queue
+            let event = match queue.events.pop_front() {
+                Some(event) => event,
+                None if queue.overflowed => {
+                    queue.overflowed = false;
+                    FsEvent {
+                        ino: 0,
+                        mask: FsEventMask::OVERFLOW.bits(),
+                        pid: 0,
+                    }
+                }
+                None => break,
+            };
+            records.extend_from_slice(unsafe {
+                core::slice::from_raw_parts(&event as *const _ as *const u8, size)
+            });
+        }
+        for (dst, src) in buf.into_iter().zip(records.iter()) {
+            unsafe {
+                *dst = *src;
+            }
+        }
+        records.len() as isize
+    }
+    fn write(&self, _buf: UserBuffer) -> isize {
+        -1
+    }
+    /// A stream has no inode, only the events waiting in it
+    fn stat(&self) -> Stat {
+        let queue = self.queue.exclusive_access();
+        // the overflow is read as one more event
+        let queued = queue.events.len() + queue.overflowed as usize;
+        Stat::anonymous((queued * core::mem::size_of::<FsEvent>()) as u64)
+    }
+}
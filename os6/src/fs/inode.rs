@@ -1,11 +1,22 @@
-use super::{File, Stat};
+use super::fsevents::{fsevent, FsEventMask};
+use super::mount::{find_parent, find_path, find_path_nofollow};
+use super::{File, SeekFrom, Stat, NAME_MAX};
 use crate::drivers::BLOCK_DEVICE;
 use crate::mm::UserBuffer;
 use crate::sync::UPSafeCell;
+use crate::syscall::errno::{
+    EEXIST, EINVAL, EIO, EISDIR, ENAMETOOLONG, ENOENT, ENOSPC, ENOTDIR, ENOTEMPTY, EROFS, EXDEV,
+};
+use crate::task::account_file_io;
+use crate::timer::get_wall_time_us;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
-use easy_fs::{EasyFileSystem, Inode};
+use easy_fs::{
+    set_clock, DirEntryInfo, DiskUsage, EasyFileSystem, FileAdvice, Inode, StatFs, DIRENT_SZ,
+};
 use lazy_static::*;
 
 /// A wrapper around a filesystem inode
@@ -13,6 +24,8 @@ use lazy_static::*;
 pub struct OSInode {
     readable: bool,
     writable: bool,
+    /// Every write goes to the end of the file
+    append: bool,
     inner: UPSafeCell<OSInodeInner>,
 }
 
@@ -23,11 +36,13 @@ pub struct OSInodeInner {
 }
 
 impl OSInode {
-    /// Construct an OS inode from a inode
+    /// Construct an OS inode from a inode, which stays open until dropped
     pub fn new(readable: bool, writable: bool, inode: Arc<Inode>) -> Self {
+        inode.open();
         Self {
             readable,
             writable,
+            append: false,
             inner: unsafe { UPSafeCell::new(OSInodeInner { offset: 0, inode }) },
         }
     }
@@ -48,9 +63,17 @@ impl OSInode {
     }
 }
 
+impl Drop for OSInode {
+    /// Close the inode, freeing it if it was unlinked while open
+    fn drop(&mut self) {
+        self.inner.exclusive_access().inode.close();
+    }
+}
+
 lazy_static! {
     /// The root of all inodes, or '/' in short
     pub static ref ROOT_INODE: Arc<Inode> = {
+        set_clock(|| (get_wall_time_us() / 1_000_000) as u32);
         let efs = EasyFileSystem::open(BLOCK_DEVICE.clone());
         Arc::new(EasyFileSystem::root_inode(&efs))
     };
@@ -59,7 +82,7 @@ lazy_static! {
 /// List all files in the filesystems
 pub fn list_apps() {
     println!("/**** APPS ****");
-    for app in ROOT_INODE.ls() {
+    for app in ROOT_INODE.ls(false) {
         println!("{}", app);
     }
     println!("**************/");
@@ -73,6 +96,8 @@ bitflags! {
         const RDWR = 1 << 1;
         const CREATE = 1 << 9;
         const TRUNC = 1 << 10;
+        const APPEND = 1 << 11;
+        const EXCL = 1 << 12;
     }
 }
 
@@ -91,39 +116,348 @@ impl OpenFlags {
     }
 }
 
+/// Check that every component of a path fits in a directory entry
+pub fn check_path(path: &str) -> Result<(), isize> {
+    if path.split('/').any(|name| name.len() > NAME_MAX) {
+        Err(-ENAMETOOLONG)
+    } else {
+        Ok(())
+    }
+}
+
 /// Open a file by path
-pub fn open_file(name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+pub fn open_file(path: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
     let (readable, writable) = flags.read_write();
+    if let Some(inode) = find_path(path) {
+        // directories can only be read
+        if inode.is_dir() && !flags.is_empty() {
+            return None;
+        }
+        // nor can verity files be written or truncated
+        if inode.is_verity() && (writable || flags.intersects(OpenFlags::CREATE | OpenFlags::TRUNC))
+        {
+            return None;
+        }
+    }
     if flags.contains(OpenFlags::CREATE) {
-        if let Some(inode) = ROOT_INODE.find(name) {
-            // clear size
-            inode.clear();
-            Some(Arc::new(OSInode::new(readable, writable, inode)))
-        } else {
-            // create file
-            ROOT_INODE
-                .create(name)
-                .map(|inode| Arc::new(OSInode::new(readable, writable, inode)))
+        match find_path(path) {
+            Some(inode) if !flags.contains(OpenFlags::EXCL) => {
+                // clear size
+                inode.clear();
+                Some(inode)
+            }
+            _ => {
+                // create file, failing on an existing name as create looks it up
+                // again under the filesystem lock
+                let (parent, name) = find_parent(path)?;
+                let inode = parent.create(name)?;
+                fsevent(FsEventMask::CREATE, inode.stat().ino);
+                Some(inode)
+            }
         }
     } else {
-        ROOT_INODE.find(name).map(|inode| {
+        find_path(path).map(|inode| {
             if flags.contains(OpenFlags::TRUNC) {
                 inode.clear();
             }
-            Arc::new(OSInode::new(readable, writable, inode))
+            inode
         })
     }
+    .map(|inode| {
+        let mut file = OSInode::new(readable, writable, inode);
+        file.append = flags.contains(OpenFlags::APPEND);
+        Arc::new(file)
+    })
+}
+
+/// Directory entry type of a directory
+pub const DT_DIR: u8 = 4;
+/// Directory entry type of a regular file
+pub const DT_REG: u8 = 8;
+/// Directory entry type of a symbolic link
+pub const DT_LNK: u8 = 10;
+
+/// Directory entry type of what `entry` refers to
+fn dirent_type(entry: &DirEntryInfo) -> u8 {
+    if entry.is_dir {
+        DT_DIR
+    } else if entry.is_symlink {
+        DT_LNK
+    } else {
+        DT_REG
+    }
+}
+
+/// A directory entry returned by getdents
+#[repr(C)]
+pub struct Dirent {
+    /// inode number
+    pub d_ino: u64,
+    /// byte offset of the entry within the directory file
+    pub d_off: u64,
+    /// length of this record
+    pub d_reclen: u16,
+    /// file type
+    pub d_type: u8,
+    /// NUL-terminated file name
+    pub d_name: [u8; NAME_MAX + 1],
+}
+
+/// Deepest level below the starting directory whose entries walk reports
+const WALK_MAX_DEPTH: usize = 8;
+/// Room for a path of WALK_MAX_DEPTH names and its NUL
+const WALK_PATH_MAX: usize = WALK_MAX_DEPTH * (NAME_MAX + 1);
+
+/// An entry found by walk
+#[repr(C)]
+pub struct WalkRecord {
+    /// inode number
+    pub ino: u64,
+    /// size in bytes
+    pub size: u64,
+    /// file type
+    pub d_type: u8,
+    /// level below the starting directory, 1 for its own entries
+    pub depth: u8,
+    /// NUL-terminated path relative to the starting directory
+    pub path: [u8; WALK_PATH_MAX],
+}
+
+/// Walk the tree below `dir` depth first, each directory before what it
+/// holds, and fill `buf` with the records that come after the first `skip`.
+/// Returns the number of bytes filled, or -EINVAL if not one record fits.
+///
+/// The work stack holds a directory per level instead of recursing, so it
+/// never grows past WALK_MAX_DEPTH. Mount points are not entered, and
+/// symbolic links are reported but not followed, so they can't lead it into
+/// a cycle. The `.` and `..` entries of each directory are left out.
+pub fn walk(dir: Arc<Inode>, skip: usize, buf: UserBuffer) -> isize {
+    let reclen = core::mem::size_of::<WalkRecord>();
+    let mut records: Vec<u8> = Vec::new();
+    let mut seen = 0;
+    let mut full = false;
+    // a directory, its path and the offset of its next entry for each level
+    let mut stack: Vec<(Arc<Inode>, String, usize)> = vec![(dir, String::new(), 0)];
+    while let Some((dir, prefix, offset)) = stack.last_mut() {
+        let entry = match dir.read_dirent(*offset) {
+            Some(entry) => entry,
+            None => {
+                stack.pop();
+                continue;
+            }
+        };
+        *offset = entry.offset + DIRENT_SZ;
+        // going up or staying put would walk the same tree again
+        if entry.name == "." || entry.name == ".." {
+            continue;
+        }
+        let inode = match dir.find(entry.name.as_str()) {
+            Some(inode) => inode,
+            None => continue,
+        };
+        let mut path = prefix.clone();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(entry.name.as_str());
+        let depth = stack.len();
+        if seen >= skip {
+            if records.len() + reclen > buf.len() {
+                full = true;
+                break;
+            }
+            let mut record = WalkRecord {
+                ino: entry.inode_id as u64,
+                size: inode.size() as u64,
+                d_type: dirent_type(&entry),
+                depth: depth as u8,
+                path: [0; WALK_PATH_MAX],
+            };
+            record.path[..path.len()].copy_from_slice(path.as_bytes());
+            records.extend_from_slice(unsafe {
+                core::slice::from_raw_parts(&record as *const _ as *const u8, reclen)
+            });
+        }
+        seen += 1;
+        if entry.is_dir && depth < WALK_MAX_DEPTH {
+            stack.push((inode, path, 0));
+        }
+    }
+    if full && records.is_empty() {
+        return -EINVAL;
+    }
+    for (dst, src) in buf.into_iter().zip(records.iter()) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    records.len() as isize
+}
+
+/// No special treatment for the file
+pub const POSIX_FADV_NORMAL: usize = 0;
+/// Expect random access, disable read-ahead
+pub const POSIX_FADV_RANDOM: usize = 1;
+/// Expect sequential access, read ahead aggressively
+pub const POSIX_FADV_SEQUENTIAL: usize = 2;
+/// The region will be accessed soon, prefetch it
+pub const POSIX_FADV_WILLNEED: usize = 3;
+/// The region will not be accessed soon, drop it from the cache
+pub const POSIX_FADV_DONTNEED: usize = 4;
+
+/// Zero the range while keeping its blocks allocated
+pub const FALLOC_FL_ZERO_RANGE: usize = 0x10;
+
+/// Create a directory by path
+pub fn mkdir(path: &str) -> isize {
+    if find_path(path).is_some() {
+        return -EEXIST;
+    }
+    match find_parent(path) {
+        Some((parent, name)) => {
+            if let Some(inode) = parent.create_dir(name) {
+                fsevent(FsEventMask::CREATE, inode.stat().ino);
+                0
+            } else {
+                // the name was free above, so the disk is full
+                -ENOSPC
+            }
+        }
+        None => -ENOENT,
+    }
+}
+
+/// Create a symbolic link at `path` holding `target`
+pub fn symlink(target: &str, path: &str) -> isize {
+    if target.is_empty() {
+        return -ENOENT;
+    }
+    let (parent, name) = match find_parent(path) {
+        Some(found) => found,
+        None => return -ENOENT,
+    };
+    // a dangling link in the way isn't found by following it
+    if parent.find(name).is_some() {
+        return -EEXIST;
+    }
+    match parent.create_symlink(name, target) {
+        Some(inode) => {
+            fsevent(FsEventMask::CREATE, inode.stat().ino);
+            0
+        }
+        None => -ENOSPC,
+    }
+}
+
+/// Read the target of the symbolic link at `path`, without following it
+pub fn readlink(path: &str) -> Result<String, isize> {
+    let inode = find_path_nofollow(path).ok_or(-ENOENT)?;
+    inode.readlink().ok_or(-EINVAL)
+}
+
+/// Get the disk usage of a file by path
+pub fn disk_usage(path: &str) -> Option<DiskUsage> {
+    find_path(path).map(|inode| inode.usage())
+}
+
+/// How full the filesystem holding a path is
+pub fn statfs(path: &str) -> Option<StatFs> {
+    find_path(path).map(|inode| inode.statfs())
+}
+
+/// Find the directories holding both paths, which must be one and the same:
+/// easy-fs moves entries only within a directory
+fn find_parents<'a>(
+    old_path: &'a str,
+    new_path: &'a str,
+) -> Result<(Arc<Inode>, &'a str, &'a str), isize> {
+    let (old_parent, old_name) = find_parent(old_path).ok_or(-ENOENT)?;
+    let (new_parent, new_name) = find_parent(new_path).ok_or(-ENOENT)?;
+    if old_parent.is_same(&new_parent) {
+        Ok((old_parent, old_name, new_name))
+    } else {
+        Err(-EXDEV)
+    }
 }
 
-pub fn linkat(old_name: &str, new_name: &str) -> isize {
+/// Give the file at `old_path` a second name `new_path` in the same directory
+pub fn linkat(old_path: &str, new_path: &str) -> isize {
+    let (parent, old_name, new_name) = match find_parents(old_path, new_path) {
+        Ok(found) => found,
+        Err(errno) => return errno,
+    };
     if old_name == new_name {
         return -1;
     }
-    ROOT_INODE.linkat(old_name, new_name)
+    parent.linkat(old_name, new_name)
+}
+
+bitflags! {
+    /// Flags for renaming files
+    pub struct RenameFlags: u32 {
+        /// Fail if the target already exists
+        const NOREPLACE = 1 << 0;
+        /// Write the source file's data back before the rename commits
+        const SYNC = 1 << 31;
+    }
+}
+
+/// Rename a file, atomically replacing the target unless `NOREPLACE` is given
+pub fn renameat(old_path: &str, new_path: &str, flags: RenameFlags) -> isize {
+    let (parent, old_name, new_name) = match find_parents(old_path, new_path) {
+        Ok(found) => found,
+        Err(errno) => return errno,
+    };
+    let inode = match parent.find(old_name) {
+        Some(inode) => inode,
+        None => return -ENOENT,
+    };
+    let sync = flags.contains(RenameFlags::SYNC);
+    if let Some(target) = parent.find(new_name) {
+        if !flags.contains(RenameFlags::NOREPLACE) && !target.is_same(&inode) {
+            // a directory only replaces an empty directory, a file only a file
+            match (inode.is_dir(), target.is_dir()) {
+                (true, false) => return -ENOTDIR,
+                (false, true) => return -EISDIR,
+                (true, true) if !target.is_empty_dir() => return -ENOTEMPTY,
+                _ => {}
+            }
+        }
+    }
+    let ret = if flags.contains(RenameFlags::NOREPLACE) {
+        // the old name was found above, so only the target can be in the way
+        match parent.rename_noreplace(old_name, new_name, sync) {
+            0 => 0,
+            _ => return -EEXIST,
+        }
+    } else {
+        parent.rename(old_name, new_name, sync)
+    };
+    if ret == 0 {
+        fsevent(FsEventMask::RENAME, inode.stat().ino);
+    }
+    ret
 }
 
-pub fn unlinkat(name: &str) -> isize {
-    ROOT_INODE.unlinkat(name)
+/// Remove the name `path`, and the file with its last name
+pub fn unlinkat(path: &str) -> isize {
+    let (parent, name) = match find_parent(path) {
+        Some(found) => found,
+        None => return -ENOENT,
+    };
+    let inode = parent.find(name);
+    if let Some(inode) = &inode {
+        if inode.is_dir() && !inode.is_empty_dir() {
+            return -ENOTEMPTY;
+        }
+    }
+    // the inode may be gone once its last name is
+    let ino = inode.map(|inode| inode.stat().ino);
+    let ret = parent.unlinkat(name);
+    if let (0, Some(ino)) = (ret, ino) {
+        fsevent(FsEventMask::UNLINK, ino);
+    }
+    ret
 }
 
 impl File for OSInode {
@@ -133,32 +467,168 @@ impl File for OSInode {
     fn writable(&self) -> bool {
         self.writable
     }
-    fn read(&self, mut buf: UserBuffer) -> usize {
+    fn read(&self, mut buf: UserBuffer) -> isize {
         let mut inner = self.inner.exclusive_access();
-        let mut total_read_size = 0usize;
-        for slice in buf.buffers.iter_mut() {
-            let read_size = inner.inode.read_at(inner.offset, *slice);
-            if read_size == 0 {
-                break;
+        // a block failing verity cuts the read short, or fails one reaching it first
+        match inner.inode.try_read_all_at(inner.offset, &mut buf.buffers) {
+            Some(read_size) => {
+                inner.offset += read_size;
+                account_file_io(read_size, 0);
+                read_size as isize
             }
-            inner.offset += read_size;
-            total_read_size += read_size;
+            None => -EIO,
         }
-        total_read_size
     }
-    fn write(&self, buf: UserBuffer) -> usize {
+    fn write(&self, buf: UserBuffer) -> isize {
         let mut inner = self.inner.exclusive_access();
-        let mut total_write_size = 0usize;
-        for slice in buf.buffers.iter() {
-            let write_size = inner.inode.write_at(inner.offset, *slice);
-            assert_eq!(write_size, slice.len());
-            inner.offset += write_size;
-            total_write_size += write_size;
+        if inner.inode.is_verity() {
+            return -EROFS;
+        }
+        let slices: Vec<&[u8]> = buf.buffers.iter().map(|slice| &**slice).collect();
+        // space for the whole buffer is reserved up front, so it is never cut short
+        let written = if self.append {
+            inner.inode.append_all(&slices)
+        } else {
+            let offset = inner.offset;
+            inner
+                .inode
+                .write_all_at(offset, &slices)
+                .map(|size| (offset, size))
+        };
+        match written {
+            Some((offset, write_size)) => {
+                assert_eq!(write_size, buf.len());
+                inner.offset = offset + write_size;
+                account_file_io(0, write_size);
+                if write_size > 0 {
+                    fsevent(FsEventMask::WRITE, inner.inode.stat().ino);
+                }
+                write_size as isize
+            }
+            None => -ENOSPC,
         }
-        total_write_size
     }
     fn stat(&self) -> Stat {
         let inner = self.inner.inclusive_access();
         inner.inode.stat()
     }
+    fn seek(&self, pos: SeekFrom) -> isize {
+        let mut inner = self.inner.exclusive_access();
+        let offset = match pos {
+            SeekFrom::Start(offset) => offset as isize,
+            SeekFrom::Current(delta) => inner.offset as isize + delta,
+            SeekFrom::End(delta) => inner.inode.size() as isize + delta,
+        };
+        if offset < 0 {
+            return -EINVAL;
+        }
+        inner.offset = offset as usize;
+        offset
+    }
+    fn getdents(&self, buf: UserBuffer) -> isize {
+        let mut inner = self.inner.exclusive_access();
+        if !inner.inode.is_dir() {
+            return -ENOTDIR;
+        }
+        let reclen = core::mem::size_of::<Dirent>();
+        let mut records: Vec<u8> = Vec::new();
+        while records.len() + reclen <= buf.len() {
+            let entry = match inner.inode.read_dirent(inner.offset) {
+                Some(entry) => entry,
+                None => break,
+            };
+            let mut dirent = Dirent {
+                d_ino: entry.inode_id as u64,
+                d_off: entry.offset as u64,
+                d_reclen: reclen as u16,
+                d_type: dirent_type(&entry),
+                d_name: [0; NAME_MAX + 1],
+            };
+            dirent.d_name[..entry.name.len()].copy_from_slice(entry.name.as_bytes());
+            records.extend_from_slice(unsafe {
+                core::slice::from_raw_parts(&dirent as *const _ as *const u8, reclen)
+            });
+            inner.offset = entry.offset + DIRENT_SZ;
+        }
+        if records.is_empty() && inner.inode.read_dirent(inner.offset).is_some() {
+            // the buffer cannot hold a single record
+            return -EINVAL;
+        }
+        for (dst, src) in buf.into_iter().zip(records.iter()) {
+            unsafe {
+                *dst = *src;
+            }
+        }
+        records.len() as isize
+    }
+    fn fallocate(&self, mode: usize, offset: usize, len: usize) -> isize {
+        let inner = self.inner.inclusive_access();
+        if inner.inode.is_verity() {
+            return -EROFS;
+        }
+        let done = match mode {
+            0 => inner.inode.allocate(offset, len),
+            FALLOC_FL_ZERO_RANGE => inner.inode.zero_range(offset, len),
+            _ => return -EINVAL,
+        };
+        if done {
+            0
+        } else {
+            -ENOSPC
+        }
+    }
+    fn truncate(&self, size: usize) -> isize {
+        if !self.writable || size > u32::MAX as usize {
+            return -EINVAL;
+        }
+        let inner = self.inner.inclusive_access();
+        if inner.inode.is_verity() {
+            return -EROFS;
+        }
+        if inner.inode.truncate(size) {
+            0
+        } else {
+            -ENOSPC
+        }
+    }
+    fn enable_verity(&self) -> isize {
+        let inner = self.inner.inclusive_access();
+        if inner.inode.is_dir() {
+            return -EINVAL;
+        }
+        if inner.inode.is_verity() {
+            return -EEXIST;
+        }
+        // the file is too large for verity or the hashes don't fit on disk
+        if !inner.inode.enable_verity() {
+            return -ENOSPC;
+        }
+        0
+    }
+    fn fsync(&self) -> isize {
+        self.inner.inclusive_access().inode.fsync();
+        0
+    }
+    fn inode(&self) -> Option<Arc<Inode>> {
+        Some(self.inner.inclusive_access().inode.clone())
+    }
+    fn readahead(&self, offset: usize, len: usize) -> isize {
+        // the handle offset is left untouched
+        self.inner.inclusive_access().inode.will_need(offset, len);
+        0
+    }
+    fn fadvise(&self, offset: usize, len: usize, advice: usize) -> isize {
+        let inner = self.inner.inclusive_access();
+        // a length of 0 means until the end of the file
+        let len = if len == 0 { usize::MAX - offset } else { len };
+        match advice {
+            POSIX_FADV_NORMAL => inner.inode.set_advice(FileAdvice::Normal),
+            POSIX_FADV_RANDOM => inner.inode.set_advice(FileAdvice::Random),
+            POSIX_FADV_SEQUENTIAL => inner.inode.set_advice(FileAdvice::Sequential),
+            POSIX_FADV_WILLNEED => inner.inode.will_need(offset, len),
+            POSIX_FADV_DONTNEED => inner.inode.dont_need(offset, len),
+            _ => return -EINVAL,
+        }
+        0
+    }
 }
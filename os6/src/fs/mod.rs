@@ -1,18 +1,81 @@
+mod dev;
+mod fsevents;
 mod inode;
+mod mount;
+mod pipe;
 mod stdio;
 
 use crate::mm::UserBuffer;
+use alloc::sync::Arc;
+use easy_fs::Inode;
 
 /// The common abstraction of all IO resources
 pub trait File: Send + Sync {
     fn readable(&self) -> bool;
     fn writable(&self) -> bool;
-    fn read(&self, buf: UserBuffer) -> usize;
-    fn write(&self, buf: UserBuffer) -> usize;
+    /// Read into `buf`, returning the number of bytes read or a negative errno
+    fn read(&self, buf: UserBuffer) -> isize;
+    /// Write `buf` out, returning the number of bytes written or a negative errno
+    fn write(&self, buf: UserBuffer) -> isize;
     fn stat(&self) -> Stat;
+    /// Give an access pattern hint for the region `[offset, offset + len)`
+    fn fadvise(&self, _offset: usize, _len: usize, _advice: usize) -> isize {
+        -1
+    }
+    /// Allocate the region `[offset, offset + len)`, zeroing it in some modes
+    fn fallocate(&self, _mode: usize, _offset: usize, _len: usize) -> isize {
+        -1
+    }
+    /// Cut the file down or grow it to `size` bytes
+    fn truncate(&self, _size: usize) -> isize {
+        -1
+    }
+    /// Load the region `[offset, offset + len)` into the cache ahead of reading
+    fn readahead(&self, _offset: usize, _len: usize) -> isize {
+        -1
+    }
+    /// Seal the file so that it can't change and reads are checked against it
+    fn enable_verity(&self) -> isize {
+        -1
+    }
+    /// Move the file offset, returning the new offset
+    fn seek(&self, _pos: SeekFrom) -> isize {
+        -1
+    }
+    /// Read directory entries into `buf`, returning the number of bytes filled
+    fn getdents(&self, _buf: UserBuffer) -> isize {
+        -1
+    }
+    /// Write what was written to the file so far back to its device
+    fn fsync(&self) -> isize {
+        -1
+    }
+    /// The filesystem inode behind the file, if there is one
+    fn inode(&self) -> Option<Arc<Inode>> {
+        None
+    }
 }
 
-pub use easy_fs::Stat;
+/// Where a seek starts from
+pub enum SeekFrom {
+    Start(usize),
+    Current(isize),
+    End(isize),
+}
+
+/// max length of a single path component
+pub use easy_fs::NAME_LENGTH_LIMIT as NAME_MAX;
+pub use easy_fs::{DiskUsage, Stat, StatFs};
 
-pub use inode::{linkat, list_apps, open_file, unlinkat, OSInode, OpenFlags};
+pub use dev::open_device;
+pub use fsevents::{fsevents_open, FsEvent, FsEventMask};
+pub use inode::{
+    check_path, disk_usage, linkat, list_apps, mkdir, open_file, readlink, renameat, statfs,
+    symlink, unlinkat, walk, OSInode, OpenFlags, RenameFlags, WalkRecord, ROOT_INODE,
+};
+pub use mount::{
+    absolute_path, find_path, find_path_at, find_path_at_nofollow, find_path_nofollow, mount,
+    umount, MountNamespace, INIT_MOUNT_NS,
+};
+pub use pipe::make_pipe;
 pub use stdio::{Stdin, Stdout};
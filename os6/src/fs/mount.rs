@@ -0,0 +1,276 @@
+//! Mount table and path resolution across mount points
+
+use super::inode::ROOT_INODE;
+use crate::drivers::get_block_device;
+use crate::sync::UPSafeCell;
+use crate::syscall::errno::{EBUSY, EINVAL, ENODEV, ENOENT, ENOTDIR};
+use crate::task::current_task;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use easy_fs::{resolve_path, EasyFileSystem, Inode};
+use lazy_static::*;
+
+/// A filesystem attached to a directory of another filesystem
+#[derive(Clone)]
+struct MountPoint {
+    /// device number of the mounted filesystem
+    dev: usize,
+    /// the directory hidden by the mount
+    covered: Arc<Inode>,
+    /// root inode of the mounted filesystem
+    root: Arc<Inode>,
+}
+
+/// A mount table of all mounted filesystems except the root one, shared by
+/// the processes in the namespace
+pub struct MountNamespace {
+    mounts: UPSafeCell<Vec<MountPoint>>,
+}
+
+lazy_static! {
+    /// The namespace of the first process and of the kernel itself
+    pub static ref INIT_MOUNT_NS: Arc<MountNamespace> = MountNamespace::register(Vec::new());
+    /// Every namespace in use, for finding the devices mounted anywhere
+    static ref MOUNT_NAMESPACES: UPSafeCell<Vec<Weak<MountNamespace>>> =
+        unsafe { UPSafeCell::new(Vec::new()) };
+}
+
+impl MountNamespace {
+    fn register(mounts: Vec<MountPoint>) -> Arc<Self> {
+        let ns = Arc::new(Self {
+            mounts: unsafe { UPSafeCell::new(mounts) },
+        });
+        let mut namespaces = MOUNT_NAMESPACES.exclusive_access();
+        namespaces.retain(|ns| ns.strong_count() > 0);
+        namespaces.push(Arc::downgrade(&ns));
+        ns
+    }
+    /// Make a private copy of the namespace, starting out with the same mounts
+    pub fn unshare(&self) -> Arc<Self> {
+        Self::register(self.mounts.exclusive_access().clone())
+    }
+    /// Get the root of the filesystem mounted at a directory, if any
+    fn mounted_root(&self, inode: &Inode) -> Option<Arc<Inode>> {
+        self.mounts
+            .exclusive_access()
+            .iter()
+            .find(|mount| mount.covered.is_same(inode))
+            .map(|mount| mount.root.clone())
+    }
+}
+
+impl Drop for MountNamespace {
+    /// Write back the filesystems no other namespace still has mounted
+    fn drop(&mut self) {
+        for mount in self.mounts.exclusive_access().iter() {
+            if Arc::strong_count(&mount.root) == 1 {
+                mount.root.flush_fs();
+            }
+        }
+    }
+}
+
+/// Get the mount namespace of the current process
+fn current_mount_ns() -> Arc<MountNamespace> {
+    match current_task() {
+        Some(task) => task.inner_exclusive_access().mount_ns.clone(),
+        None => INIT_MOUNT_NS.clone(),
+    }
+}
+
+/// Whether device `dev` is mounted in any namespace
+///
+/// A device is only ever opened once, so that two filesystem instances
+/// never cache the same blocks apart.
+fn dev_mounted(dev: usize) -> bool {
+    MOUNT_NAMESPACES
+        .exclusive_access()
+        .iter()
+        .filter_map(|ns| ns.upgrade())
+        .any(|ns| {
+            ns.mounts
+                .exclusive_access()
+                .iter()
+                .any(|mount| mount.dev == dev)
+        })
+}
+
+/// Get the current directory of the current process and its absolute path
+fn current_dir() -> Option<(Arc<Inode>, String)> {
+    let task = current_task()?;
+    let inner = task.inner_exclusive_access();
+    Some((inner.cwd.clone(), inner.cwd_path.clone()))
+}
+
+/// Find an inode by path from the current directory, or from the root if the
+/// path is absolute, descending into mounted filesystems
+///
+/// `..` goes back to the directory the path came from, so leaving the root
+/// of a mounted filesystem returns to the tree holding its mount point. A
+/// relative path climbing above the current directory is walked from the
+/// root along the path the current directory was reached by.
+pub fn find_path(path: &str) -> Option<Arc<Inode>> {
+    lookup(path, true)
+}
+
+/// Like `find_path`, but a symbolic link as the last component is found
+/// itself rather than followed
+pub fn find_path_nofollow(path: &str) -> Option<Arc<Inode>> {
+    lookup(path, false)
+}
+
+fn lookup(path: &str, follow_last: bool) -> Option<Arc<Inode>> {
+    match current_dir() {
+        Some((cwd, cwd_path)) if !path.starts_with('/') => {
+            if climbs_out(path) {
+                let path = absolute_path(&cwd_path, path);
+                resolve_at(ROOT_INODE.clone(), &path, follow_last)
+            } else {
+                resolve_at(cwd, path, follow_last)
+            }
+        }
+        _ => resolve_at(ROOT_INODE.clone(), path, follow_last),
+    }
+}
+
+/// Whether a relative path goes above the directory it starts from
+fn climbs_out(path: &str) -> bool {
+    let mut depth = 0usize;
+    for name in path.split('/') {
+        match name {
+            "" | "." => {}
+            ".." if depth == 0 => return true,
+            ".." => depth -= 1,
+            _ => depth += 1,
+        }
+    }
+    false
+}
+
+/// Join `path` onto the absolute path `dir`, folding `.` and `..` away
+pub fn absolute_path(dir: &str, path: &str) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    let start = if path.starts_with('/') { "" } else { dir };
+    for name in start.split('/').chain(path.split('/')) {
+        match name {
+            "" | "." => {}
+            ".." => {
+                names.pop();
+            }
+            _ => names.push(name),
+        }
+    }
+    if names.is_empty() {
+        return String::from("/");
+    }
+    names.iter().fold(String::new(), |mut path, name| {
+        path.push('/');
+        path.push_str(name);
+        path
+    })
+}
+
+/// Find an inode by path from the directory `dir`, or from the root if the
+/// path is absolute. A relative path can't climb above `dir` with `..`.
+/// Symbolic links are followed, up to `SYMLOOP_MAX` of them.
+pub fn find_path_at(dir: Arc<Inode>, path: &str) -> Option<Arc<Inode>> {
+    resolve_at(dir, path, true)
+}
+
+/// Like `find_path_at`, but a symbolic link as the last component is found
+/// itself rather than followed
+pub fn find_path_at_nofollow(dir: Arc<Inode>, path: &str) -> Option<Arc<Inode>> {
+    resolve_at(dir, path, false)
+}
+
+fn resolve_at(dir: Arc<Inode>, path: &str, follow_last: bool) -> Option<Arc<Inode>> {
+    let ns = current_mount_ns();
+    resolve_path(dir, &ROOT_INODE, path, follow_last, |inode| {
+        ns.mounted_root(&inode).unwrap_or(inode)
+    })
+}
+
+/// Split a path into the directory holding it and its last component
+pub fn find_parent(path: &str) -> Option<(Arc<Inode>, &str)> {
+    let path = path.trim_end_matches('/');
+    let (dir, name) = match path.rfind('/') {
+        // the root itself holds a name right under it
+        Some(0) => ("/", &path[1..]),
+        Some(pos) => (&path[..pos], &path[pos + 1..]),
+        None => ("", path),
+    };
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+    let dir = find_path(dir)?;
+    if dir.is_dir() {
+        Some((dir, name))
+    } else {
+        None
+    }
+}
+
+/// Mount the easy-fs on device `dev` at the directory `target`
+pub fn mount(dev: usize, target: &str) -> isize {
+    let block_device = match get_block_device(dev) {
+        Some(block_device) => block_device,
+        None => return -ENODEV,
+    };
+    let target = match find_path(target) {
+        Some(target) => target,
+        None => return -ENOENT,
+    };
+    if !target.is_dir() {
+        return -ENOTDIR;
+    }
+    let ns = current_mount_ns();
+    // the root filesystem lives on device 0 and cannot be covered itself
+    if dev == 0
+        || target.is_same(&ROOT_INODE)
+        || dev_mounted(dev)
+        || ns
+            .mounts
+            .exclusive_access()
+            .iter()
+            .any(|mount| mount.root.is_same(&target))
+    {
+        return -EBUSY;
+    }
+    let efs = EasyFileSystem::open(block_device);
+    ns.mounts.exclusive_access().push(MountPoint {
+        dev,
+        covered: target,
+        root: Arc::new(EasyFileSystem::root_inode(&efs)),
+    });
+    0
+}
+
+/// Detach the filesystem mounted at `target`
+///
+/// Every inode of a filesystem holds a reference to it, so the filesystem is
+/// in use as long as anything besides the mount table's root inode is alive,
+/// which includes the copy of the mount in another namespace.
+pub fn umount(target: &str) -> isize {
+    let target = match find_path(target) {
+        Some(target) => target,
+        None => return -ENOENT,
+    };
+    let ns = current_mount_ns();
+    let mut mount_table = ns.mounts.exclusive_access();
+    let idx = match mount_table
+        .iter()
+        .position(|mount| mount.root.is_same(&target))
+    {
+        Some(idx) => idx,
+        None => return -EINVAL,
+    };
+    drop(target);
+    let mount = &mount_table[idx];
+    if Arc::strong_count(&mount.root) > 1 || mount.root.fs_refs() > 1 {
+        return -EBUSY;
+    }
+    mount.root.flush_fs();
+    mount_table.remove(idx);
+    0
+}
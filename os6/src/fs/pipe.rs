@@ -0,0 +1,133 @@
+//! Pipes, carrying either a stream of bytes or a stream of packets
+
+use super::{File, Stat};
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use crate::task::suspend_current_and_run_next;
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+/// Bytes a pipe holds, which is also the largest packet
+const PIPE_BUF: usize = 4096;
+
+/// The bytes in a pipe, shared by both of its ends
+struct PipeBuffer {
+    data: VecDeque<u8>,
+    /// lengths of the packets in `data` oldest first, None for a byte stream
+    packets: Option<VecDeque<usize>>,
+    read_end: Weak<Pipe>,
+    write_end: Weak<Pipe>,
+}
+
+/// One end of a pipe
+pub struct Pipe {
+    readable: bool,
+    writable: bool,
+    buffer: Arc<UPSafeCell<PipeBuffer>>,
+}
+
+/// Create a pipe, returning its (read end, write end). In packet mode every
+/// write of up to PIPE_BUF bytes is read back by a read of its own.
+pub fn make_pipe(packet: bool) -> (Arc<Pipe>, Arc<Pipe>) {
+    let buffer = Arc::new(unsafe {
+        UPSafeCell::new(PipeBuffer {
+            data: VecDeque::new(),
+            packets: if packet { Some(VecDeque::new()) } else { None },
+            read_end: Weak::new(),
+            write_end: Weak::new(),
+        })
+    });
+    let read_end = Arc::new(Pipe {
+        readable: true,
+        writable: false,
+        buffer: buffer.clone(),
+    });
+    let write_end = Arc::new(Pipe {
+        readable: false,
+        writable: true,
+        buffer: buffer.clone(),
+    });
+    let mut inner = buffer.exclusive_access();
+    inner.read_end = Arc::downgrade(&read_end);
+    inner.write_end = Arc::downgrade(&write_end);
+    drop(inner);
+    (read_end, write_end)
+}
+
+impl File for Pipe {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+    fn writable(&self) -> bool {
+        self.writable
+    }
+    /// Wait for data and read what fits. In packet mode that is a single
+    /// packet, the part of it that doesn't fit being dropped.
+    fn read(&self, buf: UserBuffer) -> isize {
+        let mut buffer = loop {
+            let buffer = self.buffer.exclusive_access();
+            if !buffer.data.is_empty() {
+                break buffer;
+            }
+            // nothing more can come
+            if buffer.write_end.upgrade().is_none() {
+                return 0;
+            }
+            drop(buffer);
+            suspend_current_and_run_next();
+        };
+        let available = match buffer.packets.as_mut() {
+            Some(packets) => packets.pop_front().unwrap(),
+            None => buffer.data.len(),
+        };
+        let len = available.min(buf.len());
+        for dst in buf.into_iter().take(len) {
+            unsafe {
+                *dst = buffer.data.pop_front().unwrap();
+            }
+        }
+        if buffer.packets.is_some() {
+            buffer.data.drain(..available - len);
+        }
+        len as isize
+    }
+    /// Write all of `buf`, waiting for room as needed. In packet mode every
+    /// PIPE_BUF bytes of it go in as a packet of their own.
+    fn write(&self, buf: UserBuffer) -> isize {
+        let bytes: Vec<u8> = buf.into_iter().map(|src| unsafe { *src }).collect();
+        let packet = self.buffer.exclusive_access().packets.is_some();
+        let mut written = 0;
+        while written < bytes.len() {
+            let mut buffer = self.buffer.exclusive_access();
+            // nobody is left to read it
+            if buffer.read_end.upgrade().is_none() {
+                return -1;
+            }
+            let room = PIPE_BUF - buffer.data.len();
+            let len = if packet {
+                (bytes.len() - written).min(PIPE_BUF)
+            } else {
+                (bytes.len() - written).min(room)
+            };
+            // a packet only goes in whole
+            if len == 0 || len > room {
+                drop(buffer);
+                suspend_current_and_run_next();
+                continue;
+            }
+            buffer
+                .data
+                .extend(bytes[written..written + len].iter().copied());
+            if let Some(packets) = buffer.packets.as_mut() {
+                packets.push_back(len);
+            }
+            written += len;
+        }
+        written as isize
+    }
+    /// A pipe has no inode, only the bytes waiting in it
+    fn stat(&self) -> Stat {
+        Stat::anonymous(self.buffer.exclusive_access().data.len() as u64)
+    }
+}
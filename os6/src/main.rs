@@ -64,6 +64,8 @@ pub fn rust_main() -> ! {
     println!("[kernel] Hello, world!");
     mm::init();
     mm::remap_test();
+    mm::heap_guard_test();
+    task::fd_table_test();
     trap::init();
     trap::enable_timer_interrupt();
     timer::set_next_trigger();
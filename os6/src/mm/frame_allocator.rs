@@ -2,7 +2,7 @@
 //! controls all the frames in the operating system.
 
 use super::{PhysAddr, PhysPageNum};
-use crate::config::MEMORY_END;
+use crate::config::{MEMORY_END, MEMORY_NODES};
 use crate::sync::UPSafeCell;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
@@ -44,6 +44,7 @@ trait FrameAllocator {
 
 /// an implementation for frame allocator
 pub struct StackFrameAllocator {
+    start: usize,
     current: usize,
     end: usize,
     recycled: Vec<usize>,
@@ -51,14 +52,20 @@ pub struct StackFrameAllocator {
 
 impl StackFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.start = l.0;
         self.current = l.0;
         self.end = r.0;
         info!("last {} Physical Frames.", self.end - self.current);
     }
+    /// Whether the frame belongs to the range managed by this allocator
+    pub fn contains(&self, ppn: PhysPageNum) -> bool {
+        (self.start..self.end).contains(&ppn.0)
+    }
 }
 impl FrameAllocator for StackFrameAllocator {
     fn new() -> Self {
         Self {
+            start: 0,
             current: 0,
             end: 0,
             recycled: Vec::new(),
@@ -88,32 +95,56 @@ impl FrameAllocator for StackFrameAllocator {
 type FrameAllocatorImpl = StackFrameAllocator;
 
 lazy_static! {
-    /// frame allocator instance through lazy_static!
-    pub static ref FRAME_ALLOCATOR: UPSafeCell<FrameAllocatorImpl> =
-        unsafe { UPSafeCell::new(FrameAllocatorImpl::new()) };
+    /// frame allocator instances through lazy_static!, one per memory node
+    pub static ref FRAME_ALLOCATOR: UPSafeCell<Vec<FrameAllocatorImpl>> = unsafe {
+        UPSafeCell::new((0..MEMORY_NODES).map(|_| FrameAllocatorImpl::new()).collect())
+    };
 }
 
+/// initiate the frame allocators, splitting the frames between `ekernel` and
+/// `MEMORY_END` evenly among the memory nodes
 pub fn init_frame_allocator() {
     extern "C" {
         fn ekernel();
     }
-    FRAME_ALLOCATOR.exclusive_access().init(
-        PhysAddr::from(ekernel as usize).ceil(),
-        PhysAddr::from(MEMORY_END).floor(),
-    );
+    let start = PhysAddr::from(ekernel as usize).ceil().0;
+    let end = PhysAddr::from(MEMORY_END).floor().0;
+    let frames_per_node = (end - start) / MEMORY_NODES;
+    for (node, allocator) in FRAME_ALLOCATOR.exclusive_access().iter_mut().enumerate() {
+        let node_end = if node == MEMORY_NODES - 1 {
+            end
+        } else {
+            start + (node + 1) * frames_per_node
+        };
+        allocator.init((start + node * frames_per_node).into(), node_end.into());
+    }
 }
 
-/// initiate the frame allocator using `ekernel` and `MEMORY_END`
+/// allocate a frame, preferring memory node 0
 pub fn frame_alloc() -> Option<FrameTracker> {
-    FRAME_ALLOCATOR
-        .exclusive_access()
-        .alloc()
+    frame_alloc_on_node(0)
+}
+
+/// allocate a frame from memory node `node`, falling back to the other nodes
+/// when it runs out
+pub fn frame_alloc_on_node(node: usize) -> Option<FrameTracker> {
+    let mut allocators = FRAME_ALLOCATOR.exclusive_access();
+    core::iter::once(node)
+        .chain((0..MEMORY_NODES).filter(|&other| other != node))
+        .find_map(|node| allocators[node].alloc())
         .map(FrameTracker::new)
 }
 
 /// deallocate a frame
 pub fn frame_dealloc(ppn: PhysPageNum) {
-    FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
+    let mut allocators = FRAME_ALLOCATOR.exclusive_access();
+    match allocators
+        .iter_mut()
+        .find(|allocator| allocator.contains(ppn))
+    {
+        Some(allocator) => allocator.dealloc(ppn),
+        None => panic!("Frame ppn={:#x} has not been allocated!", ppn.0),
+    }
 }
 
 #[allow(unused)]
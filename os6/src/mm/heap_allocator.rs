@@ -1,16 +1,92 @@
 //! The global allocator
 
-use crate::config::KERNEL_HEAP_SIZE;
+use crate::config::{KERNEL_HEAP_HIGH_WATER, KERNEL_HEAP_SIZE};
 use buddy_system_allocator::LockedHeap;
+use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 #[global_allocator]
 /// heap allocator instance
-static HEAP_ALLOCATOR: LockedHeap = LockedHeap::empty();
+static HEAP_ALLOCATOR: AccountedHeap = AccountedHeap {
+    heap: LockedHeap::empty(),
+    used: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+    above_high_water: AtomicBool::new(false),
+    warnings: AtomicUsize::new(0),
+};
+
+/// A heap that keeps track of how many bytes are handed out
+struct AccountedHeap {
+    heap: LockedHeap,
+    /// bytes currently allocated
+    used: AtomicUsize,
+    /// most bytes ever allocated at once
+    peak: AtomicUsize,
+    /// whether usage crossed the high water mark and hasn't dropped below since
+    above_high_water: AtomicBool,
+    /// number of times usage crossed the high water mark
+    warnings: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for AccountedHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.heap.alloc(layout);
+        if !ptr.is_null() {
+            let used = self.used.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            self.peak.fetch_max(used, Ordering::Relaxed);
+            // warn once per crossing, the logger itself doesn't allocate
+            if used >= KERNEL_HEAP_HIGH_WATER
+                && !self.above_high_water.swap(true, Ordering::Relaxed)
+            {
+                self.warnings.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "kernel heap above high water: {} of {} bytes used",
+                    used, KERNEL_HEAP_SIZE
+                );
+            }
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.heap.dealloc(ptr, layout);
+        let used = self.used.fetch_sub(layout.size(), Ordering::Relaxed) - layout.size();
+        if used < KERNEL_HEAP_HIGH_WATER {
+            self.above_high_water.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Usage of the kernel heap
+#[derive(Debug)]
+pub struct HeapStats {
+    /// bytes currently allocated
+    pub used: usize,
+    /// most bytes ever allocated at once
+    pub peak: usize,
+    /// bytes the heap can hand out
+    pub total: usize,
+    /// number of times usage crossed the high water mark
+    pub warnings: usize,
+}
+
+/// Get the usage of the kernel heap
+pub fn heap_stats() -> HeapStats {
+    HeapStats {
+        used: HEAP_ALLOCATOR.used.load(Ordering::Relaxed),
+        peak: HEAP_ALLOCATOR.peak.load(Ordering::Relaxed),
+        total: KERNEL_HEAP_SIZE,
+        warnings: HEAP_ALLOCATOR.warnings.load(Ordering::Relaxed),
+    }
+}
 
 #[alloc_error_handler]
 /// panic when heap allocation error occurs
 pub fn handle_alloc_error(layout: core::alloc::Layout) -> ! {
-    panic!("Heap allocation error, layout = {:?}", layout);
+    panic!(
+        "Heap allocation error, layout = {:?}, heap = {:?}",
+        layout,
+        heap_stats()
+    );
 }
 
 /// heap space ([u8; KERNEL_HEAP_SIZE])
@@ -20,6 +96,7 @@ static mut HEAP_SPACE: [u8; KERNEL_HEAP_SIZE] = [0; KERNEL_HEAP_SIZE];
 pub fn init_heap() {
     unsafe {
         HEAP_ALLOCATOR
+            .heap
             .lock()
             .init(HEAP_SPACE.as_ptr() as usize, KERNEL_HEAP_SIZE);
     }
@@ -49,3 +126,26 @@ pub fn heap_test() {
     drop(v);
     info!("heap_test passed!");
 }
+
+/// Fill the heap up to the high water mark and check that it is reported
+pub fn heap_guard_test() {
+    use alloc::vec::Vec;
+    const CHUNK: usize = 0x1_0000;
+    let warnings = heap_stats().warnings;
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    while heap_stats().used + CHUNK < KERNEL_HEAP_HIGH_WATER {
+        chunks.push(Vec::with_capacity(CHUNK));
+    }
+    assert_eq!(heap_stats().warnings, warnings);
+    chunks.push(Vec::with_capacity(CHUNK));
+    assert_eq!(heap_stats().warnings, warnings + 1);
+    // staying above the mark doesn't warn again
+    chunks.push(Vec::with_capacity(16));
+    assert_eq!(heap_stats().warnings, warnings + 1);
+    drop(chunks);
+    assert!(heap_stats().used < KERNEL_HEAP_HIGH_WATER);
+    // below it allocation carries on as usual
+    let v: Vec<usize> = (0..500).collect();
+    assert_eq!(v.iter().sum::<usize>(), 499 * 500 / 2);
+    info!("heap_guard_test passed!");
+}
@@ -1,17 +1,27 @@
 //! Implementation of [`MapArea`] and [`MemorySet`].
 
-use super::{frame_alloc, FrameTracker};
+use super::{frame_alloc_on_node, FrameTracker};
 use super::{PTEFlags, PageTable, PageTableEntry};
 use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use super::{StepByOne, VPNRange};
 use crate::config::{MEMORY_END, MMIO, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT, USER_STACK_SIZE};
 use crate::sync::UPSafeCell;
+use crate::syscall::errno::EPERM;
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use easy_fs::Inode;
 use lazy_static::*;
 use riscv::register::satp;
 
+/// Size of the header of a 64-bit ELF
+const ELF64_HEADER_SIZE: usize = 64;
+/// Size of a program header of a 64-bit ELF
+const ELF64_PH_SIZE: usize = 56;
+/// Highest end of the segments of an ELF, which still leaves room for the
+/// guard page and user stack below the trap context
+const ELF_SEGMENT_END: usize = TRAP_CONTEXT - PAGE_SIZE - USER_STACK_SIZE;
+
 extern "C" {
     fn stext();
     fn etext();
@@ -40,6 +50,10 @@ pub fn kernel_token() -> usize {
 pub struct MemorySet {
     page_table: PageTable,
     areas: Vec<MapArea>,
+    /// memory node preferred for frames of areas mapped from now on
+    node: usize,
+    /// what sharing pages copy-on-write with forked address spaces saved
+    cow: CowStats,
 }
 
 impl MemorySet {
@@ -47,8 +61,14 @@ impl MemorySet {
         Self {
             page_table: PageTable::new(),
             areas: Vec::new(),
+            node: 0,
+            cow: CowStats::default(),
         }
     }
+    /// Prefer memory node `node` for the frames of future mappings
+    pub fn set_node(&mut self, node: usize) {
+        self.node = node;
+    }
     pub fn token(&self) -> usize {
         self.page_table.token()
     }
@@ -71,11 +91,12 @@ impl MemorySet {
             .enumerate()
             .find(|(_, area)| area.vpn_range.get_start() == start_vpn)
         {
-            area.unmap(&mut self.page_table);
+            self.cow.pages_freed_unmodified += area.unmap(&mut self.page_table);
             self.areas.remove(idx);
         }
     }
     fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
+        map_area.node = self.node;
         map_area.map(&mut self.page_table);
         if let Some(data) = data {
             map_area.copy_data(&mut self.page_table, data);
@@ -169,22 +190,54 @@ impl MemorySet {
     }
     /// Include sections in elf and trampoline and TrapContext and user stack,
     /// also returns user_sp and entry point.
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
+    ///
+    /// Fails on an ELF whose headers or segments don't fit in `elf_data`, or
+    /// whose segments overlap or leave no room for the user stack.
+    pub fn from_elf(elf_data: &[u8]) -> Result<(Self, usize, usize), &'static str> {
+        // xmas_elf slices the headers out of the input without checking it
+        if elf_data.len() < ELF64_HEADER_SIZE {
+            return Err("file is shorter than the ELF header");
+        }
+        let elf = xmas_elf::ElfFile::new(elf_data)?;
+        let elf_header = elf.header;
+        if elf_header.pt1.class() != xmas_elf::header::Class::SixtyFour {
+            return Err("not a 64-bit ELF");
+        }
+        let ph_count = elf_header.pt2.ph_count();
+        let ph_entry_size = elf_header.pt2.ph_entry_size() as usize;
+        let ph_end = (elf_header.pt2.ph_offset() as usize)
+            .checked_add(ph_count as usize * ph_entry_size)
+            .ok_or("program headers out of the file")?;
+        if ph_count > 0 && (ph_entry_size < ELF64_PH_SIZE || ph_end > elf_data.len()) {
+            return Err("program headers out of the file");
+        }
         let mut memory_set = Self::new_bare();
         // map trampoline
         memory_set.map_trampoline();
         // map program headers of elf, with U flag
-        let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
-        let elf_header = elf.header;
-        let magic = elf_header.pt1.magic;
-        assert_eq!(magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf!");
-        let ph_count = elf_header.pt2.ph_count();
         let mut max_end_vpn = VirtPageNum(0);
         for i in 0..ph_count {
-            let ph = elf.program_header(i).unwrap();
-            if ph.get_type().unwrap() == xmas_elf::program::Type::Load {
+            let ph = elf.program_header(i)?;
+            if ph.get_type()? == xmas_elf::program::Type::Load {
+                let data_end = ph
+                    .offset()
+                    .checked_add(ph.file_size())
+                    .filter(|&end| end as usize <= elf_data.len())
+                    .ok_or("segment out of the file")?;
+                let end = ph
+                    .virtual_addr()
+                    .checked_add(ph.mem_size())
+                    .filter(|&end| end as usize <= ELF_SEGMENT_END)
+                    .ok_or("segment out of user space")?;
+                if ph.file_size() > ph.mem_size() {
+                    return Err("segment larger in the file than in memory");
+                }
                 let start_va: VirtAddr = (ph.virtual_addr() as usize).into();
-                let end_va: VirtAddr = ((ph.virtual_addr() + ph.mem_size()) as usize).into();
+                let end_va: VirtAddr = (end as usize).into();
+                // loadable segments come in address order, each in pages of its own
+                if start_va.floor() < max_end_vpn {
+                    return Err("overlapping segments");
+                }
                 let mut map_perm = MapPermission::U;
                 let ph_flags = ph.flags();
                 if ph_flags.is_read() {
@@ -196,11 +249,16 @@ impl MemorySet {
                 if ph_flags.is_execute() {
                     map_perm |= MapPermission::X;
                 }
-                let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
+                let mut map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
+                map_area.kind = if ph_flags.is_execute() {
+                    AreaKind::Text
+                } else {
+                    AreaKind::Data
+                };
                 max_end_vpn = map_area.vpn_range.get_end();
                 memory_set.push(
                     map_area,
-                    Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
+                    Some(&elf.input[ph.offset() as usize..data_end as usize]),
                 );
             }
         }
@@ -210,15 +268,14 @@ impl MemorySet {
         // guard page
         user_stack_bottom += PAGE_SIZE;
         let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
-        memory_set.push(
-            MapArea::new(
-                user_stack_bottom.into(),
-                user_stack_top.into(),
-                MapType::Framed,
-                MapPermission::R | MapPermission::W | MapPermission::U,
-            ),
-            None,
+        let mut user_stack = MapArea::new(
+            user_stack_bottom.into(),
+            user_stack_top.into(),
+            MapType::Framed,
+            MapPermission::R | MapPermission::W | MapPermission::U,
         );
+        user_stack.kind = AreaKind::Stack;
+        memory_set.push(user_stack, None);
         // map TrapContext
         memory_set.push(
             MapArea::new(
@@ -229,24 +286,46 @@ impl MemorySet {
             ),
             None,
         );
-        (
+        Ok((
             memory_set,
             user_stack_top,
             elf.header.pt2.entry_point() as usize,
-        )
+        ))
     }
-    /// Copy an identical user_space
-    pub fn from_existed_user(user_space: &MemorySet) -> MemorySet {
+    /// Copy an identical user_space. The resident user pages are shared
+    /// with it and made read-only in both, to be copied on the first write.
+    pub fn from_existed_user(user_space: &mut MemorySet) -> MemorySet {
         let mut memory_set = Self::new_bare();
+        memory_set.node = user_space.node;
         // map trampoline
         memory_set.map_trampoline();
-        // copy data sections/trap_context/user_stack
         for area in user_space.areas.iter() {
-            let new_area = MapArea::from_another(area);
+            let mut new_area = MapArea::from_another(area);
+            if area.map_perm.contains(MapPermission::U) {
+                let pte_flags = area.shared_pte_flags();
+                // pages of a file are only there once faulted in, the rest
+                // are read in by each space on its own
+                for (&vpn, frame) in area.data_frames.iter() {
+                    user_space.page_table.unmap(vpn);
+                    user_space.page_table.map(vpn, frame.ppn, pte_flags);
+                    memory_set.page_table.map(vpn, frame.ppn, pte_flags);
+                    new_area.data_frames.insert(vpn, frame.clone());
+                }
+                let shared = new_area.data_frames.len();
+                user_space.cow.pages_shared += shared;
+                memory_set.cow.pages_shared += shared;
+                memory_set.areas.push(new_area);
+                continue;
+            }
+            // the kernel writes the trap context directly, so it gets a copy
             memory_set.push(new_area, None);
             // copy data from another space
             for vpn in area.vpn_range {
-                let src_ppn = user_space.translate(vpn).unwrap().ppn();
+                let src_ppn = match user_space.translate(vpn) {
+                    Some(pte) if pte.is_valid() => pte.ppn(),
+                    // a dropped page reads as zeros, just like the fresh frame
+                    _ => continue,
+                };
                 let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
                 dst_ppn
                     .get_bytes_array()
@@ -269,49 +348,303 @@ impl MemorySet {
         //*self = Self::new_bare();
         self.areas.clear();
     }
+    /// Map `[start_va, end_va)` to zeroed memory, whose pages only get a
+    /// frame when they are first touched
     pub fn map(&mut self, start_va: VirtAddr, end_va: VirtAddr, port: u8) -> isize {
-        let area = MapArea::new(
+        let mut area = MapArea::new(
             start_va,
             end_va,
             MapType::Framed,
-            MapPermission::from_bits(port << 1).unwrap() | MapPermission::U,
+            MapPermission::from_port(port),
         );
-        for vpn in area.vpn_range {
-            if let Some(pte) = self.translate(vpn) {
-                if pte.is_valid() {
-                    return -1;
-                }
-            }
+        area.kind = AreaKind::Mmap;
+        area.lazy = true;
+        if self.overlaps(area.vpn_range) {
+            return -1;
+        }
+        self.push(area, None);
+        0
+    }
+    /// Map `[start_va, end_va)` to the file `inode` from `offset` on, whose
+    /// pages are read in as they are faulted in
+    pub fn map_file(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        port: u8,
+        inode: Arc<Inode>,
+        offset: usize,
+    ) -> isize {
+        let mut area = MapArea::new(
+            start_va,
+            end_va,
+            MapType::Framed,
+            MapPermission::from_port(port),
+        );
+        area.kind = AreaKind::Mmap;
+        if self.overlaps(area.vpn_range) {
+            return -1;
         }
+        area.file = Some(FileMapping { inode, offset });
+        area.lazy = true;
         self.push(area, None);
         0
     }
+    /// Unmap `[start_va, end_va)`, which must lie in user areas. An area
+    /// the range covers only part of is split, keeping the rest mapped.
     pub fn unmap(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> isize {
+        let (start, end) = (start_va.floor(), end_va.ceil());
+        if let Err(err) = self.split_areas(start, end) {
+            return err;
+        }
+        let mut idx = 0;
+        while idx < self.areas.len() {
+            if !self.areas[idx].overlaps(start, end) {
+                idx += 1;
+                continue;
+            }
+            let mut area = self.areas.remove(idx);
+            self.cow.pages_freed_unmodified += area.unmap(&mut self.page_table);
+        }
+        0
+    }
+    /// Change the permissions of `[start_va, end_va)`, which must lie in
+    /// user areas, along with those of its resident pages. An area the range
+    /// covers only part of is split. The stale translations are flushed on
+    /// the way back to user mode.
+    pub fn protect(&mut self, start_va: VirtAddr, end_va: VirtAddr, port: u8) -> isize {
+        let (start, end) = (start_va.floor(), end_va.ceil());
+        if let Err(err) = self.split_areas(start, end) {
+            return err;
+        }
+        let map_perm = MapPermission::from_port(port);
+        for area in self
+            .areas
+            .iter_mut()
+            .filter(|area| area.overlaps(start, end))
+        {
+            area.map_perm = map_perm;
+            for (&vpn, frame) in area.data_frames.iter() {
+                self.page_table.unmap(vpn);
+                self.page_table.map(vpn, frame.ppn, area.pte_flags(frame));
+            }
+        }
+        0
+    }
+    /// Split the areas reaching across `start` or `end`, so that each area
+    /// lies either wholly inside `[start, end)` or wholly outside. Gives -1
+    /// if the range doesn't lie in user areas and -EPERM if it is sealed,
+    /// splitting nothing then.
+    fn split_areas(&mut self, start: VirtPageNum, end: VirtPageNum) -> Result<(), isize> {
+        // pages of an area may have been dropped, so look at the areas themselves
+        if !self.in_user_areas(VPNRange::new(start, end)) {
+            return Err(-1);
+        }
+        if self
+            .areas
+            .iter()
+            .any(|area| area.sealed && area.overlaps(start, end))
+        {
+            return Err(-EPERM);
+        }
+        for vpn in [start, end] {
+            if let Some(idx) = self
+                .areas
+                .iter()
+                .position(|area| area.vpn_range.get_start() < vpn && vpn < area.vpn_range.get_end())
+            {
+                let tail = self.areas[idx].split_off(vpn);
+                self.areas.insert(idx + 1, tail);
+            }
+        }
+        Ok(())
+    }
+    /// Seal the areas of `[start_va, end_va)`, which must lie in user areas,
+    /// so they can't be unmapped or have their permissions changed anymore
+    pub fn seal(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> bool {
         let vpn_range = VPNRange::new(start_va.floor(), end_va.ceil());
-        for vpn in vpn_range {
-            if let Some(pte) = self.translate(vpn) {
-                if !pte.is_valid() {
-                    return -1;
+        if !self.in_user_areas(vpn_range) {
+            return false;
+        }
+        for area in self.areas.iter_mut() {
+            if vpn_range.into_iter().any(|vpn| area.contains(vpn)) {
+                area.sealed = true;
+            }
+        }
+        true
+    }
+    /// Count the pages of the user areas by where they came from
+    pub fn statm(&self) -> Statm {
+        let mut statm = Statm::default();
+        for area in self.areas.iter() {
+            let pages = area.vpn_range.get_end().0 - area.vpn_range.get_start().0;
+            match area.kind {
+                AreaKind::Kernel => continue,
+                AreaKind::Text => statm.text += pages,
+                AreaKind::Data => statm.data += pages,
+                AreaKind::Stack => statm.stack += pages,
+                AreaKind::Mmap => statm.mmap += pages,
+            }
+            statm.size += pages;
+            statm.shared += area
+                .data_frames
+                .values()
+                .filter(|frame| Arc::strong_count(frame) > 1)
+                .count();
+            statm.resident += area
+                .vpn_range
+                .into_iter()
+                .filter(|&vpn| self.translate(vpn).map_or(false, |pte| pte.is_valid()))
+                .count();
+        }
+        statm
+    }
+    /// Map a page in if it belongs to an area but is not resident, which
+    /// happens after it was dropped or for a page of an mmap area that
+    /// wasn't touched yet, or make a page shared by fork writable again.
+    /// Returns whether it did.
+    pub fn handle_fault(&mut self, vpn: VirtPageNum) -> bool {
+        let area = match self.areas.iter_mut().find(|area| area.contains(vpn)) {
+            Some(area) if area.map_type == MapType::Framed => area,
+            _ => return false,
+        };
+        if !area.is_resident(vpn) {
+            return area.fault_in(&mut self.page_table, vpn);
+        }
+        match area.unshare(&mut self.page_table, vpn) {
+            Some(copied) => {
+                if copied {
+                    self.cow.faults_resolved += 1;
                 }
-            } else {
-                return -1;
+                true
             }
+            None => false,
         }
+    }
+    /// Counters of the pages shared copy-on-write so far
+    pub fn cow_stats(&self) -> CowStats {
+        self.cow
+    }
+    /// Whether the page belongs to a file mapping but lies wholly beyond the
+    /// end of the file, so that touching it is a bus error
+    pub fn beyond_file_end(&self, vpn: VirtPageNum) -> bool {
         self.areas
-            .iter_mut()
-            .find(|area| area.vpn_range == vpn_range)
-            .unwrap()
-            .unmap(&mut self.page_table);
-        0
+            .iter()
+            .find(|area| area.contains(vpn))
+            .map_or(false, |area| area.beyond_file_end(vpn))
+    }
+    /// Drop the resident pages of mappings of `inode` which lie wholly
+    /// beyond its new end at `size`, and clear the rest of the page the file
+    /// ends in now
+    pub fn shrink_file(&mut self, inode: &Inode, size: usize) {
+        for area in self.areas.iter_mut() {
+            if !area
+                .file
+                .as_ref()
+                .map_or(false, |file| file.inode.is_same(inode))
+            {
+                continue;
+            }
+            for vpn in area.vpn_range {
+                if area.beyond_file_end(vpn) {
+                    if area.unmap_one(&mut self.page_table, vpn) {
+                        self.cow.pages_freed_unmodified += 1;
+                    }
+                    continue;
+                }
+                let page_offset = area.file_offset(vpn);
+                if let Some(frame) = area.data_frames.get(&vpn) {
+                    if page_offset + PAGE_SIZE > size {
+                        frame.ppn.get_bytes_array()[size - page_offset..].fill(0);
+                    }
+                }
+            }
+        }
+    }
+    /// Drop the resident pages of `[start_va, end_va)`, which must lie in user
+    /// areas. They read back as zeros once faulted in again.
+    pub fn drop_pages(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> bool {
+        let vpn_range = VPNRange::new(start_va.floor(), end_va.ceil());
+        if !self.in_user_areas(vpn_range) {
+            return false;
+        }
+        for vpn in vpn_range {
+            let area = self
+                .areas
+                .iter_mut()
+                .find(|area| area.contains(vpn))
+                .unwrap();
+            if area.unmap_one(&mut self.page_table, vpn) {
+                self.cow.pages_freed_unmodified += 1;
+            }
+        }
+        true
+    }
+    /// Fault in the pages of `[start_va, end_va)` ahead of use, which must lie
+    /// in user areas
+    pub fn populate(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> bool {
+        let vpn_range = VPNRange::new(start_va.floor(), end_va.ceil());
+        if !self.in_user_areas(vpn_range) {
+            return false;
+        }
+        for vpn in vpn_range {
+            let area = self
+                .areas
+                .iter_mut()
+                .find(|area| area.contains(vpn))
+                .unwrap();
+            // pages beyond the end of a file are left to fault
+            if !area.is_resident(vpn) {
+                area.fault_in(&mut self.page_table, vpn);
+            }
+        }
+        true
+    }
+    /// Whether any page of the range is mapped already, or belongs to an
+    /// area without being resident
+    fn overlaps(&self, vpn_range: VPNRange) -> bool {
+        vpn_range.into_iter().any(|vpn| {
+            self.translate(vpn).map_or(false, |pte| pte.is_valid())
+                || self.areas.iter().any(|area| area.contains(vpn))
+        })
+    }
+    /// Whether every page of the range belongs to a framed user area
+    fn in_user_areas(&self, vpn_range: VPNRange) -> bool {
+        vpn_range.into_iter().all(|vpn| {
+            self.areas.iter().any(|area| {
+                area.contains(vpn)
+                    && area.map_type == MapType::Framed
+                    && area.map_perm.contains(MapPermission::U)
+            })
+        })
     }
 }
 
 /// map area structure, controls a contiguous piece of virtual memory
 pub struct MapArea {
     vpn_range: VPNRange,
-    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    /// frames of the resident pages, shared after a fork until written
+    data_frames: BTreeMap<VirtPageNum, Arc<FrameTracker>>,
     map_type: MapType,
     map_perm: MapPermission,
+    /// memory node the frames are allocated from
+    node: usize,
+    /// where the area came from
+    kind: AreaKind,
+    /// mapping and permissions are fixed for as long as the area lives
+    sealed: bool,
+    /// the file the pages are read from, if any
+    file: Option<FileMapping>,
+    /// pages only get a frame once they are faulted in
+    lazy: bool,
+}
+
+/// Where the pages of a file mapping come from
+#[derive(Clone)]
+struct FileMapping {
+    inode: Arc<Inode>,
+    /// file offset of the first page
+    offset: usize,
 }
 
 impl MapArea {
@@ -328,6 +661,11 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type,
             map_perm,
+            node: 0,
+            kind: AreaKind::Kernel,
+            sealed: false,
+            file: None,
+            lazy: false,
         }
     }
     pub fn from_another(another: &MapArea) -> Self {
@@ -336,6 +674,11 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type: another.map_type,
             map_perm: another.map_perm,
+            node: another.node,
+            kind: another.kind,
+            sealed: another.sealed,
+            file: another.file.clone(),
+            lazy: another.lazy,
         }
     }
     pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
@@ -345,34 +688,160 @@ impl MapArea {
                 ppn = PhysPageNum(vpn.0);
             }
             MapType::Framed => {
-                let frame = frame_alloc().unwrap();
+                let frame = frame_alloc_on_node(self.node).unwrap();
                 ppn = frame.ppn;
-                self.data_frames.insert(vpn, frame);
+                self.data_frames.insert(vpn, Arc::new(frame));
             }
         }
         let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
         page_table.map(vpn, ppn, pte_flags);
     }
 
-    pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+    /// Unmap the page, returning whether its frame was still shared with
+    /// another address space, so that it never had to be copied
+    pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool {
+        let mut shared = false;
         #[allow(clippy::single_match)]
         match self.map_type {
             MapType::Framed => {
-                self.data_frames.remove(&vpn);
+                // the page may have been dropped already
+                match self.data_frames.remove(&vpn) {
+                    Some(frame) => {
+                        shared = Arc::strong_count(&frame) > 1;
+                        self.write_back(vpn, &frame);
+                    }
+                    None => return false,
+                }
             }
             _ => {}
         }
         page_table.unmap(vpn);
+        shared
+    }
+    /// Flags of the page with the frame, which can't be written while it is
+    /// shared
+    fn pte_flags(&self, frame: &Arc<FrameTracker>) -> PTEFlags {
+        if Arc::strong_count(frame) > 1 {
+            self.shared_pte_flags()
+        } else {
+            PTEFlags::from_bits(self.map_perm.bits).unwrap()
+        }
+    }
+    /// Flags of a page of the area whose frame is shared. A file mapping
+    /// shares its pages for real, the others copy them on write.
+    fn shared_pte_flags(&self) -> PTEFlags {
+        if self.file.is_some() {
+            return PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        }
+        PTEFlags::from_bits((self.map_perm - MapPermission::W).bits).unwrap()
+    }
+    /// Make a resident page that may be written but isn't mapped writable,
+    /// because fork shared its frame, writable again. It gets a copy of the
+    /// frame if that is still shared. Returns whether it had to be copied,
+    /// or None for a page that isn't like this.
+    fn unshare(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Option<bool> {
+        if !self.map_perm.contains(MapPermission::W)
+            || page_table.translate(vpn).map_or(true, |pte| pte.writable())
+        {
+            return None;
+        }
+        let frame = self.data_frames.get_mut(&vpn)?;
+        let copied = Arc::strong_count(frame) > 1;
+        if copied {
+            let copy = frame_alloc_on_node(self.node).unwrap();
+            copy.ppn
+                .get_bytes_array()
+                .copy_from_slice(frame.ppn.get_bytes_array());
+            *frame = Arc::new(copy);
+        }
+        let ppn = frame.ppn;
+        page_table.unmap(vpn);
+        page_table.map(vpn, ppn, PTEFlags::from_bits(self.map_perm.bits).unwrap());
+        Some(copied)
+    }
+    /// Map a frame for the page, reading it from the file of a file mapping.
+    /// Returns false for a page beyond the end of the file, which is left
+    /// unmapped.
+    fn fault_in(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool {
+        if self.beyond_file_end(vpn) {
+            return false;
+        }
+        self.map_one(page_table, vpn);
+        if let Some(file) = &self.file {
+            let offset = self.file_offset(vpn);
+            file.inode
+                .read_at(offset, self.data_frames[&vpn].ppn.get_bytes_array());
+        }
+        true
+    }
+    /// Write the page back to the file of a writable file mapping, as far as
+    /// the file reaches. A frame still shared after a fork is left to the
+    /// last address space holding it.
+    fn write_back(&self, vpn: VirtPageNum, frame: &Arc<FrameTracker>) {
+        let file = match &self.file {
+            Some(file) if self.map_perm.contains(MapPermission::W) => file,
+            _ => return,
+        };
+        if Arc::strong_count(frame) > 1 {
+            return;
+        }
+        let offset = self.file_offset(vpn);
+        let len = file.inode.size().saturating_sub(offset).min(PAGE_SIZE);
+        if len > 0 {
+            file.inode
+                .write_at(offset, &frame.ppn.get_bytes_array()[..len]);
+        }
+    }
+    /// File offset the page maps
+    fn file_offset(&self, vpn: VirtPageNum) -> usize {
+        let file_offset = self.file.as_ref().map_or(0, |file| file.offset);
+        file_offset + (vpn.0 - self.vpn_range.get_start().0) * PAGE_SIZE
+    }
+    /// Whether the page maps a file but starts at or after its end
+    fn beyond_file_end(&self, vpn: VirtPageNum) -> bool {
+        match &self.file {
+            Some(file) => self.file_offset(vpn) >= file.inode.size(),
+            None => false,
+        }
+    }
+    /// Split the area at `vpn`, which must lie inside it, and return the
+    /// part from `vpn` on
+    fn split_off(&mut self, vpn: VirtPageNum) -> MapArea {
+        let mut tail = MapArea::from_another(self);
+        tail.vpn_range = VPNRange::new(vpn, self.vpn_range.get_end());
+        tail.data_frames = self.data_frames.split_off(&vpn);
+        if let Some(file) = &mut tail.file {
+            file.offset = self.file_offset(vpn);
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), vpn);
+        tail
+    }
+    /// Whether any page of `[start, end)` lies in this area
+    fn overlaps(&self, start: VirtPageNum, end: VirtPageNum) -> bool {
+        start < self.vpn_range.get_end() && self.vpn_range.get_start() < end
+    }
+    /// Whether the page lies in this area
+    pub fn contains(&self, vpn: VirtPageNum) -> bool {
+        self.vpn_range.get_start() <= vpn && vpn < self.vpn_range.get_end()
+    }
+    /// Whether a frame is currently mapped for the page
+    fn is_resident(&self, vpn: VirtPageNum) -> bool {
+        self.data_frames.contains_key(&vpn)
     }
     pub fn map(&mut self, page_table: &mut PageTable) {
+        if self.lazy {
+            return;
+        }
         for vpn in self.vpn_range {
             self.map_one(page_table, vpn);
         }
     }
-    pub fn unmap(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
-            self.unmap_one(page_table, vpn);
-        }
+    /// Unmap every page, returning how many frames were still shared
+    pub fn unmap(&mut self, page_table: &mut PageTable) -> usize {
+        self.vpn_range
+            .into_iter()
+            .filter(|&vpn| self.unmap_one(page_table, vpn))
+            .count()
     }
     /// data: start-aligned but maybe with shorter length
     /// assume that all frames were cleared before
@@ -398,6 +867,65 @@ impl MapArea {
     }
 }
 
+impl Drop for MapArea {
+    /// Write the resident pages of a writable file mapping back, for an
+    /// address space that goes away without unmapping them
+    fn drop(&mut self) {
+        for (&vpn, frame) in self.data_frames.iter() {
+            self.write_back(vpn, frame);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// where a map area came from
+pub enum AreaKind {
+    /// kernel space and the trap context, not visible to the user
+    Kernel,
+    /// executable segment of the elf
+    Text,
+    /// other segments of the elf
+    Data,
+    /// user stack
+    Stack,
+    /// mapped by mmap
+    Mmap,
+}
+
+/// Pages of a user address space by where they came from, like
+/// `/proc/self/statm`
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Statm {
+    /// pages mapped in total
+    pub size: usize,
+    /// pages with a frame behind them
+    pub resident: usize,
+    /// resident pages whose frame is shared with a forked address space
+    pub shared: usize,
+    /// pages of executable elf segments
+    pub text: usize,
+    /// pages of the other elf segments, which include the heap
+    pub data: usize,
+    /// pages of the user stack
+    pub stack: usize,
+    /// pages mapped by mmap
+    pub mmap: usize,
+}
+
+/// How much sharing pages with forked address spaces until they are
+/// written saved
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CowStats {
+    /// resident pages shared with an address space forked from or off this one
+    pub pages_shared: usize,
+    /// writes to a page still shared, each of which copied it
+    pub faults_resolved: usize,
+    /// pages unmapped while still shared, which never had to be copied
+    pub pages_freed_unmodified: usize,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 /// map type for memory set: identical or framed
 pub enum MapType {
@@ -415,6 +943,14 @@ bitflags! {
     }
 }
 
+impl MapPermission {
+    /// Permission of user pages mapped with the `port` of mmap, whose bits 0,
+    /// 1 and 2 ask for them to be readable, writable and executable
+    pub fn from_port(port: u8) -> Self {
+        Self::from_bits((port & 7) << 1).unwrap() | Self::U
+    }
+}
+
 #[allow(unused)]
 pub fn remap_test() {
     let mut kernel_space = KERNEL_SPACE.exclusive_access();
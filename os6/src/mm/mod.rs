@@ -14,10 +14,13 @@ mod page_table;
 
 pub use address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 pub use address::{StepByOne, VPNRange};
-pub use frame_allocator::{frame_alloc, frame_dealloc, FrameTracker};
+pub use frame_allocator::{frame_alloc, frame_alloc_on_node, frame_dealloc, FrameTracker};
+pub use heap_allocator::heap_guard_test;
 pub use memory_set::{kernel_token, remap_test};
-pub use memory_set::{MapPermission, MemorySet, KERNEL_SPACE};
-pub use page_table::{translated_byte_buffer, translated_refmut, translated_str, PageTableEntry};
+pub use memory_set::{CowStats, MapPermission, MemorySet, Statm, KERNEL_SPACE};
+pub use page_table::{copy_from_user, copy_to_user, PageTableEntry};
+pub use page_table::{translated_byte_buffer, translated_refmut, translated_str};
+pub use page_table::{try_copy_to_user, try_translated_refmut};
 pub use page_table::{PTEFlags, PageTable, UserBuffer};
 
 /// initiate heap allocator, frame allocator and kernel space
@@ -1,6 +1,10 @@
 //! Implementation of [`PageTableEntry`] and [`PageTable`].
 
+use super::VPNRange;
 use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use crate::config::{PAGE_SIZE, PATH_MAX};
+use crate::syscall::errno::{EFAULT, ENAMETOOLONG};
+use crate::task::handle_page_fault;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -144,8 +148,36 @@ impl PageTable {
     }
 }
 
+/// Translate a page of the user address space `token`, faulting it back in
+/// first if it was dropped. One to be written gets a frame of its own first
+/// if fork shared it.
+fn translate_user_page(
+    page_table: &PageTable,
+    token: usize,
+    vpn: VirtPageNum,
+    write: bool,
+) -> Option<PhysPageNum> {
+    match page_table.translate(vpn) {
+        Some(pte) if pte.is_valid() && (pte.writable() || !write) => Some(pte.ppn()),
+        _ if handle_page_fault(token, vpn) => page_table.translate(vpn).map(|pte| pte.ppn()),
+        // the user can't write the page, but the kernel still may
+        Some(pte) if pte.is_valid() => Some(pte.ppn()),
+        _ => None,
+    }
+}
+
 /// translate a pointer to a mutable u8 Vec through page table
 pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+    user_byte_buffer(token, ptr, len, true)
+}
+
+/// Like `translated_byte_buffer`, for a buffer that is only read
+fn user_byte_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    write: bool,
+) -> Vec<&'static mut [u8]> {
     let page_table = PageTable::from_token(token);
     let mut start = ptr as usize;
     let end = start + len;
@@ -153,7 +185,7 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
     while start < end {
         let start_va = VirtAddr::from(start);
         let mut vpn = start_va.floor();
-        let ppn = page_table.translate(vpn).unwrap().ppn();
+        let ppn = translate_user_page(&page_table, token, vpn, write).unwrap();
         vpn.step();
         let mut end_va: VirtAddr = vpn.into();
         end_va = end_va.min(VirtAddr::from(end));
@@ -167,15 +199,23 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
     v
 }
 
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
+/// translate a NUL-terminated string from user space
+///
+/// Fails with `-ENAMETOOLONG` if no NUL is found within `PATH_MAX` bytes,
+/// or with `-EFAULT` once it reaches a page that is not mapped.
+pub fn translated_str(token: usize, ptr: *const u8) -> Result<String, isize> {
     let page_table = PageTable::from_token(token);
     let mut string = String::new();
     let mut va = ptr as usize;
     loop {
-        let ch: u8 = *(page_table
-            .translate_va(VirtAddr::from(va))
-            .unwrap()
-            .get_mut());
+        if string.len() >= PATH_MAX {
+            return Err(-ENAMETOOLONG);
+        }
+        let pa = match translate_user_page(&page_table, token, VirtAddr::from(va).floor(), false) {
+            Some(_) => page_table.translate_va(VirtAddr::from(va)).unwrap(),
+            None => return Err(-EFAULT),
+        };
+        let ch: u8 = *pa.get_mut();
         if ch == 0 {
             break;
         } else {
@@ -183,13 +223,59 @@ pub fn translated_str(token: usize, ptr: *const u8) -> String {
             va += 1;
         }
     }
-    string
+    Ok(string)
+}
+
+/// End of the lower half of the Sv39 address space, which user space lies in
+const USER_SPACE_END: usize = 1 << 38;
+
+/// Translate a page of the user address space `token` that the user may
+/// write, faulting it in first like `translate_user_page` does
+fn user_writable_page(
+    page_table: &PageTable,
+    token: usize,
+    vpn: VirtPageNum,
+) -> Option<PhysPageNum> {
+    translate_user_page(page_table, token, vpn, true)?;
+    let pte = page_table.translate(vpn)?;
+    (pte.writable() && pte.flags().contains(PTEFlags::U)).then(|| pte.ppn())
+}
+
+/// Whether the user may write all of the `len` bytes at `va`
+fn user_writable(page_table: &PageTable, token: usize, va: usize, len: usize) -> bool {
+    match va.checked_add(len) {
+        Some(end) if end <= USER_SPACE_END => {
+            VPNRange::new(VirtAddr::from(va).floor(), VirtAddr::from(end).ceil())
+                .into_iter()
+                .all(|vpn| user_writable_page(page_table, token, vpn).is_some())
+        }
+        _ => false,
+    }
 }
 
+/// Like `translated_refmut`, but `None` instead of a panic unless `ptr` is a
+/// naturally aligned `T` within one page the user may write
+pub fn try_translated_refmut<T>(token: usize, ptr: *mut T) -> Option<&'static mut T> {
+    let va = ptr as usize;
+    let size = core::mem::size_of::<T>();
+    if va % core::mem::align_of::<T>() != 0 || va % PAGE_SIZE + size > PAGE_SIZE {
+        return None;
+    }
+    let page_table = PageTable::from_token(token);
+    if !user_writable(&page_table, token, va, size) {
+        return None;
+    }
+    page_table
+        .translate_va(VirtAddr::from(va))
+        .map(|pa| pa.get_mut())
+}
+
+#[allow(unused)]
 pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
     //println!("into translated_refmut!");
     let page_table = PageTable::from_token(token);
     let va = ptr as usize;
+    translate_user_page(&page_table, token, VirtAddr::from(va).floor(), true).unwrap();
     //println!("translated_refmut: before translate_va");
     page_table
         .translate_va(VirtAddr::from(va))
@@ -197,6 +283,43 @@ pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
         .get_mut()
 }
 
+/// Copy a value to user space, even if it straddles a page boundary
+pub fn copy_to_user<T>(token: usize, ptr: *mut T, value: &T) {
+    let src = unsafe {
+        core::slice::from_raw_parts(value as *const T as *const u8, core::mem::size_of::<T>())
+    };
+    let mut offset = 0;
+    for buffer in translated_byte_buffer(token, ptr as *const u8, src.len()) {
+        buffer.copy_from_slice(&src[offset..offset + buffer.len()]);
+        offset += buffer.len();
+    }
+}
+
+/// Like `copy_to_user`, but fails with `-EFAULT` instead of panicking unless
+/// the user may write every byte at `ptr`
+pub fn try_copy_to_user<T>(token: usize, ptr: *mut T, value: &T) -> Result<(), isize> {
+    let page_table = PageTable::from_token(token);
+    if !user_writable(&page_table, token, ptr as usize, core::mem::size_of::<T>()) {
+        return Err(-EFAULT);
+    }
+    copy_to_user(token, ptr, value);
+    Ok(())
+}
+
+/// Copy a value from user space, even if it straddles a page boundary
+pub fn copy_from_user<T: Copy + Default>(token: usize, ptr: *const T) -> T {
+    let mut value = T::default();
+    let dst = unsafe {
+        core::slice::from_raw_parts_mut(&mut value as *mut T as *mut u8, core::mem::size_of::<T>())
+    };
+    let mut offset = 0;
+    for buffer in user_byte_buffer(token, ptr as *const u8, dst.len(), false) {
+        dst[offset..offset + buffer.len()].copy_from_slice(buffer);
+        offset += buffer.len();
+    }
+    value
+}
+
 /// An abstraction over a buffer passed from user space to kernel space
 pub struct UserBuffer {
     pub buffers: Vec<&'static mut [u8]>,
@@ -0,0 +1,50 @@
+//! Error numbers returned by syscalls
+//!
+//! The values follow Linux, and syscalls return them negated.
+
+/// Operation not permitted
+pub const EPERM: isize = 1;
+/// No such file or directory
+pub const ENOENT: isize = 2;
+/// No such process
+pub const ESRCH: isize = 3;
+/// I/O error
+pub const EIO: isize = 5;
+/// Argument list too long
+pub const E2BIG: isize = 7;
+/// Exec format error
+pub const ENOEXEC: isize = 8;
+/// No child processes
+pub const ECHILD: isize = 10;
+/// Out of memory
+pub const ENOMEM: isize = 12;
+/// Permission denied
+pub const EACCES: isize = 13;
+/// Bad address
+pub const EFAULT: isize = 14;
+/// Device or resource busy
+pub const EBUSY: isize = 16;
+/// File exists
+pub const EEXIST: isize = 17;
+/// Cross-device link
+pub const EXDEV: isize = 18;
+/// No such device
+pub const ENODEV: isize = 19;
+/// Not a directory
+pub const ENOTDIR: isize = 20;
+/// Is a directory
+pub const EISDIR: isize = 21;
+/// Invalid argument
+pub const EINVAL: isize = 22;
+/// Too many open files
+pub const EMFILE: isize = 24;
+/// No space left on device
+pub const ENOSPC: isize = 28;
+/// Read-only file system
+pub const EROFS: isize = 30;
+/// Result too large
+pub const ERANGE: isize = 34;
+/// File name too long
+pub const ENAMETOOLONG: isize = 36;
+/// Directory not empty
+pub const ENOTEMPTY: isize = 39;
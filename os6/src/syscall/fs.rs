@@ -1,16 +1,171 @@
 //! File and filesystem-related syscalls
 
+use super::errno::{EINVAL, EMFILE, ENOENT, ENOTDIR, EPERM, ERANGE, ESRCH};
+use crate::fs::absolute_path;
+use crate::fs::check_path;
+use crate::fs::disk_usage;
+use crate::fs::find_path;
+use crate::fs::find_path_at;
+use crate::fs::find_path_at_nofollow;
+use crate::fs::find_path_nofollow;
+use crate::fs::fsevents_open;
 use crate::fs::linkat;
+use crate::fs::make_pipe;
+use crate::fs::mkdir;
+use crate::fs::mount;
+use crate::fs::open_device;
 use crate::fs::open_file;
+use crate::fs::readlink;
+use crate::fs::renameat;
+use crate::fs::statfs;
+use crate::fs::symlink;
+use crate::fs::umount;
 use crate::fs::unlinkat;
+use crate::fs::walk;
+use crate::fs::DiskUsage;
+use crate::fs::File;
+use crate::fs::FsEventMask;
 use crate::fs::OpenFlags;
+use crate::fs::RenameFlags;
+use crate::fs::SeekFrom;
 use crate::fs::Stat;
+use crate::fs::StatFs;
+use crate::fs::WalkRecord;
+use crate::mm::copy_from_user;
+use crate::mm::copy_to_user;
 use crate::mm::translated_byte_buffer;
-use crate::mm::translated_refmut;
 use crate::mm::translated_str;
+use crate::mm::try_copy_to_user;
 use crate::mm::UserBuffer;
 use crate::task::current_task;
 use crate::task::current_user_token;
+use crate::task::pid2task;
+use crate::task::shrink_file_mappings;
+use alloc::string::String;
+use alloc::sync::Arc;
+use easy_fs::block_cache_stats;
+use easy_fs::Inode;
+use easy_fs::BLOCK_SZ;
+
+/// Make a pipe carry packets
+const O_DIRECT: u32 = 0x4000;
+/// Seek from the start of the file
+const SEEK_SET: usize = 0;
+/// Seek from the current offset
+const SEEK_CUR: usize = 1;
+/// Seek from the end of the file
+const SEEK_END: usize = 2;
+/// Resolve relative paths from the current directory
+const AT_FDCWD: usize = -100isize as usize;
+/// Act on a symbolic link itself rather than what it points to
+const AT_SYMLINK_NOFOLLOW: u32 = 0x100;
+/// Mark the fds close-on-exec instead of closing them
+const CLOSE_RANGE_CLOEXEC: u32 = 4;
+/// Give the caller a mount namespace of its own
+const CLONE_NEWNS: usize = 0x20000;
+/// Read into the buffer of an entry
+const IO_OP_READ: u32 = 0;
+/// Write out the buffer of an entry
+const IO_OP_WRITE: u32 = 1;
+/// Write back the filesystem of the file
+const IO_OP_FSYNC: u32 = 2;
+/// Offset of an entry that uses the file offset instead of seeking
+const IO_OFFSET_CURRENT: usize = usize::MAX;
+/// Most entries submitted at once
+const IOSUBMIT_MAX: usize = 256;
+
+/// An I/O operation submitted in a batch to sys_iosubmit
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct IoEntry {
+    pub op: u32,
+    pub fd: u32,
+    pub buf: usize,
+    /// where to seek before the operation, or IO_OFFSET_CURRENT
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Translate a path from user space and check the length of its components
+pub fn translated_path(token: usize, ptr: *const u8) -> Result<String, isize> {
+    let path = translated_str(token, ptr)?;
+    check_path(path.as_str())?;
+    Ok(path)
+}
+
+/// Get the directory relative paths start from for `dirfd`
+fn dir_of(dirfd: usize) -> Result<Arc<Inode>, isize> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let inode = match inner.fd_table.get(dirfd) {
+        Some(Some(file)) => file.inode(),
+        _ => return Err(-1),
+    };
+    match inode {
+        Some(inode) if inode.is_dir() => Ok(inode),
+        _ => Err(-ENOTDIR),
+    }
+}
+
+/// Find the inode a `*at` syscall acts on, which with `AT_SYMLINK_NOFOLLOW`
+/// is a symbolic link itself rather than what it points to
+fn find_inode_at(dirfd: usize, path: *const u8, flags: u32) -> Result<Arc<Inode>, isize> {
+    if flags & !AT_SYMLINK_NOFOLLOW != 0 {
+        return Err(-EINVAL);
+    }
+    let follow = flags & AT_SYMLINK_NOFOLLOW == 0;
+    let path = translated_path(current_user_token(), path)?;
+    // an absolute path doesn't look at dirfd at all
+    let inode = if path.starts_with('/') || dirfd == AT_FDCWD {
+        if follow {
+            find_path(path.as_str())
+        } else {
+            find_path_nofollow(path.as_str())
+        }
+    } else if follow {
+        find_path_at(dir_of(dirfd)?, path.as_str())
+    } else {
+        find_path_at_nofollow(dir_of(dirfd)?, path.as_str())
+    };
+    inode.ok_or(-ENOENT)
+}
+
+/// Make the directory at `path` the one relative paths start from
+pub fn sys_chdir(path: *const u8) -> isize {
+    let path = match translated_path(current_user_token(), path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let dir = match find_path(path.as_str()) {
+        Some(dir) => dir,
+        None => return -ENOENT,
+    };
+    if !dir.is_dir() {
+        return -ENOTDIR;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.cwd_path = absolute_path(&inner.cwd_path, path.as_str());
+    inner.cwd = dir;
+    0
+}
+
+/// Copy the absolute path of the current directory with a trailing NUL into
+/// `buf`, returning its length including the NUL
+pub fn sys_getcwd(buf: *mut u8, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut path = task.inner_exclusive_access().cwd_path.clone();
+    path.push('\0');
+    if path.len() > len {
+        return -ERANGE;
+    }
+    let mut offset = 0;
+    for slice in translated_byte_buffer(current_user_token(), buf, path.len()) {
+        slice.copy_from_slice(&path.as_bytes()[offset..offset + slice.len()]);
+        offset += slice.len();
+    }
+    path.len() as isize
+}
 
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
     let token = current_user_token();
@@ -23,7 +178,7 @@ pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
         let file = file.clone();
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
-        file.write(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        file.write(UserBuffer::new(translated_byte_buffer(token, buf, len)))
     } else {
         -1
     }
@@ -40,7 +195,7 @@ pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
         let file = file.clone();
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
-        file.read(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        file.read(UserBuffer::new(translated_byte_buffer(token, buf, len)))
     } else {
         -1
     }
@@ -49,33 +204,151 @@ pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
 pub fn sys_open(path: *const u8, flags: u32) -> isize {
     let task = current_task().unwrap();
     let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(inode) = open_file(path.as_str(), OpenFlags::from_bits(flags).unwrap()) {
+    let path = match translated_path(token, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    // device files shadow whatever the filesystem has at their path
+    let file = open_device(path.as_str()).or_else(|| {
+        open_file(path.as_str(), OpenFlags::from_bits(flags).unwrap())
+            .map(|inode| inode as Arc<dyn File + Send + Sync>)
+    });
+    if let Some(file) = file {
         let mut inner = task.inner_exclusive_access();
-        let fd = inner.alloc_fd();
-        inner.fd_table[fd] = Some(inode);
-        fd as isize
+        match inner.alloc_fd() {
+            Some(fd) => {
+                inner.fd_table[fd] = Some(file);
+                fd as isize
+            }
+            None => -EMFILE,
+        }
     } else {
         -1
     }
 }
 
+/// Open a stream of every filesystem event in `mask`, which only a privileged
+/// caller may watch
+pub fn sys_fsevents_open(mask: u32) -> isize {
+    let mask = match FsEventMask::from_bits(mask) {
+        Some(mask) if !mask.is_empty() && !mask.contains(FsEventMask::OVERFLOW) => mask,
+        _ => return -EINVAL,
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !inner.is_privileged() {
+        return -EPERM;
+    }
+    match inner.alloc_fd() {
+        Some(fd) => {
+            inner.fd_table[fd] = Some(fsevents_open(mask));
+            fd as isize
+        }
+        None => -EMFILE,
+    }
+}
+
+/// Create a pipe, writing the fds of its read and write ends to `pipe`.
+/// With O_DIRECT it carries packets instead of a byte stream.
+pub fn sys_pipe2(pipe: *mut usize, flags: u32) -> isize {
+    if flags & !O_DIRECT != 0 {
+        return -EINVAL;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let (pipe_read, pipe_write) = make_pipe(flags & O_DIRECT != 0);
+    let read_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
+    inner.fd_table[read_fd] = Some(pipe_read);
+    let write_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => {
+            inner.close_fd(read_fd);
+            return -EMFILE;
+        }
+    };
+    inner.fd_table[write_fd] = Some(pipe_write);
+    drop(inner);
+    let token = current_user_token();
+    copy_to_user(token, pipe, &read_fd);
+    copy_to_user(token, unsafe { pipe.add(1) }, &write_fd);
+    0
+}
+
 pub fn sys_close(fd: usize) -> isize {
     let task = current_task().unwrap();
     let mut inner = task.inner_exclusive_access();
-    if fd >= inner.fd_table.len() {
-        return -1;
+    if inner.close_fd(fd) {
+        0
+    } else {
+        -1
     }
-    if inner.fd_table[fd].is_none() {
-        return -1;
+}
+
+/// Duplicate `fd` onto the lowest free fd
+pub fn sys_dup(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let file = match inner.fd_table.get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return -1,
+    };
+    match inner.alloc_fd() {
+        Some(new_fd) => {
+            inner.fd_table[new_fd] = Some(file);
+            new_fd as isize
+        }
+        None => -EMFILE,
+    }
+}
+
+/// Duplicate `old_fd` onto `new_fd`, closing whatever `new_fd` was first
+pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let file = match inner.fd_table.get(old_fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return -1,
+    };
+    // closing new_fd would lose it when it already is old_fd
+    if old_fd == new_fd {
+        return new_fd as isize;
+    }
+    if inner.install_fd(new_fd, file) {
+        new_fd as isize
+    } else {
+        -1
+    }
+}
+
+/// Close every open fd in `[first, last]`, or with CLOSE_RANGE_CLOEXEC mark them close-on-exec
+pub fn sys_close_range(first: usize, last: usize, flags: u32) -> isize {
+    if flags & !CLOSE_RANGE_CLOEXEC != 0 || first > last {
+        return -EINVAL;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    // slots past the end of the table are never open
+    let end = inner.fd_table.len().min(last.saturating_add(1));
+    for fd in first..end {
+        if inner.fd_table[fd].is_none() {
+            continue;
+        }
+        if flags & CLOSE_RANGE_CLOEXEC != 0 {
+            inner.cloexec.insert(fd);
+        } else {
+            inner.close_fd(fd);
+        }
     }
-    inner.fd_table[fd].take();
     0
 }
 
 // YOUR JOB: 扩展 easy-fs 和内核以实现以下三个 syscall
+/// Write what `File::stat` of `fd` gives to `st`, which fails with -EFAULT
+/// if the task can't write it
 pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
-    let st = translated_refmut(current_user_token(), st);
     let task = current_task().unwrap();
     let inner = task.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
@@ -85,8 +358,10 @@ pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
         let file = file.clone();
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
-        *st = file.stat();
-        0
+        match try_copy_to_user(current_user_token(), st, &file.stat()) {
+            Ok(()) => 0,
+            Err(errno) => errno,
+        }
     } else {
         -1
     }
@@ -94,13 +369,504 @@ pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
 
 pub fn sys_linkat(old_name: *const u8, new_name: *const u8) -> isize {
     let token = current_user_token();
-    let old_path = translated_str(token, old_name);
-    let new_path = translated_str(token, new_name);
+    let old_path = match translated_path(token, old_name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let new_path = match translated_path(token, new_name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
     linkat(old_path.as_str(), new_path.as_str())
 }
 
+pub fn sys_symlinkat(target: *const u8, link_name: *const u8) -> isize {
+    let token = current_user_token();
+    let target = match translated_path(token, target) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let link_path = match translated_path(token, link_name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    symlink(target.as_str(), link_path.as_str())
+}
+
+/// Copy the target of a symbolic link into `buf`, cut short to `len` bytes
+/// and with no terminating NUL
+pub fn sys_readlinkat(name: *const u8, buf: *mut u8, len: usize) -> isize {
+    let token = current_user_token();
+    let path = match translated_path(token, name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let target = match readlink(path.as_str()) {
+        Ok(target) => target,
+        Err(errno) => return errno,
+    };
+    let len = len.min(target.len());
+    let mut offset = 0;
+    for slice in translated_byte_buffer(token, buf, len) {
+        slice.copy_from_slice(&target.as_bytes()[offset..offset + slice.len()]);
+        offset += slice.len();
+    }
+    len as isize
+}
+
 pub fn sys_unlinkat(name: *const u8) -> isize {
     let token = current_user_token();
-    let path = translated_str(token, name);
+    let path = match translated_path(token, name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
     unlinkat(path.as_str())
 }
+
+/// Cut the file down or grow it to `length` bytes. Mapped pages beyond its
+/// new end are dropped, so touching them is a bus error.
+fn truncate_file(file: &dyn File, length: usize) -> isize {
+    let ret = file.truncate(length);
+    if ret == 0 {
+        if let Some(inode) = file.inode() {
+            shrink_file_mappings(&inode, length);
+        }
+    }
+    ret
+}
+
+pub fn sys_truncate(path: *const u8, length: usize) -> isize {
+    let path = match translated_path(current_user_token(), path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    match open_file(path.as_str(), OpenFlags::WRONLY) {
+        Some(file) => truncate_file(&*file, length),
+        None => -1,
+    }
+}
+
+pub fn sys_ftruncate(fd: usize, length: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let file = match inner.fd_table.get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return -1,
+    };
+    // release current task TCB manually to avoid multi-borrow
+    drop(inner);
+    truncate_file(&*file, length)
+}
+
+pub fn sys_fallocate(fd: usize, mode: usize, offset: usize, len: usize) -> isize {
+    if len == 0 {
+        return -EINVAL;
+    }
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.fallocate(mode, offset, len)
+    } else {
+        -1
+    }
+}
+
+pub fn sys_enable_verity(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.enable_verity()
+    } else {
+        -1
+    }
+}
+
+pub fn sys_fsync(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.fsync()
+    } else {
+        -1
+    }
+}
+
+pub fn sys_readahead(fd: usize, offset: usize, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.readahead(offset, len)
+    } else {
+        -1
+    }
+}
+
+pub fn sys_fadvise(fd: usize, offset: usize, len: usize, advice: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.fadvise(offset, len, advice)
+    } else {
+        -1
+    }
+}
+
+/// Write to `vec` a byte for each block of the file covering
+/// `[offset, offset + len)`, 1 if it is in the block cache and 0 if not,
+/// returning how many blocks there were
+pub fn sys_fincore(fd: usize, offset: usize, len: usize, vec: *mut u8) -> isize {
+    if offset % BLOCK_SZ != 0 {
+        return -EINVAL;
+    }
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let file = match inner.fd_table.get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return -1,
+    };
+    // release current task TCB manually to avoid multi-borrow
+    drop(inner);
+    let inode = match file.inode() {
+        Some(inode) => inode,
+        None => return -EINVAL,
+    };
+    let cached = inode.cached_blocks(offset, len);
+    let buffer = UserBuffer::new(translated_byte_buffer(
+        current_user_token(),
+        vec,
+        cached.len(),
+    ));
+    for (dst, &resident) in buffer.into_iter().zip(cached.iter()) {
+        unsafe {
+            *dst = resident as u8;
+        }
+    }
+    cached.len() as isize
+}
+
+/// Write back and drop the cached blocks of the file `fd`, returning how many
+/// there were
+pub fn sys_drop_file_cache(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let file = match inner.fd_table.get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return -1,
+    };
+    // release current task TCB manually to avoid multi-borrow
+    drop(inner);
+    match file.inode() {
+        Some(inode) => inode.drop_cache() as isize,
+        None => -EINVAL,
+    }
+}
+
+/// Hits, misses and evictions of the block cache since boot
+#[repr(C)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+}
+
+pub fn sys_block_cache_stats(stats: *mut CacheStats) -> isize {
+    let (hits, misses, evictions) = block_cache_stats();
+    copy_to_user(
+        current_user_token(),
+        stats,
+        &CacheStats {
+            hits,
+            misses,
+            evictions,
+        },
+    );
+    0
+}
+
+pub fn sys_renameat2(old_name: *const u8, new_name: *const u8, flags: u32) -> isize {
+    let flags = match RenameFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return -EINVAL,
+    };
+    let token = current_user_token();
+    let old_path = match translated_path(token, old_name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let new_path = match translated_path(token, new_name) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    renameat(old_path.as_str(), new_path.as_str(), flags)
+}
+
+/// Run an entry of a batch, or give None if it is malformed
+fn run_io_entry(token: usize, entry: &IoEntry) -> Option<isize> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let file = inner.fd_table.get(entry.fd as usize)?.clone()?;
+    // release current task TCB manually to avoid multi-borrow
+    drop(inner);
+    let allowed = match entry.op {
+        IO_OP_READ => file.readable(),
+        IO_OP_WRITE => file.writable(),
+        IO_OP_FSYNC => file.inode().is_some(),
+        _ => false,
+    };
+    if !allowed {
+        return None;
+    }
+    if entry.op == IO_OP_FSYNC {
+        file.inode().unwrap().flush_fs();
+        return Some(0);
+    }
+    if entry.offset != IO_OFFSET_CURRENT {
+        let pos = file.seek(SeekFrom::Start(entry.offset));
+        if pos < 0 {
+            return Some(pos);
+        }
+    }
+    let buf = UserBuffer::new(translated_byte_buffer(
+        token,
+        entry.buf as *const u8,
+        entry.len,
+    ));
+    Some(if entry.op == IO_OP_READ {
+        file.read(buf)
+    } else {
+        file.write(buf)
+    })
+}
+
+/// Run the `n` entries at `entries` in order, writing the result of each to
+/// `completions`. A malformed entry completes with -EINVAL and stops the batch,
+/// so the number of entries run before it is returned.
+pub fn sys_iosubmit(entries: *const IoEntry, n: usize, completions: *mut isize) -> isize {
+    if n > IOSUBMIT_MAX {
+        return -EINVAL;
+    }
+    let token = current_user_token();
+    for i in 0..n {
+        let entry: IoEntry = copy_from_user(token, entries.wrapping_add(i));
+        let result = run_io_entry(token, &entry);
+        copy_to_user(
+            token,
+            completions.wrapping_add(i),
+            &result.unwrap_or(-EINVAL),
+        );
+        if result.is_none() {
+            return i as isize;
+        }
+    }
+    n as isize
+}
+
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    let pos = match whence {
+        SEEK_SET if offset >= 0 => SeekFrom::Start(offset as usize),
+        SEEK_CUR => SeekFrom::Current(offset),
+        SEEK_END => SeekFrom::End(offset),
+        _ => return -EINVAL,
+    };
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.seek(pos)
+    } else {
+        -1
+    }
+}
+
+pub fn sys_getdents64(fd: usize, buf: *const u8, len: usize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &inner.fd_table[fd] {
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(inner);
+        file.getdents(UserBuffer::new(translated_byte_buffer(token, buf, len)))
+    } else {
+        -1
+    }
+}
+
+/// Walk the tree below the directory `path`, filling `buf` with a record per
+/// entry. `cursor` counts the records returned so far, so calling again with
+/// it continues where the last call stopped, and 0 bytes mean the walk is done.
+pub fn sys_walk(path: *const u8, buf: *const u8, len: usize, cursor: *mut usize) -> isize {
+    let token = current_user_token();
+    let path = match translated_path(token, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let dir = match find_path(path.as_str()) {
+        Some(dir) if dir.is_dir() => dir,
+        Some(_) => return -ENOTDIR,
+        None => return -ENOENT,
+    };
+    let skip: usize = copy_from_user(token, cursor);
+    let filled = walk(
+        dir,
+        skip,
+        UserBuffer::new(translated_byte_buffer(token, buf, len)),
+    );
+    if filled > 0 {
+        let next = skip + filled as usize / core::mem::size_of::<WalkRecord>();
+        copy_to_user(token, cursor, &next);
+    }
+    filled
+}
+
+pub fn sys_mkdirat(path: *const u8) -> isize {
+    let token = current_user_token();
+    let path = match translated_path(token, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    mkdir(path.as_str())
+}
+
+pub fn sys_disk_usage(path: *const u8, usage: *mut DiskUsage) -> isize {
+    let token = current_user_token();
+    let path = match translated_path(token, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    match disk_usage(path.as_str()) {
+        Some(du) => match try_copy_to_user(token, usage, &du) {
+            Ok(()) => 0,
+            Err(errno) => errno,
+        },
+        None => -ENOENT,
+    }
+}
+
+pub fn sys_statfs(path: *const u8, buf: *mut StatFs) -> isize {
+    let token = current_user_token();
+    let path = match translated_path(token, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    match statfs(path.as_str()) {
+        Some(stats) => match try_copy_to_user(token, buf, &stats) {
+            Ok(()) => 0,
+            Err(errno) => errno,
+        },
+        None => -ENOENT,
+    }
+}
+
+pub fn sys_fchmodat(dirfd: usize, path: *const u8, mode: u32, flags: u32) -> isize {
+    match find_inode_at(dirfd, path, flags) {
+        Ok(inode) => {
+            inode.chmod(mode);
+            0
+        }
+        Err(errno) => errno,
+    }
+}
+
+/// An id of `u32::MAX`, or -1, leaves it unchanged
+pub fn sys_fchownat(dirfd: usize, path: *const u8, uid: u32, gid: u32, flags: u32) -> isize {
+    match find_inode_at(dirfd, path, flags) {
+        Ok(inode) => {
+            let keep = |id: u32| if id == u32::MAX { None } else { Some(id) };
+            inode.chown(keep(uid), keep(gid));
+            0
+        }
+        Err(errno) => errno,
+    }
+}
+
+pub fn sys_mount(dev: usize, target: *const u8) -> isize {
+    let token = current_user_token();
+    let target = match translated_path(token, target) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    mount(dev, target.as_str())
+}
+
+pub fn sys_umount(target: *const u8) -> isize {
+    let token = current_user_token();
+    let target = match translated_path(token, target) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    umount(target.as_str())
+}
+
+/// Stop sharing the namespaces in `flags` with other processes. Mounts made
+/// after unsharing the mount namespace only show in the caller and its
+/// future children.
+pub fn sys_unshare(flags: usize) -> isize {
+    if flags & !CLONE_NEWNS != 0 {
+        return -EINVAL;
+    }
+    if flags & CLONE_NEWNS != 0 {
+        let task = current_task().unwrap();
+        let mut inner = task.inner_exclusive_access();
+        inner.mount_ns = inner.mount_ns.unshare();
+    }
+    0
+}
+
+/// Join the mount namespace of the process `pid`. `nstype` may be 0 or
+/// CLONE_NEWNS, as that is the only kind of namespace. Unless root, the
+/// caller must be the same user as `pid`, or it gets -EPERM.
+pub fn sys_setns(pid: usize, nstype: usize) -> isize {
+    if nstype != 0 && nstype != CLONE_NEWNS {
+        return -EINVAL;
+    }
+    let target = match pid2task(pid) {
+        Some(task) => task,
+        None => return -ESRCH,
+    };
+    let task = current_task().unwrap();
+    if !task.may_act_on(&target) {
+        return -EPERM;
+    }
+    let ns = target.inner_exclusive_access().mount_ns.clone();
+    task.inner_exclusive_access().mount_ns = ns;
+    0
+}
@@ -10,59 +10,219 @@
 //! `sys_` then the name of the syscall. You can find functions like this in
 //! submodules, and you should also implement syscalls this way.
 
+const SYSCALL_GETCWD: usize = 17;
+const SYSCALL_DUP: usize = 24;
+const SYSCALL_MKDIRAT: usize = 34;
 const SYSCALL_UNLINKAT: usize = 35;
+const SYSCALL_SYMLINKAT: usize = 36;
 const SYSCALL_LINKAT: usize = 37;
+const SYSCALL_UMOUNT2: usize = 39;
+const SYSCALL_MOUNT: usize = 40;
+const SYSCALL_STATFS: usize = 43;
+const SYSCALL_TRUNCATE: usize = 45;
+const SYSCALL_FTRUNCATE: usize = 46;
+const SYSCALL_FALLOCATE: usize = 47;
+const SYSCALL_CHDIR: usize = 49;
+const SYSCALL_FCHMODAT: usize = 53;
+const SYSCALL_FCHOWNAT: usize = 54;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE2: usize = 59;
+const SYSCALL_GETDENTS64: usize = 61;
+const SYSCALL_LSEEK: usize = 62;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_READLINKAT: usize = 78;
 const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_FSYNC: usize = 82;
+const SYSCALL_READAHEAD: usize = 213;
+const SYSCALL_FADVISE: usize = 223;
+const SYSCALL_RENAMEAT2: usize = 276;
+const SYSCALL_SETNS: usize = 268;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_UNSHARE: usize = 97;
+const SYSCALL_SET_ROBUST_LIST: usize = 99;
+const SYSCALL_GET_ROBUST_LIST: usize = 100;
+const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_SCHED_SETSCHEDULER: usize = 119;
+const SYSCALL_SCHED_SETAFFINITY: usize = 122;
+const SYSCALL_SCHED_GETAFFINITY: usize = 123;
+const SYSCALL_SCHED_RR_GET_INTERVAL: usize = 127;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_SETTIMEOFDAY: usize = 170;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SIGPENDING: usize = 136;
+const SYSCALL_GETRUSAGE: usize = 165;
+const SYSCALL_GETUID: usize = 174;
+const SYSCALL_SETUID: usize = 146;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
+const SYSCALL_SET_MEMPOLICY: usize = 237;
 const SYSCALL_WAITPID: usize = 260;
 const SYSCALL_SPAWN: usize = 400;
 const SYSCALL_MUNMAP: usize = 215;
 const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
 const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_SETPGID: usize = 154;
+const SYSCALL_GETPGID: usize = 155;
+const SYSCALL_WAITID: usize = 95;
 const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_CLOSE_RANGE: usize = 436;
+const SYSCALL_PROCESS_MADVISE: usize = 440;
+const SYSCALL_ENABLE_VERITY: usize = 460;
+const SYSCALL_DISK_USAGE: usize = 461;
+const SYSCALL_STATM: usize = 462;
+const SYSCALL_SYSCALL_TRACE: usize = 463;
+const SYSCALL_SET_TIMESLICE: usize = 464;
+const SYSCALL_FEXECVE: usize = 465;
+const SYSCALL_FSEVENTS_OPEN: usize = 466;
+const SYSCALL_PREFAULT: usize = 467;
+const SYSCALL_IOSUBMIT: usize = 468;
+const SYSCALL_WALK: usize = 469;
+const SYSCALL_YIELD_REMAINING: usize = 470;
+const SYSCALL_MSEAL: usize = 471;
+const SYSCALL_FINCORE: usize = 472;
+const SYSCALL_DROP_FILE_CACHE: usize = 473;
+const SYSCALL_BLOCK_CACHE_STATS: usize = 474;
+const SYSCALL_SCHED_DEBUG: usize = 475;
+const SYSCALL_COW_STATS: usize = 476;
+const SYSCALL_SYSCALL_TIME: usize = 477;
+const SYSCALL_DUP2: usize = 478;
+const SYSCALL_NICE: usize = 479;
 
+pub mod errno;
 mod fs;
 pub mod process;
 
 use crate::{
-    fs::Stat,
-    task::{inc_task_syscall_times, TaskInfo},
+    fs::{DiskUsage, Stat, StatFs},
+    mm::{CowStats, Statm},
+    task::{inc_task_syscall_times, RUsage, SchedDebugEntry, SyscallRecord, TaskInfo},
 };
 use fs::*;
 use process::*;
 
 /// handle syscall exception with `syscall_id` and other arguments
-pub fn syscall(syscall_id: usize, args: [usize; 4]) -> isize {
+pub fn syscall(syscall_id: usize, args: [usize; 6]) -> isize {
     inc_task_syscall_times(syscall_id);
     match syscall_id {
         SYSCALL_LINKAT => sys_linkat(args[1] as *const u8, args[3] as *const u8),
+        SYSCALL_MKDIRAT => sys_mkdirat(args[1] as *const u8),
         SYSCALL_UNLINKAT => sys_unlinkat(args[1] as *const u8),
+        SYSCALL_SYMLINKAT => sys_symlinkat(args[0] as *const u8, args[2] as *const u8),
+        SYSCALL_READLINKAT => sys_readlinkat(args[1] as *const u8, args[2] as *mut u8, args[3]),
+        SYSCALL_UMOUNT2 => sys_umount(args[0] as *const u8),
+        SYSCALL_MOUNT => sys_mount(args[0], args[1] as *const u8),
+        SYSCALL_STATFS => sys_statfs(args[0] as *const u8, args[1] as *mut StatFs),
+        SYSCALL_TRUNCATE => sys_truncate(args[0] as *const u8, args[1]),
+        SYSCALL_FTRUNCATE => sys_ftruncate(args[0], args[1]),
+        SYSCALL_FALLOCATE => sys_fallocate(args[0], args[1], args[2], args[3]),
+        SYSCALL_FCHMODAT => sys_fchmodat(
+            args[0],
+            args[1] as *const u8,
+            args[2] as u32,
+            args[3] as u32,
+        ),
+        SYSCALL_FCHOWNAT => sys_fchownat(
+            args[0],
+            args[1] as *const u8,
+            args[2] as u32,
+            args[3] as u32,
+            args[4] as u32,
+        ),
         SYSCALL_OPEN => sys_open(args[1] as *const u8, args[2] as u32),
+        SYSCALL_GETCWD => sys_getcwd(args[0] as *mut u8, args[1]),
+        SYSCALL_CHDIR => sys_chdir(args[0] as *const u8),
+        SYSCALL_DUP => sys_dup(args[0]),
+        SYSCALL_DUP2 => sys_dup2(args[0], args[1]),
         SYSCALL_CLOSE => sys_close(args[0]),
+        SYSCALL_PIPE2 => sys_pipe2(args[0] as *mut usize, args[1] as u32),
+        SYSCALL_CLOSE_RANGE => sys_close_range(args[0], args[1], args[2] as u32),
+        SYSCALL_GETDENTS64 => sys_getdents64(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_LSEEK => sys_lseek(args[0], args[1] as isize, args[2]),
         SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
         SYSCALL_FSTAT => sys_fstat(args[0], args[1] as *mut Stat),
+        SYSCALL_FSYNC => sys_fsync(args[0]),
+        SYSCALL_RENAMEAT2 => {
+            sys_renameat2(args[1] as *const u8, args[3] as *const u8, args[4] as u32)
+        }
+        SYSCALL_READAHEAD => sys_readahead(args[0], args[1], args[2]),
+        SYSCALL_FADVISE => sys_fadvise(args[0], args[1], args[2], args[3]),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_KILL => sys_kill(args[0], args[1] as u32),
+        SYSCALL_SIGPROCMASK => sys_sigprocmask(args[0], args[1] as *const u32, args[2] as *mut u32),
+        SYSCALL_SIGPENDING => sys_sigpending(args[0] as *mut u32),
+        SYSCALL_GETRUSAGE => sys_getrusage(args[0], args[1] as *mut RUsage),
+        SYSCALL_UNSHARE => sys_unshare(args[0]),
+        SYSCALL_SETNS => sys_setns(args[0], args[1]),
+        SYSCALL_SET_ROBUST_LIST => sys_set_robust_list(args[0], args[1]),
+        SYSCALL_GET_ROBUST_LIST => {
+            sys_get_robust_list(args[0], args[1] as *mut usize, args[2] as *mut usize)
+        }
+        SYSCALL_SCHED_SETSCHEDULER => sys_sched_setscheduler(args[0], args[1], args[2]),
+        SYSCALL_SCHED_SETAFFINITY => sys_sched_setaffinity(args[0], args[1]),
+        SYSCALL_SCHED_GETAFFINITY => sys_sched_getaffinity(args[0], args[1] as *mut usize),
+        SYSCALL_SCHED_RR_GET_INTERVAL => {
+            sys_sched_rr_get_interval(args[0], args[1] as *mut TimeSpec)
+        }
+        SYSCALL_SET_TIMESLICE => sys_set_timeslice(args[0]),
+        SYSCALL_SLEEP => sys_sleep(args[0]),
         SYSCALL_YIELD => sys_yield(),
+        SYSCALL_YIELD_REMAINING => sys_yield_remaining(),
         SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_GETPPID => sys_getppid(),
+        SYSCALL_GETUID => sys_getuid(),
+        SYSCALL_SETUID => sys_setuid(args[0]),
         SYSCALL_FORK => sys_fork(),
-        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
-        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
-        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
-        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_EXEC => sys_exec(
+            args[0] as *const u8,
+            args[1] as *const usize,
+            args[2] as *const usize,
+        ),
+        SYSCALL_FEXECVE => sys_fexecve(args[0], args[1] as *const usize, args[2] as *const usize),
+        SYSCALL_SET_MEMPOLICY => sys_set_mempolicy(args[0]),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32, args[2]),
+        SYSCALL_WAITID => sys_waitid(args[0], args[1], args[2] as *mut SigInfo, args[3]),
+        SYSCALL_SETPGID => sys_setpgid(args[0], args[1]),
+        SYSCALL_GETPGID => sys_getpgid(args[0]),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1] as *mut TimeZone),
+        SYSCALL_SETTIMEOFDAY => {
+            sys_settimeofday(args[0] as *const TimeVal, args[1] as *const TimeZone)
+        }
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2], args[3], args[4], args[5]),
         SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_MPROTECT => sys_mprotect(args[0], args[1], args[2]),
+        SYSCALL_MSEAL => sys_mseal(args[0], args[1]),
+        SYSCALL_FINCORE => sys_fincore(args[0], args[1], args[2], args[3] as *mut u8),
+        SYSCALL_DROP_FILE_CACHE => sys_drop_file_cache(args[0]),
+        SYSCALL_BLOCK_CACHE_STATS => sys_block_cache_stats(args[0] as *mut CacheStats),
+        SYSCALL_SCHED_DEBUG => sys_sched_debug(args[0] as *mut SchedDebugEntry, args[1]),
+        SYSCALL_COW_STATS => sys_cow_stats(args[0] as *mut CowStats),
+        SYSCALL_SYSCALL_TIME => sys_syscall_time(args[0] as *mut usize, args[1]),
         SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_NICE => sys_nice(args[0] as isize),
         SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
-        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8, args[1] as *const usize),
+        SYSCALL_PROCESS_MADVISE => sys_process_madvise(args[0], args[1], args[2], args[3]),
+        SYSCALL_PREFAULT => sys_prefault(args[0], args[1]),
+        SYSCALL_ENABLE_VERITY => sys_enable_verity(args[0]),
+        SYSCALL_STATM => sys_statm(args[0] as *mut Statm),
+        SYSCALL_SYSCALL_TRACE => sys_syscall_trace(args[0] as *mut SyscallRecord, args[1]),
+        SYSCALL_DISK_USAGE => sys_disk_usage(args[0] as *const u8, args[1] as *mut DiskUsage),
+        SYSCALL_FSEVENTS_OPEN => sys_fsevents_open(args[0] as u32),
+        SYSCALL_WALK => sys_walk(
+            args[0] as *const u8,
+            args[1] as *const u8,
+            args[2],
+            args[3] as *mut usize,
+        ),
+        SYSCALL_IOSUBMIT => sys_iosubmit(args[0] as *const IoEntry, args[1], args[2] as *mut isize),
         _ => panic!("Unsupported syscall_id: {}", syscall_id),
     }
 }
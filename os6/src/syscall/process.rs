@@ -1,21 +1,100 @@
 //! Process management syscalls
 
-use crate::fs::{open_file, OpenFlags};
-use crate::mm::{translated_refmut, translated_str, VirtAddr};
+use super::errno::{
+    E2BIG, EACCES, ECHILD, EFAULT, EINVAL, EIO, ENODEV, ENOEXEC, ENOMEM, EPERM, ESRCH,
+};
+use super::fs::translated_path;
+use crate::config::{ARG_MAX, HART_NUM, MEMORY_NODES, PAGE_SIZE};
+use crate::fs::find_path;
+use crate::mm::{
+    copy_from_user, copy_to_user, translated_byte_buffer, translated_str, try_copy_to_user,
+    try_translated_refmut, CowStats, Statm, UserBuffer, VirtAddr,
+};
 use crate::task::{
-    add_task, current_task, current_user_token, exit_current_and_run_next, get_current_task_info,
-    mmap, munmap, set_current_task_prio, suspend_current_and_run_next, TaskInfo,
+    add_task, current_syscall_time, current_syscall_trace, current_task, current_user_token,
+    exit_current_and_run_next, get_current_task_info, ignored_by_default, mmap, mmap_file,
+    mprotect, munmap, nice_current_task, pid2task, sched_debug, set_current_task_prio,
+    set_task_policy, sigmask, sleep_current_and_run_next, stops_by_default,
+    suspend_current_and_run_next, wake_task, yield_remaining_and_run_next, RUsage, SchedDebugEntry,
+    SchedPolicy, SyscallRecord, TaskControlBlock, TaskInfo, INITPROC, MIN_PRIO, NSIG,
+    ROBUST_LIST_HEAD_SIZE, SIGCHLD, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK, UNBLOCKABLE,
 };
-use crate::timer::get_time_us;
-use alloc::sync::Arc;
+use crate::timer::{get_time_us, get_wall_time_us, set_timezone, set_wall_time_us, timezone};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use easy_fs::Inode;
+
+/// Share the pages with the file they map
+const MAP_SHARED: usize = 0x01;
+/// The range will be accessed soon, fault it in
+const MADV_WILLNEED: usize = 3;
+/// The range will not be accessed soon, drop its pages
+const MADV_DONTNEED: usize = 4;
+
+/// Wait for any child
+const P_ALL: usize = 0;
+/// Wait for the child with the given pid
+const P_PID: usize = 1;
+/// Wait for any child in the given process group
+const P_PGID: usize = 2;
+/// Return at once if no child has exited
+const WNOHANG: usize = 1;
+/// Wait for children that exited
+const WEXITED: usize = 4;
+/// Leave the child reapable
+const WNOWAIT: usize = 0x0100_0000;
+/// Have waitpid write a wait status instead of the bare exit code
+const WSTATUS: usize = 0x4000_0000;
+/// The child exited by itself
+const CLD_EXITED: i32 = 1;
+/// The child was killed by a signal
+const CLD_KILLED: i32 = 2;
 
+/// Share the CPU with the other tasks by stride
+const SCHED_STRIDE: usize = 0;
+/// Run ahead of all stride tasks until giving up the CPU
+const SCHED_FIFO: usize = 1;
+/// Highest priority of a FIFO task
+const FIFO_PRIO_MAX: usize = 99;
+
+/// How a child terminated, as filled in by `sys_waitid`
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Default)]
+pub struct SigInfo {
+    pub si_signo: i32,
+    /// CLD_EXITED or CLD_KILLED
+    pub si_code: i32,
+    pub si_pid: i32,
+    /// exit code, or the signal that killed the child
+    pub si_status: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct TimeVal {
     pub sec: usize,
     pub usec: usize,
 }
 
+/// The timezone as `sys_get_time` reports it
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeZone {
+    /// minutes west of Greenwich
+    pub minuteswest: i32,
+    /// type of DST correction
+    pub dsttime: i32,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     debug!("[kernel] Application exited with code {}", exit_code);
     exit_current_and_run_next(exit_code);
@@ -28,10 +107,31 @@ pub fn sys_yield() -> isize {
     0
 }
 
+/// Park the current task for at least `ms` milliseconds, running others meanwhile
+pub fn sys_sleep(ms: usize) -> isize {
+    sleep_current_and_run_next(get_time_us().saturating_add(ms.saturating_mul(1000)));
+    0
+}
+
+/// Yield like sys_yield, but only be charged for the part of the time slice used
+pub fn sys_yield_remaining() -> isize {
+    yield_remaining_and_run_next();
+    0
+}
+
 pub fn sys_getpid() -> isize {
     current_task().unwrap().pid.0 as isize
 }
 
+/// Pid of the parent, which is initproc for an orphan, or 0 for initproc itself
+pub fn sys_getppid() -> isize {
+    let task = current_task().unwrap();
+    let parent = task.inner_exclusive_access().parent.clone();
+    parent
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.getpid() as isize)
+}
+
 /// Syscall Fork which returns 0 for child process and child_pid for parent process
 pub fn sys_fork() -> isize {
     let current_task = current_task().unwrap();
@@ -47,23 +147,144 @@ pub fn sys_fork() -> isize {
     new_pid as isize
 }
 
-/// Syscall Exec which accepts the elf path
-pub fn sys_exec(path: *const u8) -> isize {
+/// Read the program in `inode`, checking that it is an ELF before it gets loaded
+fn read_elf(inode: &Inode) -> Result<Vec<u8>, isize> {
+    if inode.is_dir() {
+        return Err(-EACCES);
+    }
+    let mut elf_data = vec![0u8; inode.size()];
+    if inode.try_read_at(0, &mut elf_data) != Some(elf_data.len()) {
+        return Err(-EIO);
+    }
+    if !elf_data.starts_with(b"\x7fELF") {
+        return Err(-ENOEXEC);
+    }
+    Ok(elf_data)
+}
+
+/// Find the program at `path` in the filesystem and read it
+///
+/// Relative paths start at the current directory, like every other path.
+fn load_elf(path: *const u8) -> Result<(String, Vec<u8>), isize> {
+    let path = translated_path(current_user_token(), path)?;
+    match find_path(path.as_str()) {
+        Some(inode) => {
+            let name = path.rsplit('/').next().unwrap_or_default();
+            Ok((String::from(name), read_elf(&inode)?))
+        }
+        None => Err(-1),
+    }
+}
+
+/// Copy the null-terminated array of strings at `argv` from user space,
+/// a null `argv` being an empty array
+///
+/// Each string takes its bytes, its NUL and its pointer out of the `room`
+/// left on the new stack, the null ending the array one more pointer.
+/// Fails with `-E2BIG` once they don't fit.
+fn translated_args(
+    token: usize,
+    argv: *const usize,
+    room: &mut usize,
+) -> Result<Vec<String>, isize> {
+    let word = core::mem::size_of::<usize>();
+    *room = room.checked_sub(word).ok_or(-E2BIG)?;
+    let mut args = Vec::new();
+    if argv.is_null() {
+        return Ok(args);
+    }
+    loop {
+        let ptr = copy_from_user(token, unsafe { argv.add(args.len()) });
+        if ptr == 0 {
+            return Ok(args);
+        }
+        let arg = translated_str(token, ptr as *const u8)?;
+        *room = room.checked_sub(arg.len() + 1 + word).ok_or(-E2BIG)?;
+        args.push(arg);
+    }
+}
+
+/// Copy the arguments and environment of a program to exec from user space,
+/// failing with `-E2BIG` if together they take more than `ARG_MAX` bytes
+fn translated_exec_args(
+    argv: *const usize,
+    envp: *const usize,
+) -> Result<(Vec<String>, Vec<String>), isize> {
     let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
-        let all_data = app_inode.read_all();
-        let task = current_task().unwrap();
-        task.exec(all_data.as_slice());
-        0
-    } else {
-        -1
+    let mut room = ARG_MAX;
+    let args = translated_args(token, argv, &mut room)?;
+    let envs = translated_args(token, envp, &mut room)?;
+    Ok((args, envs))
+}
+
+/// Syscall Exec which accepts the elf path, and the arguments and
+/// environment to pass it
+pub fn sys_exec(path: *const u8, argv: *const usize, envp: *const usize) -> isize {
+    let (args, envs) = match translated_exec_args(argv, envp) {
+        Ok(args) => args,
+        Err(errno) => return errno,
+    };
+    match load_elf(path) {
+        Ok((name, elf_data)) => {
+            let task = current_task().unwrap();
+            if task.exec(elf_data.as_slice(), &args, &envs).is_err() {
+                return -ENOEXEC;
+            }
+            task.inner_exclusive_access().name = name;
+            0
+        }
+        Err(errno) => errno,
     }
 }
 
+/// Exec the program in the open file `fd`.
+/// Like sys_exec, the arguments and environment are passed on to it.
+pub fn sys_fexecve(fd: usize, argv: *const usize, envp: *const usize) -> isize {
+    let (args, envs) = match translated_exec_args(argv, envp) {
+        Ok(args) => args,
+        Err(errno) => return errno,
+    };
+    let task = current_task().unwrap();
+    let file = match task.inner_exclusive_access().fd_table.get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return -1,
+    };
+    let inode = match file.inode() {
+        Some(inode) if file.readable() => inode,
+        _ => return -EACCES,
+    };
+    match read_elf(&inode) {
+        Ok(elf_data) => {
+            if task.exec(elf_data.as_slice(), &args, &envs).is_err() {
+                return -ENOEXEC;
+            }
+            0
+        }
+        Err(errno) => errno,
+    }
+}
+
+/// Wait status of a child killed by `signal`
+fn signaled_status(signal: u32) -> i32 {
+    (signal & 0x7f) as i32
+}
+
+/// Wait status of a child that exited with `exit_code`
+fn exited_status(exit_code: i32) -> i32 {
+    (exit_code & 0xff) << 8
+}
+
+/// Reap a zombie child, writing its bare exit code to `exit_code_ptr`, or
+/// with WSTATUS a wait status telling an exit from a kill by a signal.
 /// If there is not a child process whose pid is same as given, return -1.
-/// Else if there is a child process but it is still running, return -2.
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+/// Else if there is a child process but it is still running, return -2,
+/// or 0 with WNOHANG.
+/// A NULL `exit_code_ptr` is not written, one the task can't write gives
+/// -EFAULT.
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, options: usize) -> isize {
+    if options & !(WNOHANG | WSTATUS) != 0 {
+        return -EINVAL;
+    }
     let task = current_task().unwrap();
     // find a child process
 
@@ -88,51 +309,235 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         assert_eq!(Arc::strong_count(&child), 1);
         let found_pid = child.getpid();
         // ++++ temporarily access child TCB exclusively
-        let exit_code = child.inner_exclusive_access().exit_code;
+        let child_inner = child.inner_exclusive_access();
+        let exit_code = if options & WSTATUS == 0 {
+            child_inner.exit_code
+        } else if child_inner.exit_signal != 0 {
+            signaled_status(child_inner.exit_signal)
+        } else {
+            exited_status(child_inner.exit_code)
+        };
+        drop(child_inner);
         // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
+        let token = inner.memory_set.token();
+        // release current PCB before touching user memory, which may fault a page in
+        drop(inner);
+        if !exit_code_ptr.is_null() {
+            match try_translated_refmut(token, exit_code_ptr) {
+                Some(code) => *code = exit_code,
+                None => return -EFAULT,
+            }
+        }
         found_pid as isize
+    } else if options & WNOHANG != 0 {
+        0
     } else {
         -2
     }
     // ---- release current PCB lock automatically
 }
 
+/// Wait for a child selected by `idtype` and `id` to exit.
+///
+/// Blocks unless WNOHANG is given, in which case `si_pid` is left 0 if none
+/// has exited yet. A NULL `infop` is not written, one the task can't write
+/// gives -EFAULT.
+pub fn sys_waitid(idtype: usize, id: usize, infop: *mut SigInfo, options: usize) -> isize {
+    if idtype > P_PGID || options & WEXITED == 0 || options & !(WNOHANG | WEXITED | WNOWAIT) != 0 {
+        return -EINVAL;
+    }
+    let selected = |child: &Arc<TaskControlBlock>| match idtype {
+        P_ALL => true,
+        P_PID => child.getpid() == id,
+        _ => child.inner_exclusive_access().pgid == id,
+    };
+    let task = current_task().unwrap();
+    loop {
+        // ---- access current TCB exclusively
+        let mut inner = task.inner_exclusive_access();
+        if !inner.children.iter().any(selected) {
+            return -ECHILD;
+        }
+        let token = inner.memory_set.token();
+        let found = inner
+            .children
+            .iter()
+            .position(|child| selected(child) && child.inner_exclusive_access().is_zombie());
+        if let Some(idx) = found {
+            let child = if options & WNOWAIT != 0 {
+                inner.children[idx].clone()
+            } else {
+                let child = inner.children.remove(idx);
+                // confirm that child will be deallocated after removing from children list
+                assert_eq!(Arc::strong_count(&child), 1);
+                child
+            };
+            // release current PCB before touching user memory, which may fault a page in
+            drop(inner);
+            let child_inner = child.inner_exclusive_access();
+            let info = SigInfo {
+                si_signo: SIGCHLD as i32,
+                si_code: if child_inner.exit_signal == 0 {
+                    CLD_EXITED
+                } else {
+                    CLD_KILLED
+                },
+                si_pid: child.getpid() as i32,
+                si_status: if child_inner.exit_signal == 0 {
+                    child_inner.exit_code
+                } else {
+                    child_inner.exit_signal as i32
+                },
+            };
+            drop(child_inner);
+            return report_siginfo(token, infop, &info);
+        }
+        drop(inner);
+        // ---- release current PCB
+        if options & WNOHANG != 0 {
+            return report_siginfo(token, infop, &SigInfo::default());
+        }
+        suspend_current_and_run_next();
+    }
+}
+
+/// Write `info` to `infop` unless it is NULL, or give -EFAULT if the task
+/// can't write it
+fn report_siginfo(token: usize, infop: *mut SigInfo, info: &SigInfo) -> isize {
+    if infop.is_null() {
+        return 0;
+    }
+    match try_copy_to_user(token, infop, info) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Move the task `pid`, itself if 0, into process group `pgid`, one named
+/// after it if 0. Only the caller and its children can be moved.
+pub fn sys_setpgid(pid: usize, pgid: usize) -> isize {
+    let current = current_task().unwrap();
+    let task = if pid == 0 || pid == current.getpid() {
+        current
+    } else {
+        let inner = current.inner_exclusive_access();
+        match inner.children.iter().find(|child| child.getpid() == pid) {
+            Some(child) => child.clone(),
+            None => return -ESRCH,
+        }
+    };
+    let pgid = if pgid == 0 { task.getpid() } else { pgid };
+    task.inner_exclusive_access().pgid = pgid;
+    0
+}
+
+/// Get the process group of the task `pid`, or of the caller if 0
+pub fn sys_getpgid(pid: usize) -> isize {
+    let task = if pid == 0 {
+        current_task()
+    } else {
+        pid2task(pid)
+    };
+    match task {
+        Some(task) => task.inner_exclusive_access().pgid as isize,
+        None => -ESRCH,
+    }
+}
+
 // YOUR JOB: 引入虚地址后重写 sys_get_time
-pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
-    let us = get_time_us();
+/// Get the wall-clock time, and the timezone too unless `tz` is NULL.
+/// Gives -EFAULT if the task can't write either of them.
+pub fn sys_get_time(ts: *mut TimeVal, tz: *mut TimeZone) -> isize {
+    let us = get_wall_time_us();
     // unsafe {
     //     *ts = TimeVal {
     //         sec: us / 1_000_000,
     //         usec: us % 1_000_000,
     //     };
     // }
-    let ts = translated_refmut(current_user_token(), ts);
-    *ts = TimeVal {
+    let token = current_user_token();
+    let tv = TimeVal {
         sec: us / 1_000_000,
         usec: us % 1_000_000,
     };
+    if let Err(errno) = try_copy_to_user(token, ts, &tv) {
+        return errno;
+    }
+    if !tz.is_null() {
+        let (minuteswest, dsttime) = timezone();
+        let tz_value = TimeZone {
+            minuteswest,
+            dsttime,
+        };
+        if let Err(errno) = try_copy_to_user(token, tz, &tz_value) {
+            return errno;
+        }
+    }
+    0
+}
+
+/// Set the wall-clock time unless `ts` is NULL, and the timezone unless `tz` is NULL
+pub fn sys_settimeofday(ts: *const TimeVal, tz: *const TimeZone) -> isize {
+    let token = current_user_token();
+    if !ts.is_null() {
+        let tv = copy_from_user(token, ts);
+        if tv.usec >= 1_000_000 {
+            return -EINVAL;
+        }
+        let us = match tv
+            .sec
+            .checked_mul(1_000_000)
+            .and_then(|us| us.checked_add(tv.usec))
+        {
+            Some(us) => us,
+            None => return -EINVAL,
+        };
+        if !set_wall_time_us(us) {
+            return -EINVAL;
+        }
+    }
+    if !tz.is_null() {
+        let tz = copy_from_user(token, tz);
+        set_timezone(tz.minuteswest, tz.dsttime);
+    }
     0
 }
 
 // YOUR JOB: 引入虚地址后重写 sys_task_info
 pub fn sys_task_info(ti: *mut TaskInfo) -> isize {
-    let ti = translated_refmut(current_user_token(), ti);
-    *ti = get_current_task_info();
-    0
+    match try_copy_to_user(current_user_token(), ti, &get_current_task_info()) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
 }
 
 // YOUR JOB: 实现sys_set_priority，为任务添加优先级
 pub fn sys_set_priority(prio: isize) -> isize {
-    if prio <= 1 {
+    if prio < MIN_PRIO as isize {
         return -1;
     }
     set_current_task_prio(prio as u64);
     prio
 }
 
+/// Change the priority of the current task by `delta` and return the new one
+pub fn sys_nice(delta: isize) -> isize {
+    nice_current_task(delta) as isize
+}
+
 // YOUR JOB: 扩展内核以实现 sys_mmap 和 sys_munmap
-pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
+/// Map anonymous pages with no flags, or with MAP_SHARED the file `fd` from
+/// the page-aligned `offset` on, whose pages are read in as they are touched.
+/// Writes to a file mapping reach the file when it is unmapped or the
+/// process goes away, and are shared with forked children.
+pub fn sys_mmap(
+    start: usize,
+    len: usize,
+    port: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> isize {
     let start_va = VirtAddr::from(start);
     if start_va.page_offset() != 0 {
         return -1;
@@ -142,7 +547,126 @@ pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
         return -1;
     }
     let end_va = VirtAddr::from(start + len);
-    mmap(start_va, end_va, p as u8)
+    match flags {
+        0 => mmap(start_va, end_va, p as u8),
+        MAP_SHARED if offset % PAGE_SIZE == 0 => {
+            let file = match current_task()
+                .unwrap()
+                .inner_exclusive_access()
+                .fd_table
+                .get(fd)
+            {
+                Some(Some(file)) => file.clone(),
+                _ => return -1,
+            };
+            let inode = match file.inode() {
+                Some(inode) if !inode.is_dir() => inode,
+                _ => return -ENODEV,
+            };
+            // the pages can't be used in ways the file can't
+            if !file.readable() || (p & 2 != 0 && !file.writable()) {
+                return -EACCES;
+            }
+            mmap_file(start_va, end_va, p as u8, inode, offset)
+        }
+        _ => -EINVAL,
+    }
+}
+
+pub fn sys_statm(statm: *mut Statm) -> isize {
+    let stats = current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .statm();
+    match try_copy_to_user(current_user_token(), statm, &stats) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Write how much sharing pages copy-on-write after fork saved the caller
+/// to `stats`
+pub fn sys_cow_stats(stats: *mut CowStats) -> isize {
+    let cow = current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .cow_stats();
+    copy_to_user(current_user_token(), stats, &cow);
+    0
+}
+
+/// Copy up to `len` of the ready stride tasks into `buf` in the order they
+/// would run, returning how many were copied. Only root may look.
+pub fn sys_sched_debug(buf: *mut SchedDebugEntry, len: usize) -> isize {
+    if current_task().unwrap().inner_exclusive_access().uid != 0 {
+        return -EPERM;
+    }
+    let mut entries = sched_debug();
+    entries.truncate(len);
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            entries.as_ptr() as *const u8,
+            entries.len() * core::mem::size_of::<SchedDebugEntry>(),
+        )
+    };
+    let buffer = UserBuffer::new(translated_byte_buffer(
+        current_user_token(),
+        buf as *const u8,
+        bytes.len(),
+    ));
+    for (dst, src) in buffer.into_iter().zip(bytes.iter()) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    entries.len() as isize
+}
+
+/// Copy the microseconds current task spent in each of the first `len`
+/// syscall ids into `buf`, returning how many were copied
+pub fn sys_syscall_time(buf: *mut usize, len: usize) -> isize {
+    let time = current_syscall_time();
+    let time = &time[..len.min(time.len())];
+    let bytes = unsafe {
+        core::slice::from_raw_parts(time.as_ptr() as *const u8, core::mem::size_of_val(time))
+    };
+    let buffer = UserBuffer::new(translated_byte_buffer(
+        current_user_token(),
+        buf as *const u8,
+        bytes.len(),
+    ));
+    for (dst, src) in buffer.into_iter().zip(bytes.iter()) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    time.len() as isize
+}
+
+/// Copy up to `len` of the last syscalls of current task into `buf`, oldest
+/// first, returning how many there were
+pub fn sys_syscall_trace(buf: *mut SyscallRecord, len: usize) -> isize {
+    let trace = current_syscall_trace();
+    let records = &trace[trace.len().saturating_sub(len)..];
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            records.as_ptr() as *const u8,
+            records.len() * core::mem::size_of::<SyscallRecord>(),
+        )
+    };
+    let buffer = UserBuffer::new(translated_byte_buffer(
+        current_user_token(),
+        buf as *const u8,
+        bytes.len(),
+    ));
+    for (dst, src) in buffer.into_iter().zip(bytes.iter()) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    records.len() as isize
 }
 
 pub fn sys_munmap(start: usize, len: usize) -> isize {
@@ -154,21 +678,342 @@ pub fn sys_munmap(start: usize, len: usize) -> isize {
     munmap(start_va, end_va)
 }
 
+/// Change the permissions of the pages of `[start, start + len)`, -EPERM if
+/// any of them is sealed
+pub fn sys_mprotect(start: usize, len: usize, port: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    if start_va.page_offset() != 0 {
+        return -1;
+    }
+    let p = port & 7;
+    if p == 0 || p != port {
+        return -1;
+    }
+    let end_va = VirtAddr::from(start + len);
+    mprotect(start_va, end_va, p as u8)
+}
+
+/// Seal the areas of `[start, start + len)` for as long as they are mapped,
+/// after which munmap and mprotect on them give -EPERM
+pub fn sys_mseal(start: usize, len: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    let end_va = match start.checked_add(len) {
+        Some(end) if start_va.page_offset() == 0 => VirtAddr::from(end),
+        _ => return -EINVAL,
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if inner.memory_set.seal(start_va, end_va) {
+        0
+    } else {
+        -1
+    }
+}
+
 //
 // YOUR JOB: 实现 sys_spawn 系统调用
 // ALERT: 注意在实现 SPAWN 时不需要复制父进程地址空间，SPAWN != FORK + EXEC
-pub fn sys_spawn(path: *const u8) -> isize {
+pub fn sys_spawn(path: *const u8, argv: *const usize) -> isize {
+    let (args, _) = match translated_exec_args(argv, core::ptr::null()) {
+        Ok(args) => args,
+        Err(errno) => return errno,
+    };
+    match load_elf(path) {
+        Ok((name, elf_data)) => {
+            let task = current_task().unwrap();
+            let new_task = match task.spawn(elf_data.as_slice(), &args) {
+                Ok(new_task) => new_task,
+                Err(_) => return -ENOEXEC,
+            };
+            new_task.inner_exclusive_access().name = name;
+            let new_pid = new_task.pid.0;
+            // add new task to scheduler
+            add_task(new_task);
+            new_pid as isize
+        }
+        Err(errno) => errno,
+    }
+}
+
+/// Find the process a syscall refers to, pid 0 meaning the caller itself
+fn target_task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    if pid == 0 {
+        current_task()
+    } else {
+        pid2task(pid)
+    }
+}
+
+/// Register the robust futex list of the caller, whose futexes are marked
+/// OWNER_DIED when it exits. `head` may be 0 to unregister it.
+pub fn sys_set_robust_list(head: usize, len: usize) -> isize {
+    if len != ROBUST_LIST_HEAD_SIZE {
+        return -EINVAL;
+    }
+    current_task().unwrap().inner_exclusive_access().robust_list = head;
+    0
+}
+
+/// Get the robust futex list head of the process `pid`, or of the caller if 0
+pub fn sys_get_robust_list(pid: usize, head_ptr: *mut usize, len_ptr: *mut usize) -> isize {
+    let task = match target_task(pid) {
+        Some(task) => task,
+        None => return -ESRCH,
+    };
+    let head = task.inner_exclusive_access().robust_list;
     let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
-        let all_data = app_inode.read_all();
-        let task = current_task().unwrap();
-        let new_task = task.spawn(all_data.as_slice());
-        let new_pid = new_task.pid.0;
-        // add new task to scheduler
-        add_task(new_task);
-        new_pid as isize
+    copy_to_user(token, head_ptr, &head);
+    copy_to_user(token, len_ptr, &ROBUST_LIST_HEAD_SIZE);
+    0
+}
+
+/// Get the time slice of the process `pid`, or of the caller if 0
+pub fn sys_sched_rr_get_interval(pid: usize, ts: *mut TimeSpec) -> isize {
+    let task = match target_task(pid) {
+        Some(task) => task,
+        None => return -1,
+    };
+    let us = task.inner_exclusive_access().time_slice_us;
+    let interval = TimeSpec {
+        sec: us / 1_000_000,
+        nsec: us % 1_000_000 * 1000,
+    };
+    match try_copy_to_user(current_user_token(), ts, &interval) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Set the time slice of the caller to `us` microseconds, starting with its next one
+pub fn sys_set_timeslice(us: usize) -> isize {
+    if us == 0 {
+        return -EINVAL;
+    }
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .time_slice_us = us;
+    0
+}
+
+/// Set the scheduling policy of the process `pid`, or of the caller if 0.
+/// A stride task takes `prio` as its priority, a FIFO one as its place among
+/// the FIFO tasks, and only a privileged caller may make a task FIFO. Nor
+/// may any but root change the tasks of another user.
+pub fn sys_sched_setscheduler(pid: usize, policy: usize, prio: usize) -> isize {
+    let policy = match policy {
+        SCHED_STRIDE if prio as u64 >= MIN_PRIO => SchedPolicy::Stride,
+        SCHED_FIFO if (1..=FIFO_PRIO_MAX).contains(&prio) => SchedPolicy::Fifo(prio),
+        _ => return -EINVAL,
+    };
+    let task = match target_task(pid) {
+        Some(task) => task,
+        None => return -ESRCH,
+    };
+    let current = current_task().unwrap();
+    if !current.may_act_on(&task)
+        || (policy != SchedPolicy::Stride && !current.inner_exclusive_access().is_privileged())
+    {
+        return -EPERM;
+    }
+    if policy == SchedPolicy::Stride {
+        task.inner_exclusive_access().prio = prio as u64;
+    }
+    set_task_policy(&task, policy);
+    0
+}
+
+pub fn sys_getuid() -> isize {
+    current_task().unwrap().inner_exclusive_access().uid as isize
+}
+
+/// Run the caller as user `uid`, which only root may do for another user
+pub fn sys_setuid(uid: usize) -> isize {
+    if uid > u32::MAX as usize {
+        return -EINVAL;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !inner.is_privileged() && inner.uid != uid as u32 {
+        return -EPERM;
+    }
+    inner.uid = uid as u32;
+    0
+}
+
+/// Restrict a process to the harts in `mask`, which must contain an existing hart.
+/// Unless root, the caller must be the same user as `pid`, or it gets -EPERM.
+pub fn sys_sched_setaffinity(pid: usize, mask: usize) -> isize {
+    if mask & ((1 << HART_NUM) - 1) == 0 {
+        return -EINVAL;
+    }
+    let task = match target_task(pid) {
+        Some(task) => task,
+        None => return -ESRCH,
+    };
+    if !current_task().unwrap().may_act_on(&task) {
+        return -EPERM;
+    }
+    task.inner_exclusive_access().cpu_mask = mask;
+    0
+}
+
+pub fn sys_sched_getaffinity(pid: usize, mask: *mut usize) -> isize {
+    if let Some(task) = target_task(pid) {
+        let cpu_mask = task.inner_inclusive_access().cpu_mask;
+        match try_copy_to_user(current_user_token(), mask, &cpu_mask) {
+            Ok(()) => 0,
+            Err(errno) => errno,
+        }
+    } else {
+        -ESRCH
+    }
+}
+
+pub fn sys_set_mempolicy(node: usize) -> isize {
+    if node >= MEMORY_NODES {
+        return -EINVAL;
+    }
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .set_node(node);
+    0
+}
+
+pub fn sys_process_madvise(pid: usize, start: usize, len: usize, advice: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    let end_va = match start.checked_add(len) {
+        Some(end) if start_va.page_offset() == 0 => VirtAddr::from(end),
+        _ => return -EINVAL,
+    };
+    let child = match pid2task(pid) {
+        Some(child) => child,
+        None => return -ESRCH,
+    };
+    let current = current_task().unwrap();
+    let is_child = child
+        .inner_exclusive_access()
+        .parent
+        .as_ref()
+        .and_then(Weak::upgrade)
+        .map_or(false, |parent| Arc::ptr_eq(&parent, &current));
+    if !is_child {
+        return -EPERM;
+    }
+    let mut child_inner = child.inner_exclusive_access();
+    let done = match advice {
+        MADV_WILLNEED => child_inner.memory_set.populate(start_va, end_va),
+        MADV_DONTNEED => child_inner.memory_set.drop_pages(start_va, end_va),
+        _ => return -EINVAL,
+    };
+    if done {
+        0
+    } else {
+        -ENOMEM
+    }
+}
+
+/// Fault in every page of `[start, start + len)` of the caller, so that using
+/// them later can't fault. Fails if a page of the range isn't mapped.
+pub fn sys_prefault(start: usize, len: usize) -> isize {
+    let start_va = VirtAddr::from(start);
+    let end_va = match start.checked_add(len) {
+        Some(end) if start_va.page_offset() == 0 => VirtAddr::from(end),
+        _ => return -EINVAL,
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if inner.memory_set.populate(start_va, end_va) {
+        0
     } else {
         -1
     }
 }
+
+/// Send `signal` to the process `pid`, or only check that it may be sent if 0
+pub fn sys_kill(pid: usize, signal: u32) -> isize {
+    if signal >= NSIG || stops_by_default(signal) {
+        return -EINVAL;
+    }
+    let task = match pid2task(pid) {
+        Some(task) => task,
+        None => return -ESRCH,
+    };
+    let uid = current_task().unwrap().inner_exclusive_access().uid;
+    let mut inner = task.inner_exclusive_access();
+    if uid != 0 && uid != inner.uid {
+        return -EPERM;
+    }
+    // initproc has nobody to hand its children to
+    if task.getpid() == INITPROC.getpid() {
+        return -EPERM;
+    }
+    if signal != 0 {
+        inner.signal_pending |= sigmask(signal);
+        // a signal that is going to kill the task cuts its sleep short
+        let fatal = !ignored_by_default(signal) && inner.signal_mask & sigmask(signal) == 0;
+        drop(inner);
+        if fatal {
+            wake_task(&task);
+        }
+    }
+    0
+}
+
+/// Change the signal mask of the caller as `how` says by `set`, unless it is
+/// null, after writing the old mask to `oldset` unless that is null
+pub fn sys_sigprocmask(how: usize, set: *const u32, oldset: *mut u32) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let old = task.inner_exclusive_access().signal_mask;
+    if !set.is_null() {
+        let set = copy_from_user(token, set) & !UNBLOCKABLE;
+        let mask = match how {
+            SIG_BLOCK => old | set,
+            SIG_UNBLOCK => old & !set,
+            SIG_SETMASK => set,
+            _ => return -EINVAL,
+        };
+        task.inner_exclusive_access().signal_mask = mask;
+    }
+    if !oldset.is_null() {
+        copy_to_user(token, oldset, &old);
+    }
+    0
+}
+
+/// Write the signals pending on the caller but kept back by its mask to `set`
+pub fn sys_sigpending(set: *mut u32) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let pending = inner.signal_pending & inner.signal_mask;
+    drop(inner);
+    copy_to_user(current_user_token(), set, &pending);
+    0
+}
+
+/// Resources used by the calling process
+const RUSAGE_SELF: usize = 0;
+/// Resources used by the calling thread
+const RUSAGE_THREAD: usize = 1;
+
+/// Write the resources used by the caller to `usage`. Every task is a
+/// process of a single thread, so RUSAGE_SELF, the sum over the threads of
+/// the process, is the same as RUSAGE_THREAD.
+pub fn sys_getrusage(who: usize, usage: *mut RUsage) -> isize {
+    if who != RUSAGE_SELF && who != RUSAGE_THREAD {
+        return -EINVAL;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    // the time in this syscall so far counts too
+    let now = get_time_us();
+    inner.rusage.stime_us += now - inner.mode_start_us;
+    inner.mode_start_us = now;
+    let rusage = inner.rusage;
+    drop(inner);
+    copy_to_user(current_user_token(), usage, &rusage);
+    0
+}
@@ -3,44 +3,22 @@
 //! It is only used to manage processes and schedule process based on ready queue.
 //! Other CPU process monitoring functions are in Processor.
 
-use core::cmp::Ordering;
-
-use super::TaskControlBlock;
+use super::ready::{ReadyQueue, ReadyTasks};
+use super::{Pass, SchedDebugEntry, SchedPolicy, TaskControlBlock, TaskStatus};
 use crate::sync::UPSafeCell;
-use alloc::collections::BinaryHeap;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use easy_fs::Inode;
 use lazy_static::*;
 
-struct HeapElement(Arc<TaskControlBlock>);
-
-impl Ord for HeapElement {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.0
-            .inner_inclusive_access()
-            .pass
-            .cmp(&other.0.inner_inclusive_access().pass)
-    }
-}
-
-impl Eq for HeapElement {}
-
-impl PartialOrd for HeapElement {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl PartialEq for HeapElement {
-    fn eq(&self, other: &Self) -> bool {
-        self.0
-            .inner_inclusive_access()
-            .pass
-            .eq(&other.0.inner_inclusive_access().pass)
-    }
-}
-
 pub struct TaskManager {
-    ready_queue: BinaryHeap<HeapElement>,
+    /// Ready stride tasks, in the order of the queue the kernel is built with
+    ready_queue: ReadyTasks,
+    /// Ready FIFO tasks, which all run before the stride ones
+    fifo_queue: VecDeque<Arc<TaskControlBlock>>,
+    /// Sleeping tasks by the time in microseconds they wake at, then by pid
+    sleeping: BTreeMap<(usize, usize), Arc<TaskControlBlock>>,
 }
 
 // YOUR JOB: FIFO->Stride
@@ -48,16 +26,92 @@ pub struct TaskManager {
 impl TaskManager {
     pub fn new() -> Self {
         Self {
-            ready_queue: BinaryHeap::new(),
+            ready_queue: ReadyTasks::default(),
+            fifo_queue: VecDeque::new(),
+            sleeping: BTreeMap::new(),
         }
     }
     /// Add process back to ready queue
     pub fn add(&mut self, task: Arc<TaskControlBlock>) {
-        self.ready_queue.push(HeapElement(task));
+        let policy = task.inner_inclusive_access().policy;
+        match policy {
+            SchedPolicy::Stride => self.ready_queue.add(task),
+            SchedPolicy::Fifo(_) => self.fifo_queue.push_back(task),
+        }
+    }
+    /// Park a task until `wake_us`
+    pub fn add_sleeping(&mut self, task: Arc<TaskControlBlock>, wake_us: usize) {
+        self.sleeping.insert((wake_us, task.getpid()), task);
+    }
+    /// Move the sleeping tasks whose deadline is at or before `now_us` to
+    /// the ready queues
+    pub fn wake_sleeping(&mut self, now_us: usize) {
+        while let Some(&key) = self.sleeping.keys().next() {
+            if key.0 > now_us {
+                break;
+            }
+            self.wake_key(key);
+        }
+    }
+    /// Make `task` ready before its deadline if it is sleeping
+    pub fn wake(&mut self, task: &Arc<TaskControlBlock>) {
+        let key = self
+            .sleeping
+            .iter()
+            .find(|(_, sleeping)| Arc::ptr_eq(sleeping, task))
+            .map(|(&key, _)| key);
+        if let Some(key) = key {
+            self.wake_key(key);
+        }
+    }
+    fn wake_key(&mut self, key: (usize, usize)) {
+        let task = self.sleeping.remove(&key).unwrap();
+        task.inner_exclusive_access().task_status = TaskStatus::Ready;
+        self.add(task);
+    }
+    /// Take `task` out of the ready queue it is in, returning whether it was ready
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        if let Some(index) = self.fifo_queue.iter().position(|t| Arc::ptr_eq(t, task)) {
+            self.fifo_queue.remove(index);
+            return true;
+        }
+        self.ready_queue.remove(task)
+    }
+    /// Put a ready task into the queue of its current policy
+    pub fn requeue(&mut self, task: &Arc<TaskControlBlock>) {
+        if self.remove(task) {
+            self.add(task.clone());
+        }
+    }
+    /// Pass of the stride task that would run next
+    pub fn next_pass(&self) -> Option<u64> {
+        self.ready_queue
+            .peek()
+            .map(|task| task.inner_inclusive_access().pass.0)
     }
-    /// Take a process out of the ready queue
-    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
-        self.ready_queue.pop().map(|e| e.0)
+    /// The ready stride tasks in the order they would run
+    pub fn stride_tasks(&self) -> Vec<&Arc<TaskControlBlock>> {
+        self.ready_queue.tasks()
+    }
+    /// Take a process allowed to run on the given hart out of the ready queue
+    pub fn fetch(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+        // the first FIFO task of the highest priority preempts all stride tasks
+        let mut best: Option<(usize, usize)> = None;
+        for (index, task) in self.fifo_queue.iter().enumerate() {
+            let inner = task.inner_inclusive_access();
+            if inner.cpu_mask & (1 << hart_id) == 0 {
+                continue;
+            }
+            if let SchedPolicy::Fifo(prio) = inner.policy {
+                if best.map_or(true, |(_, best_prio)| prio > best_prio) {
+                    best = Some((index, prio));
+                }
+            }
+        }
+        if let Some((index, _)) = best {
+            return self.fifo_queue.remove(index);
+        }
+        self.ready_queue.fetch(hart_id)
     }
 }
 
@@ -65,12 +119,82 @@ lazy_static! {
     /// TASK_MANAGER instance through lazy_static!
     pub static ref TASK_MANAGER: UPSafeCell<TaskManager> =
         unsafe { UPSafeCell::new(TaskManager::new()) };
+    /// All living processes indexed by pid
+    pub static ref PID2TCB: UPSafeCell<BTreeMap<usize, Arc<TaskControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
 }
 
 pub fn add_task(task: Arc<TaskControlBlock>) {
     TASK_MANAGER.exclusive_access().add(task);
 }
 
-pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
-    TASK_MANAGER.exclusive_access().fetch()
+pub fn fetch_task(hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch(hart_id)
+}
+
+/// Park a task until `wake_us`, after which it is ready again
+pub fn add_sleeping_task(task: Arc<TaskControlBlock>, wake_us: usize) {
+    TASK_MANAGER.exclusive_access().add_sleeping(task, wake_us);
+}
+
+/// Cut the sleep of `task` short, if it is sleeping
+pub fn wake_task(task: &Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().wake(task);
+}
+
+/// Make the sleeping tasks whose deadline passed ready again
+pub fn wake_sleeping_tasks(now_us: usize) {
+    TASK_MANAGER.exclusive_access().wake_sleeping(now_us);
+}
+
+/// Switch a task to `policy`, moving it to the matching queue if it is ready
+///
+/// A task joining the stride tasks starts at the pass of the next one to run,
+/// so it neither starves nor monopolizes them.
+pub fn set_task_policy(task: &Arc<TaskControlBlock>, policy: SchedPolicy) {
+    let mut manager = TASK_MANAGER.exclusive_access();
+    // the task itself may be the next one, so look before borrowing it
+    let next_pass = manager.next_pass();
+    let mut inner = task.inner_exclusive_access();
+    if inner.policy != SchedPolicy::Stride && policy == SchedPolicy::Stride {
+        if let Some(pass) = next_pass {
+            inner.pass = Pass(pass);
+        }
+    }
+    inner.policy = policy;
+    drop(inner);
+    manager.requeue(task);
+}
+
+/// Snapshot the ready stride tasks in the order they would run
+pub fn sched_debug() -> Vec<SchedDebugEntry> {
+    let manager = TASK_MANAGER.exclusive_access();
+    manager
+        .stride_tasks()
+        .into_iter()
+        .map(|task| SchedDebugEntry::new(task))
+        .collect()
+}
+
+/// Find a living process by pid
+pub fn pid2task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    PID2TCB.exclusive_access().get(&pid).cloned()
+}
+
+pub fn insert_into_pid2task(pid: usize, task: Arc<TaskControlBlock>) {
+    PID2TCB.exclusive_access().insert(pid, task);
+}
+
+pub fn remove_from_pid2task(pid: usize) {
+    PID2TCB.exclusive_access().remove(&pid);
+}
+
+/// Drop the pages of every process mapping `inode` that lie beyond its new
+/// end at `size`, so touching them is a bus error until the file grows again
+pub fn shrink_file_mappings(inode: &Inode, size: usize) {
+    for task in PID2TCB.exclusive_access().values() {
+        task.inner_exclusive_access()
+            .memory_set
+            .shrink_file(inode, size);
+    }
 }
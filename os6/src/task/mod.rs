@@ -13,45 +13,167 @@ mod context;
 mod manager;
 mod pid;
 mod processor;
+mod ready;
+mod signal;
 mod switch;
 #[allow(clippy::module_inception)]
 mod task;
 
 use crate::fs::{open_file, OpenFlags};
+use crate::timer::get_time_us;
+use alloc::string::String;
 use alloc::sync::Arc;
 use lazy_static::*;
-use manager::fetch_task;
+use manager::{add_sleeping_task, fetch_task, wake_sleeping_tasks};
 use switch::__switch;
-pub use task::{TaskControlBlock, TaskInfo, TaskStatus};
+use task::Pass;
+pub use task::{
+    fd_table_test, RUsage, SchedDebugEntry, SchedPolicy, SyscallRecord, TaskControlBlock, TaskInfo,
+    TaskStatus, MAX_NICE_PRIO, MIN_PRIO, ROBUST_LIST_HEAD_SIZE,
+};
 
 pub use context::TaskContext;
-pub use manager::add_task;
+use manager::remove_from_pid2task;
+pub use manager::{
+    add_task, insert_into_pid2task, pid2task, sched_debug, set_task_policy, shrink_file_mappings,
+    wake_task,
+};
 pub use pid::{pid_alloc, KernelStack, PidHandle};
 pub use processor::{
-    current_task, current_trap_cx, current_user_token, get_current_task_info,
-    inc_task_syscall_times, mmap, munmap, run_tasks, schedule, set_current_task_prio,
-    take_current_task,
+    account_file_io, add_task_syscall_time, current_syscall_time, current_syscall_trace,
+    current_task, current_trap_cx, current_user_token, get_current_task_info, handle_page_fault,
+    inc_task_syscall_times, is_bus_error, mmap, mmap_file, mprotect, munmap, nice_current_task,
+    record_syscall, run_tasks, schedule, set_current_task_prio, take_current_task,
+};
+pub use signal::{
+    ignored_by_default, sigmask, stops_by_default, NSIG, SIGBUS, SIGCHLD, SIGILL, SIGSEGV,
+    SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK, UNBLOCKABLE,
 };
 
 /// Make current task suspended and switch to the next task
 pub fn suspend_current_and_run_next() {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .rusage
+        .nvcsw += 1;
+    switch_out_current();
+}
+
+/// Preempt current task at the end of its time slice and switch to the next task
+pub fn preempt_current_and_run_next() {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .rusage
+        .nivcsw += 1;
+    switch_out_current();
+}
+
+/// Park current task until `wake_us` and switch to the next task
+pub fn sleep_current_and_run_next(wake_us: usize) {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .rusage
+        .nvcsw += 1;
+    switch_out_current_until(Some(wake_us));
+}
+
+/// Put current task back into the ready queue and switch to the next task
+fn switch_out_current() {
+    switch_out_current_until(None);
+}
+
+/// Switch to the next task, putting current task back into the ready queue
+/// or, given a wake time, among the sleeping ones
+fn switch_out_current_until(wake_us: Option<usize>) {
     // There must be an application running.
     let task = take_current_task().unwrap();
 
     // ---- access current TCB exclusively
     let mut task_inner = task.inner_exclusive_access();
+    // the time until now was spent in the kernel
+    let now = get_time_us();
+    task_inner.rusage.stime_us += now - task_inner.mode_start_us;
+    task_inner.mode_start_us = now;
     let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
     // Change status to Ready
-    task_inner.task_status = TaskStatus::Ready;
+    task_inner.task_status = match wake_us {
+        Some(_) => TaskStatus::Sleeping,
+        None => TaskStatus::Ready,
+    };
     drop(task_inner);
     // ---- release current PCB
 
     // push back to ready queue.
-    add_task(task);
+    match wake_us {
+        Some(wake_us) => add_sleeping_task(task, wake_us),
+        None => add_task(task),
+    }
     // jump to scheduling cycle
     schedule(task_cx_ptr);
 }
 
+/// Yield what is left of the current time slice, charging the current task
+/// only for the part of its stride it ran for
+pub fn yield_remaining_and_run_next() {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    if task_inner.policy == SchedPolicy::Stride {
+        let used_us = get_time_us() - task_inner.slice_start_us;
+        let (prio, slice_us) = (task_inner.prio, task_inner.time_slice_us);
+        task_inner.pass.refund(prio, used_us, slice_us);
+    }
+    drop(task_inner);
+    drop(task);
+    suspend_current_and_run_next();
+}
+
+/// Charge the time since current task last switched modes to user mode,
+/// as it enters the kernel
+pub fn account_kernel_entry() {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let now = get_time_us();
+    inner.rusage.utime_us += now - inner.mode_start_us;
+    inner.mode_start_us = now;
+}
+
+/// Charge the time since current task last switched modes to the kernel,
+/// as it goes back to user mode
+pub fn account_kernel_exit() {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let now = get_time_us();
+    inner.rusage.stime_us += now - inner.mode_start_us;
+    inner.mode_start_us = now;
+}
+
+/// Kill current task for a fault it took, as if by `signal`
+pub fn kill_current_and_run_next(signal: u32, exit_code: i32) {
+    current_task().unwrap().inner_exclusive_access().exit_signal = signal;
+    exit_current_and_run_next(exit_code);
+}
+
+/// Deliver the signals of current task that aren't blocked, before it goes
+/// back to user mode. The first one not ignored kills it.
+pub fn handle_signals() {
+    loop {
+        let signal = match current_task()
+            .unwrap()
+            .inner_exclusive_access()
+            .take_signal()
+        {
+            Some(signal) => signal,
+            None => return,
+        };
+        if !ignored_by_default(signal) {
+            kill_current_and_run_next(signal, -(signal as i32));
+        }
+    }
+}
+
 /// Exit current task, recycle process resources and switch to the next task
 pub fn exit_current_and_run_next(exit_code: i32) {
     // take from Processor
@@ -77,6 +199,9 @@ pub fn exit_current_and_run_next(exit_code: i32) {
     // ++++++ release parent PCB
 
     inner.children.clear();
+    remove_from_pid2task(task.getpid());
+    // the locks it held are released before its memory is
+    inner.release_robust_list(task.getpid());
     // deallocate user space
     inner.memory_set.recycle_data_pages();
     drop(inner);
@@ -96,10 +221,13 @@ lazy_static! {
     pub static ref INITPROC: Arc<TaskControlBlock> = Arc::new({
         let inode = open_file("ch6b_initproc", OpenFlags::RDONLY).unwrap();
         let v = inode.read_all();
-        TaskControlBlock::new(v.as_slice())
+        let task = TaskControlBlock::new(v.as_slice()).expect("ch6b_initproc is not a valid ELF");
+        task.inner_exclusive_access().name = String::from("ch6b_initproc");
+        task
     });
 }
 
 pub fn add_initproc() {
+    insert_into_pid2task(INITPROC.getpid(), INITPROC.clone());
     add_task(INITPROC.clone());
 }
@@ -6,16 +6,21 @@
 
 use core::convert::TryInto;
 
-use super::{fetch_task, TaskStatus};
+use super::{fetch_task, wake_sleeping_tasks, TaskStatus};
+use super::{SyscallRecord, TaskInfo, __switch, MAX_NICE_PRIO, MIN_PRIO};
 use super::{TaskContext, TaskControlBlock};
-use super::{TaskInfo, __switch};
-use crate::mm::VirtAddr;
+use crate::mm::{VirtAddr, VirtPageNum};
 use crate::sync::UPSafeCell;
-use crate::timer::get_time_us;
+use crate::timer::{get_time_us, set_next_trigger_after};
 use crate::trap::TrapContext;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use easy_fs::Inode;
 use lazy_static::*;
 
+/// The only hart running the scheduler
+const BOOT_HART_ID: usize = 0;
+
 /// Processor management structure
 pub struct Processor {
     /// The task currently executing on the current processor
@@ -54,7 +59,8 @@ lazy_static! {
 pub fn run_tasks() {
     loop {
         let mut processor = PROCESSOR.exclusive_access();
-        if let Some(task) = fetch_task() {
+        wake_sleeping_tasks(get_time_us());
+        if let Some(task) = fetch_task(BOOT_HART_ID) {
             let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
             // access coming task TCB exclusively
             let mut task_inner = task.inner_exclusive_access();
@@ -62,6 +68,15 @@ pub fn run_tasks() {
             task_inner.task_status = TaskStatus::Running;
             let prio = task_inner.prio;
             task_inner.pass.stride(prio);
+            task_inner.schedule_count += 1;
+            // the coming task gets a whole slice of its own
+            task_inner.slice_start_us = get_time_us();
+            task_inner.mode_start_us = task_inner.slice_start_us;
+            if !task_inner.started {
+                task_inner.started = true;
+                task_inner.start_time = task_inner.slice_start_us;
+            }
+            set_next_trigger_after(task_inner.time_slice_us);
             drop(task_inner);
             // release coming task TCB manually
             processor.current = Some(task);
@@ -106,6 +121,46 @@ pub fn inc_task_syscall_times(syscall_id: usize) {
         .syscall_times[syscall_id] += 1;
 }
 
+/// Charge `us` microseconds to the syscall `syscall_id` of current task
+pub fn add_task_syscall_time(syscall_id: usize, us: usize) {
+    if let Some(time) = current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .syscall_time
+        .get_mut(syscall_id)
+    {
+        *time += us;
+    }
+}
+
+/// Get the microseconds current task spent in each syscall
+pub fn current_syscall_time() -> Vec<usize> {
+    current_task()
+        .unwrap()
+        .inner_inclusive_access()
+        .syscall_time
+        .clone()
+}
+
+/// Append a syscall that returned to the trace of current task
+pub fn record_syscall(id: usize, args: [usize; 6], ret: isize) {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .record_syscall(SyscallRecord {
+            id,
+            args: [args[0], args[1], args[2]],
+            ret,
+        });
+}
+
+/// Get the last syscalls of current task, oldest first
+pub fn current_syscall_trace() -> Vec<SyscallRecord> {
+    let task = current_task().unwrap();
+    let inner = task.inner_inclusive_access();
+    inner.syscall_trace.iter().copied().collect()
+}
+
 pub fn get_current_task_info() -> TaskInfo {
     let task = current_task().unwrap();
     let status = task.inner_inclusive_access().task_status;
@@ -115,11 +170,17 @@ pub fn get_current_task_info() -> TaskInfo {
         .as_slice()
         .try_into()
         .unwrap();
-    let time = (get_time_us() - task.inner_inclusive_access().start_time) / 1000;
+    let now = get_time_us();
+    let inner = task.inner_inclusive_access();
+    let time = (now - inner.start_time) / 1000;
+    // the current task is running, in the kernel, since mode_start_us
+    let cpu_time_us = inner.rusage.utime_us + inner.rusage.stime_us + (now - inner.mode_start_us);
     TaskInfo {
         status,
         syscall_times,
         time,
+        schedule_count: inner.schedule_count,
+        cpu_time_us,
     }
 }
 
@@ -131,6 +192,21 @@ pub fn mmap(start_va: VirtAddr, end_va: VirtAddr, port: u8) -> isize {
         .map(start_va, end_va, port)
 }
 
+/// Map `[start_va, end_va)` to the file `inode` from `offset` on
+pub fn mmap_file(
+    start_va: VirtAddr,
+    end_va: VirtAddr,
+    port: u8,
+    inode: Arc<Inode>,
+    offset: usize,
+) -> isize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .map_file(start_va, end_va, port, inode, offset)
+}
+
 pub fn munmap(start_va: VirtAddr, end_va: VirtAddr) -> isize {
     current_task()
         .unwrap()
@@ -139,10 +215,68 @@ pub fn munmap(start_va: VirtAddr, end_va: VirtAddr) -> isize {
         .unmap(start_va, end_va)
 }
 
+pub fn mprotect(start_va: VirtAddr, end_va: VirtAddr, port: u8) -> isize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .protect(start_va, end_va, port)
+}
+
+/// Map a dropped page of the current task back in, if `token` is its address
+/// space. Returns whether the page is usable now.
+pub fn handle_page_fault(token: usize, vpn: VirtPageNum) -> bool {
+    match current_task() {
+        Some(task) => {
+            let mut inner = task.inner_exclusive_access();
+            let handled = inner.memory_set.token() == token && inner.memory_set.handle_fault(vpn);
+            if handled {
+                inner.rusage.minflt += 1;
+            }
+            handled
+        }
+        None => false,
+    }
+}
+
+/// Whether the page maps a file of the current task beyond its end, if
+/// `token` is its address space
+pub fn is_bus_error(token: usize, vpn: VirtPageNum) -> bool {
+    current_task().map_or(false, |task| {
+        let inner = task.inner_exclusive_access();
+        inner.memory_set.token() == token && inner.memory_set.beyond_file_end(vpn)
+    })
+}
+
+/// Count bytes read and written through files by current task, if one is
+/// running
+pub fn account_file_io(read_bytes: usize, write_bytes: usize) {
+    if let Some(task) = current_task() {
+        let mut inner = task.inner_exclusive_access();
+        inner.rusage.read_bytes += read_bytes;
+        inner.rusage.write_bytes += write_bytes;
+    }
+}
+
 pub fn set_current_task_prio(prio: u64) {
     current_task().unwrap().inner_exclusive_access().prio = prio;
 }
 
+/// Add `delta` to the priority of current task, clamped into
+/// `[MIN_PRIO, MAX_NICE_PRIO]`, and return the new priority. Its strides are
+/// taken at that priority from the next time it is picked on.
+pub fn nice_current_task(delta: isize) -> u64 {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let prio = if delta < 0 {
+        inner.prio.saturating_sub(delta.unsigned_abs() as u64)
+    } else {
+        inner.prio.saturating_add(delta as u64)
+    };
+    inner.prio = prio.clamp(MIN_PRIO, MAX_NICE_PRIO);
+    inner.prio
+}
+
 /// Return to idle control flow for new scheduling
 pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
     let mut processor = PROCESSOR.exclusive_access();
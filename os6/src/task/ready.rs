@@ -0,0 +1,139 @@
+//! Ready queues of the stride-policy tasks
+//!
+//! [`TaskManager`](super::manager::TaskManager) keeps its stride tasks in a
+//! [`ReadyTasks`], which is the stride heap by default and a plain
+//! round-robin queue when built with the `round_robin` feature.
+
+use core::cmp::Ordering;
+
+use super::TaskControlBlock;
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// The ready queue the kernel is built with
+#[cfg(not(feature = "round_robin"))]
+pub type ReadyTasks = StrideQueue;
+/// The ready queue the kernel is built with
+#[cfg(feature = "round_robin")]
+pub type ReadyTasks = RoundRobinQueue;
+
+/// A queue of ready tasks deciding which one runs next
+pub trait ReadyQueue {
+    /// Make `task` ready
+    fn add(&mut self, task: Arc<TaskControlBlock>);
+    /// Take out the task to run next among those allowed on the given hart
+    fn fetch(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>>;
+    /// Take `task` out, returning whether it was in the queue
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> bool;
+    /// The task that would run next on a hart allowing all of them
+    fn peek(&self) -> Option<&Arc<TaskControlBlock>>;
+    /// The ready tasks in the order they would run
+    fn tasks(&self) -> Vec<&Arc<TaskControlBlock>>;
+}
+
+fn allowed_on(task: &Arc<TaskControlBlock>, hart_id: usize) -> bool {
+    task.inner_inclusive_access().cpu_mask & (1 << hart_id) != 0
+}
+
+struct HeapElement(Arc<TaskControlBlock>);
+
+/// Reversed pass order, so that the max-heap pops the task furthest behind
+impl Ord for HeapElement {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .0
+            .inner_inclusive_access()
+            .pass
+            .cmp(&self.0.inner_inclusive_access().pass)
+    }
+}
+
+impl Eq for HeapElement {}
+
+impl PartialOrd for HeapElement {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for HeapElement {
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .inner_inclusive_access()
+            .pass
+            .eq(&other.0.inner_inclusive_access().pass)
+    }
+}
+
+/// Runs the task with the least pass first
+#[derive(Default)]
+pub struct StrideQueue(BinaryHeap<HeapElement>);
+
+impl ReadyQueue for StrideQueue {
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.0.push(HeapElement(task));
+    }
+    fn fetch(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+        let mut skipped = Vec::new();
+        let mut found = None;
+        while let Some(e) = self.0.pop() {
+            if allowed_on(&e.0, hart_id) {
+                found = Some(e.0);
+                break;
+            }
+            skipped.push(e);
+        }
+        self.0.extend(skipped);
+        found
+    }
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut elements = core::mem::take(&mut self.0).into_vec();
+        let len = elements.len();
+        elements.retain(|e| !Arc::ptr_eq(&e.0, task));
+        let removed = elements.len() != len;
+        self.0 = elements.into();
+        removed
+    }
+    fn peek(&self) -> Option<&Arc<TaskControlBlock>> {
+        self.0.peek().map(|e| &e.0)
+    }
+    fn tasks(&self) -> Vec<&Arc<TaskControlBlock>> {
+        let mut tasks: Vec<&Arc<TaskControlBlock>> = self.0.iter().map(|e| &e.0).collect();
+        tasks.sort_by(|a, b| {
+            a.inner_inclusive_access()
+                .pass
+                .cmp(&b.inner_inclusive_access().pass)
+        });
+        tasks
+    }
+}
+
+/// Runs the tasks in the order they became ready, ignoring their priority
+#[derive(Default)]
+pub struct RoundRobinQueue(VecDeque<Arc<TaskControlBlock>>);
+
+impl ReadyQueue for RoundRobinQueue {
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.0.push_back(task);
+    }
+    fn fetch(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+        let index = self.0.iter().position(|task| allowed_on(task, hart_id))?;
+        self.0.remove(index)
+    }
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        match self.0.iter().position(|t| Arc::ptr_eq(t, task)) {
+            Some(index) => {
+                self.0.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+    fn peek(&self) -> Option<&Arc<TaskControlBlock>> {
+        self.0.front()
+    }
+    fn tasks(&self) -> Vec<&Arc<TaskControlBlock>> {
+        self.0.iter().collect()
+    }
+}
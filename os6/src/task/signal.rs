@@ -0,0 +1,57 @@
+//! Signals sent to tasks
+//!
+//! There are no user handlers yet, so a signal either kills the task it is
+//! delivered to or, for those ignored by default, is dropped. Sets of signals
+//! are kept as masks with bit `n` standing for signal `n`.
+
+/// Illegal instruction
+pub const SIGILL: u32 = 4;
+/// Bus error, like touching a mapped page beyond the end of its file
+pub const SIGBUS: u32 = 7;
+/// Kill, which can't be blocked
+pub const SIGKILL: u32 = 9;
+/// Invalid memory reference
+pub const SIGSEGV: u32 = 11;
+/// Child stopped or terminated
+pub const SIGCHLD: u32 = 17;
+/// Continue if stopped
+pub const SIGCONT: u32 = 18;
+/// Stop, which can't be blocked
+pub const SIGSTOP: u32 = 19;
+/// Stop typed at terminal
+pub const SIGTSTP: u32 = 20;
+/// Terminal input for background process
+pub const SIGTTIN: u32 = 21;
+/// Terminal output for background process
+pub const SIGTTOU: u32 = 22;
+/// Urgent condition on socket
+pub const SIGURG: u32 = 23;
+/// Window resize
+pub const SIGWINCH: u32 = 28;
+/// Number of signals, 0 not being one
+pub const NSIG: u32 = 32;
+
+/// How sigprocmask changes the mask: add the signals of the set
+pub const SIG_BLOCK: usize = 0;
+/// remove the signals of the set
+pub const SIG_UNBLOCK: usize = 1;
+/// replace the mask by the set
+pub const SIG_SETMASK: usize = 2;
+
+/// Mask of a single signal
+pub fn sigmask(signal: u32) -> u32 {
+    1 << signal
+}
+
+/// Signals the mask of a task can't hold
+pub const UNBLOCKABLE: u32 = (1 << SIGKILL) | (1 << SIGSTOP);
+
+/// Whether `signal` is dropped on delivery instead of killing the task
+pub fn ignored_by_default(signal: u32) -> bool {
+    matches!(signal, SIGCHLD | SIGCONT | SIGURG | SIGWINCH)
+}
+
+/// Whether `signal` would stop the task, which isn't supported
+pub fn stops_by_default(signal: u32) -> bool {
+    matches!(signal, SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU)
+}
@@ -1,16 +1,36 @@
 //! Types related to task management & Functions for completely changing TCB
 
 use super::TaskContext;
-use super::{pid_alloc, KernelStack, PidHandle};
-use crate::config::{MAX_SYSCALL_NUM, TRAP_CONTEXT};
-use crate::fs::{File, Stdin, Stdout};
-use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE};
+use super::{insert_into_pid2task, pid_alloc, sigmask, KernelStack, PidHandle};
+use crate::config::{
+    FD_TABLE_INIT_CAP, MAX_SYSCALL_NUM, RLIMIT_NOFILE, SYSCALL_TRACE_LEN, TRAP_CONTEXT,
+};
+use crate::fs::{File, MountNamespace, Stdin, Stdout, INIT_MOUNT_NS, ROOT_INODE};
+use crate::mm::{
+    copy_to_user, translated_byte_buffer, MemorySet, PTEFlags, PhysAddr, PhysPageNum, VirtAddr,
+    KERNEL_SPACE,
+};
 use crate::sync::UPSafeCell;
+use crate::timer::{get_time_us, TIME_SLICE_US};
 use crate::trap::{trap_handler, TrapContext};
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use alloc::{vec, vec::Vec};
 use core::cell::{Ref, RefMut};
 use core::cmp::Ordering;
+use easy_fs::Inode;
+
+/// Most entries walked on a robust list, in case it loops
+const ROBUST_LIST_LIMIT: usize = 2048;
+/// Size of the robust list head, its first entry, the futex offset and the pending entry
+pub const ROBUST_LIST_HEAD_SIZE: usize = 24;
+/// The futex was held by a task that died
+const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+/// Tasks are waiting on the futex
+const FUTEX_WAITERS: u32 = 0x8000_0000;
+/// Bits of a futex word holding the tid of its owner
+const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
 
 /// Task control block structure
 ///
@@ -48,12 +68,56 @@ pub struct TaskControlBlockInner {
     pub children: Vec<Arc<TaskControlBlock>>,
     /// It is set when active exit or execution error occurs
     pub exit_code: i32,
+    /// Signal the task was killed by, 0 if it exited by itself
+    pub exit_signal: u32,
+    /// Process group the task belongs to
+    pub pgid: usize,
+    /// Name of the program the task runs, shown when debugging
+    pub name: String,
     pub fd_table: Vec<Option<Arc<dyn File + Send + Sync>>>,
+    /// Fds to close when the task execs a new program
+    pub cloexec: BTreeSet<usize>,
     pub syscall_times: Vec<u32>,
+    /// Microseconds spent in each syscall, indexed like `syscall_times`
+    pub syscall_time: Vec<usize>,
+    /// The last syscalls that returned, oldest first
+    pub syscall_trace: VecDeque<SyscallRecord>,
+    /// When the program the task runs started, in microseconds since boot:
+    /// the first time the task was scheduled, or its last exec
     pub start_time: usize,
+    /// Whether `start_time` is set
     pub started: bool,
     pub pass: Pass,
     pub prio: u64,
+    /// Bitmask of harts the task may run on
+    pub cpu_mask: usize,
+    /// Time the task runs before it is preempted, in microseconds
+    pub time_slice_us: usize,
+    /// When the current slice of the task started, in microseconds since boot
+    pub slice_start_us: usize,
+    /// How the task is scheduled
+    pub policy: SchedPolicy,
+    /// User the task runs as, 0 being the privileged root
+    pub uid: u32,
+    /// User address of the robust futex list head, 0 if none is registered
+    pub robust_list: usize,
+    /// Mount table paths of the task are resolved in
+    pub mount_ns: Arc<MountNamespace>,
+    /// Directory relative paths of the task start from
+    pub cwd: Arc<Inode>,
+    /// Absolute path `cwd` was reached by
+    pub cwd_path: String,
+    /// Signals sent to the task and not delivered yet
+    pub signal_pending: u32,
+    /// Signals kept pending instead of being delivered
+    pub signal_mask: u32,
+    /// Resources used by the task so far
+    pub rusage: RUsage,
+    /// When the task last switched between user and kernel mode, in
+    /// microseconds since boot
+    pub mode_start_us: usize,
+    /// How many times the scheduler picked the task
+    pub schedule_count: usize,
 }
 
 /// Simple access to its internal fields
@@ -69,20 +133,235 @@ impl TaskControlBlockInner {
     pub fn get_user_token(&self) -> usize {
         self.memory_set.token()
     }
+    /// Put `args` and `envs` on the user stack of a task about to start, so
+    /// that its entry finds argc in a0, argv in a1 and envp in a2
+    fn pass_args(&mut self, args: &[String], envs: &[String]) {
+        let trap_cx = self.get_trap_cx();
+        let user_sp = push_args(self.get_user_token(), trap_cx.x[2], args, envs);
+        let word = core::mem::size_of::<usize>();
+        trap_cx.set_sp(user_sp);
+        trap_cx.x[10] = args.len();
+        trap_cx.x[11] = user_sp + word;
+        trap_cx.x[12] = user_sp + word * (args.len() + 2);
+    }
     fn get_status(&self) -> TaskStatus {
         self.task_status
     }
     pub fn is_zombie(&self) -> bool {
         self.get_status() == TaskStatus::Zombie
     }
-    pub fn alloc_fd(&mut self) -> usize {
-        if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none()) {
-            fd
-        } else {
-            self.fd_table.push(None);
-            self.fd_table.len() - 1
+    /// Append a syscall to the trace, dropping the oldest one if it is full
+    pub fn record_syscall(&mut self, record: SyscallRecord) {
+        if self.syscall_trace.len() == SYSCALL_TRACE_LEN {
+            self.syscall_trace.pop_front();
+        }
+        self.syscall_trace.push_back(record);
+    }
+    /// Take the lowest pending signal that isn't blocked, if there is one
+    pub fn take_signal(&mut self) -> Option<u32> {
+        let deliverable = self.signal_pending & !self.signal_mask;
+        if deliverable == 0 {
+            return None;
+        }
+        let signal = deliverable.trailing_zeros();
+        self.signal_pending &= !sigmask(signal);
+        Some(signal)
+    }
+    /// Whether the task may do what only root may
+    pub fn is_privileged(&self) -> bool {
+        self.uid == 0
+    }
+    /// Get a naturally aligned `T` at `va` that is mapped writable for the task
+    fn user_ptr<T>(&self, va: usize) -> Option<&'static mut T> {
+        if va % core::mem::align_of::<T>() != 0 {
+            return None;
+        }
+        let va = VirtAddr::from(va);
+        let pte = self.memory_set.translate(va.floor())?;
+        if !pte.is_valid() || !pte.writable() || !pte.flags().contains(PTEFlags::U) {
+            return None;
+        }
+        let pa: usize = PhysAddr::from(pte.ppn()).into();
+        Some(PhysAddr::from(pa + va.page_offset()).get_mut())
+    }
+    /// Mark the futex of a robust list entry as left behind by its dead owner
+    fn release_robust_futex(&self, entry: usize, offset: isize, tid: usize) {
+        if let Some(word) = self.user_ptr::<u32>(entry.wrapping_add(offset as usize)) {
+            if (*word & FUTEX_TID_MASK) as usize == tid {
+                *word = (*word & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+            }
+        }
+    }
+    /// Walk the robust list of the dying task `tid`, setting OWNER_DIED on
+    /// every futex it still holds. The walk stops at the first entry that is
+    /// not mapped.
+    ///
+    /// There are no threads, shared mappings or futex waiters yet, so nobody
+    /// else sees these words or needs waking until there are.
+    pub fn release_robust_list(&self, tid: usize) {
+        let head = self.robust_list;
+        if head == 0 {
+            return;
+        }
+        let (offset, pending) = match (
+            self.user_ptr::<isize>(head + 8),
+            self.user_ptr::<usize>(head + 16),
+        ) {
+            (Some(offset), Some(pending)) => (*offset, *pending),
+            _ => return,
+        };
+        let mut entry = match self.user_ptr::<usize>(head) {
+            Some(next) => *next,
+            None => return,
+        };
+        for _ in 0..ROBUST_LIST_LIMIT {
+            if entry == head {
+                break;
+            }
+            self.release_robust_futex(entry, offset, tid);
+            entry = match self.user_ptr::<usize>(entry) {
+                Some(next) => *next,
+                None => break,
+            };
+        }
+        // a lock the task was in the middle of taking or releasing
+        if pending != 0 {
+            self.release_robust_futex(pending, offset, tid);
+        }
+    }
+    /// Close `fd`, returning false if it wasn't open
+    pub fn close_fd(&mut self, fd: usize) -> bool {
+        self.cloexec.remove(&fd);
+        matches!(self.fd_table.get_mut(fd).map(Option::take), Some(Some(_)))
+    }
+    /// Get the lowest free fd, or None if RLIMIT_NOFILE fds are open
+    pub fn alloc_fd(&mut self) -> Option<usize> {
+        alloc_slot(&mut self.fd_table)
+    }
+    /// Put `file` at `fd`, closing whatever was open there, or return false
+    /// if `fd` is past RLIMIT_NOFILE
+    pub fn install_fd(&mut self, fd: usize, file: Arc<dyn File + Send + Sync>) -> bool {
+        if !reserve_slot(&mut self.fd_table, fd) {
+            return false;
+        }
+        self.close_fd(fd);
+        self.fd_table[fd] = Some(file);
+        true
+    }
+}
+
+/// Capacity of an fd table holding `len` slots
+///
+/// Tables start at FD_TABLE_INIT_CAP slots and double from there up to RLIMIT_NOFILE,
+/// so that the few fds most processes use don't cost the whole limit.
+fn fd_table_capacity(len: usize) -> usize {
+    let mut capacity = FD_TABLE_INIT_CAP;
+    while capacity < len {
+        capacity *= 2;
+    }
+    capacity.min(RLIMIT_NOFILE)
+}
+
+/// Find the lowest free slot of an fd table, growing the table if every slot is used
+fn alloc_slot<T>(table: &mut Vec<Option<T>>) -> Option<usize> {
+    if let Some(fd) = table.iter().position(Option::is_none) {
+        return Some(fd);
+    }
+    if table.len() >= RLIMIT_NOFILE {
+        return None;
+    }
+    if table.len() == table.capacity() {
+        // reserve_exact keeps the growth to our own schedule
+        let additional = fd_table_capacity(table.len() + 1) - table.len();
+        table.reserve_exact(additional);
+    }
+    table.push(None);
+    Some(table.len() - 1)
+}
+
+/// Make sure an fd table has slot `fd`, growing it like [`alloc_slot`] does
+fn reserve_slot<T>(table: &mut Vec<Option<T>>, fd: usize) -> bool {
+    if fd >= RLIMIT_NOFILE {
+        return false;
+    }
+    if fd >= table.capacity() {
+        let additional = fd_table_capacity(fd + 1) - table.len();
+        table.reserve_exact(additional);
+    }
+    if fd >= table.len() {
+        table.resize_with(fd + 1, || None);
+    }
+    true
+}
+
+/// Lay the arguments and environment of a new program out below `user_sp`
+/// in the address space `token`, the way a C `_start` expects them:
+///
+/// ```text
+/// new sp -> argc
+///           argv[0], ..., argv[argc - 1], NULL
+///           envp[0], ..., envp[envc - 1], NULL
+///           padding that keeps the new sp 16-byte aligned
+///           the strings the pointers refer to, each NUL-terminated
+/// user_sp
+/// ```
+///
+/// Every slot above argc is a pointer-sized word. Returns the new stack
+/// pointer.
+fn push_args(token: usize, mut user_sp: usize, args: &[String], envs: &[String]) -> usize {
+    let mut words = vec![args.len()];
+    for strings in [args, envs] {
+        for string in strings {
+            user_sp -= string.len() + 1;
+            words.push(user_sp);
+            let mut bytes = string.bytes().chain(core::iter::once(0));
+            for buffer in translated_byte_buffer(token, user_sp as *const u8, string.len() + 1) {
+                buffer.fill_with(|| bytes.next().unwrap());
+            }
         }
+        words.push(0);
+    }
+    user_sp = (user_sp - words.len() * core::mem::size_of::<usize>()) & !0xf;
+    for (i, word) in words.iter().enumerate() {
+        let slot = user_sp + i * core::mem::size_of::<usize>();
+        copy_to_user(token, slot as *mut usize, word);
     }
+    user_sp
+}
+
+/// Check that fd tables start small and grow without losing entries
+pub fn fd_table_test() {
+    let mut table: Vec<Option<usize>> = Vec::with_capacity(fd_table_capacity(3));
+    table.extend([Some(0), Some(1), Some(2)]);
+    assert_eq!(table.capacity(), FD_TABLE_INIT_CAP);
+    for fd in 3..FD_TABLE_INIT_CAP * 2 + 1 {
+        assert_eq!(alloc_slot(&mut table), Some(fd));
+        table[fd] = Some(fd);
+    }
+    assert_eq!(table.capacity(), FD_TABLE_INIT_CAP * 4);
+    assert!(table.iter().enumerate().all(|(fd, slot)| *slot == Some(fd)));
+    // the lowest free slot is reused before the table grows again
+    table[5] = None;
+    table[9] = None;
+    assert_eq!(alloc_slot(&mut table), Some(5));
+    table[5] = Some(5);
+    assert_eq!(alloc_slot(&mut table), Some(9));
+    table[9] = Some(9);
+    // a chosen slot past the end grows the table on the same schedule
+    assert!(reserve_slot(&mut table, FD_TABLE_INIT_CAP * 4 + 1));
+    assert_eq!(table.len(), FD_TABLE_INIT_CAP * 4 + 2);
+    assert_eq!(table.capacity(), FD_TABLE_INIT_CAP * 8);
+    assert!(table[FD_TABLE_INIT_CAP * 2 + 1..]
+        .iter()
+        .all(Option::is_none));
+    assert!(!reserve_slot(&mut table, RLIMIT_NOFILE));
+    while table.len() < RLIMIT_NOFILE {
+        let fd = alloc_slot(&mut table).unwrap();
+        table[fd] = Some(fd);
+    }
+    assert_eq!(table.capacity(), RLIMIT_NOFILE);
+    assert_eq!(alloc_slot(&mut table), None);
+    info!("fd_table_test passed!");
 }
 
 impl TaskControlBlock {
@@ -95,12 +374,12 @@ impl TaskControlBlock {
         self.inner.inclusive_access()
     }
 
-    /// Create a new process
+    /// Create a new process, failing if `elf_data` is not a valid ELF
     ///
-    /// At present, it is only used for the creation of initproc
-    pub fn new(elf_data: &[u8]) -> Self {
+    /// At present, it is only used for the creation of initproc and by spawn
+    pub fn new(elf_data: &[u8]) -> Result<Self, &'static str> {
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data)?;
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
@@ -109,6 +388,8 @@ impl TaskControlBlock {
         let pid_handle = pid_alloc();
         let kernel_stack = KernelStack::new(&pid_handle);
         let kernel_stack_top = kernel_stack.get_top();
+        // a new process leads a process group of its own
+        let pgid = pid_handle.0;
         // push a task context which goes to trap_return to the top of kernel stack
         let task_control_block = Self {
             pid: pid_handle,
@@ -123,19 +404,42 @@ impl TaskControlBlock {
                     parent: None,
                     children: Vec::new(),
                     exit_code: 0,
-                    fd_table: alloc::vec![
+                    exit_signal: 0,
+                    pgid,
+                    name: String::new(),
+                    fd_table: {
+                        let mut fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> =
+                            Vec::with_capacity(fd_table_capacity(3));
                         // 0 -> stdin
-                        Some(Arc::new(Stdin)),
+                        fd_table.push(Some(Arc::new(Stdin)));
                         // 1 -> stdout
-                        Some(Arc::new(Stdout)),
+                        fd_table.push(Some(Arc::new(Stdout)));
                         // 2 -> stderr
-                        Some(Arc::new(Stdout)),
-                    ],
+                        fd_table.push(Some(Arc::new(Stdout)));
+                        fd_table
+                    },
+                    cloexec: BTreeSet::new(),
                     syscall_times: vec![0; MAX_SYSCALL_NUM],
+                    syscall_time: vec![0; MAX_SYSCALL_NUM],
+                    syscall_trace: VecDeque::with_capacity(SYSCALL_TRACE_LEN),
                     start_time: 0,
                     started: false,
                     pass: Pass(0),
                     prio: 16,
+                    cpu_mask: usize::MAX,
+                    time_slice_us: TIME_SLICE_US,
+                    slice_start_us: 0,
+                    policy: SchedPolicy::Stride,
+                    uid: 0,
+                    robust_list: 0,
+                    mount_ns: INIT_MOUNT_NS.clone(),
+                    cwd: ROOT_INODE.clone(),
+                    cwd_path: String::from("/"),
+                    signal_pending: 0,
+                    signal_mask: 0,
+                    rusage: RUsage::default(),
+                    mode_start_us: 0,
+                    schedule_count: 0,
                 })
             },
         };
@@ -148,12 +452,22 @@ impl TaskControlBlock {
             kernel_stack_top,
             trap_handler as usize,
         );
-        task_control_block
+        Ok(task_control_block)
     }
-    /// Load a new elf to replace the original application address space and start execution
-    pub fn exec(&self, elf_data: &[u8]) {
+    /// Load a new elf to replace the original application address space and start execution,
+    /// with `args` as its argv and `envs` as its environment.
+    ///
+    /// The pid stays, while the syscall counts and the start time task_info
+    /// reports restart from zero and now. An invalid ELF leaves the task as
+    /// it was.
+    pub fn exec(
+        &self,
+        elf_data: &[u8],
+        args: &[String],
+        envs: &[String],
+    ) -> Result<(), &'static str> {
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data)?;
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
@@ -164,6 +478,18 @@ impl TaskControlBlock {
         inner.memory_set = memory_set;
         // update trap_cx ppn
         inner.trap_cx_ppn = trap_cx_ppn;
+        // the list lived in the old address space
+        inner.robust_list = 0;
+        // the syscalls and age in task_info are those of the new program,
+        // while the resource usage carries over like in getrusage
+        inner.syscall_times.fill(0);
+        inner.syscall_time.fill(0);
+        inner.start_time = get_time_us();
+        inner.started = true;
+        // close the fds marked close-on-exec
+        for fd in core::mem::take(&mut inner.cloexec) {
+            inner.close_fd(fd);
+        }
         // initialize trap_cx
         let trap_cx = inner.get_trap_cx();
         *trap_cx = TrapContext::app_init_context(
@@ -173,6 +499,8 @@ impl TaskControlBlock {
             self.kernel_stack.get_top(),
             trap_handler as usize,
         );
+        inner.pass_args(args, envs);
+        Ok(())
         // **** release inner automatically
     }
     /// Fork from parent to child
@@ -180,7 +508,7 @@ impl TaskControlBlock {
         // ---- access parent PCB exclusively
         let mut parent_inner = self.inner_exclusive_access();
         // copy user space(include trap context)
-        let memory_set = MemorySet::from_existed_user(&parent_inner.memory_set);
+        let memory_set = MemorySet::from_existed_user(&mut parent_inner.memory_set);
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
@@ -189,7 +517,8 @@ impl TaskControlBlock {
         let pid_handle = pid_alloc();
         let kernel_stack = KernelStack::new(&pid_handle);
         let kernel_stack_top = kernel_stack.get_top();
-        let mut new_fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> = Vec::new();
+        let mut new_fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> =
+            Vec::with_capacity(fd_table_capacity(parent_inner.fd_table.len()));
         // clone all fds from parent to child
         for fd in parent_inner.fd_table.iter() {
             if let Some(file) = fd {
@@ -211,17 +540,40 @@ impl TaskControlBlock {
                     parent: Some(Arc::downgrade(self)),
                     children: Vec::new(),
                     exit_code: 0,
+                    exit_signal: 0,
+                    pgid: parent_inner.pgid,
+                    name: parent_inner.name.clone(),
                     fd_table: new_fd_table,
+                    cloexec: parent_inner.cloexec.clone(),
                     syscall_times: vec![0; MAX_SYSCALL_NUM],
+                    syscall_time: vec![0; MAX_SYSCALL_NUM],
+                    syscall_trace: VecDeque::with_capacity(SYSCALL_TRACE_LEN),
                     start_time: 0,
                     started: false,
                     pass: Pass(0),
                     prio: 16,
+                    cpu_mask: parent_inner.cpu_mask,
+                    time_slice_us: parent_inner.time_slice_us,
+                    slice_start_us: 0,
+                    policy: parent_inner.policy,
+                    uid: parent_inner.uid,
+                    // the locks of the parent are not the child's
+                    robust_list: 0,
+                    mount_ns: parent_inner.mount_ns.clone(),
+                    cwd: parent_inner.cwd.clone(),
+                    cwd_path: parent_inner.cwd_path.clone(),
+                    // signals sent to the parent are not the child's
+                    signal_pending: 0,
+                    signal_mask: parent_inner.signal_mask,
+                    rusage: RUsage::default(),
+                    mode_start_us: 0,
+                    schedule_count: 0,
                 })
             },
         });
         // add child
         parent_inner.children.push(task_control_block.clone());
+        insert_into_pid2task(task_control_block.getpid(), task_control_block.clone());
         // modify kernel_sp in trap_cx
         // **** access children PCB exclusively
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
@@ -231,19 +583,41 @@ impl TaskControlBlock {
         // ---- release parent PCB automatically
         // **** release children PCB automatically
     }
-    pub fn spawn(self: &Arc<TaskControlBlock>, elf_data: &[u8]) -> Arc<TaskControlBlock> {
-        let task_control_block = Arc::new(TaskControlBlock::new(elf_data));
-        task_control_block.inner_exclusive_access().parent = Some(Arc::downgrade(self));
+    pub fn spawn(
+        self: &Arc<TaskControlBlock>,
+        elf_data: &[u8],
+        args: &[String],
+    ) -> Result<Arc<TaskControlBlock>, &'static str> {
+        let task_control_block = Arc::new(TaskControlBlock::new(elf_data)?);
         // ---- access parent PCB exclusively
         let mut parent_inner = self.inner_exclusive_access();
+        let mut child_inner = task_control_block.inner_exclusive_access();
+        child_inner.pass_args(args, &[]);
+        child_inner.parent = Some(Arc::downgrade(self));
+        child_inner.cpu_mask = parent_inner.cpu_mask;
+        child_inner.pgid = parent_inner.pgid;
+        child_inner.mount_ns = parent_inner.mount_ns.clone();
+        child_inner.cwd = parent_inner.cwd.clone();
+        child_inner.cwd_path = parent_inner.cwd_path.clone();
+        child_inner.signal_mask = parent_inner.signal_mask;
+        drop(child_inner);
         // add child
         parent_inner.children.push(task_control_block.clone());
+        insert_into_pid2task(task_control_block.getpid(), task_control_block.clone());
         // return
-        task_control_block
+        Ok(task_control_block)
     }
     pub fn getpid(&self) -> usize {
         self.pid.0
     }
+    /// Whether this task may change `target`, being root or the same user.
+    /// `target` may be this task itself.
+    pub fn may_act_on(&self, target: &TaskControlBlock) -> bool {
+        let inner = self.inner_exclusive_access();
+        let (privileged, uid) = (inner.is_privileged(), inner.uid);
+        drop(inner);
+        privileged || target.inner_exclusive_access().uid == uid
+    }
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -252,34 +626,125 @@ pub enum TaskStatus {
     Ready = 1,
     Running,
     Zombie,
+    /// parked until a deadline passes
+    Sleeping,
 }
 
+#[repr(C)]
 pub struct TaskInfo {
     pub status: TaskStatus,
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
     pub time: usize,
+    /// how many times the task was scheduled
+    pub schedule_count: usize,
+    /// time the task spent running, in microseconds
+    pub cpu_time_us: usize,
+}
+
+/// Resources a task used, counted for every task on its own
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct RUsage {
+    /// time spent in user mode, in microseconds
+    pub utime_us: usize,
+    /// time spent in the kernel on behalf of the task, in microseconds
+    pub stime_us: usize,
+    /// page faults served without the task noticing
+    pub minflt: usize,
+    /// times the task gave up the CPU itself
+    pub nvcsw: usize,
+    /// times the task was preempted
+    pub nivcsw: usize,
+    /// bytes read from files
+    pub read_bytes: usize,
+    /// bytes written to files
+    pub write_bytes: usize,
+}
+
+/// A ready stride task, as dumped by sched_debug
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SchedDebugEntry {
+    pub pid: usize,
+    pub prio: u64,
+    /// pass the task is ordered by, the lowest running next
+    pub pass: u64,
+    /// name of the program, cut short and padded with NULs
+    pub name: [u8; 16],
+}
+
+impl SchedDebugEntry {
+    pub fn new(task: &TaskControlBlock) -> Self {
+        let inner = task.inner_inclusive_access();
+        let mut name = [0u8; 16];
+        let len = inner.name.len().min(name.len() - 1);
+        name[..len].copy_from_slice(&inner.name.as_bytes()[..len]);
+        Self {
+            pid: task.getpid(),
+            prio: inner.prio,
+            pass: inner.pass.0,
+            name,
+        }
+    }
+}
+
+/// A syscall that returned, as kept in the trace of a task
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SyscallRecord {
+    /// syscall id
+    pub id: usize,
+    /// the first arguments
+    pub args: [usize; 3],
+    /// return value
+    pub ret: isize,
 }
 
 const BIG_STRIDE: u64 = u64::MAX;
 const STRIDE_LESS: u64 = BIG_STRIDE >> 1;
+/// The lowest priority a task can have, whose stride is at most `STRIDE_LESS`
+pub const MIN_PRIO: u64 = 2;
+/// The highest priority nice raises a task to: short of `BIG_STRIDE`, as
+/// far as the priority fits the isize a syscall returns
+pub const MAX_NICE_PRIO: u64 = isize::MAX as u64;
 
-pub struct Pass(u64);
+/// Stride pass of a task, which wraps around instead of overflowing
+///
+/// Ready passes never drift more than one stride, at most `STRIDE_LESS`,
+/// apart, so the order between two of them is that of their wrapping
+/// difference: the pass behind, the one that runs first, compares less.
+///
+/// A stride of `BIG_STRIDE / prio` rounds down by less than one, so the share
+/// of a task is off from being proportional to `prio` by less than
+/// `prio / BIG_STRIDE`.
+pub struct Pass(pub u64);
 
 impl Pass {
+    /// The pass a slice costs at `prio`, taken as `MIN_PRIO` when lower
+    fn step(prio: u64) -> u64 {
+        BIG_STRIDE / prio.clamp(MIN_PRIO, BIG_STRIDE)
+    }
     pub fn stride(&mut self, prio: u64) {
-        self.0 += BIG_STRIDE / prio;
+        self.0 = self.0.wrapping_add(Self::step(prio));
+    }
+    /// Give back the part of the last stride that went unused, the task having
+    /// run for `used_us` of its `slice_us` slice
+    pub fn refund(&mut self, prio: u64, used_us: usize, slice_us: usize) {
+        let unused = slice_us.saturating_sub(used_us) as u128;
+        let refund = Self::step(prio) as u128 * unused / slice_us as u128;
+        self.0 = self.0.wrapping_sub(refund as u64);
     }
 }
 
 impl Ord for Pass {
     fn cmp(&self, other: &Self) -> Ordering {
-        let diff = self.0 - other.0;
+        let diff = self.0.wrapping_sub(other.0);
         if diff == 0 {
             Ordering::Equal
         } else if diff <= STRIDE_LESS {
-            Ordering::Less
-        } else {
             Ordering::Greater
+        } else {
+            Ordering::Less
         }
     }
 }
@@ -296,3 +761,13 @@ impl PartialEq for Pass {
         self.0 == other.0
     }
 }
+
+/// Scheduling policy of a task
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// Share the CPU by stride, the default
+    Stride,
+    /// Run before every stride task until blocking, yielding or exiting.
+    /// Higher priorities go first, equal ones in the order they became ready.
+    Fifo(usize),
+}
@@ -2,10 +2,20 @@
 
 use crate::config::CLOCK_FREQ;
 use crate::sbi::set_timer;
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
 use riscv::register::time;
 
 const TICKS_PER_SEC: usize = 100;
 const MICRO_PER_SEC: usize = 1_000_000;
+/// default time slice of a task in microseconds
+pub const TIME_SLICE_US: usize = MICRO_PER_SEC / TICKS_PER_SEC;
+
+/// wall-clock time at boot, in microseconds since the Unix epoch
+static BOOT_EPOCH_US: AtomicUsize = AtomicUsize::new(0);
+/// minutes west of Greenwich of the system timezone
+static TZ_MINUTESWEST: AtomicI32 = AtomicI32::new(0);
+/// type of DST correction of the system timezone
+static TZ_DSTTIME: AtomicI32 = AtomicI32::new(0);
 
 /// read the `mtime` register
 pub fn get_time() -> usize {
@@ -21,3 +31,38 @@ pub fn get_time_us() -> usize {
 pub fn set_next_trigger() {
     set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
 }
+
+/// set the next timer interrupt `us` microseconds from now
+pub fn set_next_trigger_after(us: usize) {
+    set_timer(get_time() + us * (CLOCK_FREQ / MICRO_PER_SEC));
+}
+
+/// get wall-clock time in microseconds since the Unix epoch
+pub fn get_wall_time_us() -> usize {
+    BOOT_EPOCH_US.load(Ordering::Relaxed) + get_time_us()
+}
+
+/// set wall-clock time by moving the boot epoch, false if it would be before boot
+pub fn set_wall_time_us(us: usize) -> bool {
+    match us.checked_sub(get_time_us()) {
+        Some(epoch) => {
+            BOOT_EPOCH_US.store(epoch, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// get the system timezone as (minutes west of Greenwich, DST correction)
+pub fn timezone() -> (i32, i32) {
+    (
+        TZ_MINUTESWEST.load(Ordering::Relaxed),
+        TZ_DSTTIME.load(Ordering::Relaxed),
+    )
+}
+
+/// set the system timezone
+pub fn set_timezone(minuteswest: i32, dsttime: i32) {
+    TZ_MINUTESWEST.store(minuteswest, Ordering::Relaxed);
+    TZ_DSTTIME.store(dsttime, Ordering::Relaxed);
+}
@@ -15,11 +15,15 @@
 mod context;
 
 use crate::config::{TRAMPOLINE, TRAP_CONTEXT};
+use crate::mm::VirtAddr;
 use crate::syscall::syscall;
 use crate::task::{
-    current_trap_cx, current_user_token, exit_current_and_run_next, suspend_current_and_run_next,
+    account_kernel_entry, account_kernel_exit, add_task_syscall_time, current_task,
+    current_trap_cx, current_user_token, handle_page_fault, handle_signals, is_bus_error,
+    kill_current_and_run_next, preempt_current_and_run_next, record_syscall, SchedPolicy, SIGBUS,
+    SIGILL, SIGSEGV,
 };
-use crate::timer::set_next_trigger;
+use crate::timer::{get_time_us, set_next_trigger};
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
@@ -53,6 +57,7 @@ pub fn enable_timer_interrupt() {
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
+    account_kernel_entry();
     let scause = scause::read();
     let stval = stval::read();
     match scause.cause() {
@@ -61,11 +66,35 @@ pub fn trap_handler() -> ! {
             let mut cx = current_trap_cx();
             cx.sepc += 4;
             // get system call return value
-            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12], cx.x[13]]);
+            let id = cx.x[17];
+            let args = [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14], cx.x[15]];
+            let start_us = get_time_us();
+            let result = syscall(id, args);
+            add_task_syscall_time(id, get_time_us() - start_us);
+            record_syscall(id, args, result);
             // cx is changed during sys_exec, so we have to call it again
             cx = current_trap_cx();
             cx.x[10] = result as usize;
         }
+        Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::InstructionPageFault)
+        | Trap::Exception(Exception::LoadPageFault)
+            if handle_page_fault(current_user_token(), VirtAddr::from(stval).floor()) =>
+        {
+            // the dropped page is back, retry the access
+        }
+        Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::InstructionPageFault)
+        | Trap::Exception(Exception::LoadPageFault)
+            if is_bus_error(current_user_token(), VirtAddr::from(stval).floor()) =>
+        {
+            println!(
+                "[kernel] {:?} beyond the end of a mapped file, bad addr = {:#x}, core dumped.",
+                scause.cause(),
+                stval,
+            );
+            kill_current_and_run_next(SIGBUS, -(SIGBUS as i32));
+        }
         Trap::Exception(Exception::StoreFault)
         | Trap::Exception(Exception::StorePageFault)
         | Trap::Exception(Exception::InstructionFault)
@@ -79,16 +108,20 @@ pub fn trap_handler() -> ! {
                 current_trap_cx().sepc,
             );
             // page fault exit code
-            exit_current_and_run_next(-2);
+            kill_current_and_run_next(SIGSEGV, -2);
         }
         Trap::Exception(Exception::IllegalInstruction) => {
             println!("[kernel] IllegalInstruction in application, core dumped.");
             // illegal instruction exit code
-            exit_current_and_run_next(-3);
+            kill_current_and_run_next(SIGILL, -3);
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
             set_next_trigger();
-            suspend_current_and_run_next();
+            // FIFO tasks run until they give up the CPU themselves
+            let policy = current_task().unwrap().inner_exclusive_access().policy;
+            if policy == SchedPolicy::Stride {
+                preempt_current_and_run_next();
+            }
         }
         _ => {
             panic!(
@@ -98,11 +131,13 @@ pub fn trap_handler() -> ! {
             );
         }
     }
+    handle_signals();
     trap_return();
 }
 
 #[no_mangle]
 pub fn trap_return() -> ! {
+    account_kernel_exit();
     set_user_trap_entry();
     let trap_cx_ptr = TRAP_CONTEXT;
     let user_satp = current_user_token();
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, getppid, sched_getaffinity, sched_setaffinity, sched_setscheduler, setuid,
+    waitpid, SCHED_STRIDE,
+};
+
+/// 测试 sched_setaffinity/sched_getaffinity，输出　Test affinity OK! 就算正确。
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut mask = 0usize;
+    assert_eq!(sched_setaffinity(0, 0b101), 0);
+    assert_eq!(sched_getaffinity(0, &mut mask), 0);
+    assert_eq!(mask, 0b101);
+    assert_eq!(sched_getaffinity(getpid() as usize, &mut mask), 0);
+    assert_eq!(mask, 0b101);
+    // empty or hart-0-excluding masks are rejected and leave the mask alone
+    assert_eq!(sched_setaffinity(0, 0), -EINVAL);
+    assert_eq!(sched_setaffinity(0, 0b10), -EINVAL);
+    assert_eq!(sched_getaffinity(0, &mut mask), 0);
+    assert_eq!(mask, 0b101);
+    // the child inherits the mask and still gets scheduled
+    let pid = fork();
+    if pid == 0 {
+        let mut mask = 0usize;
+        assert_eq!(sched_getaffinity(0, &mut mask), 0);
+        exit(if mask == 0b101 { 0 } else { -1 });
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // another user can only re-pin or reschedule its own tasks
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(setuid(1000), 0);
+        let parent = getppid() as usize;
+        assert_eq!(sched_setaffinity(parent, 0b1), -EPERM);
+        assert_eq!(sched_setscheduler(parent, SCHED_STRIDE, 16), -EPERM);
+        assert_eq!(sched_setaffinity(0, 0b1), 0);
+        assert_eq!(sched_setscheduler(0, SCHED_STRIDE, 16), 0);
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(sched_getaffinity(0, &mut mask), 0);
+    assert_eq!(mask, 0b101);
+    println!("Test affinity OK!");
+    0
+}
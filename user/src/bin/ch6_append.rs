@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, lseek, open, read, unlink, write, OpenFlags, SEEK_SET};
+
+/// 测试 APPEND 打开的文件每次都写到末尾，输出　Test append OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("append_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"head,"), 5);
+    close(fd as usize);
+    // two descriptors with their own offsets, both appending
+    let first = open("append_file\0", OpenFlags::WRONLY | OpenFlags::APPEND);
+    let second = open("append_file\0", OpenFlags::RDWR | OpenFlags::APPEND);
+    assert!(first > 0 && second > 0);
+    let (first, second) = (first as usize, second as usize);
+    assert_eq!(write(first, b"first,"), 6);
+    assert_eq!(write(second, b"second,"), 7);
+    // seeking back doesn't keep a write from going to the end
+    assert_eq!(lseek(first, 0, SEEK_SET), 0);
+    assert_eq!(write(first, b"again"), 5);
+    // reads still follow the offset, which a write left at the end
+    assert_eq!(lseek(second, 0, SEEK_SET), 0);
+    let mut buffer = [0u8; 32];
+    let len = read(second, &mut buffer) as usize;
+    assert_eq!(&buffer[..len], b"head,first,second,again");
+    close(first);
+    close(second);
+    assert_eq!(unlink("append_file\0"), 0);
+    println!("Test append OK!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::null;
+use user_lib::{close, dup2, exec, fork, pipe, read, spawn, spawnv, waitpid};
+
+/// 测试 exec 和 spawn 把参数传给新程序，输出　Test argv OK! 就算正确。
+
+const ECHO: &str = "ch6_argv_echo\0";
+const E2BIG: isize = 7;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the child echoes its arguments into a pipe
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(dup2(pipe_fd[1], 1), 1);
+        close(pipe_fd[0]);
+        close(pipe_fd[1]);
+        let args = [
+            ECHO.as_ptr(),
+            "hello\0".as_ptr(),
+            "world\0".as_ptr(),
+            null(),
+        ];
+        exec(ECHO, &args);
+        panic!("exec failed");
+    }
+    close(pipe_fd[1]);
+    let mut buffer = [0u8; 32];
+    let mut len = 0;
+    loop {
+        let n = read(pipe_fd[0], &mut buffer[len..]);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    close(pipe_fd[0]);
+    assert_eq!(&buffer[..len], b"hello world\n");
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 3);
+
+    // spawn passes them too, and without any the child gets argc 0
+    let pid = spawnv(ECHO, &[ECHO.as_ptr(), "spawned\0".as_ptr(), null()]);
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 2);
+    let pid = spawn(ECHO);
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // arguments that don't fit the new stack fail the exec
+    let mut long = [b'x'; 3001];
+    long[3000] = 0;
+    assert_eq!(exec(ECHO, &[long.as_ptr(), long.as_ptr(), null()]), -E2BIG);
+    println!("Test argv OK!");
+    0
+}
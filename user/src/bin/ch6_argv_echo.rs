@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/// 输出参数（不含程序名），以空格分隔，退出码为 argc，供 ch6_argv 使用。
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    for (i, arg) in argv.iter().enumerate().skip(1) {
+        if i > 1 {
+            print!(" ");
+        }
+        print!("{}", arg);
+    }
+    println!("");
+    argc as i32
+}
@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, close_range, exec, exit, fork, open, read, waitpid, write, OpenFlags,
+    CLOSE_RANGE_CLOEXEC,
+};
+
+/// 测试 close_range 批量关闭，输出　Test close range OK! 就算正确。
+
+const EINVAL: isize = 22;
+const COUNT: usize = 8;
+
+fn is_open(fd: usize) -> bool {
+    let mut buffer = [0u8; 1];
+    read(fd, &mut buffer) >= 0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("cr_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"close range"), 11);
+    close(fd as usize);
+    let mut fds = [0usize; COUNT];
+    for slot in fds.iter_mut() {
+        let fd = open("cr_file\0", OpenFlags::RDONLY);
+        assert!(fd > 0);
+        *slot = fd as usize;
+    }
+    assert_eq!(close_range(fds[2], fds[5], 0), 0);
+    for (i, &fd) in fds.iter().enumerate() {
+        assert_eq!(is_open(fd), !(2..=5).contains(&i));
+    }
+    // closed and out-of-range slots are skipped
+    assert_eq!(close_range(fds[2], usize::MAX, 0), 0);
+    assert!(is_open(fds[1]));
+    assert!(!is_open(fds[7]));
+    assert_eq!(close_range(fds[1], fds[0], 0), -EINVAL);
+    assert_eq!(close_range(fds[0], fds[1], 1), -EINVAL);
+    assert_eq!(close_range(fds[0], fds[1], 0), 0);
+
+    let pid = fork();
+    if pid == 0 {
+        // the child sees fd 3 marked close-on-exec and fd 4 still open
+        close_range(3, usize::MAX, 0);
+        assert_eq!(open("cr_file\0", OpenFlags::RDONLY), 3);
+        assert_eq!(open("cr_file\0", OpenFlags::RDONLY), 4);
+        assert_eq!(close_range(3, 3, CLOSE_RANGE_CLOEXEC), 0);
+        assert!(is_open(3));
+        exec("ch6_close_range_exec\0", &[core::ptr::null::<u8>()]);
+        exit(-1);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test close range OK!");
+    0
+}
@@ -0,0 +1,17 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::read;
+
+/// exec'd by ch6_close_range with fd 3 marked close-on-exec and fd 4 open
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut buffer = [0u8; 1];
+    if read(3, &mut buffer) != -1 || read(4, &mut buffer) != 1 {
+        return -1;
+    }
+    0
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, mmap, munmap, pipe, read, waitpid, write};
+
+/// 测试 fork 写时复制后父子进程的内存互不影响，输出　Test cow OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+
+static mut GLOBAL: usize = 1;
+
+fn page() -> *mut usize {
+    START as *mut usize
+}
+
+fn global() -> *mut usize {
+    unsafe { core::ptr::addr_of_mut!(GLOBAL) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, PAGE, 3), 0);
+    unsafe { page().write_volatile(1) };
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        close(pipe_fd[1]);
+        // wait for the parent to write its copies first
+        let mut byte = [0u8; 1];
+        assert_eq!(read(pipe_fd[0], &mut byte), 1);
+        assert_eq!(unsafe { page().read_volatile() }, 1);
+        assert_eq!(unsafe { global().read_volatile() }, 1);
+        unsafe {
+            page().write_volatile(3);
+            global().write_volatile(3);
+        }
+        assert_eq!(unsafe { page().read_volatile() }, 3);
+        exit(0);
+    }
+    close(pipe_fd[0]);
+    unsafe {
+        page().write_volatile(2);
+        global().write_volatile(2);
+    }
+    assert_eq!(write(pipe_fd[1], b"x"), 1);
+    close(pipe_fd[1]);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // the child wrote its own copies of both pages
+    assert_eq!(unsafe { page().read_volatile() }, 2);
+    assert_eq!(unsafe { global().read_volatile() }, 2);
+    assert_eq!(munmap(START, PAGE), 0);
+    println!("Test cow OK!");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{cow_stats, exit, fork, mmap, munmap, waitpid, CowStats};
+
+/// 测试 fork 写时复制的统计，输出　Test cow stats OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const PAGES: usize = 16;
+const WRITTEN: [usize; 5] = [0, 3, 6, 9, 12];
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE) as *mut usize
+}
+
+fn child() -> ! {
+    let mut start = CowStats::default();
+    // the first call writes the stack, which copies its page
+    cow_stats(&mut start);
+    cow_stats(&mut start);
+    assert!(start.pages_shared >= PAGES);
+    for &i in WRITTEN.iter() {
+        unsafe { page(i).write_volatile(usize::MAX) };
+    }
+    let mut written = CowStats::default();
+    cow_stats(&mut written);
+    assert_eq!(
+        written.faults_resolved - start.faults_resolved,
+        WRITTEN.len()
+    );
+    // a page that was copied already is the child's own
+    unsafe { page(WRITTEN[0]).write_volatile(0) };
+    let mut again = CowStats::default();
+    cow_stats(&mut again);
+    assert_eq!(again.faults_resolved, written.faults_resolved);
+    // the pages never written are still shared with the parent
+    assert_eq!(munmap(START, PAGES * PAGE), 0);
+    let mut unmapped = CowStats::default();
+    cow_stats(&mut unmapped);
+    assert_eq!(
+        unmapped.pages_freed_unmodified - again.pages_freed_unmodified,
+        PAGES - WRITTEN.len()
+    );
+    exit(0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, PAGES * PAGE, 3), 0);
+    for i in 0..PAGES {
+        unsafe { page(i).write_volatile(i + 1) };
+    }
+    let mut before = CowStats::default();
+    cow_stats(&mut before);
+    let pid = fork();
+    if pid == 0 {
+        child();
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    let mut after = CowStats::default();
+    cow_stats(&mut after);
+    assert!(after.pages_shared - before.pages_shared >= PAGES);
+    // the writes of the child went to its own copies
+    for i in 0..PAGES {
+        assert_eq!(unsafe { page(i).read_volatile() }, i + 1);
+    }
+    // with the child gone nothing is shared, so writing copies nothing
+    for i in 0..PAGES {
+        unsafe { page(i).write_volatile(0) };
+    }
+    let mut written = CowStats::default();
+    cow_stats(&mut written);
+    assert_eq!(written.faults_resolved, after.faults_resolved);
+    assert_eq!(munmap(START, PAGES * PAGE), 0);
+    println!("Test cow stats OK!");
+    0
+}
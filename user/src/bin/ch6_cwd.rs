@@ -0,0 +1,100 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    chdir, close, exit, fork, getcwd, link, mkdir, open, read, rename, unlink, waitpid, write,
+    OpenFlags, RenameFlags,
+};
+
+/// 测试 chdir 之后相对路径从当前目录开始查找，输出　Test cwd OK! 就算正确。
+
+const ENOENT: isize = 2;
+const EXDEV: isize = 18;
+const ENOTDIR: isize = 20;
+const ERANGE: isize = 34;
+
+fn assert_cwd(expected: &[u8]) {
+    let mut buf = [0u8; 64];
+    let len = getcwd(&mut buf);
+    assert_eq!(len, expected.len() as isize + 1);
+    assert_eq!(&buf[..expected.len()], expected);
+    assert_eq!(buf[expected.len()], 0);
+}
+
+fn write_file(path: &str) {
+    let fd = open(path, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"relative"), 8);
+    close(fd as usize);
+}
+
+fn read_back(path: &str) -> bool {
+    let fd = open(path, OpenFlags::RDONLY);
+    if fd < 0 {
+        return false;
+    }
+    let mut buf = [0u8; 16];
+    let len = read(fd as usize, &mut buf);
+    close(fd as usize);
+    len == 8 && &buf[..8] == b"relative"
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_cwd(b"/");
+    mkdir("cwd_dir\0");
+    assert_eq!(chdir("cwd_dir\0"), 0);
+    assert_cwd(b"/cwd_dir");
+    // a bare name is made in the current directory
+    write_file("cwd_file\0");
+    assert!(read_back("./cwd_file\0"));
+    assert!(read_back("/cwd_dir/cwd_file\0"));
+    assert!(read_back("../cwd_dir/cwd_file\0"));
+    assert!(!read_back("/cwd_file\0"));
+
+    // children start out where their parent is
+    let pid = fork();
+    if pid == 0 {
+        assert_cwd(b"/cwd_dir");
+        assert!(read_back("cwd_file\0"));
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // a bare name is unlinked, renamed and linked in the current directory
+    write_file("/cwd_twin\0");
+    write_file("cwd_twin\0");
+    assert_eq!(unlink("cwd_twin\0"), 0);
+    assert!(!read_back("/cwd_dir/cwd_twin\0"));
+    assert!(read_back("/cwd_twin\0"));
+    assert_eq!(unlink("/cwd_twin\0"), 0);
+    assert_eq!(rename("cwd_file\0", "cwd_moved\0", RenameFlags::empty()), 0);
+    assert!(read_back("/cwd_dir/cwd_moved\0"));
+    assert!(!read_back("cwd_file\0"));
+    assert_eq!(link("cwd_moved\0", "cwd_file\0"), 0);
+    assert!(read_back("/cwd_dir/cwd_file\0"));
+    assert_eq!(unlink("cwd_moved\0"), 0);
+    assert!(read_back("cwd_file\0"));
+    // entries only move within their directory
+    assert_eq!(
+        rename("cwd_file\0", "/cwd_file\0", RenameFlags::empty()),
+        -EXDEV
+    );
+    assert_eq!(link("cwd_file\0", "/cwd_file\0"), -EXDEV);
+
+    assert_eq!(chdir("cwd_file\0"), -ENOTDIR);
+    assert_eq!(chdir("missing\0"), -ENOENT);
+    assert_cwd(b"/cwd_dir");
+    let mut short = [0u8; 4];
+    assert_eq!(getcwd(&mut short), -ERANGE);
+    assert_eq!(chdir("..\0"), 0);
+    assert_cwd(b"/");
+    assert!(read_back("cwd_dir/cwd_file\0"));
+    println!("Test cwd OK!");
+    0
+}
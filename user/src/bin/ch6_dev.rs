@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, write, OpenFlags};
+
+/// 测试 /dev/zero 和 /dev/null 设备文件，输出　Test dev OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let zero = open("/dev/zero\0", OpenFlags::RDONLY);
+    assert!(zero > 0);
+    let zero = zero as usize;
+    let mut buffer = [0xffu8; 100];
+    assert_eq!(read(zero, &mut buffer), 100);
+    assert!(buffer.iter().all(|&b| b == 0));
+    // as many zeros as are asked for, every time
+    let mut buffer = [0xffu8; 100];
+    assert_eq!(read(zero, &mut buffer[..40]), 40);
+    assert!(buffer[..40].iter().all(|&b| b == 0));
+    assert!(buffer[40..].iter().all(|&b| b == 0xff));
+    close(zero);
+
+    let null = open("/dev/null\0", OpenFlags::RDWR);
+    assert!(null > 0);
+    let null = null as usize;
+    assert_eq!(write(null, b"discarded"), 9);
+    assert_eq!(write(null, &[7u8; 1000]), 1000);
+    assert_eq!(read(null, &mut buffer), 0);
+    close(null);
+    println!("Test dev OK!");
+    0
+}
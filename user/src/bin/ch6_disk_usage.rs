@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, disk_usage, fstat, open, unlink, write, DiskUsage, OpenFlags, Stat};
+
+/// 测试文件占用块数统计，输出　Test disk usage OK! 就算正确。
+
+const ENOENT: isize = 2;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("du_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let buffer = [b'u'; 512];
+    for _ in 0..30 {
+        assert_eq!(write(fd, &buffer), 512);
+    }
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    close(fd);
+    let mut usage = DiskUsage::default();
+    assert_eq!(disk_usage("du_file\0", &mut usage), 0);
+    assert_eq!(usage.total_blocks, stat.blocks);
+    assert!(usage.total_blocks > 30);
+    // nothing shares the blocks of a freshly written file
+    assert_eq!(usage.unique_blocks, usage.total_blocks);
+    assert_eq!(disk_usage("du_missing\0", &mut usage), -ENOENT);
+    unlink("du_file\0");
+    println!("Test disk usage OK!");
+    0
+}
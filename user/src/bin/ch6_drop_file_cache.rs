@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    block_cache_stats, close, drop_file_cache, fincore, lseek, open, read, unlink, write,
+    CacheStats, OpenFlags, FINCORE_BLOCK_SIZE, SEEK_SET,
+};
+
+/// 测试按文件丢弃块缓存，输出　Test drop file cache OK! 就算正确。
+
+const EINVAL: isize = 22;
+const BLOCKS: usize = 3;
+
+fn create(name: &str, ch: u8) -> usize {
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let data = [ch; FINCORE_BLOCK_SIZE];
+    for _ in 0..BLOCKS {
+        assert_eq!(write(fd as usize, &data), FINCORE_BLOCK_SIZE as isize);
+    }
+    fd as usize
+}
+
+/// Read the whole file, returning the (hits, misses) it took
+fn read_all(fd: usize, ch: u8) -> (usize, usize) {
+    let mut before = CacheStats::default();
+    assert_eq!(block_cache_stats(&mut before), 0);
+    assert_eq!(lseek(fd, 0, SEEK_SET), 0);
+    let mut buffer = [0u8; FINCORE_BLOCK_SIZE];
+    for _ in 0..BLOCKS {
+        assert_eq!(read(fd, &mut buffer), FINCORE_BLOCK_SIZE as isize);
+        assert!(buffer.iter().all(|&c| c == ch));
+    }
+    let mut after = CacheStats::default();
+    assert_eq!(block_cache_stats(&mut after), 0);
+    (after.hits - before.hits, after.misses - before.misses)
+}
+
+/// How many blocks of the file are in the cache
+fn resident(fd: usize) -> usize {
+    let mut vec = [0u8; BLOCKS];
+    assert_eq!(fincore(fd, 0, &mut vec), BLOCKS as isize);
+    vec.iter().filter(|&&resident| resident == 1).count()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the blocks just written are dirty in the cache
+    let a = create("dfc_a\0", b'a');
+    let b = create("dfc_b\0", b'b');
+    assert_eq!(resident(a), BLOCKS);
+    assert_eq!(resident(b), BLOCKS);
+    assert_eq!(drop_file_cache(a), BLOCKS as isize);
+    assert_eq!(resident(a), 0);
+    assert_eq!(resident(b), BLOCKS);
+    assert_eq!(drop_file_cache(a), 0);
+    // the other file still hits
+    let (hits, misses) = read_all(b, b'b');
+    assert!(hits >= BLOCKS);
+    assert_eq!(misses, 0);
+    // the dropped one was written back and misses now
+    let (_, misses) = read_all(a, b'a');
+    assert!(misses >= BLOCKS);
+    assert_eq!(resident(a), BLOCKS);
+    close(a);
+    close(b);
+    assert_eq!(drop_file_cache(a), -1);
+    assert_eq!(drop_file_cache(1), -EINVAL);
+    unlink("dfc_a\0");
+    unlink("dfc_b\0");
+    println!("Test drop file cache OK!");
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, dup2, open, read, unlink, write, OpenFlags};
+
+/// 测试 dup2 把文件描述符复制到指定的编号，输出　Test dup2 OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // stdout copied onto a slot nothing was open at
+    assert_eq!(dup2(1, 5), 5);
+    assert_eq!(write(5, b"written through fd 5\n"), 21);
+    assert_eq!(close(5), 0);
+    // an fd far past the end of the table
+    assert_eq!(dup2(1, 100), 100);
+    assert_eq!(write(100, b"written through fd 100\n"), 23);
+    assert_eq!(close(100), 0);
+
+    // redirecting an open fd closes the file it had
+    let fd = open("dup2_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let copy = dup(fd);
+    assert!(copy > 0 && copy as usize != fd);
+    let copy = copy as usize;
+    assert_eq!(dup2(1, copy), copy as isize);
+    assert_eq!(dup2(fd, fd), fd as isize);
+    // the copies share one offset
+    assert_eq!(dup2(fd, 6), 6);
+    assert_eq!(write(fd, b"one,"), 4);
+    assert_eq!(write(6, b"two"), 3);
+    close(fd);
+    close(6);
+    close(copy);
+    let fd = open("dup2_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; 16];
+    assert_eq!(read(fd as usize, &mut buffer), 7);
+    assert_eq!(&buffer[..7], b"one,two");
+    close(fd as usize);
+    assert_eq!(unlink("dup2_file\0"), 0);
+
+    // nothing to copy from
+    assert_eq!(dup2(fd as usize, 7), -1);
+    assert_eq!(dup2(1, 1 << 20), -1);
+    println!("Test dup2 OK!");
+    0
+}
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, syscall, waitpid, yield_, SYSCALL_GETTIMEOFDAY, SYSCALL_TASK_INFO,
+    SYSCALL_WAITPID,
+};
+
+/// 测试系统调用写入无效的用户指针时返回 -EFAULT 而不是让内核崩溃，输出　Test efault OK! 就算正确。
+
+const EFAULT: isize = 14;
+const START: usize = 0x10000000;
+/// The trap context page, mapped in every task but only for the kernel
+const TRAP_CONTEXT: usize = usize::MAX - 2 * 4096 + 1;
+
+/// Wait for `pid` with the exit code written to the raw address `status`
+fn wait_raw(pid: usize, status: usize) -> isize {
+    loop {
+        match syscall(SYSCALL_WAITPID, [pid, status, 0]) {
+            -2 => {
+                yield_();
+            }
+            n => return n,
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // nothing is mapped at NULL
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [0, 0, 0]), -EFAULT);
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [START, 0, 0]), -EFAULT);
+    assert_eq!(syscall(SYSCALL_TASK_INFO, [0, 0, 0]), -EFAULT);
+    // a page the user may only read, or not touch at all
+    assert_eq!(mmap(START, 4096, 1), 0);
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [START, 0, 0]), -EFAULT);
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [TRAP_CONTEXT, 0, 0]), -EFAULT);
+    assert_eq!(syscall(SYSCALL_TASK_INFO, [TRAP_CONTEXT, 0, 0]), -EFAULT);
+    assert_eq!(munmap(START, 4096), 0);
+    // the timezone is checked as well
+    let mut time = [0usize; 2];
+    assert_eq!(
+        syscall(SYSCALL_GETTIMEOFDAY, [time.as_mut_ptr() as usize, START, 0]),
+        -EFAULT
+    );
+
+    // the child is reaped all the same
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    assert_eq!(wait_raw(pid as usize, 4), -EFAULT);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), -1);
+    // a NULL status is just not written
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    assert_eq!(wait_raw(pid as usize, 0), pid);
+    println!("Test efault OK!");
+    0
+}
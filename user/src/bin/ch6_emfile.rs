@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use user_lib::{close, dup, open, pipe, unlink, OpenFlags};
+
+/// 测试打开的文件描述符数有上限，超过时返回 -EMFILE，关闭后又能打开，输出　Test emfile OK! 就算正确。
+
+const EMFILE: isize = 24;
+/// RLIMIT_NOFILE of the kernel
+const MAX_FDS: usize = 1024;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("emfile_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    // stdin, stdout and stderr take the first three
+    let mut fds = Vec::with_capacity(MAX_FDS);
+    loop {
+        let fd = open("emfile_file\0", OpenFlags::RDONLY);
+        if fd < 0 {
+            assert_eq!(fd, -EMFILE);
+            break;
+        }
+        fds.push(fd as usize);
+    }
+    assert_eq!(fds.len(), MAX_FDS - 3);
+    assert_eq!(*fds.last().unwrap(), MAX_FDS - 1);
+    // every way of getting an fd hits the same limit
+    assert_eq!(dup(0), -EMFILE);
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), -EMFILE);
+
+    // the limit is on the fds open at once, so closing frees a slot again
+    let freed = fds[100];
+    assert_eq!(close(freed), 0);
+    assert_eq!(open("emfile_file\0", OpenFlags::RDONLY), freed as isize);
+    assert_eq!(open("emfile_file\0", OpenFlags::RDONLY), -EMFILE);
+    for &fd in fds.iter() {
+        assert_eq!(close(fd), 0);
+    }
+    for _ in 0..3 {
+        let fd = open("emfile_file\0", OpenFlags::RDONLY);
+        assert!(fd > 0);
+        close(fd as usize);
+    }
+    assert_eq!(unlink("emfile_file\0"), 0);
+    println!("Test emfile OK!");
+    0
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::null;
+use user_lib::{close, dup2, exec, execve, fork, pipe, read, waitpid};
+
+/// 测试 exec 把环境变量传给新程序，输出　Test env OK! 就算正确。
+
+const ECHO: &str = "ch6_env_echo\0";
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the child writes the value of one variable into a pipe
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(dup2(pipe_fd[1], 1), 1);
+        close(pipe_fd[0]);
+        close(pipe_fd[1]);
+        let args = [ECHO.as_ptr(), "GREETING\0".as_ptr(), null()];
+        let envp = [
+            "GREET=wrong\0".as_ptr(),
+            "GREETING=hello there\0".as_ptr(),
+            "HOME=/\0".as_ptr(),
+            null(),
+        ];
+        execve(ECHO, &args, &envp);
+        panic!("execve failed");
+    }
+    close(pipe_fd[1]);
+    let mut buffer = [0u8; 32];
+    let mut len = 0;
+    loop {
+        let n = read(pipe_fd[0], &mut buffer[len..]);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    close(pipe_fd[0]);
+    assert_eq!(&buffer[..len], b"hello there\n");
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // a plain exec starts the program with an empty environment
+    let pid = fork();
+    if pid == 0 {
+        exec(ECHO, &[ECHO.as_ptr(), "GREETING\0".as_ptr(), null()]);
+        panic!("exec failed");
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 1);
+    println!("Test env OK!");
+    0
+}
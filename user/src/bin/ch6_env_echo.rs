@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::getenv;
+
+/// 输出环境变量 argv[1] 的值，未设置时退出码为 1，供 ch6_env 使用。
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    assert_eq!(argc, 2);
+    match getenv(argv[1]) {
+        Some(value) => {
+            println!("{}", value);
+            0
+        }
+        None => 1,
+    }
+}
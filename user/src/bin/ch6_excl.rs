@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, unlink, write, OpenFlags};
+
+/// 测试 EXCL 只在文件不存在时创建，输出　Test excl OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let flags = OpenFlags::CREATE | OpenFlags::EXCL | OpenFlags::WRONLY;
+    // the first to create the lock file holds it
+    let fd = open("excl_lock\0", flags);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"owner"), 5);
+    close(fd as usize);
+    assert_eq!(open("excl_lock\0", flags), -1);
+    // failing left the file as it was, where a plain create would clear it
+    let fd = open("excl_lock\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; 8];
+    assert_eq!(read(fd as usize, &mut buffer), 5);
+    assert_eq!(&buffer[..5], b"owner");
+    close(fd as usize);
+    // once released the lock can be taken again
+    assert_eq!(unlink("excl_lock\0"), 0);
+    let fd = open("excl_lock\0", flags);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert_eq!(unlink("excl_lock\0"), 0);
+    println!("Test excl OK!");
+    0
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use core::ptr::null;
+use user_lib::{
+    exec, get_time, getpid, sleep, task_info, TaskInfo, SYSCALL_EXEC, SYSCALL_GETTIMEOFDAY,
+    SYSCALL_TASK_INFO,
+};
+
+/// 测试 exec 之后 task_info 的系统调用计数和运行时间从新程序开始算，pid 不变，输出　Test exec info OK! 就算正确。
+
+const SELF: &str = "ch6_exec_info\0";
+/// How long the program runs before it execs, in milliseconds
+const BEFORE_EXEC_MS: isize = 100;
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc < 2 {
+        // the first image makes some calls and ages a bit
+        let start = get_time();
+        while get_time() - start < BEFORE_EXEC_MS {
+            sleep(10);
+        }
+        let info = TaskInfo::new();
+        assert_eq!(task_info(&info), 0);
+        assert!(info.syscall_times[SYSCALL_GETTIMEOFDAY] >= 2);
+        assert!(info.time >= BEFORE_EXEC_MS as usize);
+        let pid = format!("{}\0", getpid());
+        exec(SELF, &[SELF.as_ptr(), pid.as_ptr(), null()]);
+        panic!("exec failed");
+    }
+    // the new image counts from its own start
+    let info = TaskInfo::new();
+    assert_eq!(task_info(&info), 0);
+    assert_eq!(info.syscall_times[SYSCALL_GETTIMEOFDAY], 0);
+    assert_eq!(info.syscall_times[SYSCALL_EXEC], 0);
+    assert_eq!(info.syscall_times[SYSCALL_TASK_INFO], 1);
+    assert!(info.time < BEFORE_EXEC_MS as usize);
+    assert_eq!(argv[1].parse::<isize>().unwrap(), getpid());
+    println!("Test exec info OK!");
+    0
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exec, exit, fork, mkdir, open, read, spawn, waitpid, write, OpenFlags};
+
+/// 测试按文件系统路径执行程序，输出　Test exec path OK! 就算正确。
+
+const EACCES: isize = 13;
+const ENOEXEC: isize = 8;
+
+/// Copy the file `from` into a new file `to`
+fn copy(from: &str, to: &str) {
+    let src = open(from, OpenFlags::RDONLY);
+    assert!(src > 0);
+    let dst = open(to, OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC);
+    assert!(dst > 0);
+    let mut buffer = [0u8; 1024];
+    loop {
+        let len = read(src as usize, &mut buffer);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        assert_eq!(write(dst as usize, &buffer[..len as usize]), len);
+    }
+    close(src as usize);
+    close(dst as usize);
+}
+
+/// Exec `path` in a child and return its exit code
+fn run(path: &str) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        let ret = exec(path, &[core::ptr::null::<u8>()]);
+        exit(ret as i32);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the directory may be left over from an earlier run
+    mkdir("exec_dir\0");
+    copy("ch6_fexecve_child\0", "exec_dir/child\0");
+    assert_eq!(run("exec_dir/child\0"), 42);
+    assert_eq!(run("/exec_dir/child\0"), 42);
+    let pid = spawn("exec_dir/child\0");
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 42);
+
+    // anything but an ELF is refused instead of loaded
+    let fd = open("exec_dir/text\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"#!/bin/sh"), 9);
+    close(fd as usize);
+    assert_eq!(run("exec_dir/text\0"), -ENOEXEC as i32);
+    assert_eq!(spawn("exec_dir/text\0"), -ENOEXEC);
+    assert_eq!(run("exec_dir\0"), -EACCES as i32);
+    assert_eq!(run("exec_dir/missing\0"), -1);
+    println!("Test exec path OK!");
+    0
+}
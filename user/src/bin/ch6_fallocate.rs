@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fallocate, fstat, open, read, unlink, write, OpenFlags, Stat, FALLOC_FL_ZERO_RANGE,
+};
+
+/// 测试 fallocate 清零区间，输出　Test fallocate OK! 就算正确。
+
+const EINVAL: isize = 22;
+const LEN: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("fa_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let data = [b'f'; LEN];
+    assert_eq!(write(fd, &data), LEN as isize);
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    let blocks = stat.blocks;
+    assert_eq!(stat.size, LEN as u64);
+
+    // zero a middle portion with partial blocks on both ends
+    assert_eq!(fallocate(fd, FALLOC_FL_ZERO_RANGE, 1000, 2000), 0);
+    assert_eq!(fstat(fd, &stat), 0);
+    assert_eq!(stat.size, LEN as u64);
+    assert_eq!(stat.blocks, blocks);
+    assert_eq!(fallocate(fd, FALLOC_FL_ZERO_RANGE, 0, 0), -EINVAL);
+    assert_eq!(fallocate(fd, 0x2, 0, 512), -EINVAL);
+    // a range past the end grows the file
+    assert_eq!(fallocate(fd, FALLOC_FL_ZERO_RANGE, LEN, 512), 0);
+    assert_eq!(fstat(fd, &stat), 0);
+    assert_eq!(stat.size, (LEN + 512) as u64);
+    close(fd);
+
+    let fd = open("fa_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; LEN + 512];
+    assert_eq!(read(fd as usize, &mut buffer), (LEN + 512) as isize);
+    close(fd as usize);
+    assert!(buffer[..1000].iter().all(|&ch| ch == b'f'));
+    assert!(buffer[1000..3000].iter().all(|&ch| ch == 0));
+    assert!(buffer[3000..LEN].iter().all(|&ch| ch == b'f'));
+    assert!(buffer[LEN..].iter().all(|&ch| ch == 0));
+    unlink("fa_file\0");
+    println!("Test fallocate OK!");
+    0
+}
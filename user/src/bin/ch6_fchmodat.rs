@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fchmodat, fchownat, fstat, mkdir, open, OpenFlags, Stat, AT_FDCWD, AT_SYMLINK_NOFOLLOW,
+};
+
+/// 测试相对目录修改权限与属主，输出　Test fchmodat OK! 就算正确。
+
+const ENOENT: isize = 2;
+const ENOTDIR: isize = 20;
+const EINVAL: isize = 22;
+
+fn stat_of(path: &str) -> Stat {
+    let fd = open(path, OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let stat = Stat::new();
+    assert_eq!(fstat(fd as usize, &stat), 0);
+    close(fd as usize);
+    stat
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the directory may be left over from an earlier run
+    mkdir("chm_dir\0");
+    let fd = open("chm_dir/file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let file_fd = fd as usize;
+    let dirfd = open("chm_dir\0", OpenFlags::RDONLY);
+    assert!(dirfd > 0);
+    let dirfd = dirfd as usize;
+
+    assert_eq!(fchmodat(dirfd, "file\0", 0o600, 0), 0);
+    assert_eq!(stat_of("chm_dir/file\0").perm, 0o600);
+    // without symbolic links NOFOLLOW acts on the file itself
+    assert_eq!(fchmodat(dirfd, "file\0", 0o640, AT_SYMLINK_NOFOLLOW), 0);
+    assert_eq!(stat_of("chm_dir/file\0").perm, 0o640);
+    assert_eq!(fchmodat(AT_FDCWD as usize, "chm_dir/file\0", 0o604, 0), 0);
+    assert_eq!(stat_of("chm_dir/file\0").perm, 0o604);
+
+    assert_eq!(fchownat(dirfd, "file\0", 7, u32::MAX, 0), 0);
+    assert_eq!(fchownat(dirfd, "file\0", u32::MAX, 8, 0), 0);
+    let stat = stat_of("chm_dir/file\0");
+    assert_eq!((stat.uid, stat.gid), (7, 8));
+
+    assert_eq!(fchmodat(99, "file\0", 0o600, 0), -1);
+    assert_eq!(fchmodat(file_fd, "file\0", 0o600, 0), -ENOTDIR);
+    // an absolute path ignores dirfd
+    assert_eq!(fchmodat(file_fd, "/chm_dir/file\0", 0o600, 0), 0);
+    assert_eq!(fchmodat(dirfd, "missing\0", 0o600, 0), -ENOENT);
+    assert_eq!(fchmodat(dirfd, "file\0", 0o600, 1), -EINVAL);
+    close(dirfd);
+    close(file_fd);
+    println!("Test fchmodat OK!");
+    0
+}
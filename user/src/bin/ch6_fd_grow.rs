@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, open, read, waitpid, write, OpenFlags};
+
+/// 测试 fd 表扩容，输出　Test fd grow OK! 就算正确。
+
+/// more fds than a new process has slots for
+const COUNT: usize = 40;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("fd_grow\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"grow"), 4);
+    close(fd as usize);
+    let mut fds = [0usize; COUNT];
+    for slot in fds.iter_mut() {
+        let fd = open("fd_grow\0", OpenFlags::RDONLY);
+        assert!(fd > 0);
+        *slot = fd as usize;
+    }
+    // fds are handed out lowest first, so the last one is past the initial table
+    for i in 1..COUNT {
+        assert_eq!(fds[i], fds[i - 1] + 1);
+    }
+    assert!(fds[COUNT - 1] >= 16);
+    let mut buffer = [0u8; 4];
+    assert_eq!(read(fds[0], &mut buffer), 4);
+    assert_eq!(&buffer, b"grow");
+    assert_eq!(read(fds[COUNT - 1], &mut buffer), 4);
+    assert_eq!(&buffer, b"grow");
+    // a freed low slot is reused before the table grows again
+    close(fds[3]);
+    let fd = open("fd_grow\0", OpenFlags::RDONLY);
+    assert_eq!(fd as usize, fds[3]);
+    // the grown table is inherited as it is
+    let pid = fork();
+    if pid == 0 {
+        let mut buffer = [0u8; 4];
+        if read(fds[COUNT - 2], &mut buffer) != 4 || &buffer != b"grow" {
+            exit(-1);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    for fd in fds {
+        close(fd);
+    }
+    println!("Test fd grow OK!");
+    0
+}
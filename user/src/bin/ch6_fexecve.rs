@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fexecve, fork, open, read, waitpid, write, OpenFlags};
+
+/// 测试通过 fd 执行程序，输出　Test fexecve OK! 就算正确。
+
+const EACCES: isize = 13;
+const ENOEXEC: isize = 8;
+
+/// Copy the first `limit` bytes of the ELF of ch6_fexecve_child into a
+/// file of our own
+fn copy_elf(to: &str, limit: usize) {
+    let src = open("ch6_fexecve_child\0", OpenFlags::RDONLY);
+    assert!(src > 0);
+    let dst = open(to, OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC);
+    assert!(dst > 0);
+    let mut buffer = [0u8; 1024];
+    let mut copied = 0;
+    while copied < limit {
+        let len = read(src as usize, &mut buffer);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        let len = (len as usize).min(limit - copied);
+        assert_eq!(write(dst as usize, &buffer[..len]), len as isize);
+        copied += len;
+    }
+    close(src as usize);
+    close(dst as usize);
+}
+
+/// Run `fexecve(fd)` in a child and return its exit code
+fn run(fd: usize) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        let ret = fexecve(fd, &[core::ptr::null::<u8>()], &[core::ptr::null::<u8>()]);
+        exit(ret as i32);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    copy_elf("fexec_copy\0", usize::MAX);
+    let fd = open("fexec_copy\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(run(fd as usize), 42);
+    close(fd as usize);
+
+    // the fd has to be a readable regular file holding an ELF
+    let fd = open("fexec_copy\0", OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(run(fd as usize), -EACCES as i32);
+    close(fd as usize);
+    let fd = open("fexec_text\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"not an elf"), 10);
+    assert_eq!(run(fd as usize), -ENOEXEC as i32);
+    close(fd as usize);
+    // nor is a file with just the magic, or an ELF cut short
+    let fd = open("fexec_magic\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"\x7fELF\x02\x01\x01"), 7);
+    assert_eq!(run(fd as usize), -ENOEXEC as i32);
+    close(fd as usize);
+    copy_elf("fexec_short\0", 512);
+    let fd = open("fexec_short\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(run(fd as usize), -ENOEXEC as i32);
+    close(fd as usize);
+    assert_eq!(run(0), -EACCES as i32);
+    assert_eq!(run(99), -1);
+    println!("Test fexecve OK!");
+    0
+}
@@ -0,0 +1,11 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+/// fexecve'd by ch6_fexecve, which checks for the exit code
+
+#[no_mangle]
+pub fn main() -> i32 {
+    42
+}
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, link, open, read, sleep_blocking, unlink, write, OpenFlags, Stat};
+
+/// 测试文件的时间戳，输出　Test file times OK! 就算正确。
+
+/// Stat of the open file `fd`
+fn stat_of(fd: usize) -> Stat {
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    stat
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("times_file\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let created = stat_of(fd);
+    assert_eq!(created.mtime, created.ctime);
+    assert_eq!(write(fd, b"first"), 5);
+    let first = stat_of(fd);
+    assert!(first.mtime >= created.mtime);
+    // timestamps count whole seconds
+    sleep_blocking(1100);
+    assert_eq!(write(fd, b"second"), 6);
+    let second = stat_of(fd);
+    assert!(second.mtime > first.mtime);
+    assert_eq!(second.ctime, second.mtime);
+    assert_eq!(second.atime, first.atime);
+    sleep_blocking(1100);
+    let mut buffer = [0u8; 4];
+    close(fd);
+    let fd = open("times_file\0", OpenFlags::RDONLY) as usize;
+    assert_eq!(read(fd, &mut buffer), 4);
+    let third = stat_of(fd);
+    assert!(third.atime > second.mtime);
+    assert_eq!(third.mtime, second.mtime);
+    // a new link changes the status, not the contents
+    assert_eq!(link("times_file\0", "times_link\0"), 0);
+    let linked = stat_of(fd);
+    assert!(linked.ctime > second.ctime);
+    assert_eq!(linked.mtime, second.mtime);
+    close(fd);
+    unlink("times_link\0");
+    unlink("times_file\0");
+    println!("Test file times OK!");
+    0
+}
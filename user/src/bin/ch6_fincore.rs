@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fincore, iosubmit, lseek, open, read, unlink, write, IoEntry, OpenFlags,
+    FINCORE_BLOCK_SIZE, IO_OFFSET_CURRENT, IO_OP_FSYNC, SEEK_SET,
+};
+
+/// 测试 fincore 查询文件块是否在缓存中，输出　Test fincore OK! 就算正确。
+
+const EINVAL: isize = 22;
+/// long enough to need an indirect block
+const BLOCKS: usize = 30;
+/// the blocks read back before looking
+const READ: [usize; 2] = [3, 25];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("fc_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let data = [b'f'; FINCORE_BLOCK_SIZE];
+    for _ in 0..BLOCKS {
+        assert_eq!(write(fd as usize, &data), FINCORE_BLOCK_SIZE as isize);
+    }
+    // write everything back, which leaves the cache empty
+    let fsync = [IoEntry {
+        op: IO_OP_FSYNC,
+        fd: fd as u32,
+        buf: 0,
+        offset: IO_OFFSET_CURRENT,
+        len: 0,
+    }];
+    let mut completions = [0isize; 1];
+    assert_eq!(iosubmit(&fsync, &mut completions), 1);
+    close(fd as usize);
+
+    let fd = open("fc_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buffer = [0u8; FINCORE_BLOCK_SIZE];
+    for &block in READ.iter() {
+        assert_eq!(
+            lseek(fd, (block * FINCORE_BLOCK_SIZE) as isize, SEEK_SET),
+            (block * FINCORE_BLOCK_SIZE) as isize
+        );
+        assert_eq!(read(fd, &mut buffer), FINCORE_BLOCK_SIZE as isize);
+    }
+    // a larger vec than the file is only filled up to its end
+    let mut vec = [0xffu8; BLOCKS + 2];
+    assert_eq!(fincore(fd, 0, &mut vec), BLOCKS as isize);
+    for (block, &resident) in vec[..BLOCKS].iter().enumerate() {
+        assert_eq!(resident, READ.contains(&block) as u8);
+    }
+    // looking must not have cached anything
+    assert_eq!(fincore(fd, 0, &mut vec), BLOCKS as isize);
+    assert_eq!(vec.iter().filter(|&&resident| resident == 1).count(), 2);
+    assert_eq!(fincore(fd, 25 * FINCORE_BLOCK_SIZE, &mut vec[..1]), 1);
+    assert_eq!(vec[0], 1);
+    assert_eq!(fincore(fd, 1, &mut vec), -EINVAL);
+    assert_eq!(fincore(fd, BLOCKS * FINCORE_BLOCK_SIZE, &mut vec), 0);
+    close(fd);
+    assert_eq!(fincore(fd, 0, &mut vec), -1);
+    unlink("fc_file\0");
+    println!("Test fincore OK!");
+    0
+}
@@ -0,0 +1,109 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use user_lib::{
+    close, exit, fork, fsevents_open, fstat, open, read, rename, setuid, unlink, waitpid, write,
+    FsEvent, FsEventMask, OpenFlags, RenameFlags, Stat,
+};
+
+/// 测试全局文件系统事件流，输出　Test fsevents OK! 就算正确。
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+const MAX_EVENTS: usize = 80;
+
+/// Read every queued event from `fd` into `events`
+fn read_events(fd: usize, events: &mut [FsEvent; MAX_EVENTS]) -> usize {
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(
+            events.as_mut_ptr() as *mut u8,
+            MAX_EVENTS * size_of::<FsEvent>(),
+        )
+    };
+    let len = read(fd, buf);
+    assert!(len >= 0);
+    assert_eq!(len as usize % size_of::<FsEvent>(), 0);
+    len as usize / size_of::<FsEvent>()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(fsevents_open(FsEventMask::empty()), -EINVAL);
+    assert_eq!(fsevents_open(FsEventMask::OVERFLOW), -EINVAL);
+    let all = fsevents_open(
+        FsEventMask::CREATE | FsEventMask::UNLINK | FsEventMask::RENAME | FsEventMask::WRITE,
+    );
+    assert!(all > 0);
+    let unlinks = fsevents_open(FsEventMask::UNLINK);
+    assert!(unlinks > 0);
+    let pid = fork();
+    if pid == 0 {
+        let fd = open("fse_a\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+        assert!(fd > 0);
+        let stat = Stat::new();
+        assert_eq!(fstat(fd as usize, &stat), 0);
+        assert_eq!(write(fd as usize, b"hello"), 5);
+        close(fd as usize);
+        assert_eq!(rename("fse_a\0", "fse_b\0", RenameFlags::empty()), 0);
+        assert_eq!(unlink("fse_b\0"), 0);
+        // only root may watch everything
+        assert_eq!(setuid(1000), 0);
+        assert_eq!(fsevents_open(FsEventMask::CREATE), -EPERM);
+        // tell the parent which inode it was
+        exit(stat.ino as i32);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    let ino = exit_code as u64;
+    // a stream's size is that of the events waiting in it
+    let stat = Stat::new();
+    assert_eq!(fstat(all as usize, &stat), 0);
+    assert_eq!(stat.size, 4 * size_of::<FsEvent>() as u64);
+    let mut events = [FsEvent::default(); MAX_EVENTS];
+    assert_eq!(read_events(all as usize, &mut events), 4);
+    let expected = [
+        FsEventMask::CREATE,
+        FsEventMask::WRITE,
+        FsEventMask::RENAME,
+        FsEventMask::UNLINK,
+    ];
+    for (event, mask) in events.iter().zip(expected.iter()) {
+        assert_eq!(
+            *event,
+            FsEvent {
+                ino,
+                mask: mask.bits(),
+                pid: pid as u32,
+            }
+        );
+    }
+    assert_eq!(read_events(all as usize, &mut events), 0);
+    assert_eq!(read_events(unlinks as usize, &mut events), 1);
+    assert_eq!(events[0].mask, FsEventMask::UNLINK.bits());
+    assert_eq!(events[0].ino, ino);
+    close(all as usize);
+    close(unlinks as usize);
+    // a full queue drops what comes after and says so
+    let writes = fsevents_open(FsEventMask::WRITE);
+    assert!(writes > 0);
+    let fd = open("fse_c\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    for _ in 0..70 {
+        assert_eq!(write(fd as usize, b"x"), 1);
+    }
+    close(fd as usize);
+    assert_eq!(read_events(writes as usize, &mut events), 65);
+    assert!(events[..64]
+        .iter()
+        .all(|event| event.mask == FsEventMask::WRITE.bits()));
+    assert_eq!(events[64].mask, FsEventMask::OVERFLOW.bits());
+    assert_eq!(read_events(writes as usize, &mut events), 0);
+    close(writes as usize);
+    unlink("fse_c\0");
+    println!("Test fsevents OK!");
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fsync, open, pipe, read, unlink, write, OpenFlags};
+
+/// 测试 fsync 把文件写回磁盘，输出　Test fsync OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("fsync_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(write(fd, b"durable"), 7);
+    assert_eq!(fsync(fd), 0);
+    close(fd);
+    let fd = open("fsync_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buffer = [0u8; 7];
+    assert_eq!(read(fd, &mut buffer), 7);
+    assert_eq!(&buffer, b"durable");
+    // even a file opened read-only can be synced
+    assert_eq!(fsync(fd), 0);
+    close(fd);
+    assert_eq!(unlink("fsync_file\0"), 0);
+    // the console has nothing to flush, a pipe can't be synced
+    assert_eq!(fsync(0), 0);
+    assert_eq!(fsync(1), 0);
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    assert_eq!(fsync(pipe_fd[0]), -1);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    assert_eq!(fsync(fd), -1);
+    println!("Test fsync OK!");
+    0
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use user_lib::{close, getdents, lseek, open, unlink, Dirent, OpenFlags, DT_REG, SEEK_SET};
+
+/// 测试 getdents 的 d_off，输出　Test getdents OK! 就算正确。
+
+const RECLEN: usize = size_of::<Dirent>();
+
+fn dirent_at(buffer: &[u8], pos: usize) -> &Dirent {
+    assert!(pos + RECLEN <= buffer.len());
+    unsafe { &*(buffer[pos..].as_ptr() as *const Dirent) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("gd_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+
+    let dir = open("/\0", OpenFlags::RDONLY);
+    assert!(dir > 0);
+    let dir = dir as usize;
+    // scan the whole directory looking for the file
+    let mut buffer = [0u8; RECLEN * 8];
+    let mut found: Option<(u64, u64)> = None;
+    loop {
+        let len = getdents(dir, &mut buffer);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        let mut pos = 0;
+        while pos < len as usize {
+            let dirent = dirent_at(&buffer, pos);
+            if dirent.name() == "gd_file" {
+                assert_eq!(dirent.d_type, DT_REG);
+                found = Some((dirent.d_ino, dirent.d_off));
+            }
+            pos += dirent.d_reclen as usize;
+        }
+    }
+    let (ino, off) = found.unwrap();
+
+    // seeking to d_off yields the same entry again
+    assert_eq!(lseek(dir, off as isize, SEEK_SET), off as isize);
+    let mut one = [0u8; RECLEN];
+    assert_eq!(getdents(dir, &mut one), RECLEN as isize);
+    let dirent = dirent_at(&one, 0);
+    assert_eq!(dirent.name(), "gd_file");
+    assert_eq!(dirent.d_ino, ino);
+    assert_eq!(dirent.d_off, off);
+    close(dir);
+    unlink("gd_file\0");
+    println!("Test getdents OK!");
+    0
+}
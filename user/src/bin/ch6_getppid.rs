@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, getpid, getppid, pipe, read, waitpid, write, yield_};
+
+/// 测试 getppid 返回父进程的 pid，输出　Test getppid OK! 就算正确。
+
+/// pid of initproc, which adopts orphans
+const INITPROC_PID: isize = 0;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let child = fork();
+    if child == 0 {
+        exit((getppid() == pid) as i32);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    assert_eq!(exit_code, 1);
+
+    // a grandchild is adopted by initproc once its parent exits
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let child = fork();
+    if child == 0 {
+        let parent = getpid();
+        if fork() == 0 {
+            close(pipe_fd[0]);
+            assert_eq!(getppid(), parent);
+            let mut byte = [0u8];
+            assert_eq!(write(pipe_fd[1], &byte), 1);
+            while getppid() != INITPROC_PID {
+                yield_();
+            }
+            byte[0] = 1;
+            assert_eq!(write(pipe_fd[1], &byte), 1);
+            exit(0);
+        }
+        let mut byte = [0u8];
+        // wait for the grandchild to have seen its parent alive
+        assert_eq!(read(pipe_fd[0], &mut byte), 1);
+        exit(0);
+    }
+    close(pipe_fd[1]);
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    // the child took the first byte, the second comes once adopted
+    let mut byte = [0u8];
+    assert_eq!(read(pipe_fd[0], &mut byte), 1);
+    assert_eq!(byte[0], 1);
+    close(pipe_fd[0]);
+    println!("Test getppid OK!");
+    0
+}
@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{gettimeofday, mmap, settimeofday, yield_, TimeVal, TimeZone};
+
+/// 测试墙上时间与时区，输出　Test gettimeofday OK! 就算正确。
+
+const EINVAL: isize = 22;
+
+fn now_us() -> usize {
+    let mut time = TimeVal::new();
+    assert_eq!(gettimeofday(&mut time, None), 0);
+    time.sec * 1_000_000 + time.usec
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // without an epoch the wall clock is the uptime
+    let uptime = now_us();
+    let epoch = TimeVal {
+        sec: 1_700_000_000,
+        usec: 250_000,
+    };
+    assert_eq!(settimeofday(Some(&epoch), None), 0);
+    let set = epoch.sec * 1_000_000 + epoch.usec;
+    let before = now_us();
+    assert!(before >= set);
+    for _ in 0..10 {
+        yield_();
+    }
+    // the clock keeps running from the new epoch
+    let after = now_us();
+    assert!(after >= before);
+    assert!(after - set < 1_000_000);
+
+    let tz = TimeZone {
+        minuteswest: -480,
+        dsttime: 0,
+    };
+    assert_eq!(settimeofday(None, Some(&tz)), 0);
+    let mut time = TimeVal::new();
+    let mut got = TimeZone::default();
+    assert_eq!(gettimeofday(&mut time, Some(&mut got)), 0);
+    assert_eq!(got, tz);
+
+    // both structs may straddle a page boundary
+    let start: usize = 0x10000000;
+    assert_eq!(mmap(start, 8192, 3), 0);
+    let time = unsafe { &mut *((start + 4096 - 8) as *mut TimeVal) };
+    let got = unsafe { &mut *((start + 4096 - 4) as *mut TimeZone) };
+    assert_eq!(gettimeofday(time, Some(got)), 0);
+    assert!(time.sec >= epoch.sec);
+    assert_eq!(got.minuteswest, -480);
+
+    let bad = TimeVal {
+        sec: 0,
+        usec: 1_000_000,
+    };
+    assert_eq!(settimeofday(Some(&bad), None), -EINVAL);
+    // a wall clock before boot is refused
+    assert_eq!(settimeofday(Some(&TimeVal::new()), None), -EINVAL);
+    // put the clock back near the uptime, a little ahead so it is not before boot
+    let uptime = now_us() - set + uptime + 100_000;
+    let restore = TimeVal {
+        sec: uptime / 1_000_000,
+        usec: uptime % 1_000_000,
+    };
+    assert_eq!(settimeofday(Some(&restore), Some(&TimeZone::default())), 0);
+    println!("Test gettimeofday OK!");
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, iosubmit, open, unlink, IoEntry, OpenFlags, IO_OFFSET_CURRENT, IO_OP_FSYNC, IO_OP_READ,
+    IO_OP_WRITE,
+};
+
+/// 测试批量提交 I/O，输出　Test iosubmit OK! 就算正确。
+
+const EINVAL: isize = 22;
+
+fn entry(op: u32, fd: usize, buf: &[u8], offset: usize) -> IoEntry {
+    IoEntry {
+        op,
+        fd: fd as u32,
+        buf: buf.as_ptr() as usize,
+        offset,
+        len: buf.len(),
+    }
+}
+
+fn read_entry(fd: usize, buf: &mut [u8], offset: usize) -> IoEntry {
+    IoEntry {
+        op: IO_OP_READ,
+        fd: fd as u32,
+        buf: buf.as_mut_ptr() as usize,
+        offset,
+        len: buf.len(),
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("iosubmit\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buf = [0u8; 16];
+    let batch = [
+        entry(IO_OP_WRITE, fd, b"abc", 0),
+        entry(IO_OP_WRITE, fd, b"defg", IO_OFFSET_CURRENT),
+        entry(IO_OP_WRITE, fd, b"hi", IO_OFFSET_CURRENT),
+        read_entry(fd, &mut buf, 0),
+        entry(IO_OP_FSYNC, fd, &[], IO_OFFSET_CURRENT),
+    ];
+    let mut completions = [isize::MIN; 5];
+    assert_eq!(iosubmit(&batch, &mut completions), 5);
+    assert_eq!(completions, [3, 4, 2, 9, 0]);
+    assert_eq!(&buf[..9], b"abcdefghi");
+    // a malformed entry stops the batch
+    let batch = [
+        entry(IO_OP_WRITE, fd, b"xyz", 0),
+        entry(99, fd, b"xyz", 0),
+        entry(IO_OP_WRITE, fd, b"xyz", 3),
+    ];
+    let mut completions = [isize::MIN; 3];
+    assert_eq!(iosubmit(&batch, &mut completions), 1);
+    assert_eq!(completions, [3, -EINVAL, isize::MIN]);
+    let batch = [read_entry(fd, &mut buf, 0), read_entry(100, &mut buf, 0)];
+    let mut completions = [isize::MIN; 2];
+    assert_eq!(iosubmit(&batch, &mut completions), 1);
+    assert_eq!(completions, [9, -EINVAL]);
+    assert_eq!(&buf[..9], b"xyzdefghi");
+    assert_eq!(iosubmit(&[], &mut []), 0);
+    close(fd);
+    unlink("iosubmit\0");
+    println!("Test iosubmit OK!");
+    0
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    fork, get_time, kill, sleep_blocking, waitpid, waitpid_status, wifsignaled, wtermsig, yield_,
+    SIGKILL,
+};
+
+/// 测试 kill 结束另一个进程，输出　Test kill OK! 就算正确。
+
+const ESRCH: isize = 3;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // a child that never gives up on its own
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            yield_();
+        }
+    }
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    let mut status: i32 = 0;
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifsignaled(status));
+    assert_eq!(wtermsig(status), SIGKILL);
+    // the pid is gone with it
+    assert_eq!(kill(pid as usize, SIGKILL), -ESRCH);
+
+    // a sleeping child dies right away instead of at its deadline
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(10_000);
+        unreachable!();
+    }
+    sleep_blocking(10);
+    let start = get_time();
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -(SIGKILL as i32));
+    assert!(get_time() - start < 1000);
+    println!("Test kill OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, statm, Statm};
+
+/// 测试 mmap 的页在首次访问时才分配，输出　Test lazy mmap OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const LEN: usize = 1 << 20;
+
+fn resident() -> usize {
+    let mut info = Statm::default();
+    assert_eq!(statm(&mut info), 0);
+    info.resident
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let before = resident();
+    assert_eq!(mmap(START, LEN, 3), 0);
+    assert_eq!(resident(), before);
+    // one page written in the middle of the region takes one frame
+    let page = (START + LEN / 2) as *mut usize;
+    unsafe { page.write_volatile(0x5a5a) };
+    assert_eq!(resident(), before + 1);
+    assert_eq!(unsafe { page.read_volatile() }, 0x5a5a);
+    assert_eq!(resident(), before + 1);
+    // an untouched page reads as zeros
+    let last = (START + LEN - PAGE) as *const usize;
+    assert_eq!(unsafe { last.read_volatile() }, 0);
+    assert_eq!(resident(), before + 2);
+    // the region unmaps as a whole with most of it never faulted in
+    assert_eq!(munmap(START, LEN), 0);
+    assert_eq!(resident(), before);
+    assert_eq!(mmap(START, LEN, 3), 0);
+    assert_eq!(unsafe { page.read_volatile() }, 0);
+    assert_eq!(munmap(START, LEN), 0);
+    println!("Test lazy mmap OK!");
+    0
+}
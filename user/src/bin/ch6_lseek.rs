@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, lseek, open, read, unlink, write, OpenFlags, SEEK_CUR, SEEK_END, SEEK_SET};
+
+/// 测试 lseek 移动文件偏移，输出　Test lseek OK! 就算正确。
+
+const EINVAL: isize = 22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("lseek_file\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(write(fd, b"0123456789"), 10);
+    assert_eq!(lseek(fd, 0, SEEK_CUR), 10);
+    assert_eq!(lseek(fd, 2, SEEK_SET), 2);
+    let mut buffer = [0u8; 4];
+    assert_eq!(read(fd, &mut buffer), 4);
+    assert_eq!(&buffer, b"2345");
+    assert_eq!(lseek(fd, -3, SEEK_CUR), 3);
+    assert_eq!(read(fd, &mut buffer[..1]), 1);
+    assert_eq!(buffer[0], b'3');
+    assert_eq!(lseek(fd, -1, SEEK_END), 9);
+    assert_eq!(read(fd, &mut buffer), 1);
+    assert_eq!(buffer[0], b'9');
+    // writing past the end leaves a hole of zeros
+    assert_eq!(lseek(fd, 2, SEEK_END), 12);
+    assert_eq!(write(fd, b"x"), 1);
+    assert_eq!(lseek(fd, 9, SEEK_SET), 9);
+    assert_eq!(read(fd, &mut buffer), 4);
+    assert_eq!(&buffer, b"9\0\0x");
+    // the offset stays put on a bad request
+    assert_eq!(lseek(fd, -1, SEEK_SET), -EINVAL);
+    assert_eq!(lseek(fd, -14, SEEK_CUR), -EINVAL);
+    assert_eq!(lseek(fd, 0, 3), -EINVAL);
+    assert_eq!(lseek(fd, 0, SEEK_CUR), 13);
+    // the console can't seek
+    assert_eq!(lseek(0, 0, SEEK_SET), -1);
+    assert_eq!(lseek(1, 0, SEEK_SET), -1);
+    close(fd);
+    unlink("lseek_file\0");
+    println!("Test lseek OK!");
+    0
+}
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{fork, mmap, munmap, set_mempolicy, waitpid};
+
+/// 测试内存节点策略，输出　Test mempolicy OK! 就算正确。
+
+const EINVAL: isize = 22;
+
+fn touch(start: usize, len: usize) {
+    assert_eq!(mmap(start, len, 3), 0);
+    for addr in (start..start + len).step_by(4096) {
+        unsafe {
+            *(addr as *mut usize) = addr;
+        }
+    }
+    for addr in (start..start + len).step_by(4096) {
+        assert_eq!(unsafe { *(addr as *const usize) }, addr);
+    }
+    assert_eq!(munmap(start, len), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_mempolicy(0), 0);
+    assert_eq!(set_mempolicy(1), -EINVAL);
+    assert_eq!(set_mempolicy(usize::MAX), -EINVAL);
+    touch(0x10000000, 16 * 4096);
+    // the policy is inherited by children
+    let pid = fork();
+    if pid == 0 {
+        touch(0x10000000, 16 * 4096);
+        return 0;
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test mempolicy OK!");
+    0
+}
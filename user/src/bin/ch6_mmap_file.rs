@@ -0,0 +1,91 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, fstat, lseek, mmap_shared, munmap, open, read, unlink, waitpid, write,
+    OpenFlags, Stat, SEEK_SET,
+};
+
+/// 测试映射文件并写回，输出　Test mmap file OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const LEN: usize = PAGE + 100;
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+
+fn byte(offset: usize) -> *mut u8 {
+    (START + offset) as *mut u8
+}
+
+/// Read the byte at `offset` of the file through `fd`
+fn read_byte(fd: usize, offset: usize) -> u8 {
+    let mut buffer = [0u8; 1];
+    assert_eq!(lseek(fd, offset as isize, SEEK_SET), offset as isize);
+    assert_eq!(read(fd, &mut buffer), 1);
+    buffer[0]
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("mmap_file\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut data = [0u8; LEN];
+    for (i, b) in data.iter_mut().enumerate() {
+        *b = (i % 251) as u8;
+    }
+    assert_eq!(write(fd, &data), LEN as isize);
+    assert_eq!(
+        mmap_shared(START, 2 * PAGE, PROT_READ | PROT_WRITE, fd, 0),
+        0
+    );
+    // the mapping reads what the file holds
+    for offset in [0, 7, PAGE - 1, PAGE, LEN - 1] {
+        assert_eq!(
+            unsafe { byte(offset).read_volatile() },
+            read_byte(fd, offset)
+        );
+    }
+    unsafe {
+        byte(7).write_volatile(b'p');
+        // past the end of the file, which doesn't grow
+        byte(LEN + 1).write_volatile(b'x');
+    }
+    // a child writes to the same pages instead of copies, and what it
+    // wrote reaches the file as it exits
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(unsafe { byte(7).read_volatile() }, b'p');
+        unsafe { byte(PAGE + 1).write_volatile(b'c') };
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(unsafe { byte(PAGE + 1).read_volatile() }, b'c');
+    assert_eq!(munmap(START, 2 * PAGE), 0);
+    assert_eq!(read_byte(fd, 7), b'p');
+    assert_eq!(read_byte(fd, PAGE + 1), b'c');
+    assert_eq!(read_byte(fd, 8), 8);
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    assert_eq!(stat.size, LEN as u64);
+
+    // a process that exits with the file still mapped writes it back too
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(mmap_shared(START, PAGE, PROT_READ | PROT_WRITE, fd, 0), 0);
+        unsafe { byte(0).write_volatile(b'e') };
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(read_byte(fd, 0), b'e');
+    close(fd);
+    unlink("mmap_file\0");
+    println!("Test mmap file OK!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap};
+
+/// 测试 mmap 拒绝与已有映射重叠的区间，输出　Test mmap overlap OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the middle region is left untouched, so none of its pages is resident
+    assert_eq!(mmap(START, 4 * PAGE, 3), 0);
+    // fully inside
+    assert_eq!(mmap(START + PAGE, PAGE, 3), -1);
+    assert_eq!(mmap(START, 4 * PAGE, 3), -1);
+    // covering it
+    assert_eq!(mmap(START - PAGE, 6 * PAGE, 3), -1);
+    // overlapping the start or the end
+    assert_eq!(mmap(START - PAGE, 2 * PAGE, 3), -1);
+    assert_eq!(mmap(START + 3 * PAGE, 2 * PAGE, 3), -1);
+    // abutting on either side is fine
+    assert_eq!(mmap(START - PAGE, PAGE, 3), 0);
+    assert_eq!(mmap(START + 4 * PAGE, PAGE, 3), 0);
+    for i in 0..6 {
+        let addr = (START - PAGE + i * PAGE) as *mut usize;
+        unsafe { addr.write_volatile(i) };
+    }
+    for i in 0..6 {
+        let addr = (START - PAGE + i * PAGE) as *const usize;
+        assert_eq!(unsafe { addr.read_volatile() }, i);
+    }
+    // the code and the stack are mapped too
+    let code = main as usize & !(PAGE - 1);
+    assert_eq!(mmap(code, PAGE, 3), -1);
+    let local = 0usize;
+    let stack = &local as *const usize as usize & !(PAGE - 1);
+    assert_eq!(mmap(stack, PAGE, 3), -1);
+    assert_eq!(munmap(START - PAGE, 6 * PAGE), 0);
+    println!("Test mmap overlap OK!");
+    0
+}
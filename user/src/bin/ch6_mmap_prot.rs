@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, waitpid};
+
+/// 测试 mmap 的 port 决定页能否读、写、执行，输出　Test mmap prot OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+const PROT_EXEC: usize = 4;
+/// Exit code of a task killed by a page fault
+const FAULTED: i32 = -2;
+/// `ret`, returning straight to the caller
+const RET: u32 = 0x0000_8067;
+
+/// Run `f` in a child, returning its exit code
+fn in_child(f: fn()) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+/// Map a page holding a lone `ret`, then give it `port`
+fn code_page(port: usize) {
+    assert_eq!(mmap(START, PAGE, PROT_READ | PROT_WRITE | port), 0);
+    unsafe {
+        (START as *mut u32).write_volatile(RET);
+        core::arch::asm!("fence.i");
+    }
+}
+
+fn call_page() {
+    let f: fn() = unsafe { core::mem::transmute(START) };
+    f();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // a read-only page can't be written
+    assert_eq!(mmap(START, PAGE, PROT_READ), 0);
+    assert_eq!(unsafe { (START as *const usize).read_volatile() }, 0);
+    let write = || unsafe { (START as *mut usize).write_volatile(1) };
+    assert_eq!(in_child(write), FAULTED);
+    assert_eq!(munmap(START, PAGE), 0);
+
+    // a page both readable and writable reads back what was written
+    assert_eq!(mmap(START, PAGE, PROT_READ | PROT_WRITE), 0);
+    assert_eq!(in_child(write), 0);
+    unsafe { (START as *mut usize).write_volatile(42) };
+    assert_eq!(unsafe { (START as *const usize).read_volatile() }, 42);
+    assert_eq!(munmap(START, PAGE), 0);
+
+    // without the readable bit the page isn't readable, nor is it writable
+    // as riscv has no writable pages that can't be read
+    assert_eq!(mmap(START, PAGE, PROT_WRITE), 0);
+    let read = || unsafe {
+        (START as *const usize).read_volatile();
+    };
+    assert_eq!(in_child(read), FAULTED);
+    assert_eq!(in_child(write), FAULTED);
+    assert_eq!(munmap(START, PAGE), 0);
+
+    // code only runs where the page is executable
+    code_page(0);
+    assert_eq!(in_child(call_page), FAULTED);
+    assert_eq!(munmap(START, PAGE), 0);
+    code_page(PROT_EXEC);
+    call_page();
+    assert_eq!(in_child(call_page), 0);
+    assert_eq!(munmap(START, PAGE), 0);
+    println!("Test mmap prot OK!");
+    0
+}
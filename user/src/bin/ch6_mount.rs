@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, mkdir, mount, open, read, umount, write, OpenFlags};
+
+/// 测试挂载第二个文件系统，输出　Test mount OK! 就算正确。
+
+const EBUSY: isize = 16;
+const EEXIST: isize = 17;
+const ENOTDIR: isize = 20;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let ret = mkdir("/mnt\0");
+    assert!(ret == 0 || ret == -EEXIST);
+    let fd = open("mnt_root\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"root"), 4);
+    close(fd as usize);
+    assert_eq!(mount(1, "/mnt_root\0"), -ENOTDIR);
+    assert_eq!(mount(1, "/mnt\0"), 0);
+    // the file lands in the mounted filesystem
+    let fd = open("/mnt/mfile\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"mounted"), 7);
+    close(fd as usize);
+    let fd = open("/mnt/mfile\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; 16];
+    assert_eq!(read(fd as usize, &mut buffer), 7);
+    assert_eq!(&buffer[..7], b"mounted");
+    close(fd as usize);
+    assert!(open("/mfile\0", OpenFlags::RDONLY) < 0);
+    // the root filesystem is hidden below /mnt
+    assert!(open("/mnt/mnt_root\0", OpenFlags::RDONLY) < 0);
+    // .. at the mounted root leaves back into the original tree
+    let fd = open("/mnt/../mnt_root\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), 4);
+    assert_eq!(&buffer[..4], b"root");
+    close(fd as usize);
+    assert_eq!(mount(1, "/mnt\0"), -EBUSY);
+    assert_eq!(umount("/mnt\0"), 0);
+    println!("Test mount OK!");
+    0
+}
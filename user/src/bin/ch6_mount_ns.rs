@@ -0,0 +1,102 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, mkdir, mount, open, setns, setuid, umount, unlink, unshare, waitpid, write,
+    yield_, OpenFlags, CLONE_NEWNS,
+};
+
+/// 测试挂载命名空间，输出　Test mount ns OK! 就算正确。
+
+const EPERM: isize = 1;
+const ESRCH: isize = 3;
+const EBUSY: isize = 16;
+const EEXIST: isize = 17;
+const EINVAL: isize = 22;
+
+fn touch(path: &str) {
+    let fd = open(path, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+}
+
+fn wait_for(path: &str) {
+    loop {
+        let fd = open(path, OpenFlags::RDONLY);
+        if fd > 0 {
+            close(fd as usize);
+            return;
+        }
+        yield_();
+    }
+}
+
+fn visible() -> bool {
+    let fd = open("/mnt/ns_file\0", OpenFlags::RDONLY);
+    if fd > 0 {
+        close(fd as usize);
+    }
+    fd > 0
+}
+
+fn wait(pid: isize) {
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let ret = mkdir("/mnt\0");
+    assert!(ret == 0 || ret == -EEXIST);
+    unlink("ns_ready\0");
+    unlink("ns_done\0");
+    assert_eq!(unshare(1), -EINVAL);
+    assert_eq!(setns(9999, CLONE_NEWNS), -ESRCH);
+    let owner = fork();
+    if owner == 0 {
+        assert_eq!(unshare(CLONE_NEWNS), 0);
+        assert_eq!(mount(1, "/mnt\0"), 0);
+        let fd = open("/mnt/ns_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+        assert!(fd > 0);
+        assert_eq!(write(fd as usize, b"private"), 7);
+        close(fd as usize);
+        assert!(visible());
+        touch("ns_ready\0");
+        wait_for("ns_done\0");
+        assert_eq!(unlink("/mnt/ns_file\0"), 0);
+        assert_eq!(umount("/mnt\0"), 0);
+        exit(0);
+    }
+    wait_for("ns_ready\0");
+    // the mount only shows in the namespace it was made in
+    assert!(!visible());
+    // and the device is already in use there
+    assert_eq!(mount(1, "/mnt\0"), -EBUSY);
+    let joiner = fork();
+    if joiner == 0 {
+        assert_eq!(setns(owner as usize, CLONE_NEWNS), 0);
+        assert!(visible());
+        exit(0);
+    }
+    wait(joiner);
+    // another user may not join it
+    let stranger = fork();
+    if stranger == 0 {
+        assert_eq!(setuid(1000), 0);
+        assert_eq!(setns(owner as usize, CLONE_NEWNS), -EPERM);
+        assert!(!visible());
+        exit(0);
+    }
+    wait(stranger);
+    assert!(!visible());
+    touch("ns_done\0");
+    wait(owner);
+    unlink("ns_ready\0");
+    unlink("ns_done\0");
+    println!("Test mount ns OK!");
+    0
+}
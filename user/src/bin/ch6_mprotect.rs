@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, munmap, waitpid};
+
+/// 测试 mprotect 修改页的权限，输出　Test mprotect OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE) as *mut usize
+}
+
+/// Write to page `i` in a child, returning its exit code
+fn write_in_child(i: usize) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        unsafe { page(i).write_volatile(0) };
+        exit(0);
+    }
+    let mut exit_code: i32 = 1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, 3 * PAGE, PROT_READ | PROT_WRITE), 0);
+    for i in 0..3 {
+        unsafe { page(i).write_volatile(i + 1) };
+    }
+    // only the middle page turns read-only
+    assert_eq!(mprotect(START + PAGE, PAGE, PROT_READ), 0);
+    for i in 0..3 {
+        assert_eq!(unsafe { page(i).read_volatile() }, i + 1);
+    }
+    assert_eq!(write_in_child(0), 0);
+    assert_eq!(write_in_child(1), -2);
+    assert_eq!(write_in_child(2), 0);
+    // malformed permissions and unmapped pages are refused
+    assert_eq!(mprotect(START, PAGE, 0), -1);
+    assert_eq!(mprotect(START, PAGE, 8 | PROT_READ), -1);
+    assert_eq!(mprotect(START + 2 * PAGE, 2 * PAGE, PROT_READ), -1);
+    assert_eq!(write_in_child(2), 0);
+    // a page not touched yet is faulted in with the new permissions
+    assert_eq!(mmap(START + 3 * PAGE, PAGE, PROT_READ | PROT_WRITE), 0);
+    assert_eq!(mprotect(START, 4 * PAGE, PROT_READ), 0);
+    assert_eq!(write_in_child(3), -2);
+    assert_eq!(unsafe { page(3).read_volatile() }, 0);
+    assert_eq!(write_in_child(0), -2);
+    assert_eq!(mprotect(START + PAGE, PAGE, PROT_READ | PROT_WRITE), 0);
+    unsafe { page(1).write_volatile(5) };
+    assert_eq!(unsafe { page(1).read_volatile() }, 5);
+    assert_eq!(munmap(START, 4 * PAGE), 0);
+    println!("Test mprotect OK!");
+    0
+}
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, mseal, munmap, waitpid};
+
+/// 测试 mseal 封存映射，输出　Test mseal OK! 就算正确。
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len: usize = 4096 * 2;
+    assert_eq!(mmap(start, len, PROT_READ | PROT_WRITE), 0);
+    for i in start..(start + len) {
+        unsafe {
+            *(i as *mut u8) = i as u8;
+        }
+    }
+    // an unsealed area may still change
+    assert_eq!(mprotect(start, len, PROT_READ), 0);
+    assert_eq!(mseal(start + 1, len), -EINVAL);
+    // the page behind the area isn't mapped
+    assert_eq!(mseal(start, len + 4096), -1);
+    assert_eq!(mseal(start, len), 0);
+    assert_eq!(mprotect(start, len, PROT_READ | PROT_WRITE), -EPERM);
+    assert_eq!(munmap(start, len), -EPERM);
+    // sealing twice is fine
+    assert_eq!(mseal(start, 4096), 0);
+    for i in start..(start + len) {
+        assert_eq!(unsafe { *(i as *const u8) }, i as u8);
+    }
+    // still read-only, writing kills a child
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(munmap(start, len), -EPERM);
+        unsafe {
+            *(start as *mut u8) = 0;
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+    println!("Test mseal OK!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, statm, Statm};
+
+/// 测试 munmap 拆分一段映射，输出　Test munmap split OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const PAGES: usize = 8;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE) as *mut usize
+}
+
+fn resident() -> usize {
+    let mut info = Statm::default();
+    assert_eq!(statm(&mut info), 0);
+    info.resident
+}
+
+/// The pages still mapped hold what was written to them
+fn check(pages: &[usize]) {
+    for &i in pages {
+        assert_eq!(unsafe { page(i).read_volatile() }, i + 1);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let unmapped = resident();
+    assert_eq!(mmap(START, PAGES * PAGE, 3), 0);
+    for i in 0..PAGES {
+        unsafe { page(i).write_volatile(i + 1) };
+    }
+    assert_eq!(resident(), unmapped + PAGES);
+    // head
+    assert_eq!(munmap(START, PAGE), 0);
+    assert_eq!(resident(), unmapped + PAGES - 1);
+    check(&[1, 2, 3, 4, 5, 6, 7]);
+    // tail
+    assert_eq!(munmap(START + 7 * PAGE, PAGE), 0);
+    assert_eq!(resident(), unmapped + PAGES - 2);
+    check(&[1, 2, 3, 4, 5, 6]);
+    // middle, which leaves two mappings
+    assert_eq!(munmap(START + 3 * PAGE, 2 * PAGE), 0);
+    assert_eq!(resident(), unmapped + PAGES - 4);
+    check(&[1, 2, 5, 6]);
+    // ranges reaching into the holes fail and unmap nothing
+    assert_eq!(munmap(START, 2 * PAGE), -1);
+    assert_eq!(munmap(START + 2 * PAGE, 2 * PAGE), -1);
+    assert_eq!(munmap(START + 6 * PAGE, 2 * PAGE), -1);
+    assert_eq!(resident(), unmapped + PAGES - 4);
+    check(&[1, 2, 5, 6]);
+    // the hole in the middle can be mapped again
+    assert_eq!(mmap(START + 3 * PAGE, 2 * PAGE, 3), 0);
+    assert_eq!(unsafe { page(3).read_volatile() }, 0);
+    // one range may cover several mappings
+    assert_eq!(munmap(START + PAGE, 6 * PAGE), 0);
+    assert_eq!(resident(), unmapped);
+    assert_eq!(munmap(START + PAGE, PAGE), -1);
+    println!("Test munmap split OK!");
+    0
+}
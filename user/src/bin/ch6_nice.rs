@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, nice, set_priority, waitpid, yield_};
+
+/// 测试 nice 相对地调整优先级，调低后的任务分到的调度次数更少，输出　Test nice OK! 就算正确。
+
+const PRIO: isize = 16;
+const RUN_MS: isize = 300;
+
+/// Yield until `deadline`, returning how many times the task got to run
+fn count_runs(deadline: isize) -> i32 {
+    let mut runs = 0;
+    while get_time() < deadline {
+        yield_();
+        runs += 1;
+    }
+    runs
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_priority(PRIO), PRIO);
+    assert_eq!(nice(4), PRIO + 4);
+    assert_eq!(nice(-4), PRIO);
+    assert_eq!(nice(0), PRIO);
+    // the priority stays at least 2 however far it's niced down
+    assert_eq!(nice(-100), 2);
+    assert_eq!(nice(isize::MIN), 2);
+    assert_eq!(nice(isize::MAX), isize::MAX);
+    assert_eq!(nice(isize::MAX), isize::MAX);
+    assert_eq!(set_priority(PRIO), PRIO);
+
+    // two tasks start at the same priority, one of them is niced down
+    let deadline = get_time() + RUN_MS;
+    let mut pids = [0usize; 2];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        let child = fork();
+        if child == 0 {
+            assert_eq!(set_priority(PRIO), PRIO);
+            if i == 1 {
+                assert_eq!(nice(2 - PRIO), 2);
+            }
+            exit(count_runs(deadline));
+        }
+        *pid = child as usize;
+    }
+    let mut runs = [0i32; 2];
+    for (pid, runs) in pids.iter().zip(runs.iter_mut()) {
+        assert_eq!(waitpid(*pid, runs), *pid as isize);
+    }
+    println!(
+        "runs at prio {}: {}, niced down to 2: {}",
+        PRIO, runs[0], runs[1]
+    );
+    // a share of 2/16 of the other one's, with room to spare
+    assert!(runs[1] > 0);
+    assert!(runs[1] * 3 < runs[0]);
+    println!("Test nice OK!");
+    0
+}
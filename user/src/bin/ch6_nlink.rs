@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, link, open, syscall, unlink, OpenFlags, Stat, SYSCALL_FSTAT};
+
+/// 测试多次 link/unlink 之后 fstat 报告的 nlink，输出　Test nlink OK! 就算正确。
+
+const EFAULT: isize = 14;
+
+/// The link count `fstat` reports for `fd`
+fn nlink(fd: usize) -> u32 {
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    stat.nlink
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("nlink0\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(nlink(fd), 1);
+    assert_eq!(link("nlink0\0", "nlink1\0"), 0);
+    assert_eq!(link("nlink1\0", "nlink2\0"), 0);
+    assert_eq!(nlink(fd), 3);
+    // a second fd, opened through another name, sees the same count
+    let other = open("nlink2\0", OpenFlags::RDONLY);
+    assert!(other > 0);
+    let other = other as usize;
+    assert_eq!(nlink(other), 3);
+    assert_eq!(unlink("nlink1\0"), 0);
+    assert_eq!(nlink(fd), 2);
+    assert_eq!(nlink(other), 2);
+    assert_eq!(unlink("nlink0\0"), 0);
+    assert_eq!(unlink("nlink2\0"), 0);
+    assert_eq!(nlink(fd), 0);
+
+    assert_eq!(syscall(SYSCALL_FSTAT, [fd, 0, 0]), -EFAULT);
+    close(fd);
+    close(other);
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), -1);
+    println!("Test nlink OK!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    gettimeofday, mmap, munmap, syscall, task_info, TaskInfo, TaskStatus, TimeVal,
+    SYSCALL_GETTIMEOFDAY, SYSCALL_TASK_INFO,
+};
+
+/// 测试跨页的 TimeVal 和 TaskInfo 两半都被正确写入，输出　Test page straddle OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGE_SIZE: usize = 4096;
+const GUARD: u8 = 0xaa;
+
+fn bytes_at(addr: usize, len: usize) -> &'static [u8] {
+    unsafe { core::slice::from_raw_parts(addr as *const u8, len) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, PAGE_SIZE * 2, 3), 0);
+    unsafe { core::slice::from_raw_parts_mut(START as *mut u8, PAGE_SIZE * 2).fill(GUARD) };
+
+    // half of `sec` ends the first page, the other half starts the second
+    let addr = START + PAGE_SIZE - 4;
+    let size = core::mem::size_of::<TimeVal>();
+    let mut before = TimeVal::new();
+    assert_eq!(gettimeofday(&mut before, None), 0);
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [addr, 0, 0]), 0);
+    let mut after = TimeVal::new();
+    assert_eq!(gettimeofday(&mut after, None), 0);
+    let time = unsafe { (addr as *const TimeVal).read_unaligned() };
+    assert!(time.sec >= before.sec && time.sec <= after.sec);
+    assert!(time.usec < 1_000_000);
+    assert!(bytes_at(START, PAGE_SIZE - 4).iter().all(|&b| b == GUARD));
+    assert!(bytes_at(addr + size, PAGE_SIZE - 4 - size)
+        .iter()
+        .all(|&b| b == GUARD));
+
+    // the same for the much larger TaskInfo
+    unsafe { core::slice::from_raw_parts_mut(START as *mut u8, PAGE_SIZE * 2).fill(GUARD) };
+    let size = core::mem::size_of::<TaskInfo>();
+    assert_eq!(syscall(SYSCALL_TASK_INFO, [addr, 0, 0]), 0);
+    let info = unsafe { (addr as *const TaskInfo).read_unaligned() };
+    let aligned = TaskInfo::new();
+    assert_eq!(task_info(&aligned), 0);
+    assert!(matches!(info.status, TaskStatus::Running));
+    assert_eq!(
+        info.syscall_times[SYSCALL_TASK_INFO] + 1,
+        aligned.syscall_times[SYSCALL_TASK_INFO]
+    );
+    assert_eq!(
+        info.syscall_times[SYSCALL_GETTIMEOFDAY],
+        aligned.syscall_times[SYSCALL_GETTIMEOFDAY]
+    );
+    assert!(info.time <= aligned.time);
+    assert!(bytes_at(START, PAGE_SIZE - 4).iter().all(|&b| b == GUARD));
+    assert!(bytes_at(addr + size, PAGE_SIZE - 4 - size)
+        .iter()
+        .all(|&b| b == GUARD));
+    assert_eq!(munmap(START, PAGE_SIZE * 2), 0);
+    println!("Test page straddle OK!");
+    0
+}
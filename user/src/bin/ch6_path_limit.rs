@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, open, OpenFlags};
+
+/// 测试路径长度限制，输出　Test path limit OK! 就算正确。
+
+const EFAULT: isize = 14;
+const ENAMETOOLONG: isize = 36;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len: usize = 4096;
+    assert_eq!(0, mmap(start, len, 3));
+    for i in start..(start + len) {
+        unsafe {
+            *(i as *mut u8) = b'a';
+        }
+    }
+    // a whole page without NUL is longer than PATH_MAX
+    let path = unsafe {
+        core::str::from_utf8_unchecked(core::slice::from_raw_parts(start as *const u8, len))
+    };
+    assert_eq!(open(path, OpenFlags::RDONLY), -ENAMETOOLONG);
+    // running into the unmapped page behind it
+    let path = unsafe {
+        core::str::from_utf8_unchecked(core::slice::from_raw_parts(
+            (start + len - 16) as *const u8,
+            16,
+        ))
+    };
+    assert_eq!(open(path, OpenFlags::RDONLY), -EFAULT);
+    // a single component longer than NAME_MAX
+    let name = "a_file_name_longer_than_name_max\0";
+    assert_eq!(
+        open(name, OpenFlags::CREATE | OpenFlags::WRONLY),
+        -ENAMETOOLONG
+    );
+    println!("Test path limit OK!");
+    0
+}
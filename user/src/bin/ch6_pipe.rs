@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, fstat, pipe, read, waitpid, write, Stat, StatMode};
+
+/// 测试父进程写入管道、子进程读出，输出　Test pipe OK! 就算正确。
+
+const MESSAGE: &[u8] = b"through the pipe";
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [rfd, wfd] = fds;
+    let pid = fork();
+    if pid == 0 {
+        close(wfd);
+        // blocks until the parent has written, then sees it close
+        let mut buffer = [0u8; 64];
+        let mut len = 0;
+        loop {
+            let read_len = read(rfd, &mut buffer[len..]);
+            assert!(read_len >= 0);
+            if read_len == 0 {
+                break;
+            }
+            len += read_len as usize;
+        }
+        assert_eq!(&buffer[..len], MESSAGE);
+        close(rfd);
+        exit(0);
+    }
+    close(rfd);
+    assert_eq!(write(wfd, MESSAGE), MESSAGE.len() as isize);
+    // a pipe isn't a file on disk, it only holds what wasn't read yet
+    let stat = Stat::new();
+    assert_eq!(fstat(wfd, &stat), 0);
+    assert_eq!(stat.mode, StatMode::NULL);
+    assert!(stat.size <= MESSAGE.len() as u64);
+    close(wfd);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test pipe OK!");
+    0
+}
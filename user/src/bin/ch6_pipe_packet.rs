@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, pipe, pipe2, read, waitpid, write, O_DIRECT, PIPE_BUF};
+
+/// 测试 packet 模式的管道，输出　Test pipe packet OK! 就算正确。
+
+const EINVAL: isize = 22;
+const PACKETS: [&[u8]; 3] = [b"first", b"the second packet", b"3"];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe2(&mut fds, 1), -EINVAL);
+    assert_eq!(pipe2(&mut fds, O_DIRECT), 0);
+    let [rfd, wfd] = fds;
+    for packet in PACKETS.iter() {
+        assert_eq!(write(wfd, packet), packet.len() as isize);
+    }
+    // one packet per read, never merged
+    let mut buffer = [0u8; 64];
+    for packet in PACKETS.iter() {
+        let len = read(rfd, &mut buffer);
+        assert_eq!(&buffer[..len as usize], *packet);
+    }
+    // a packet that doesn't fit loses its tail
+    assert_eq!(write(wfd, b"truncated"), 9);
+    assert_eq!(write(wfd, b"next"), 4);
+    assert_eq!(read(rfd, &mut buffer[..5]), 5);
+    assert_eq!(&buffer[..5], b"trunc");
+    assert_eq!(read(rfd, &mut buffer), 4);
+    assert_eq!(&buffer[..4], b"next");
+    // a reader waits for the packets of another task
+    let pid = fork();
+    if pid == 0 {
+        close(rfd);
+        for packet in PACKETS.iter() {
+            assert_eq!(write(wfd, packet), packet.len() as isize);
+        }
+        // more than a packet holds is split up
+        let big = [b'b'; PIPE_BUF + 10];
+        assert_eq!(write(wfd, &big), big.len() as isize);
+        exit(0);
+    }
+    close(wfd);
+    for packet in PACKETS.iter() {
+        let len = read(rfd, &mut buffer);
+        assert_eq!(&buffer[..len as usize], *packet);
+    }
+    let mut big = [0u8; PIPE_BUF + 10];
+    assert_eq!(read(rfd, &mut big), PIPE_BUF as isize);
+    assert_eq!(read(rfd, &mut big), 10);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // every write end is closed
+    assert_eq!(read(rfd, &mut buffer), 0);
+    close(rfd);
+    // a plain pipe is a byte stream
+    assert_eq!(pipe(&mut fds), 0);
+    let [rfd, wfd] = fds;
+    for packet in PACKETS.iter() {
+        assert_eq!(write(wfd, packet), packet.len() as isize);
+    }
+    let len = read(rfd, &mut buffer) as usize;
+    assert_eq!(&buffer[..len], b"firstthe second packet3");
+    close(wfd);
+    assert_eq!(read(rfd, &mut buffer), 0);
+    close(rfd);
+    println!("Test pipe packet OK!");
+    0
+}
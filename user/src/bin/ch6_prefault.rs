@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, pipe, prefault, process_madvise, read, statm, waitpid, write, yield_, Statm,
+    MADV_DONTNEED,
+};
+
+/// 测试预先载入内存页，输出　Test prefault OK! 就算正确。
+
+const EINVAL: isize = 22;
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const PAGES: usize = 4;
+
+fn resident() -> usize {
+    let mut info = Statm::default();
+    assert_eq!(statm(&mut info), 0);
+    info.resident
+}
+
+fn child(pipe_fd: [usize; 2]) -> ! {
+    let unmapped = resident();
+    let mapped = unmapped + PAGES;
+    assert_eq!(mmap(START, PAGES * PAGE, 3), 0);
+    // the fresh pages get no frame until touched
+    assert_eq!(resident(), unmapped);
+    for i in 0..PAGES {
+        unsafe { ((START + i * PAGE) as *mut usize).write_volatile(0) };
+    }
+    assert_eq!(resident(), mapped);
+    // the parent drops the pages, which are left untouched until then
+    assert_eq!(write(pipe_fd[1], b"x"), 1);
+    while resident() != unmapped {
+        yield_();
+    }
+    assert_eq!(prefault(START, PAGES * PAGE), 0);
+    assert_eq!(resident(), mapped);
+    // resident pages don't fault, so nothing more gets mapped by using them
+    for i in 0..PAGES {
+        unsafe { ((START + i * PAGE) as *mut usize).write_volatile(i + 1) };
+    }
+    assert_eq!(resident(), mapped);
+    for i in 0..PAGES {
+        assert_eq!(
+            unsafe { ((START + i * PAGE) as *const usize).read_volatile() },
+            i + 1
+        );
+    }
+    assert_eq!(resident(), mapped);
+    // prefaulting what is already resident changes nothing
+    assert_eq!(prefault(START, PAGES * PAGE), 0);
+    assert_eq!(resident(), mapped);
+    assert_eq!(prefault(START, (PAGES + 1) * PAGE), -1);
+    assert_eq!(prefault(START + 1, PAGE), -EINVAL);
+    exit(0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        child(pipe_fd);
+    }
+    let pid = pid as usize;
+    // wait for the child to map and touch the pages
+    let mut byte = [0u8; 1];
+    assert_eq!(read(pipe_fd[0], &mut byte), 1);
+    assert_eq!(process_madvise(pid, START, PAGES * PAGE, MADV_DONTNEED), 0);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(exit_code, 0);
+    println!("Test prefault OK!");
+    0
+}
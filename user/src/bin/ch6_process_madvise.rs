@@ -0,0 +1,92 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, getpid, mmap, open, process_madvise, unlink, waitpid, yield_, OpenFlags,
+    MADV_DONTNEED, MADV_WILLNEED,
+};
+
+/// 测试父进程回收子进程内存，输出　Test process_madvise OK! 就算正确。
+
+const EPERM: isize = 1;
+const ESRCH: isize = 3;
+const ENOMEM: isize = 12;
+const EINVAL: isize = 22;
+const START: usize = 0x10000000;
+const PAGE: usize = 4096;
+const PAGES: usize = 4;
+const PATTERN: usize = 0x5a5a_5a5a;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE) as *mut usize
+}
+
+fn child() -> ! {
+    assert_eq!(mmap(START, PAGES * PAGE, 3), 0);
+    for i in 0..PAGES {
+        unsafe { page(i).write_volatile(PATTERN + i) };
+    }
+    let fd = open("pm_ready\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    // the parent drops the first pages, which fault back in as zeros
+    while unsafe { page(0).read_volatile() } != 0 {
+        yield_();
+    }
+    for i in 0..PAGES - 1 {
+        assert_eq!(unsafe { page(i).read_volatile() }, 0);
+    }
+    assert_eq!(
+        unsafe { page(PAGES - 1).read_volatile() },
+        PATTERN + PAGES - 1
+    );
+    exit(0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    unlink("pm_ready\0");
+    let pid = fork();
+    if pid == 0 {
+        child();
+    }
+    let pid = pid as usize;
+    loop {
+        let fd = open("pm_ready\0", OpenFlags::RDONLY);
+        if fd > 0 {
+            close(fd as usize);
+            break;
+        }
+        yield_();
+    }
+    assert_eq!(
+        process_madvise(pid, START + (PAGES - 1) * PAGE, PAGE, MADV_WILLNEED),
+        0
+    );
+    assert_eq!(
+        process_madvise(pid, START + PAGES * PAGE, PAGE, MADV_DONTNEED),
+        -ENOMEM
+    );
+    assert_eq!(
+        process_madvise(pid, START + 1, PAGE, MADV_DONTNEED),
+        -EINVAL
+    );
+    assert_eq!(
+        process_madvise(getpid() as usize, START, PAGE, MADV_DONTNEED),
+        -EPERM
+    );
+    assert_eq!(process_madvise(9999, START, PAGE, MADV_DONTNEED), -ESRCH);
+    assert_eq!(
+        process_madvise(pid, START, (PAGES - 1) * PAGE, MADV_DONTNEED),
+        0
+    );
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(exit_code, 0);
+    unlink("pm_ready\0");
+    println!("Test process_madvise OK!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, lseek, open, read, readahead, unlink, write, OpenFlags, SEEK_CUR};
+
+/// 测试 readahead 预读，输出　Test readahead OK! 就算正确。
+
+const LEN: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("ra_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let data = [b'r'; LEN];
+    assert_eq!(write(fd as usize, &data), LEN as isize);
+    close(fd as usize);
+
+    let fd = open("ra_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(readahead(fd, 512, 2048), 0);
+    // the handle offset does not move
+    assert_eq!(lseek(fd, 0, SEEK_CUR), 0);
+    // a range past the end of the file is clamped
+    assert_eq!(readahead(fd, 2048, usize::MAX), 0);
+    assert_eq!(readahead(fd, 4 * LEN, 512), 0);
+    let mut buffer = [0u8; LEN];
+    assert_eq!(read(fd, &mut buffer), LEN as isize);
+    assert!(buffer.iter().all(|&ch| ch == b'r'));
+    close(fd);
+    assert_eq!(readahead(fd, 0, 512), -1);
+    unlink("ra_file\0");
+    println!("Test readahead OK!");
+    0
+}
@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, open, read, rename, unlink, waitpid, write, yield_, OpenFlags, RenameFlags,
+};
+
+/// 测试 rename 原子替换，输出　Test rename OK! 就算正确。
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const LEN: usize = 2048;
+
+fn write_file(name: &str, ch: u8) {
+    let fd = open(name, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let buffer = [ch; LEN];
+    assert_eq!(write(fd as usize, &buffer), LEN as isize);
+    close(fd as usize);
+}
+
+/// Read the whole target, which must consist of a single kind of byte
+fn read_target() -> u8 {
+    let fd = open("rn_target\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; LEN];
+    let mut len = 0;
+    loop {
+        let size = read(fd as usize, &mut buffer[len..]);
+        if size <= 0 {
+            break;
+        }
+        len += size as usize;
+    }
+    close(fd as usize);
+    assert_eq!(len, LEN);
+    assert!(buffer.iter().all(|&ch| ch == buffer[0]));
+    buffer[0]
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    write_file("rn_target\0", b'o');
+    write_file("rn_tmp\0", b'n');
+    let pid = fork();
+    if pid == 0 {
+        // observe the target until the new contents show up
+        loop {
+            match read_target() {
+                b'o' => {
+                    yield_();
+                }
+                b'n' => exit(0),
+                _ => exit(-1),
+            }
+        }
+    }
+    for _ in 0..10 {
+        yield_();
+    }
+    assert_eq!(rename("rn_tmp\0", "rn_target\0", RenameFlags::SYNC), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(read_target(), b'n');
+    assert!(open("rn_tmp\0", OpenFlags::RDONLY) < 0);
+    assert_eq!(
+        rename("rn_tmp\0", "rn_target\0", RenameFlags::empty()),
+        -ENOENT
+    );
+    write_file("rn_tmp\0", b'x');
+    assert_eq!(
+        rename("rn_tmp\0", "rn_target\0", RenameFlags::NOREPLACE),
+        -EEXIST
+    );
+    assert_eq!(read_target(), b'n');
+    unlink("rn_tmp\0");
+    unlink("rn_target\0");
+    println!("Test rename OK!");
+    0
+}
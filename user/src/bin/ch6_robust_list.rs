@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use user_lib::{exit, fork, get_robust_list, getpid, set_robust_list, waitpid, RobustListHead};
+
+/// 测试 robust futex 链表的注册，输出　Test robust list OK! 就算正确。
+
+const ESRCH: isize = 3;
+const EINVAL: isize = 22;
+
+fn robust_list(pid: usize) -> usize {
+    let mut head = usize::MAX;
+    let mut len = 0;
+    assert_eq!(get_robust_list(pid, &mut head, &mut len), 0);
+    assert_eq!(len, size_of::<RobustListHead>());
+    head
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let len = size_of::<RobustListHead>();
+    assert_eq!(robust_list(0), 0);
+    // an empty list points back at its own head
+    let mut head = RobustListHead::default();
+    head.next = &head as *const _ as usize;
+    let addr = &head as *const _ as usize;
+    assert_eq!(set_robust_list(addr, len - 1), -EINVAL);
+    assert_eq!(robust_list(0), 0);
+    assert_eq!(set_robust_list(addr, len), 0);
+    assert_eq!(robust_list(0), addr);
+    assert_eq!(robust_list(getpid() as usize), addr);
+    let (mut head_out, mut len_out) = (0, 0);
+    assert_eq!(
+        get_robust_list(usize::MAX, &mut head_out, &mut len_out),
+        -ESRCH
+    );
+    let pid = fork();
+    if pid == 0 {
+        // the child holds none of the locks of its parent
+        if robust_list(0) != 0 {
+            exit(-1);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(set_robust_list(0, len), 0);
+    assert_eq!(robust_list(0), 0);
+    println!("Test robust list OK!");
+    0
+}
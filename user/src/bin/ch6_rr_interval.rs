@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, sched_rr_get_interval, set_timeslice, waitpid, TimeSpec};
+
+/// 测试时间片查询，输出　Test rr interval OK! 就算正确。
+
+const EINVAL: isize = 22;
+
+fn interval(pid: usize) -> TimeSpec {
+    let mut ts = TimeSpec::default();
+    assert_eq!(sched_rr_get_interval(pid, &mut ts), 0);
+    ts
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the timer fires 100 times a second by default
+    let default = TimeSpec {
+        sec: 0,
+        nsec: 10_000_000,
+    };
+    assert_eq!(interval(0), default);
+    assert_eq!(interval(getpid() as usize), default);
+    assert_eq!(set_timeslice(2_500_000), 0);
+    assert_eq!(
+        interval(0),
+        TimeSpec {
+            sec: 2,
+            nsec: 500_000_000,
+        }
+    );
+    assert_eq!(set_timeslice(0), -EINVAL);
+    assert_eq!(set_timeslice(5000), 0);
+    let pid = fork();
+    if pid == 0 {
+        // the child inherits the slice of its parent
+        let ts = interval(0);
+        exit(if ts.sec == 0 && ts.nsec == 5_000_000 {
+            0
+        } else {
+            -1
+        });
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    let mut ts = TimeSpec::default();
+    assert_eq!(sched_rr_get_interval(pid as usize, &mut ts), -1);
+    println!("Test rr interval OK!");
+    0
+}
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, getrusage, mmap, munmap, open, read, unlink, write, yield_, OpenFlags, RUsage,
+    RUSAGE_SELF,
+};
+
+/// 测试 getrusage 统计的用户态时间、缺页、文件读写字节数和主动让出次数，输出　Test rusage OK! 就算正确。
+
+const START: usize = 0x10000000;
+const PAGES: usize = 4;
+const LEN: usize = 1000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = RUsage::default();
+    assert_eq!(getrusage(RUSAGE_SELF, &mut before), 0);
+
+    // every page of a fresh mapping faults in on its first touch
+    assert_eq!(mmap(START, PAGES * 4096, 3), 0);
+    for page in 0..PAGES {
+        unsafe { core::ptr::write_volatile((START + page * 4096) as *mut u8, 1) };
+    }
+    assert_eq!(munmap(START, PAGES * 4096), 0);
+
+    let data = [b'r'; LEN];
+    let fd = open("rusage_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, &data), LEN as isize);
+    close(fd as usize);
+    let fd = open("rusage_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let mut buffer = [0u8; LEN];
+    assert_eq!(read(fd as usize, &mut buffer), LEN as isize);
+    close(fd as usize);
+    assert_eq!(unlink("rusage_file\0"), 0);
+
+    for _ in 0..5 {
+        yield_();
+    }
+    let mut x: usize = 0;
+    for i in 0..1_000_000 {
+        unsafe { core::ptr::write_volatile(&mut x, core::ptr::read_volatile(&x).wrapping_add(i)) };
+    }
+
+    let mut after = RUsage::default();
+    assert_eq!(getrusage(RUSAGE_SELF, &mut after), 0);
+    assert!(after.minflt >= before.minflt + PAGES);
+    assert!(after.write_bytes >= before.write_bytes + LEN);
+    assert!(after.read_bytes >= before.read_bytes + LEN);
+    assert!(after.nvcsw >= before.nvcsw + 5);
+    assert!(after.utime_us > before.utime_us);
+    println!("Test rusage OK!");
+    0
+}
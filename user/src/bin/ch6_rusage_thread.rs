@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getrusage, waitpid, yield_, RUsage, RUSAGE_SELF, RUSAGE_THREAD};
+
+/// 测试 RUSAGE_THREAD 只统计调用者自己，输出　Test rusage thread OK! 就算正确。
+/// 每个任务都是单线程的进程，所以用两个进程代替两个线程。
+
+const EINVAL: isize = 22;
+/// Iterations of the light workload, the heavy one doing four times as many
+const WORK: usize = 2_000_000;
+
+/// Spin in user mode for `n` iterations
+fn work(n: usize) {
+    let mut x: usize = 0;
+    for i in 0..n {
+        unsafe {
+            core::ptr::write_volatile(&mut x, core::ptr::read_volatile(&x).wrapping_add(i));
+        }
+    }
+}
+
+/// Do `n` iterations in a child, which exits with its user time in milliseconds
+fn worker(n: usize) -> isize {
+    let pid = fork();
+    if pid == 0 {
+        work(n);
+        let mut usage = RUsage::default();
+        assert_eq!(getrusage(RUSAGE_THREAD, &mut usage), 0);
+        exit((usage.utime_us / 1000) as i32);
+    }
+    pid
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut usage = RUsage::default();
+    assert_eq!(getrusage(2, &mut usage), -EINVAL);
+    assert_eq!(getrusage(RUSAGE_THREAD, &mut usage), 0);
+    let before = usage;
+    for _ in 0..10 {
+        yield_();
+    }
+    assert_eq!(getrusage(RUSAGE_THREAD, &mut usage), 0);
+    assert!(usage.nvcsw >= before.nvcsw + 10);
+    assert!(usage.utime_us >= before.utime_us);
+    assert!(usage.stime_us > before.stime_us);
+    // a process of a single thread uses what its thread does
+    let mut self_usage = RUsage::default();
+    assert_eq!(getrusage(RUSAGE_SELF, &mut self_usage), 0);
+    assert_eq!(self_usage.nvcsw, usage.nvcsw);
+    assert!(self_usage.utime_us >= usage.utime_us);
+
+    // both run at once, but each only sees its own work
+    let light = worker(WORK);
+    let heavy = worker(4 * WORK);
+    let mut light_ms: i32 = 0;
+    let mut heavy_ms: i32 = 0;
+    assert_eq!(waitpid(light as usize, &mut light_ms), light);
+    assert_eq!(waitpid(heavy as usize, &mut heavy_ms), heavy);
+    println!("light: {}ms, heavy: {}ms of user time", light_ms, heavy_ms);
+    assert!(light_ms > 0);
+    assert!(heavy_ms > 2 * light_ms);
+    assert!(heavy_ms < 8 * light_ms);
+    // the time of the children isn't ours
+    assert_eq!(getrusage(RUSAGE_THREAD, &mut usage), 0);
+    assert!(usage.utime_us < (light_ms as usize) * 1000);
+    println!("Test rusage thread OK!");
+    0
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, kill, sched_debug, set_priority, setuid, waitpid, yield_, SchedDebugEntry, SIGKILL,
+};
+
+/// 测试调度器就绪队列的导出，输出　Test sched debug OK! 就算正确。
+
+const EPERM: isize = 1;
+const PRIOS: [isize; 4] = [3, 8, 16, 40];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0isize; PRIOS.len()];
+    for (pid, &prio) in pids.iter_mut().zip(PRIOS.iter()) {
+        *pid = fork();
+        if *pid == 0 {
+            assert_eq!(set_priority(prio), prio);
+            loop {
+                yield_();
+            }
+        }
+    }
+    // let every child set its priority
+    for _ in 0..10 {
+        yield_();
+    }
+    let mut entries = [SchedDebugEntry::default(); 32];
+    let len = sched_debug(&mut entries);
+    assert!(len >= PRIOS.len() as isize);
+    let entries = &entries[..len as usize];
+    for (&pid, &prio) in pids.iter().zip(PRIOS.iter()) {
+        let entry = entries
+            .iter()
+            .find(|entry| entry.pid == pid as usize)
+            .unwrap();
+        assert_eq!(entry.prio, prio as u64);
+        assert_eq!(entry.name(), "ch6_sched_debug");
+    }
+    // the task that runs next comes first
+    for pair in entries.windows(2) {
+        assert!(pair[1].pass.wrapping_sub(pair[0].pass) <= u64::MAX / 2);
+    }
+    let mut first = [SchedDebugEntry::default(); 1];
+    assert_eq!(sched_debug(&mut first), 1);
+    assert_eq!(first[0].pid, entries[0].pid);
+
+    // only root may look
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(setuid(1000), 0);
+        exit(sched_debug(&mut first) as i32);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code as isize, -EPERM);
+
+    for &pid in pids.iter() {
+        assert_eq!(kill(pid as usize, SIGKILL), 0);
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    }
+    println!("Test sched debug OK!");
+    0
+}
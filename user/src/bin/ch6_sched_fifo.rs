@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, fstat, get_time, getuid, open, sched_setscheduler, setuid, waitpid, write,
+    yield_, OpenFlags, Stat, SCHED_FIFO, SCHED_STRIDE,
+};
+
+/// 测试 FIFO 调度策略，输出　Test sched fifo OK! 就算正确。
+
+const EPERM: isize = 1;
+const ESRCH: isize = 3;
+const EINVAL: isize = 22;
+/// bytes the stride child appends, one per round
+const ROUNDS: usize = 50;
+
+fn log_size(fd: usize) -> u64 {
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    stat.size
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("fifo_log\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let pid = fork();
+    if pid == 0 {
+        // a stride task making progress whenever it gets the CPU
+        for _ in 0..ROUNDS {
+            write(fd, b"s");
+            yield_();
+        }
+        exit(0);
+    }
+    while log_size(fd) == 0 {
+        yield_();
+    }
+
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 10), 0);
+    let size = log_size(fd);
+    // neither timer interrupts nor yields let the stride child in
+    let start = get_time();
+    while get_time() < start + 50 {
+        yield_();
+    }
+    assert_eq!(log_size(fd), size);
+    assert_eq!(sched_setscheduler(0, SCHED_STRIDE, 16), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(log_size(fd), ROUNDS as u64);
+    close(fd);
+
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 100), -EINVAL);
+    assert_eq!(sched_setscheduler(0, 7, 10), -EINVAL);
+    assert_eq!(sched_setscheduler(99999, SCHED_STRIDE, 16), -ESRCH);
+    let pid = fork();
+    if pid == 0 {
+        // an unprivileged task may not make itself FIFO
+        assert_eq!(setuid(1000), 0);
+        assert_eq!(getuid(), 1000);
+        assert_eq!(setuid(0), -EPERM);
+        let ret = sched_setscheduler(0, SCHED_FIFO, 10);
+        exit(if ret == -EPERM { 0 } else { -1 });
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(getuid(), 0);
+    println!("Test sched fifo OK!");
+    0
+}
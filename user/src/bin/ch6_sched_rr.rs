@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{fork, kill, sched_debug, set_priority, waitpid, yield_, SchedDebugEntry, SIGKILL};
+
+/// 测试 round_robin 调度器按就绪的先后轮流运行任务，需要以 FEATURES=round_robin
+/// 构建内核，输出　Test sched rr OK! 就算正确。
+
+// round-robin ignores priorities, which would reorder the tasks under stride
+const PRIOS: [isize; 3] = [40, 8, 2];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0usize; PRIOS.len()];
+    for (pid, &prio) in pids.iter_mut().zip(PRIOS.iter()) {
+        let child = fork();
+        if child == 0 {
+            assert_eq!(set_priority(prio), prio);
+            loop {
+                yield_();
+            }
+        }
+        *pid = child as usize;
+    }
+    let mut entries = [SchedDebugEntry::default(); 32];
+    for _ in 0..20 {
+        yield_();
+        let len = sched_debug(&mut entries) as usize;
+        let mut order = [0usize; PRIOS.len()];
+        let mut found = 0;
+        for entry in entries[..len].iter() {
+            if pids.contains(&entry.pid) {
+                order[found] = entry.pid;
+                found += 1;
+            }
+        }
+        // the children being ready go round in the order they were forked
+        if found == PRIOS.len() {
+            let first = pids.iter().position(|&pid| pid == order[0]).unwrap();
+            for (i, &pid) in order.iter().enumerate() {
+                assert_eq!(pid, pids[(first + i) % PRIOS.len()]);
+            }
+        }
+    }
+    let mut exit_code: i32 = 0;
+    for &pid in pids.iter() {
+        assert_eq!(kill(pid, SIGKILL), 0);
+        assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    }
+    println!("Test sched rr OK!");
+    0
+}
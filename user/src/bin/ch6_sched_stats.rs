@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, sleep_blocking, task_info, yield_, TaskInfo};
+
+/// 测试 task_info 报告的调度次数和运行时间，输出　Test sched stats OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let before = TaskInfo::new();
+    assert_eq!(task_info(&before), 0);
+    assert!(before.schedule_count >= 1);
+    for _ in 0..5 {
+        yield_();
+    }
+    let after = TaskInfo::new();
+    assert_eq!(task_info(&after), 0);
+    // every yield gets the task picked again
+    assert!(after.schedule_count >= before.schedule_count + 5);
+    assert!(after.cpu_time_us >= before.cpu_time_us);
+
+    // sleeping takes wall time but no CPU time
+    let start = get_time();
+    sleep_blocking(100);
+    let slept = TaskInfo::new();
+    assert_eq!(task_info(&slept), 0);
+    assert!(get_time() - start >= 100);
+    assert!(slept.cpu_time_us - after.cpu_time_us < 100_000);
+    assert!(slept.cpu_time_us <= slept.time * 1000 + 1000);
+    println!("Test sched stats OK!");
+    0
+}
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, kill, sigmask, sigpending, sigprocmask, waitpid, yield_, SIGTERM, SIGUSR1,
+    SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK,
+};
+
+/// 测试 sigpending 查询被屏蔽的信号，输出　Test sigpending OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut set = 0;
+    assert_eq!(sigpending(&mut set), 0);
+    assert_eq!(set, 0);
+    // block before forking, so the child has it blocked from the start
+    let mut old = 0;
+    assert_eq!(
+        sigprocmask(SIG_BLOCK, sigmask(SIGTERM) | sigmask(SIGUSR1), &mut old),
+        0
+    );
+    let pid = fork();
+    if pid == 0 {
+        let mut set = 0;
+        for _ in 0..1000 {
+            assert_eq!(sigpending(&mut set), 0);
+            if set != 0 {
+                break;
+            }
+            yield_();
+        }
+        assert_eq!(set, sigmask(SIGTERM));
+        // still pending until it is unblocked
+        assert_eq!(sigpending(&mut set), 0);
+        assert_eq!(set, sigmask(SIGTERM));
+        let mut old = 0;
+        assert_eq!(sigprocmask(SIG_UNBLOCK, sigmask(SIGTERM), &mut old), 0);
+        exit(1);
+    }
+    assert_eq!(kill(pid as usize, SIGTERM), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -(SIGTERM as i32));
+    // signals sent to the child are not ours
+    assert_eq!(sigpending(&mut set), 0);
+    assert_eq!(set, 0);
+    assert_eq!(sigprocmask(SIG_SETMASK, 0, &mut old), 0);
+    println!("Test sigpending OK!");
+    0
+}
@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, kill, sigmask, sigprocmask, waitpid, yield_, SIGKILL, SIGSTOP, SIGTERM,
+    SIGUSR1, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK,
+};
+
+/// 测试 sigprocmask 屏蔽信号，输出　Test sigprocmask OK! 就算正确。
+
+const EINVAL: isize = 22;
+
+/// Run `f` in a child, returning the exit code it was reaped with
+fn in_child(f: fn()) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+/// Block SIGTERM and send it to ourselves
+fn block_and_send() {
+    let mut old = 0;
+    assert_eq!(sigprocmask(SIG_BLOCK, sigmask(SIGTERM), &mut old), 0);
+    assert_eq!(kill(getpid() as usize, SIGTERM), 0);
+    // it stays pending
+    for _ in 0..10 {
+        yield_();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut old = 0;
+    assert_eq!(sigprocmask(SIG_BLOCK, sigmask(SIGUSR1), &mut old), 0);
+    assert_eq!(old, 0);
+    assert_eq!(sigprocmask(3, 0, &mut old), -EINVAL);
+    // the mask is inherited
+    assert_eq!(
+        in_child(|| {
+            let mut mask = 0;
+            assert_eq!(sigprocmask(SIG_BLOCK, 0, &mut mask), 0);
+            assert_eq!(mask, sigmask(SIGUSR1));
+        }),
+        0
+    );
+    assert_eq!(sigprocmask(SIG_SETMASK, 0, &mut old), 0);
+    assert_eq!(old, sigmask(SIGUSR1));
+
+    // a blocked signal doesn't kill the task
+    assert_eq!(in_child(block_and_send), 0);
+    // until it is unblocked
+    assert_eq!(
+        in_child(|| {
+            block_and_send();
+            let mut old = 0;
+            assert_eq!(sigprocmask(SIG_UNBLOCK, sigmask(SIGTERM), &mut old), 0);
+            // not reached
+            exit(1);
+        }),
+        -(SIGTERM as i32)
+    );
+    // SIGKILL and SIGSTOP can't be blocked
+    assert_eq!(
+        in_child(|| {
+            let mut mask = 0;
+            assert_eq!(sigprocmask(SIG_SETMASK, u32::MAX, &mut mask), 0);
+            assert_eq!(sigprocmask(SIG_BLOCK, 0, &mut mask), 0);
+            assert_eq!(mask & (sigmask(SIGKILL) | sigmask(SIGSTOP)), 0);
+            assert_eq!(kill(getpid() as usize, SIGKILL), 0);
+            exit(1);
+        }),
+        -(SIGKILL as i32)
+    );
+    println!("Test sigprocmask OK!");
+    0
+}
@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, sleep_blocking, waitpid};
+
+/// 测试 sys_sleep 挂起进程至少指定的时间，输出　Test sleep OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time();
+    sleep_blocking(50);
+    assert!(get_time() - start >= 50);
+    // sleeping no time at all comes right back
+    let start = get_time();
+    sleep_blocking(0);
+    assert!(get_time() - start < 50);
+    // two sleepers each wake after their own deadline
+    let start = get_time();
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(30);
+        exit((get_time() - start >= 30) as i32);
+    }
+    sleep_blocking(60);
+    assert!(get_time() - start >= 60);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 1);
+    println!("Test sleep OK!");
+    0
+}
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, statfs, unlink, write, OpenFlags, StatFs};
+
+/// 测试 statfs 报告的空闲块与 inode，输出　Test statfs OK! 就算正确。
+
+const ENOENT: isize = 2;
+const BLOCK_SZ: usize = 512;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = StatFs::default();
+    assert_eq!(statfs("/\0", &mut before), 0);
+    assert_eq!(before.block_size, BLOCK_SZ as u64);
+    assert!(before.free_blocks <= before.blocks);
+    assert!(0 < before.free_inodes && before.free_inodes < before.inodes);
+    let fd = open("statfs_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    // the new entry may have grown the directory by a block
+    let mut created = StatFs::default();
+    assert_eq!(statfs("/\0", &mut created), 0);
+    assert_eq!(created.free_inodes, before.free_inodes - 1);
+    let data = [7u8; 10 * BLOCK_SZ];
+    assert_eq!(write(fd as usize, &data), data.len() as isize);
+    close(fd as usize);
+    let mut used = StatFs::default();
+    // any path on the filesystem tells the same
+    assert_eq!(statfs("statfs_file\0", &mut used), 0);
+    assert_eq!((used.blocks, used.inodes), (before.blocks, before.inodes));
+    assert_eq!(used.free_blocks, created.free_blocks - 10);
+    assert_eq!(unlink("statfs_file\0"), 0);
+    let mut after = StatFs::default();
+    assert_eq!(statfs("/\0", &mut after), 0);
+    assert_eq!(after.free_blocks, created.free_blocks);
+    assert_eq!(after.free_inodes, before.free_inodes);
+    assert_eq!(statfs("statfs_file\0", &mut after), -ENOENT);
+    println!("Test statfs OK!");
+    0
+}
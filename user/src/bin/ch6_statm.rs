@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, statm, Statm};
+
+/// 测试地址空间页数统计，输出　Test statm OK! 就算正确。
+
+const PAGE: usize = 4096;
+const USER_STACK_PAGES: usize = 2;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = Statm::default();
+    assert_eq!(statm(&mut before), 0);
+    assert!(before.text > 0);
+    assert_eq!(before.stack, USER_STACK_PAGES);
+    assert_eq!(before.mmap, 0);
+    assert_eq!(
+        before.size,
+        before.text + before.data + before.stack + before.mmap
+    );
+    assert!(before.resident <= before.size);
+
+    let start: usize = 0x10000000;
+    assert_eq!(mmap(start, 3 * PAGE, 3), 0);
+    unsafe {
+        *(start as *mut u8) = 1;
+    }
+    let mut after = Statm::default();
+    assert_eq!(statm(&mut after), 0);
+    assert_eq!(after.mmap, 3);
+    assert_eq!(after.size, before.size + 3);
+    // only the page touched has a frame
+    assert_eq!(after.resident, before.resident + 1);
+    assert_eq!(after.text, before.text);
+    assert_eq!(after.data, before.data);
+
+    assert_eq!(munmap(start, 3 * PAGE), 0);
+    assert_eq!(munmap(start, 3 * PAGE), -1);
+    assert_eq!(statm(&mut after), 0);
+    assert_eq!(after.mmap, 0);
+    assert_eq!(after.size, before.size);
+    println!("Test statm OK!");
+    0
+}
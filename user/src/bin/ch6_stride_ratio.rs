@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, set_priority, waitpid};
+
+/// 测试优先级 4 的任务得到的 CPU 约为优先级 2 的两倍，输出　Test stride ratio OK! 就算正确。
+
+const PRIOS: [isize; 2] = [2, 4];
+const MAX_TIME: isize = 1000;
+
+fn count_during(prio: isize) -> i32 {
+    assert_eq!(set_priority(prio), prio);
+    let start_time = get_time();
+    let mut acc = 0;
+    while get_time() - start_time <= MAX_TIME {
+        acc += 1;
+    }
+    acc
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // anything lower than the least priority is refused
+    assert_eq!(set_priority(1), -1);
+    assert_eq!(set_priority(0), -1);
+    let mut pids = [0isize; PRIOS.len()];
+    for (pid, &prio) in pids.iter_mut().zip(PRIOS.iter()) {
+        *pid = fork();
+        if *pid == 0 {
+            exit(count_during(prio));
+        }
+    }
+    let mut counts = [0i32; PRIOS.len()];
+    for (&pid, count) in pids.iter().zip(counts.iter_mut()) {
+        assert_eq!(waitpid(pid as usize, count), pid);
+    }
+    println!("stride ratio counts: {:?}", counts);
+    // roughly twice as many slices, give or take the first and last ones
+    let (low, high) = (counts[0] as i64, counts[1] as i64);
+    assert!(low > 0);
+    assert!(high * 10 >= low * 15 && high * 10 <= low * 25);
+    println!("Test stride ratio OK!");
+    0
+}
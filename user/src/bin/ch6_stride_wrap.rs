@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, set_priority, waitpid};
+
+/// 测试 stride 的 pass 溢出回绕后调度仍然正确，输出　Test stride wrap OK! 就算正确。
+
+// a stride of u64::MAX / 2 or / 3 wraps the pass every few slices
+const PRIOS: [isize; 2] = [2, 3];
+const MAX_TIME: isize = 500;
+
+fn count_during(prio: isize) -> i32 {
+    assert_eq!(set_priority(prio), prio);
+    let start_time = get_time();
+    let mut acc = 0;
+    while get_time() - start_time <= MAX_TIME {
+        acc += 1;
+    }
+    acc
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0isize; PRIOS.len()];
+    for (pid, &prio) in pids.iter_mut().zip(PRIOS.iter()) {
+        *pid = fork();
+        if *pid == 0 {
+            exit(count_during(prio));
+        }
+    }
+    let mut counts = [0i32; PRIOS.len()];
+    for (&pid, count) in pids.iter().zip(counts.iter_mut()) {
+        assert_eq!(waitpid(pid as usize, count), pid);
+    }
+    println!("stride wrap counts: {:?}", counts);
+    // neither task starves once its pass wrapped below the other's, and
+    // the higher priority still gets the larger share
+    assert!(counts[0] > 0);
+    assert!(counts[1] > counts[0]);
+    println!("Test stride wrap OK!");
+    0
+}
@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fchmodat, fstat, open, read, readlink, symlink, unlink, write, OpenFlags, Stat,
+    AT_FDCWD, AT_SYMLINK_NOFOLLOW,
+};
+
+/// 测试 symlink/readlink 以及 open 跟随符号链接，输出　Test symlink OK! 就算正确。
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const EINVAL: isize = 22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("symlink_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, b"target"), 6);
+    close(fd as usize);
+    assert_eq!(symlink("symlink_file\0", "symlink_rel\0"), 0);
+    assert_eq!(symlink("/symlink_rel\0", "symlink_abs\0"), 0);
+    let mut buffer = [0u8; 32];
+    assert_eq!(readlink("symlink_abs\0", &mut buffer), 12);
+    assert_eq!(&buffer[..12], b"/symlink_rel");
+    // a short buffer gets the start of the target
+    assert_eq!(readlink("symlink_rel\0", &mut buffer[..7]), 7);
+    assert_eq!(&buffer[..7], b"symlink");
+    assert_eq!(readlink("symlink_file\0", &mut buffer), -EINVAL);
+    // opening goes through both links to the file
+    let fd = open("symlink_abs\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(read(fd as usize, &mut buffer), 6);
+    assert_eq!(&buffer[..6], b"target");
+    close(fd as usize);
+
+    // a dangling link reads back, but can't be opened
+    assert_eq!(symlink("symlink_nowhere\0", "symlink_dangling\0"), 0);
+    assert_eq!(symlink("symlink_file\0", "symlink_dangling\0"), -EEXIST);
+    assert_eq!(readlink("symlink_dangling\0", &mut buffer), 15);
+    assert!(open("symlink_dangling\0", OpenFlags::RDONLY) < 0);
+    assert_eq!(symlink("\0", "symlink_empty\0"), -ENOENT);
+    // nor can one looping onto itself
+    assert_eq!(symlink("symlink_loop\0", "symlink_loop\0"), 0);
+    assert!(open("symlink_loop\0", OpenFlags::RDONLY) < 0);
+
+    // chmod goes through a link unless told not to
+    let perm = || {
+        let fd = open("symlink_file\0", OpenFlags::RDONLY);
+        assert!(fd > 0);
+        let stat = Stat::new();
+        assert_eq!(fstat(fd as usize, &stat), 0);
+        close(fd as usize);
+        stat.perm
+    };
+    let at = AT_FDCWD as usize;
+    assert_eq!(fchmodat(at, "symlink_rel\0", 0o600, 0), 0);
+    assert_eq!(perm(), 0o600);
+    assert_eq!(fchmodat(at, "symlink_rel\0", 0o640, AT_SYMLINK_NOFOLLOW), 0);
+    assert_eq!(perm(), 0o600);
+
+    // removing the links leaves the file alone
+    for name in [
+        "symlink_rel\0",
+        "symlink_abs\0",
+        "symlink_dangling\0",
+        "symlink_loop\0",
+    ] {
+        assert_eq!(unlink(name), 0);
+    }
+    let fd = open("symlink_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert_eq!(unlink("symlink_file\0"), 0);
+    println!("Test symlink OK!");
+    0
+}
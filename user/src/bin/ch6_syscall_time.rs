@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, getppid, syscall_time, SYSCALL_GETPPID, SYSCALL_GETTIMEOFDAY};
+
+/// 测试每个系统调用累计的耗时，输出　Test syscall time OK! 就算正确。
+
+const MAX_SYSCALL_NUM: usize = 500;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    getppid();
+    for _ in 0..1000 {
+        get_time();
+    }
+    let mut time = [0usize; MAX_SYSCALL_NUM];
+    assert_eq!(syscall_time(&mut time), MAX_SYSCALL_NUM as isize);
+    // a thousand calls outweigh a single one
+    assert!(time[SYSCALL_GETTIMEOFDAY] > 0);
+    assert!(time[SYSCALL_GETTIMEOFDAY] > time[SYSCALL_GETPPID]);
+    // a short buffer gets the lowest ids only
+    let mut head = [usize::MAX; 2];
+    assert_eq!(syscall_time(&mut head), 2);
+    assert_eq!(head, [time[0], time[1]]);
+    println!("Test syscall time OK!");
+    0
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, getpid, open, syscall_trace, yield_, OpenFlags, SyscallRecord};
+
+/// 测试最近系统调用记录，输出　Test syscall trace OK! 就算正确。
+
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_GETPID: usize = 172;
+const TRACE_LEN: usize = 16;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let path = "st_missing\0";
+    assert_eq!(open(path, OpenFlags::RDONLY), -1);
+    assert_eq!(close(100), -1);
+    assert_eq!(yield_(), 0);
+    let mut records = [SyscallRecord::default(); 4];
+    assert_eq!(syscall_trace(&mut records), 4);
+    assert_eq!(records[0].id, SYSCALL_GETPID);
+    assert_eq!(records[0].ret, pid);
+    assert_eq!(records[1].id, SYSCALL_OPEN);
+    assert_eq!(records[1].args[1], path.as_ptr() as usize);
+    assert_eq!(records[1].ret, -1);
+    assert_eq!(records[2].id, SYSCALL_CLOSE);
+    assert_eq!(records[2].args[0], 100);
+    assert_eq!(records[2].ret, -1);
+    assert_eq!(records[3].id, SYSCALL_YIELD);
+    assert_eq!(records[3].ret, 0);
+
+    // the ring keeps only the newest entries
+    for _ in 0..2 * TRACE_LEN {
+        getpid();
+    }
+    let mut records = [SyscallRecord::default(); 2 * TRACE_LEN];
+    assert_eq!(syscall_trace(&mut records), TRACE_LEN as isize);
+    assert!(records[..TRACE_LEN]
+        .iter()
+        .all(|record| record.id == SYSCALL_GETPID && record.ret == pid));
+    println!("Test syscall trace OK!");
+    0
+}
@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, ftruncate, mmap_shared, munmap, open, truncate, unlink, waitpid_status,
+    wifexited, wifsignaled, write, wtermsig, OpenFlags, SIGBUS,
+};
+
+/// 测试截断被 MAP_SHARED 映射的文件，输出　Test truncate mmap OK! 就算正确。
+
+const EACCES: isize = 13;
+const EINVAL: isize = 22;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+
+fn byte_at(offset: usize) -> u8 {
+    unsafe { core::ptr::read_volatile((START + offset) as *const u8) }
+}
+
+/// Touch the mapping at `offset` in a child, which must die of SIGBUS
+fn assert_bus_error(offset: usize) {
+    let pid = fork();
+    if pid == 0 {
+        byte_at(offset);
+        exit(0);
+    }
+    let mut status: i32 = 0;
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifsignaled(status));
+    assert_eq!(wtermsig(status), SIGBUS);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("tm_file\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    for page in 0..3 {
+        assert_eq!(
+            write(fd, &[b'a' + page as u8; PAGE_SIZE]),
+            PAGE_SIZE as isize
+        );
+    }
+    assert_eq!(mmap_shared(START, 3 * PAGE_SIZE, 1, fd, 1), -EINVAL);
+    assert_eq!(mmap_shared(START, 3 * PAGE_SIZE, 1, fd, 0), 0);
+    assert_eq!(byte_at(0), b'a');
+    assert_eq!(byte_at(2 * PAGE_SIZE + 5), b'c');
+
+    // the last page was resident, but lies beyond the end now
+    assert_eq!(ftruncate(fd, PAGE_SIZE + 100), 0);
+    assert_bus_error(2 * PAGE_SIZE + 5);
+    // the page the file ends in still reads, as zeros past the end
+    assert_eq!(byte_at(PAGE_SIZE + 99), b'b');
+    assert_eq!(byte_at(PAGE_SIZE + 100), 0);
+    assert_eq!(byte_at(0), b'a');
+
+    // growing the file back brings the page back, as zeros
+    assert_eq!(ftruncate(fd, 3 * PAGE_SIZE), 0);
+    assert_eq!(byte_at(2 * PAGE_SIZE + 5), 0);
+    let pid = fork();
+    if pid == 0 {
+        exit(byte_at(2 * PAGE_SIZE) as i32);
+    }
+    let mut status: i32 = 0;
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifexited(status));
+
+    // truncating by path works just the same
+    assert_eq!(truncate("tm_file\0", 0), 0);
+    assert_bus_error(0);
+    assert_eq!(munmap(START, 3 * PAGE_SIZE), 0);
+    close(fd);
+
+    // a read-only file can be neither truncated nor mapped writable
+    let fd = open("tm_file\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(ftruncate(fd as usize, 0), -EINVAL);
+    assert_eq!(mmap_shared(START, PAGE_SIZE, 3, fd as usize, 0), -EACCES);
+    close(fd as usize);
+    unlink("tm_file\0");
+    println!("Test truncate mmap OK!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, mkdir, mount, open, umount, OpenFlags};
+
+/// 测试卸载文件系统，输出　Test umount OK! 就算正确。
+
+const EBUSY: isize = 16;
+const EEXIST: isize = 17;
+const EINVAL: isize = 22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let ret = mkdir("/umnt\0");
+    assert!(ret == 0 || ret == -EEXIST);
+    let fd = open("/umnt/covered\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert_eq!(mount(1, "/umnt\0"), 0);
+    assert!(open("/umnt/covered\0", OpenFlags::RDONLY) < 0);
+    let fd = open("/umnt/ufile\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    // an open file keeps the filesystem busy
+    assert_eq!(umount("/umnt\0"), -EBUSY);
+    close(fd as usize);
+    assert_eq!(umount("/umnt\0"), 0);
+    // the covered directory shows up again
+    let fd = open("/umnt/covered\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert!(open("/umnt/ufile\0", OpenFlags::RDONLY) < 0);
+    assert_eq!(umount("/umnt\0"), -EINVAL);
+    println!("Test umount OK!");
+    0
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, lseek, open, read, statfs, unlink, write, OpenFlags, StatFs, SEEK_SET};
+
+/// 测试删除仍打开的文件后数据保留到最后一次关闭，输出　Test unlink open OK! 就算正确。
+
+const BLOCK_SZ: usize = 512;
+
+fn free_blocks() -> u64 {
+    let mut buf = StatFs::default();
+    assert_eq!(statfs("/\0", &mut buf), 0);
+    buf.free_blocks
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("unlink_open\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let data = [3u8; 8 * BLOCK_SZ];
+    assert_eq!(write(fd, &data), data.len() as isize);
+    let other = open("unlink_open\0", OpenFlags::RDONLY);
+    assert!(other > 0);
+    let other = other as usize;
+    let free = free_blocks();
+    assert_eq!(unlink("unlink_open\0"), 0);
+    assert_eq!(open("unlink_open\0", OpenFlags::RDONLY), -1);
+    // both files still read the data, which keeps its blocks
+    let mut buffer = [0u8; 8 * BLOCK_SZ];
+    assert_eq!(read(other, &mut buffer), buffer.len() as isize);
+    assert!(buffer == data);
+    assert_eq!(free_blocks(), free);
+    close(other);
+    assert_eq!(lseek(fd, 0, SEEK_SET), 0);
+    let mut buffer = [0u8; 8 * BLOCK_SZ];
+    assert_eq!(read(fd, &mut buffer), buffer.len() as isize);
+    assert!(buffer == data);
+    assert_eq!(free_blocks(), free);
+    // closing the last of them frees the blocks
+    close(fd);
+    assert_eq!(free_blocks(), free + 8);
+    println!("Test unlink open OK!");
+    0
+}
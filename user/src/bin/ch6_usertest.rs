@@ -27,6 +27,83 @@ static TESTS: &[&str] = &[
     "ch6_file1\0",
     "ch6_file2\0",
     "ch6_file3\0",
+    "ch6_path_limit\0",
+    "ch6_affinity\0",
+    "ch6_rename\0",
+    "ch6_getdents\0",
+    "ch6_mount\0",
+    "ch6_umount\0",
+    "ch6_readahead\0",
+    "ch6_mempolicy\0",
+    "ch6_fallocate\0",
+    "ch6_process_madvise\0",
+    "ch6_verity\0",
+    "ch6_disk_usage\0",
+    "ch6_statm\0",
+    "ch6_syscall_trace\0",
+    "ch6_waitid\0",
+    "ch6_fchmodat\0",
+    "ch6_fd_grow\0",
+    "ch6_close_range\0",
+    "ch6_rr_interval\0",
+    "ch6_fexecve\0",
+    "ch6_exec_path\0",
+    "ch6_gettimeofday\0",
+    "ch6_sched_fifo\0",
+    "ch6_robust_list\0",
+    "ch6_fsevents\0",
+    "ch6_prefault\0",
+    "ch6_iosubmit\0",
+    "ch6_mount_ns\0",
+    "ch6_walk\0",
+    "ch6_yield_remaining\0",
+    "ch6_mseal\0",
+    "ch6_fincore\0",
+    "ch6_sigprocmask\0",
+    "ch6_sigpending\0",
+    "ch6_wait_status\0",
+    "ch6_drop_file_cache\0",
+    "ch6_pipe_packet\0",
+    "ch6_rusage_thread\0",
+    "ch6_truncate_mmap\0",
+    "ch6_sched_debug\0",
+    "ch6_cow_stats\0",
+    "ch6_lseek\0",
+    "ch6_file_times\0",
+    "ch6_statfs\0",
+    "ch6_cow\0",
+    "ch6_lazy_mmap\0",
+    "ch6_munmap_split\0",
+    "ch6_mprotect\0",
+    "ch6_mmap_overlap\0",
+    "ch6_mmap_file\0",
+    "ch6_sleep\0",
+    "ch6_getppid\0",
+    "ch6_kill\0",
+    "ch6_wnohang\0",
+    "ch6_stride_wrap\0",
+    "ch6_stride_ratio\0",
+    "ch6_sched_stats\0",
+    "ch6_syscall_time\0",
+    "ch6_fsync\0",
+    "ch6_append\0",
+    "ch6_excl\0",
+    "ch6_dup2\0",
+    "ch6_pipe\0",
+    "ch6_dev\0",
+    "ch6_cwd\0",
+    "ch6_mmap_prot\0",
+    "ch6_unlink_open\0",
+    "ch6_argv\0",
+    "ch6_env\0",
+    "ch6_page_straddle\0",
+    "ch6_efault\0",
+    "ch6_nice\0",
+    "ch6_rusage\0",
+    "ch6_exec_info\0",
+    "ch6_emfile\0",
+    "ch6_nlink\0",
+    "ch6_symlink\0",
 ];
 
 use user_lib::{spawn, waitpid};
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, enable_verity, fallocate, open, read, unlink, write, OpenFlags};
+
+/// 测试 verity 文件只读，输出　Test verity OK! 就算正确。
+
+const EEXIST: isize = 17;
+const EINVAL: isize = 22;
+const EROFS: isize = 30;
+const LEN: usize = 1500;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("sealed\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut data = [0u8; LEN];
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte = (i % 251) as u8;
+    }
+    assert_eq!(write(fd, &data), LEN as isize);
+    assert_eq!(enable_verity(fd), 0);
+    assert_eq!(enable_verity(fd), -EEXIST);
+    // the handle opened for writing can't write any more
+    assert_eq!(write(fd, &data), -EROFS);
+    assert_eq!(fallocate(fd, 0, 0, LEN), -EROFS);
+    close(fd);
+
+    assert!(open("sealed\0", OpenFlags::WRONLY) < 0);
+    assert!(open("sealed\0", OpenFlags::CREATE | OpenFlags::RDONLY) < 0);
+    assert!(open("sealed\0", OpenFlags::TRUNC) < 0);
+    let fd = open("sealed\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    let mut buffer = [0u8; LEN];
+    assert_eq!(read(fd, &mut buffer), LEN as isize);
+    assert_eq!(buffer, data);
+    close(fd);
+
+    let fd = open("/\0", OpenFlags::RDONLY);
+    assert!(fd > 0);
+    assert_eq!(enable_verity(fd as usize), -EINVAL);
+    close(fd as usize);
+    assert_eq!(unlink("sealed\0"), 0);
+    println!("Test verity OK!");
+    0
+}
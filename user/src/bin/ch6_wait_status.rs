@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, kill, waitpid, waitpid_status, wexitstatus, wifexited, wifsignaled, wtermsig,
+    yield_, SIGSEGV, SIGTERM,
+};
+
+/// 测试 waitpid 区分正常退出与被信号杀死，输出　Test wait status OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // a normal exit
+    let pid = fork();
+    if pid == 0 {
+        exit(3);
+    }
+    let mut status: i32 = 0;
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifexited(status));
+    assert!(!wifsignaled(status));
+    assert_eq!(wexitstatus(status), 3);
+
+    // killed by a signal it was sent
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            yield_();
+        }
+    }
+    assert_eq!(kill(pid as usize, SIGTERM), 0);
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifsignaled(status));
+    assert!(!wifexited(status));
+    assert_eq!(wtermsig(status), SIGTERM);
+
+    // killed for a fault it took
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            core::ptr::null_mut::<u8>().write_volatile(0);
+        }
+        exit(0);
+    }
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifsignaled(status));
+    assert_eq!(wtermsig(status), SIGSEGV);
+
+    // plain waitpid still gets the bare exit code
+    let pid = fork();
+    if pid == 0 {
+        exit(-7);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -7);
+    println!("Test wait status OK!");
+    0
+}
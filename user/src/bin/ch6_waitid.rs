@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpgid, getpid, setpgid, waitid, yield_, SigInfo, CLD_EXITED, CLD_KILLED, P_ALL,
+    P_PGID, P_PID, WEXITED, WNOHANG, WNOWAIT,
+};
+
+/// 测试按进程组等待子进程，输出　Test waitid OK! 就算正确。
+
+const ECHILD: isize = 10;
+const EINVAL: isize = 22;
+const SIGSEGV: i32 = 11;
+
+fn spawn_child(exit_code: i32) -> usize {
+    let pid = fork();
+    if pid == 0 {
+        for _ in 0..10 {
+            yield_();
+        }
+        exit(exit_code);
+    }
+    pid as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(getpgid(0), getpgid(getpid() as usize));
+    // group a holds two children, group b one that crashes right away
+    let a1 = spawn_child(1);
+    let a2 = spawn_child(2);
+    assert_eq!(setpgid(a1, 0), 0);
+    assert_eq!(setpgid(a2, a1), 0);
+    let b1 = fork();
+    if b1 == 0 {
+        unsafe {
+            (0 as *mut u8).write_volatile(0);
+        }
+        exit(0);
+    }
+    let b1 = b1 as usize;
+    assert_eq!(setpgid(b1, b1), 0);
+    assert_eq!(getpgid(a2), a1 as isize);
+
+    let mut info = SigInfo::default();
+    assert_eq!(waitid(P_PGID, a1, &mut info, 0), -EINVAL);
+    let mut reaped = [0usize; 2];
+    for pid in reaped.iter_mut() {
+        assert_eq!(waitid(P_PGID, a1, &mut info, WEXITED), 0);
+        assert_eq!(info.si_code, CLD_EXITED);
+        *pid = info.si_pid as usize;
+        let exit_code = if *pid == a1 { 1 } else { 2 };
+        assert_eq!(info.si_status, exit_code);
+    }
+    assert!(reaped.contains(&a1) && reaped.contains(&a2));
+    assert_eq!(waitid(P_PGID, a1, &mut info, WEXITED | WNOHANG), -ECHILD);
+
+    // the crashed child is left in place by WNOWAIT
+    assert_eq!(waitid(P_PID, b1, &mut info, WEXITED | WNOWAIT), 0);
+    assert_eq!(info.si_pid as usize, b1);
+    assert_eq!(info.si_code, CLD_KILLED);
+    assert_eq!(info.si_status, SIGSEGV);
+    let mut info = SigInfo::default();
+    assert_eq!(waitid(P_ALL, 0, &mut info, WEXITED), 0);
+    assert_eq!(info.si_pid as usize, b1);
+    assert_eq!(waitid(P_ALL, 0, &mut info, WEXITED), -ECHILD);
+    println!("Test waitid OK!");
+    0
+}
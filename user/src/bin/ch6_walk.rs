@@ -0,0 +1,109 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use user_lib::{
+    close, fstat, mkdir, open, walk, write, OpenFlags, Stat, WalkRecord, DT_DIR, DT_REG,
+    WALK_MAX_DEPTH,
+};
+
+/// 测试内核遍历目录树，输出　Test walk OK! 就算正确。
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const ENOTDIR: isize = 20;
+const EINVAL: isize = 22;
+const RECLEN: usize = size_of::<WalkRecord>();
+
+fn make_dir(path: &str) {
+    let ret = mkdir(path);
+    assert!(ret == 0 || ret == -EEXIST);
+}
+
+/// Create a file holding `len` bytes, returning its inode number
+fn make_file(path: &str, len: usize) -> u64 {
+    let fd = open(path, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, &[b'w'; 8][..len]), len as isize);
+    let stat = Stat::new();
+    assert_eq!(fstat(fd as usize, &stat), 0);
+    close(fd as usize);
+    stat.ino
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    make_dir("/walk_root\0");
+    let f1 = make_file("/walk_root/f1\0", 5);
+    make_dir("/walk_root/d1\0");
+    make_file("/walk_root/d1/f2\0", 3);
+    make_dir("/walk_root/d1/d2\0");
+    make_file("/walk_root/d1/d2/f3\0", 0);
+    // a chain one level deeper than the walk goes
+    let mut chain = String::from("/walk_root");
+    for _ in 0..=WALK_MAX_DEPTH {
+        chain.push_str("/c");
+        make_dir((chain.clone() + "\0").as_str());
+    }
+
+    let mut cursor = 0;
+    let mut small = [0u8; RECLEN - 1];
+    assert_eq!(walk("/walk_root\0", &mut small, &mut cursor), -EINVAL);
+    assert_eq!(walk("/walk_root/f1\0", &mut small, &mut cursor), -ENOTDIR);
+    assert_eq!(walk("/walk_none\0", &mut small, &mut cursor), -ENOENT);
+    // a buffer of three records, so the walk takes several calls
+    let mut records: [WalkRecord; 3] = unsafe { core::mem::zeroed() };
+    let mut paths: Vec<String> = Vec::new();
+    loop {
+        let buffer =
+            unsafe { core::slice::from_raw_parts_mut(records.as_mut_ptr() as *mut u8, 3 * RECLEN) };
+        let len = walk("/walk_root\0", buffer, &mut cursor);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        assert_eq!(len as usize % RECLEN, 0);
+        for record in records[..len as usize / RECLEN].iter() {
+            let path = record.path();
+            assert_eq!(record.depth as usize, path.split('/').count());
+            match path {
+                "f1" => {
+                    assert_eq!(record.ino, f1);
+                    assert_eq!((record.d_type, record.size), (DT_REG, 5));
+                }
+                "d1/f2" => assert_eq!((record.d_type, record.size), (DT_REG, 3)),
+                "d1" | "d1/d2" => assert_eq!(record.d_type, DT_DIR),
+                _ => {}
+            }
+            paths.push(String::from(path));
+        }
+    }
+    assert_eq!(cursor, paths.len());
+    // every directory comes before what it holds
+    for (i, path) in paths.iter().enumerate() {
+        if let Some(pos) = path.rfind('/') {
+            let parent = paths.iter().position(|p| *p == path[..pos]).unwrap();
+            assert!(parent < i);
+        }
+    }
+    let mut expected: Vec<String> = ["f1", "d1", "d1/f2", "d1/d2", "d1/d2/f3"]
+        .iter()
+        .map(|path| String::from(*path))
+        .collect();
+    let mut chain = String::from("c");
+    for _ in 0..WALK_MAX_DEPTH {
+        expected.push(chain.clone());
+        chain.push_str("/c");
+    }
+    paths.sort();
+    expected.sort();
+    assert_eq!(paths, expected);
+    println!("Test walk OK!");
+    0
+}
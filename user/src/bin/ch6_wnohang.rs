@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sleep_blocking, waitpid_nohang, yield_};
+
+/// 测试 waitpid 的 WNOHANG 选项，输出　Test wnohang OK! 就算正确。
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code: i32 = -1;
+    // no child at all is still an error
+    assert_eq!(waitpid_nohang(1234, &mut exit_code), -1);
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(50);
+        exit(7);
+    }
+    // the child is asleep, so it can't have exited yet
+    assert_eq!(waitpid_nohang(pid as usize, &mut exit_code), 0);
+    assert_eq!(exit_code, -1);
+    loop {
+        match waitpid_nohang(pid as usize, &mut exit_code) {
+            0 => {
+                yield_();
+            }
+            found => {
+                assert_eq!(found, pid);
+                break;
+            }
+        }
+    }
+    assert_eq!(exit_code, 7);
+    assert_eq!(waitpid_nohang(pid as usize, &mut exit_code), -1);
+    println!("Test wnohang OK!");
+    0
+}
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, waitpid, yield_, yield_remaining};
+
+/// 测试只按已用时间计费的让出，输出　Test yield remaining OK! 就算正确。
+
+/// How long the tasks compete, in milliseconds
+const WINDOW_MS: isize = 300;
+
+/// Count the turns a task gets until `deadline`, yielding right away in each
+fn turns(deadline: isize, remaining: bool) -> ! {
+    let mut turns = 0;
+    while get_time() < deadline {
+        turns += 1;
+        if remaining {
+            yield_remaining();
+        } else {
+            yield_();
+        }
+    }
+    exit(turns);
+}
+
+fn wait(pid: isize) -> i32 {
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let deadline = get_time() + WINDOW_MS;
+    let hog = fork();
+    if hog == 0 {
+        while get_time() < deadline {}
+        exit(0);
+    }
+    let plain = fork();
+    if plain == 0 {
+        turns(deadline, false);
+    }
+    let donor = fork();
+    if donor == 0 {
+        turns(deadline, true);
+    }
+    assert_eq!(wait(hog), 0);
+    let plain = wait(plain);
+    let donor = wait(donor);
+    println!(
+        "plain yield: {} turns, yield remaining: {} turns",
+        plain, donor
+    );
+    // a full stride per yield leaves the plain task a turn per slice of the hog,
+    // while the donor keeps running for the share it didn't use
+    assert!(plain > 0);
+    assert!(donor > 4 * plain);
+    println!("Test yield remaining OK!");
+    0
+}
@@ -44,31 +44,50 @@ fn clear_bss() {
     }
 }
 
+/// The environment the program was started with, a null-terminated array of
+/// "NAME=value" strings
+static mut ENVP: usize = 0;
+
+/// The NUL-terminated string at `start`
+fn c_str(start: usize) -> &'static str {
+    let len = (0usize..)
+        .find(|i| unsafe { ((start + *i) as *const u8).read_volatile() == 0 })
+        .unwrap();
+    core::str::from_utf8(unsafe { core::slice::from_raw_parts(start as *const u8, len) }).unwrap()
+}
+
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub extern "C" fn _start(argc: usize, argv: usize) -> ! {
+pub extern "C" fn _start(argc: usize, argv: usize, envp: usize) -> ! {
     clear_bss();
     unsafe {
         HEAP.lock()
             .init(HEAP_SPACE.as_ptr() as usize, USER_HEAP_SIZE);
+        ENVP = envp;
     }
     let mut v: Vec<&'static str> = Vec::new();
     for i in 0..argc {
         let str_start =
             unsafe { ((argv + i * core::mem::size_of::<usize>()) as *const usize).read_volatile() };
-        let len = (0usize..)
-            .find(|i| unsafe { ((str_start + *i) as *const u8).read_volatile() == 0 })
-            .unwrap();
-        v.push(
-            core::str::from_utf8(unsafe {
-                core::slice::from_raw_parts(str_start as *const u8, len)
-            })
-            .unwrap(),
-        );
+        v.push(c_str(str_start));
     }
     exit(main(argc, v.as_slice()));
 }
 
+/// The value of the environment variable `name`, if it is set
+pub fn getenv(name: &str) -> Option<&'static str> {
+    let envp = unsafe { ENVP };
+    if envp == 0 {
+        return None;
+    }
+    (0..)
+        .map(|i| unsafe {
+            ((envp + i * core::mem::size_of::<usize>()) as *const usize).read_volatile()
+        })
+        .take_while(|&ptr| ptr != 0)
+        .find_map(|ptr| c_str(ptr).strip_prefix(name)?.strip_prefix('='))
+}
+
 #[linkage = "weak"]
 #[no_mangle]
 fn main(_argc: usize, _argv: &[&str]) -> i32 {
@@ -82,6 +101,15 @@ bitflags! {
         const RDWR = 1 << 1;
         const CREATE = 1 << 9;
         const TRUNC = 1 << 10;
+        const APPEND = 1 << 11;
+        const EXCL = 1 << 12;
+    }
+}
+
+bitflags! {
+    pub struct RenameFlags: u32 {
+        const NOREPLACE = 1 << 0;
+        const SYNC = 1 << 31;
     }
 }
 
@@ -98,6 +126,115 @@ impl TimeVal {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TimeZone {
+    /// minutes west of Greenwich
+    pub minuteswest: i32,
+    /// type of DST correction
+    pub dsttime: i32,
+}
+
+/// Head of a robust futex list, whose entries point at each other through `next`
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct RobustListHead {
+    pub next: usize,
+    /// Offset from an entry to the futex word it guards
+    pub futex_offset: isize,
+    /// Entry being locked or unlocked
+    pub list_op_pending: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Statm {
+    /// pages mapped in total
+    pub size: usize,
+    /// pages with a frame behind them
+    pub resident: usize,
+    /// pages shared with other address spaces
+    pub shared: usize,
+    /// pages of executable elf segments
+    pub text: usize,
+    /// pages of the other elf segments, which include the heap
+    pub data: usize,
+    /// pages of the user stack
+    pub stack: usize,
+    /// pages mapped by mmap
+    pub mmap: usize,
+}
+
+/// How much sharing pages copy-on-write after fork saved a process
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CowStats {
+    /// resident pages shared with a process forked from or off this one
+    pub pages_shared: usize,
+    /// writes to a page still shared, each of which copied it
+    pub faults_resolved: usize,
+    /// pages unmapped while still shared, which never had to be copied
+    pub pages_freed_unmodified: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SyscallRecord {
+    /// syscall id
+    pub id: usize,
+    /// the first arguments
+    pub args: [usize; 3],
+    /// return value
+    pub ret: isize,
+}
+
+/// Resources used, as counted by the kernel
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RUsage {
+    /// time spent in user mode, in microseconds
+    pub utime_us: usize,
+    /// time spent in the kernel, in microseconds
+    pub stime_us: usize,
+    /// page faults served without noticing
+    pub minflt: usize,
+    /// times the CPU was given up voluntarily
+    pub nvcsw: usize,
+    /// times of being preempted
+    pub nivcsw: usize,
+    /// bytes read from files
+    pub read_bytes: usize,
+    /// bytes written to files
+    pub write_bytes: usize,
+}
+
+/// Hits, misses and evictions of the block cache since boot
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SigInfo {
+    pub si_signo: i32,
+    /// CLD_EXITED or CLD_KILLED
+    pub si_code: i32,
+    pub si_pid: i32,
+    /// exit code, or the signal that killed the child
+    pub si_status: i32,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TaskStatus {
     UnInit,
@@ -114,11 +251,16 @@ pub struct SyscallInfo {
 
 const MAX_SYSCALL_NUM: usize = 500;
 
+#[repr(C)]
 #[derive(Debug)]
 pub struct TaskInfo {
     pub status: TaskStatus,
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
     pub time: usize,
+    /// how many times the task was scheduled
+    pub schedule_count: usize,
+    /// time the task spent running, in microseconds
+    pub cpu_time_us: usize,
 }
 
 impl TaskInfo {
@@ -127,6 +269,8 @@ impl TaskInfo {
             status: TaskStatus::UnInit,
             syscall_times: [0; MAX_SYSCALL_NUM],
             time: 0,
+            schedule_count: 0,
+            cpu_time_us: 0,
         }
     }
 }
@@ -142,8 +286,24 @@ pub struct Stat {
     pub mode: StatMode,
     /// number of hard links
     pub nlink: u32,
+    /// size in bytes
+    pub size: u64,
+    /// number of blocks allocated, including index and verity blocks
+    pub blocks: u64,
+    /// permission bits
+    pub perm: u32,
+    /// owner user id
+    pub uid: u32,
+    /// owner group id
+    pub gid: u32,
     /// unused pad
-    pad: [u64; 7],
+    pad: u32,
+    /// last access time in seconds
+    pub atime: u64,
+    /// last modification time in seconds
+    pub mtime: u64,
+    /// last status change time in seconds
+    pub ctime: u64,
 }
 
 impl Stat {
@@ -153,7 +313,15 @@ impl Stat {
             ino: 0,
             mode: StatMode::NULL,
             nlink: 0,
-            pad: [0; 7],
+            size: 0,
+            blocks: 0,
+            perm: 0,
+            uid: 0,
+            gid: 0,
+            pad: 0,
+            atime: 0,
+            mtime: 0,
+            ctime: 0,
         }
     }
 }
@@ -164,6 +332,30 @@ impl Default for Stat {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct DiskUsage {
+    /// number of blocks held, including index and verity blocks
+    pub total_blocks: u64,
+    /// number of those blocks no other inode refers to
+    pub unique_blocks: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct StatFs {
+    /// size of a block in bytes
+    pub block_size: u64,
+    /// number of data blocks
+    pub blocks: u64,
+    /// number of data blocks free for writes
+    pub free_blocks: u64,
+    /// number of inodes
+    pub inodes: u64,
+    /// number of inodes free
+    pub free_inodes: u64,
+}
+
 bitflags! {
     pub struct StatMode: u32 {
         const NULL  = 0;
@@ -171,15 +363,105 @@ bitflags! {
         const DIR   = 0o040000;
         /// ordinary regular file
         const FILE  = 0o100000;
+        /// symbolic link
+        const LINK  = 0o120000;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct Dirent {
+    /// inode number
+    pub d_ino: u64,
+    /// byte offset of the entry within the directory file
+    pub d_off: u64,
+    /// length of this record
+    pub d_reclen: u16,
+    /// file type
+    pub d_type: u8,
+    /// NUL-terminated file name
+    pub d_name: [u8; 28],
+}
+
+impl Dirent {
+    pub fn name(&self) -> &str {
+        let len = self.d_name.iter().position(|&c| c == 0).unwrap_or(self.d_name.len());
+        core::str::from_utf8(&self.d_name[..len]).unwrap()
     }
 }
 
-const AT_FDCWD: isize = -100;
+pub const DT_DIR: u8 = 4;
+pub const DT_REG: u8 = 8;
+pub const DT_LNK: u8 = 10;
+
+/// deepest level below the starting directory that walk reports
+pub const WALK_MAX_DEPTH: usize = 8;
+
+#[repr(C)]
+pub struct WalkRecord {
+    /// inode number
+    pub ino: u64,
+    /// size in bytes
+    pub size: u64,
+    /// file type
+    pub d_type: u8,
+    /// level below the starting directory, 1 for its own entries
+    pub depth: u8,
+    /// NUL-terminated path relative to the starting directory
+    pub path: [u8; WALK_MAX_DEPTH * 28],
+}
+
+impl WalkRecord {
+    pub fn path(&self) -> &str {
+        let len = self.path.iter().position(|&c| c == 0).unwrap_or(self.path.len());
+        core::str::from_utf8(&self.path[..len]).unwrap()
+    }
+}
+
+bitflags! {
+    pub struct FsEventMask: u32 {
+        const CREATE = 1 << 0;
+        const UNLINK = 1 << 1;
+        const RENAME = 1 << 2;
+        const WRITE = 1 << 3;
+        /// events after the ones before this were dropped
+        const OVERFLOW = 1 << 31;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FsEvent {
+    /// inode the event happened to
+    pub ino: u64,
+    /// a single kind of event
+    pub mask: u32,
+    /// process that caused it
+    pub pid: u32,
+}
+
+pub const SEEK_SET: usize = 0;
+pub const SEEK_CUR: usize = 1;
+pub const SEEK_END: usize = 2;
+
+pub const AT_FDCWD: isize = -100;
+pub const AT_SYMLINK_NOFOLLOW: u32 = 0x100;
+pub const CLOSE_RANGE_CLOEXEC: u32 = 4;
+pub const SCHED_STRIDE: usize = 0;
+pub const SCHED_FIFO: usize = 1;
 
 pub fn open(path: &str, flags: OpenFlags) -> isize {
     sys_openat(AT_FDCWD as usize, path, flags.bits, OpenFlags::RDWR.bits)
 }
 
+pub fn fchmodat(dirfd: usize, path: &str, mode: u32, flags: u32) -> isize {
+    sys_fchmodat(dirfd, path, mode, flags)
+}
+
+pub fn fchownat(dirfd: usize, path: &str, uid: u32, gid: u32, flags: u32) -> isize {
+    sys_fchownat(dirfd, path, uid, gid, flags)
+}
+
 pub fn close(fd: usize) -> isize {
     if fd == STDOUT {
         console::flush();
@@ -187,6 +469,16 @@ pub fn close(fd: usize) -> isize {
     sys_close(fd)
 }
 
+/// a pipe carries packets instead of a byte stream
+pub const O_DIRECT: u32 = 0x4000;
+/// bytes a pipe holds, and the largest packet
+pub const PIPE_BUF: usize = 4096;
+
+pub fn close_range(first: usize, last: usize, flags: u32) -> isize {
+    console::flush();
+    sys_close_range(first, last, flags)
+}
+
 pub fn read(fd: usize, buf: &mut [u8]) -> isize {
     sys_read(fd, buf)
 }
@@ -195,18 +487,147 @@ pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf)
 }
 
+pub fn lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    sys_lseek(fd, offset, whence)
+}
+
+pub fn getdents(fd: usize, buf: &mut [u8]) -> isize {
+    sys_getdents64(fd, buf)
+}
+
+/// Fill `buf` with the records of the tree below `path` after the first
+/// `cursor` ones, moving `cursor` past them
+pub fn walk(path: &str, buf: &mut [u8], cursor: &mut usize) -> isize {
+    sys_walk(path, buf, cursor)
+}
+
 pub fn link(old_path: &str, new_path: &str) -> isize {
     sys_linkat(AT_FDCWD as usize, old_path, AT_FDCWD as usize, new_path, 0)
 }
 
+/// Create a symbolic link at `link_path` pointing to `target`
+pub fn symlink(target: &str, link_path: &str) -> isize {
+    sys_symlinkat(target, AT_FDCWD as usize, link_path)
+}
+
+/// Read the target of a symbolic link into `buf`, returning its length
+pub fn readlink(path: &str, buf: &mut [u8]) -> isize {
+    sys_readlinkat(AT_FDCWD as usize, path, buf)
+}
+
 pub fn unlink(path: &str) -> isize {
     sys_unlinkat(AT_FDCWD as usize, path, 0)
 }
 
+pub fn mkdir(path: &str) -> isize {
+    sys_mkdirat(AT_FDCWD as usize, path, 0)
+}
+
+pub fn chdir(path: &str) -> isize {
+    sys_chdir(path)
+}
+
+pub fn getcwd(buf: &mut [u8]) -> isize {
+    sys_getcwd(buf)
+}
+
+pub fn mount(dev: usize, target: &str) -> isize {
+    sys_mount(dev, target)
+}
+
+pub fn umount(target: &str) -> isize {
+    sys_umount2(target, 0)
+}
+
+/// a mount namespace of its own
+pub const CLONE_NEWNS: usize = 0x20000;
+
+pub fn unshare(flags: usize) -> isize {
+    sys_unshare(flags)
+}
+
+/// Join the namespace `nstype` of the process `pid`
+pub fn setns(pid: usize, nstype: usize) -> isize {
+    sys_setns(pid, nstype)
+}
+
+pub fn rename(old_path: &str, new_path: &str, flags: RenameFlags) -> isize {
+    sys_renameat2(
+        AT_FDCWD as usize,
+        old_path,
+        AT_FDCWD as usize,
+        new_path,
+        flags.bits,
+    )
+}
+
 pub fn fstat(fd: usize, st: &Stat) -> isize {
     sys_fstat(fd, st)
 }
 
+pub fn disk_usage(path: &str, usage: &mut DiskUsage) -> isize {
+    sys_disk_usage(path, usage)
+}
+
+pub fn statfs(path: &str, buf: &mut StatFs) -> isize {
+    sys_statfs(path, buf)
+}
+
+pub const POSIX_FADV_NORMAL: usize = 0;
+pub const POSIX_FADV_RANDOM: usize = 1;
+pub const POSIX_FADV_SEQUENTIAL: usize = 2;
+pub const POSIX_FADV_WILLNEED: usize = 3;
+pub const POSIX_FADV_DONTNEED: usize = 4;
+
+pub const FALLOC_FL_ZERO_RANGE: usize = 0x10;
+
+/// Cut the file at `path` down or grow it to `length` bytes
+pub fn truncate(path: &str, length: usize) -> isize {
+    sys_truncate(path, length)
+}
+
+pub fn ftruncate(fd: usize, length: usize) -> isize {
+    sys_ftruncate(fd, length)
+}
+
+pub fn fallocate(fd: usize, mode: usize, offset: usize, len: usize) -> isize {
+    sys_fallocate(fd, mode, offset, len)
+}
+
+pub fn enable_verity(fd: usize) -> isize {
+    sys_enable_verity(fd)
+}
+
+pub fn fsync(fd: usize) -> isize {
+    sys_fsync(fd)
+}
+
+pub fn readahead(fd: usize, offset: usize, len: usize) -> isize {
+    sys_readahead(fd, offset, len)
+}
+
+pub fn fadvise(fd: usize, offset: usize, len: usize, advice: usize) -> isize {
+    sys_fadvise(fd, offset, len, advice)
+}
+
+/// size of the blocks fincore reports on
+pub const FINCORE_BLOCK_SIZE: usize = 512;
+
+/// Find out which blocks of the file from `offset` on are in the block
+/// cache, one byte in `vec` for each, returning how many blocks there were
+pub fn fincore(fd: usize, offset: usize, vec: &mut [u8]) -> isize {
+    sys_fincore(fd, offset, vec.len() * FINCORE_BLOCK_SIZE, vec)
+}
+
+/// Write back and drop the cached blocks of a file, returning how many there were
+pub fn drop_file_cache(fd: usize) -> isize {
+    sys_drop_file_cache(fd)
+}
+
+pub fn block_cache_stats(stats: &mut CacheStats) -> isize {
+    sys_block_cache_stats(stats)
+}
+
 pub fn mail_read(buf: &mut [u8]) -> isize {
     sys_mail_read(buf)
 }
@@ -220,10 +641,23 @@ pub fn exit(exit_code: i32) -> ! {
     sys_exit(exit_code);
 }
 
+pub fn set_robust_list(head: usize, len: usize) -> isize {
+    sys_set_robust_list(head, len)
+}
+
+pub fn get_robust_list(pid: usize, head: &mut usize, len: &mut usize) -> isize {
+    sys_get_robust_list(pid, head, len)
+}
+
 pub fn yield_() -> isize {
     sys_yield()
 }
 
+/// Yield, only being charged for the part of the time slice used
+pub fn yield_remaining() -> isize {
+    sys_yield_remaining()
+}
+
 pub fn get_time() -> isize {
     let time = TimeVal::new();
     match sys_get_time(&time, 0) {
@@ -232,10 +666,73 @@ pub fn get_time() -> isize {
     }
 }
 
+pub fn gettimeofday(time: &mut TimeVal, tz: Option<&mut TimeZone>) -> isize {
+    let tz = tz.map_or(0, |tz| tz as *mut _ as usize);
+    sys_get_time(time, tz)
+}
+
+pub fn settimeofday(time: Option<&TimeVal>, tz: Option<&TimeZone>) -> isize {
+    let time = time.map_or(0, |time| time as *const _ as usize);
+    let tz = tz.map_or(0, |tz| tz as *const _ as usize);
+    sys_settimeofday(time, tz)
+}
+
+pub fn getuid() -> isize {
+    sys_getuid()
+}
+
+pub fn setuid(uid: usize) -> isize {
+    sys_setuid(uid)
+}
+
 pub fn getpid() -> isize {
     sys_getpid()
 }
 
+/// Pid of the parent, that of initproc once the parent is gone
+pub fn getppid() -> isize {
+    sys_getppid()
+}
+
+pub const SIGINT: u32 = 2;
+pub const SIGBUS: u32 = 7;
+pub const SIGKILL: u32 = 9;
+pub const SIGUSR1: u32 = 10;
+pub const SIGSEGV: u32 = 11;
+pub const SIGTERM: u32 = 15;
+pub const SIGCHLD: u32 = 17;
+pub const SIGSTOP: u32 = 19;
+
+/// Mask of a single signal in a signal set
+pub fn sigmask(signal: u32) -> u32 {
+    1 << signal
+}
+
+pub fn kill(pid: usize, signal: u32) -> isize {
+    sys_kill(pid, signal)
+}
+
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// Change the signal mask as `how` says by `set`, writing the old one to `oldset`
+pub fn sigprocmask(how: usize, set: u32, oldset: &mut u32) -> isize {
+    sys_sigprocmask(how, &set, oldset)
+}
+
+pub const RUSAGE_SELF: usize = 0;
+pub const RUSAGE_THREAD: usize = 1;
+
+pub fn getrusage(who: usize, usage: &mut RUsage) -> isize {
+    sys_getrusage(who, usage)
+}
+
+/// Get the signals sent to us that are kept pending by the mask
+pub fn sigpending(set: &mut u32) -> isize {
+    sys_sigpending(set)
+}
+
 pub fn fork() -> isize {
     sys_fork()
 }
@@ -244,13 +741,31 @@ pub fn exec(path: &str, args: &[*const u8]) -> isize {
     sys_exec(path, args)
 }
 
+/// Like `exec`, with the null-terminated `envp` as the environment
+pub fn execve(path: &str, args: &[*const u8], envp: &[*const u8]) -> isize {
+    sys_execve(path, args, envp)
+}
+
+pub fn fexecve(fd: usize, args: &[*const u8], envp: &[*const u8]) -> isize {
+    sys_fexecve(fd, args, envp)
+}
+
+pub fn fsevents_open(mask: FsEventMask) -> isize {
+    sys_fsevents_open(mask.bits())
+}
+
 pub fn set_priority(prio: isize) -> isize {
     sys_set_priority(prio)
 }
 
+/// Add `delta` to the priority of the task, returning the new one
+pub fn nice(delta: isize) -> isize {
+    sys_nice(delta)
+}
+
 pub fn wait(exit_code: &mut i32) -> isize {
     loop {
-        match sys_waitpid(-1, exit_code as *mut _) {
+        match sys_waitpid(-1, exit_code as *mut _, 0) {
             -2 => {
                 sys_yield();
             }
@@ -261,9 +776,32 @@ pub fn wait(exit_code: &mut i32) -> isize {
     }
 }
 
+pub const P_ALL: usize = 0;
+pub const P_PID: usize = 1;
+pub const P_PGID: usize = 2;
+
+pub const WNOHANG: usize = 1;
+pub const WEXITED: usize = 4;
+pub const WNOWAIT: usize = 0x0100_0000;
+
+pub const CLD_EXITED: i32 = 1;
+pub const CLD_KILLED: i32 = 2;
+
+pub fn waitid(idtype: usize, id: usize, info: &mut SigInfo, options: usize) -> isize {
+    sys_waitid(idtype, id, info, options)
+}
+
+pub fn setpgid(pid: usize, pgid: usize) -> isize {
+    sys_setpgid(pid, pgid)
+}
+
+pub fn getpgid(pid: usize) -> isize {
+    sys_getpgid(pid)
+}
+
 pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     loop {
-        match sys_waitpid(pid as isize, exit_code as *mut _) {
+        match sys_waitpid(pid as isize, exit_code as *mut _, 0) {
             -2 => {
                 sys_yield();
             }
@@ -274,6 +812,48 @@ pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     }
 }
 
+/// write a wait status instead of the bare exit code
+pub const WSTATUS: usize = 0x4000_0000;
+
+/// Like `waitpid`, but giving 0 right away while the child is still running
+pub fn waitpid_nohang(pid: usize, exit_code: &mut i32) -> isize {
+    sys_waitpid(pid as isize, exit_code as *mut _, WNOHANG)
+}
+
+/// Like `waitpid`, but getting a wait status to decode with the functions below
+pub fn waitpid_status(pid: usize, status: &mut i32) -> isize {
+    loop {
+        match sys_waitpid(pid as isize, status as *mut _, WSTATUS) {
+            -2 => {
+                sys_yield();
+            }
+            n => {
+                return n;
+            }
+        }
+    }
+}
+
+/// Whether the child exited by itself
+pub fn wifexited(status: i32) -> bool {
+    status & 0x7f == 0
+}
+
+/// The low byte of the exit code of a child that exited
+pub fn wexitstatus(status: i32) -> i32 {
+    (status >> 8) & 0xff
+}
+
+/// Whether the child was killed by a signal
+pub fn wifsignaled(status: i32) -> bool {
+    status & 0x7f != 0
+}
+
+/// The signal that killed the child
+pub fn wtermsig(status: i32) -> u32 {
+    (status & 0x7f) as u32
+}
+
 pub fn sleep_blocking(sleep_ms: usize) {
     sys_sleep(sleep_ms);
 }
@@ -285,23 +865,153 @@ pub fn sleep(period_ms: usize) {
     }
 }
 pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
-    sys_mmap(start, len, prot)
+    sys_mmap(start, len, prot, 0, 0, 0)
+}
+
+/// Share the pages of a file mapping with the file
+pub const MAP_SHARED: usize = 0x01;
+
+/// Map the file `fd` from the page-aligned `offset` on, its pages read in as
+/// they are touched
+pub fn mmap_shared(start: usize, len: usize, prot: usize, fd: usize, offset: usize) -> isize {
+    sys_mmap(start, len, prot, MAP_SHARED, fd, offset)
 }
 
 pub fn munmap(start: usize, len: usize) -> isize {
     sys_munmap(start, len)
 }
 
+/// Change the permissions of a whole area mapped by mmap
+pub fn mprotect(start: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(start, len, prot)
+}
+
+/// Fix the mapping and permissions of `[start, start + len)` until exit
+pub fn mseal(start: usize, len: usize) -> isize {
+    sys_mseal(start, len)
+}
+
+pub fn statm(statm: &mut Statm) -> isize {
+    sys_statm(statm)
+}
+
+pub fn syscall_trace(records: &mut [SyscallRecord]) -> isize {
+    sys_syscall_trace(records)
+}
+
+/// Fill `time` with the microseconds spent in each syscall, by id
+pub fn syscall_time(time: &mut [usize]) -> isize {
+    sys_syscall_time(time)
+}
+
+pub fn cow_stats(stats: &mut CowStats) -> isize {
+    sys_cow_stats(stats)
+}
+
+/// A ready stride task, as dumped by `sched_debug`
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct SchedDebugEntry {
+    pub pid: usize,
+    pub prio: u64,
+    /// pass the task is ordered by, the lowest running next
+    pub pass: u64,
+    /// name of the program, cut short and padded with NULs
+    pub name: [u8; 16],
+}
+
+impl SchedDebugEntry {
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|&ch| ch == 0).unwrap_or(16);
+        core::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+}
+
+/// Fill `entries` with the ready stride tasks in the order they would run,
+/// returning how many there were room for. Only root may look.
+pub fn sched_debug(entries: &mut [SchedDebugEntry]) -> isize {
+    sys_sched_debug(entries)
+}
+
+pub const MADV_WILLNEED: usize = 3;
+pub const MADV_DONTNEED: usize = 4;
+
+pub fn process_madvise(pid: usize, start: usize, len: usize, advice: usize) -> isize {
+    sys_process_madvise(pid, start, len, advice)
+}
+
+pub fn prefault(start: usize, len: usize) -> isize {
+    sys_prefault(start, len)
+}
+
+pub const IO_OP_READ: u32 = 0;
+pub const IO_OP_WRITE: u32 = 1;
+pub const IO_OP_FSYNC: u32 = 2;
+/// use the file offset instead of seeking first
+pub const IO_OFFSET_CURRENT: usize = usize::MAX;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IoEntry {
+    pub op: u32,
+    pub fd: u32,
+    pub buf: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Run `entries` in order, returning how many ran before a malformed one
+pub fn iosubmit(entries: &[IoEntry], completions: &mut [isize]) -> isize {
+    assert!(completions.len() >= entries.len());
+    sys_iosubmit(entries, completions)
+}
+
 pub fn spawn(path: &str) -> isize {
-    sys_spawn(path)
+    sys_spawn(path, &[core::ptr::null::<u8>()])
+}
+
+/// Like `spawn`, passing the child the null-terminated `args`
+pub fn spawnv(path: &str, args: &[*const u8]) -> isize {
+    sys_spawn(path, args)
+}
+
+pub fn sched_setscheduler(pid: usize, policy: usize, prio: usize) -> isize {
+    sys_sched_setscheduler(pid, policy, prio)
+}
+
+pub fn sched_setaffinity(pid: usize, mask: usize) -> isize {
+    sys_sched_setaffinity(pid, mask)
+}
+
+pub fn sched_getaffinity(pid: usize, mask: &mut usize) -> isize {
+    sys_sched_getaffinity(pid, mask)
+}
+
+pub fn sched_rr_get_interval(pid: usize, ts: &mut TimeSpec) -> isize {
+    sys_sched_rr_get_interval(pid, ts)
+}
+
+pub fn set_timeslice(us: usize) -> isize {
+    sys_set_timeslice(us)
+}
+
+pub fn set_mempolicy(node: usize) -> isize {
+    sys_set_mempolicy(node)
 }
 
 pub fn dup(fd: usize) -> isize {
     sys_dup(fd)
 }
+pub fn dup2(old_fd: usize, new_fd: usize) -> isize {
+    sys_dup2(old_fd, new_fd)
+}
 pub fn pipe(pipe_fd: &mut [usize]) -> isize {
     sys_pipe(pipe_fd)
 }
+/// Create a pipe like `pipe`, carrying packets if `flags` has O_DIRECT
+pub fn pipe2(pipe_fd: &mut [usize], flags: u32) -> isize {
+    sys_pipe2(pipe_fd, flags)
+}
 
 pub fn task_info(info: &TaskInfo) -> isize {
     sys_task_info(info)
@@ -1,32 +1,96 @@
-use crate::TaskInfo;
+use crate::{
+    CacheStats, CowStats, IoEntry, RUsage, SchedDebugEntry, SigInfo, Statm, SyscallRecord, TaskInfo,
+};
 
-use super::{Stat, TimeVal};
+use super::{DiskUsage, Stat, StatFs, TimeSpec, TimeVal};
 
+pub const SYSCALL_TRUNCATE: usize = 45;
+pub const SYSCALL_FTRUNCATE: usize = 46;
+pub const SYSCALL_FALLOCATE: usize = 47;
+pub const SYSCALL_FCHMODAT: usize = 53;
+pub const SYSCALL_FCHOWNAT: usize = 54;
 pub const SYSCALL_OPENAT: usize = 56;
 pub const SYSCALL_CLOSE: usize = 57;
+pub const SYSCALL_CLOSE_RANGE: usize = 436;
+pub const SYSCALL_GETDENTS64: usize = 61;
+pub const SYSCALL_LSEEK: usize = 62;
 pub const SYSCALL_READ: usize = 63;
 pub const SYSCALL_WRITE: usize = 64;
+pub const SYSCALL_GETCWD: usize = 17;
+pub const SYSCALL_CHDIR: usize = 49;
+pub const SYSCALL_MKDIRAT: usize = 34;
 pub const SYSCALL_UNLINKAT: usize = 35;
+pub const SYSCALL_SYMLINKAT: usize = 36;
 pub const SYSCALL_LINKAT: usize = 37;
+pub const SYSCALL_UMOUNT2: usize = 39;
+pub const SYSCALL_MOUNT: usize = 40;
+pub const SYSCALL_STATFS: usize = 43;
+pub const SYSCALL_READLINKAT: usize = 78;
 pub const SYSCALL_FSTAT: usize = 80;
+pub const SYSCALL_FSYNC: usize = 82;
+pub const SYSCALL_READAHEAD: usize = 213;
+pub const SYSCALL_FADVISE: usize = 223;
+pub const SYSCALL_RENAMEAT2: usize = 276;
+pub const SYSCALL_SETNS: usize = 268;
 pub const SYSCALL_EXIT: usize = 93;
+pub const SYSCALL_UNSHARE: usize = 97;
+pub const SYSCALL_SET_ROBUST_LIST: usize = 99;
+pub const SYSCALL_GET_ROBUST_LIST: usize = 100;
 pub const SYSCALL_SLEEP: usize = 101;
+pub const SYSCALL_SCHED_SETSCHEDULER: usize = 119;
+pub const SYSCALL_SCHED_SETAFFINITY: usize = 122;
+pub const SYSCALL_SCHED_GETAFFINITY: usize = 123;
+pub const SYSCALL_SCHED_RR_GET_INTERVAL: usize = 127;
 pub const SYSCALL_YIELD: usize = 124;
 pub const SYSCALL_GETTIMEOFDAY: usize = 169;
+pub const SYSCALL_SETTIMEOFDAY: usize = 170;
 pub const SYSCALL_GETPID: usize = 172;
+pub const SYSCALL_GETPPID: usize = 173;
+pub const SYSCALL_KILL: usize = 129;
+pub const SYSCALL_SIGPROCMASK: usize = 135;
+pub const SYSCALL_SIGPENDING: usize = 136;
+pub const SYSCALL_GETRUSAGE: usize = 165;
+pub const SYSCALL_GETUID: usize = 174;
+pub const SYSCALL_SETUID: usize = 146;
 pub const SYSCALL_GETTID: usize = 178;
 pub const SYSCALL_FORK: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
+pub const SYSCALL_SET_MEMPOLICY: usize = 237;
 pub const SYSCALL_WAITPID: usize = 260;
+pub const SYSCALL_WAITID: usize = 95;
+pub const SYSCALL_SETPGID: usize = 154;
+pub const SYSCALL_GETPGID: usize = 155;
 pub const SYSCALL_SET_PRIORITY: usize = 140;
 pub const SYSCALL_MUNMAP: usize = 215;
 pub const SYSCALL_MMAP: usize = 222;
+pub const SYSCALL_MPROTECT: usize = 226;
 pub const SYSCALL_SPAWN: usize = 400;
 pub const SYSCALL_MAIL_READ: usize = 401;
 pub const SYSCALL_MAIL_WRITE: usize = 402;
 pub const SYSCALL_DUP: usize = 24;
 pub const SYSCALL_PIPE: usize = 59;
 pub const SYSCALL_TASK_INFO: usize = 410;
+pub const SYSCALL_PROCESS_MADVISE: usize = 440;
+pub const SYSCALL_ENABLE_VERITY: usize = 460;
+pub const SYSCALL_DISK_USAGE: usize = 461;
+pub const SYSCALL_STATM: usize = 462;
+pub const SYSCALL_SYSCALL_TRACE: usize = 463;
+pub const SYSCALL_SET_TIMESLICE: usize = 464;
+pub const SYSCALL_FEXECVE: usize = 465;
+pub const SYSCALL_FSEVENTS_OPEN: usize = 466;
+pub const SYSCALL_PREFAULT: usize = 467;
+pub const SYSCALL_IOSUBMIT: usize = 468;
+pub const SYSCALL_WALK: usize = 469;
+pub const SYSCALL_YIELD_REMAINING: usize = 470;
+pub const SYSCALL_MSEAL: usize = 471;
+pub const SYSCALL_FINCORE: usize = 472;
+pub const SYSCALL_DROP_FILE_CACHE: usize = 473;
+pub const SYSCALL_BLOCK_CACHE_STATS: usize = 474;
+pub const SYSCALL_SCHED_DEBUG: usize = 475;
+pub const SYSCALL_COW_STATS: usize = 476;
+pub const SYSCALL_SYSCALL_TIME: usize = 477;
+pub const SYSCALL_DUP2: usize = 478;
+pub const SYSCALL_NICE: usize = 479;
 pub const SYSCALL_THREAD_CREATE: usize = 460;
 pub const SYSCALL_WAITTID: usize = 462;
 pub const SYSCALL_MUTEX_CREATE: usize = 463;
@@ -88,6 +152,35 @@ pub fn sys_close(fd: usize) -> isize {
     syscall(SYSCALL_CLOSE, [fd, 0, 0])
 }
 
+pub fn sys_close_range(first: usize, last: usize, flags: u32) -> isize {
+    syscall(SYSCALL_CLOSE_RANGE, [first, last, flags as usize])
+}
+
+pub fn sys_walk(path: &str, buffer: &mut [u8], cursor: &mut usize) -> isize {
+    syscall6(
+        SYSCALL_WALK,
+        [
+            path.as_ptr() as usize,
+            buffer.as_mut_ptr() as usize,
+            buffer.len(),
+            cursor as *mut _ as usize,
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_getdents64(fd: usize, buffer: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_GETDENTS64,
+        [fd, buffer.as_mut_ptr() as usize, buffer.len()],
+    )
+}
+
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    syscall(SYSCALL_LSEEK, [fd, offset as usize, whence])
+}
+
 pub fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
     syscall(
         SYSCALL_READ,
@@ -119,14 +212,160 @@ pub fn sys_linkat(
     )
 }
 
+pub fn sys_symlinkat(target: &str, new_dirfd: usize, link_path: &str) -> isize {
+    syscall(
+        SYSCALL_SYMLINKAT,
+        [
+            target.as_ptr() as usize,
+            new_dirfd,
+            link_path.as_ptr() as usize,
+        ],
+    )
+}
+
+pub fn sys_readlinkat(dirfd: usize, path: &str, buf: &mut [u8]) -> isize {
+    syscall6(
+        SYSCALL_READLINKAT,
+        [
+            dirfd,
+            path.as_ptr() as usize,
+            buf.as_mut_ptr() as usize,
+            buf.len(),
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_mkdirat(dirfd: usize, path: &str, mode: u32) -> isize {
+    syscall(
+        SYSCALL_MKDIRAT,
+        [dirfd, path.as_ptr() as usize, mode as usize],
+    )
+}
+
+pub fn sys_chdir(path: &str) -> isize {
+    syscall(SYSCALL_CHDIR, [path.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_getcwd(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETCWD, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_fchmodat(dirfd: usize, path: &str, mode: u32, flags: u32) -> isize {
+    syscall6(
+        SYSCALL_FCHMODAT,
+        [
+            dirfd,
+            path.as_ptr() as usize,
+            mode as usize,
+            flags as usize,
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_fchownat(dirfd: usize, path: &str, uid: u32, gid: u32, flags: u32) -> isize {
+    syscall6(
+        SYSCALL_FCHOWNAT,
+        [
+            dirfd,
+            path.as_ptr() as usize,
+            uid as usize,
+            gid as usize,
+            flags as usize,
+            0,
+        ],
+    )
+}
+
+pub fn sys_mount(dev: usize, target: &str) -> isize {
+    syscall(SYSCALL_MOUNT, [dev, target.as_ptr() as usize, 0])
+}
+
+pub fn sys_umount2(target: &str, flags: usize) -> isize {
+    syscall(SYSCALL_UMOUNT2, [target.as_ptr() as usize, flags, 0])
+}
+
+pub fn sys_unshare(flags: usize) -> isize {
+    syscall(SYSCALL_UNSHARE, [flags, 0, 0])
+}
+
+pub fn sys_setns(pid: usize, nstype: usize) -> isize {
+    syscall(SYSCALL_SETNS, [pid, nstype, 0])
+}
+
 pub fn sys_unlinkat(dirfd: usize, path: &str, flags: usize) -> isize {
     syscall(SYSCALL_UNLINKAT, [dirfd, path.as_ptr() as usize, flags])
 }
 
+pub fn sys_renameat2(
+    old_dirfd: usize,
+    old_path: &str,
+    new_dirfd: usize,
+    new_path: &str,
+    flags: u32,
+) -> isize {
+    syscall6(
+        SYSCALL_RENAMEAT2,
+        [
+            old_dirfd,
+            old_path.as_ptr() as usize,
+            new_dirfd,
+            new_path.as_ptr() as usize,
+            flags as usize,
+            0,
+        ],
+    )
+}
+
 pub fn sys_fstat(fd: usize, st: &Stat) -> isize {
     syscall(SYSCALL_FSTAT, [fd, st as *const _ as usize, 0])
 }
 
+pub fn sys_disk_usage(path: &str, usage: &mut DiskUsage) -> isize {
+    syscall(
+        SYSCALL_DISK_USAGE,
+        [path.as_ptr() as usize, usage as *mut _ as usize, 0],
+    )
+}
+
+pub fn sys_statfs(path: &str, buf: &mut StatFs) -> isize {
+    syscall(
+        SYSCALL_STATFS,
+        [path.as_ptr() as usize, buf as *mut _ as usize, 0],
+    )
+}
+
+pub fn sys_truncate(path: &str, length: usize) -> isize {
+    syscall(SYSCALL_TRUNCATE, [path.as_ptr() as usize, length, 0])
+}
+
+pub fn sys_ftruncate(fd: usize, length: usize) -> isize {
+    syscall(SYSCALL_FTRUNCATE, [fd, length, 0])
+}
+
+pub fn sys_fallocate(fd: usize, mode: usize, offset: usize, len: usize) -> isize {
+    syscall6(SYSCALL_FALLOCATE, [fd, mode, offset, len, 0, 0])
+}
+
+pub fn sys_enable_verity(fd: usize) -> isize {
+    syscall(SYSCALL_ENABLE_VERITY, [fd, 0, 0])
+}
+
+pub fn sys_fsync(fd: usize) -> isize {
+    syscall(SYSCALL_FSYNC, [fd, 0, 0])
+}
+
+pub fn sys_readahead(fd: usize, offset: usize, len: usize) -> isize {
+    syscall(SYSCALL_READAHEAD, [fd, offset, len])
+}
+
+pub fn sys_fadvise(fd: usize, offset: usize, len: usize, advice: usize) -> isize {
+    syscall6(SYSCALL_FADVISE, [fd, offset, len, advice, 0, 0])
+}
+
 pub fn sys_mail_read(buffer: &mut [u8]) -> isize {
     syscall(
         SYSCALL_MAIL_READ,
@@ -146,22 +385,92 @@ pub fn sys_exit(exit_code: i32) -> ! {
     panic!("sys_exit never returns!");
 }
 
+pub fn sys_set_robust_list(head: usize, len: usize) -> isize {
+    syscall(SYSCALL_SET_ROBUST_LIST, [head, len, 0])
+}
+
+pub fn sys_get_robust_list(pid: usize, head: &mut usize, len: &mut usize) -> isize {
+    syscall(
+        SYSCALL_GET_ROBUST_LIST,
+        [pid, head as *mut _ as usize, len as *mut _ as usize],
+    )
+}
+
 pub fn sys_sleep(sleep_ms: usize) -> isize {
     syscall(SYSCALL_SLEEP, [sleep_ms, 0, 0])
 }
 
+pub fn sys_sched_setscheduler(pid: usize, policy: usize, prio: usize) -> isize {
+    syscall(SYSCALL_SCHED_SETSCHEDULER, [pid, policy, prio])
+}
+
+pub fn sys_sched_setaffinity(pid: usize, mask: usize) -> isize {
+    syscall(SYSCALL_SCHED_SETAFFINITY, [pid, mask, 0])
+}
+
+pub fn sys_sched_getaffinity(pid: usize, mask: &mut usize) -> isize {
+    syscall(SYSCALL_SCHED_GETAFFINITY, [pid, mask as *mut _ as usize, 0])
+}
+
+pub fn sys_sched_rr_get_interval(pid: usize, ts: &mut TimeSpec) -> isize {
+    syscall(
+        SYSCALL_SCHED_RR_GET_INTERVAL,
+        [pid, ts as *mut _ as usize, 0],
+    )
+}
+
+pub fn sys_set_timeslice(us: usize) -> isize {
+    syscall(SYSCALL_SET_TIMESLICE, [us, 0, 0])
+}
+
 pub fn sys_yield() -> isize {
     syscall(SYSCALL_YIELD, [0, 0, 0])
 }
 
+pub fn sys_yield_remaining() -> isize {
+    syscall(SYSCALL_YIELD_REMAINING, [0, 0, 0])
+}
+
 pub fn sys_get_time(time: &TimeVal, tz: usize) -> isize {
     syscall(SYSCALL_GETTIMEOFDAY, [time as *const _ as usize, tz, 0])
 }
 
+pub fn sys_settimeofday(time: usize, tz: usize) -> isize {
+    syscall(SYSCALL_SETTIMEOFDAY, [time, tz, 0])
+}
+
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+pub fn sys_getppid() -> isize {
+    syscall(SYSCALL_GETPPID, [0, 0, 0])
+}
+
+pub fn sys_kill(pid: usize, signal: u32) -> isize {
+    syscall(SYSCALL_KILL, [pid, signal as usize, 0])
+}
+
+pub fn sys_sigprocmask(how: usize, set: *const u32, oldset: *mut u32) -> isize {
+    syscall(SYSCALL_SIGPROCMASK, [how, set as usize, oldset as usize])
+}
+
+pub fn sys_getrusage(who: usize, usage: &mut RUsage) -> isize {
+    syscall(SYSCALL_GETRUSAGE, [who, usage as *mut RUsage as usize, 0])
+}
+
+pub fn sys_sigpending(set: &mut u32) -> isize {
+    syscall(SYSCALL_SIGPENDING, [set as *mut u32 as usize, 0, 0])
+}
+
+pub fn sys_getuid() -> isize {
+    syscall(SYSCALL_GETUID, [0, 0, 0])
+}
+
+pub fn sys_setuid(uid: usize) -> isize {
+    syscall(SYSCALL_SETUID, [uid, 0, 0])
+}
+
 pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
 }
@@ -173,34 +482,174 @@ pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
     )
 }
 
-pub fn sys_waitpid(pid: isize, xstatus: *mut i32) -> isize {
-    syscall(SYSCALL_WAITPID, [pid as usize, xstatus as usize, 0])
+pub fn sys_execve(path: &str, args: &[*const u8], envp: &[*const u8]) -> isize {
+    syscall(
+        SYSCALL_EXEC,
+        [
+            path.as_ptr() as usize,
+            args.as_ptr() as usize,
+            envp.as_ptr() as usize,
+        ],
+    )
+}
+
+pub fn sys_fexecve(fd: usize, args: &[*const u8], envp: &[*const u8]) -> isize {
+    syscall(
+        SYSCALL_FEXECVE,
+        [fd, args.as_ptr() as usize, envp.as_ptr() as usize],
+    )
+}
+
+pub fn sys_fsevents_open(mask: u32) -> isize {
+    syscall(SYSCALL_FSEVENTS_OPEN, [mask as usize, 0, 0])
+}
+
+pub fn sys_set_mempolicy(node: usize) -> isize {
+    syscall(SYSCALL_SET_MEMPOLICY, [node, 0, 0])
+}
+
+pub fn sys_waitpid(pid: isize, xstatus: *mut i32, options: usize) -> isize {
+    syscall(SYSCALL_WAITPID, [pid as usize, xstatus as usize, options])
+}
+
+pub fn sys_waitid(idtype: usize, id: usize, info: &mut SigInfo, options: usize) -> isize {
+    syscall6(
+        SYSCALL_WAITID,
+        [idtype, id, info as *mut _ as usize, options, 0, 0],
+    )
+}
+
+pub fn sys_setpgid(pid: usize, pgid: usize) -> isize {
+    syscall(SYSCALL_SETPGID, [pid, pgid, 0])
+}
+
+pub fn sys_getpgid(pid: usize) -> isize {
+    syscall(SYSCALL_GETPGID, [pid, 0, 0])
 }
 
 pub fn sys_set_priority(prio: isize) -> isize {
     syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
 }
 
-pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
-    syscall(SYSCALL_MMAP, [start, len, prot])
+pub fn sys_nice(delta: isize) -> isize {
+    syscall(SYSCALL_NICE, [delta as usize, 0, 0])
+}
+
+pub fn sys_mmap(
+    start: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> isize {
+    syscall6(SYSCALL_MMAP, [start, len, prot, flags, fd, offset])
 }
 
 pub fn sys_munmap(start: usize, len: usize) -> isize {
     syscall(SYSCALL_MUNMAP, [start, len, 0])
 }
 
-pub fn sys_spawn(path: &str) -> isize {
-    syscall(SYSCALL_SPAWN, [path.as_ptr() as usize, 0, 0])
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [start, len, prot])
+}
+
+pub fn sys_mseal(start: usize, len: usize) -> isize {
+    syscall(SYSCALL_MSEAL, [start, len, 0])
+}
+
+pub fn sys_fincore(fd: usize, offset: usize, len: usize, vec: &mut [u8]) -> isize {
+    syscall6(
+        SYSCALL_FINCORE,
+        [fd, offset, len, vec.as_mut_ptr() as usize, 0, 0],
+    )
+}
+
+pub fn sys_drop_file_cache(fd: usize) -> isize {
+    syscall(SYSCALL_DROP_FILE_CACHE, [fd, 0, 0])
+}
+
+pub fn sys_block_cache_stats(stats: &mut CacheStats) -> isize {
+    syscall(
+        SYSCALL_BLOCK_CACHE_STATS,
+        [stats as *mut CacheStats as usize, 0, 0],
+    )
+}
+
+pub fn sys_statm(statm: &mut Statm) -> isize {
+    syscall(SYSCALL_STATM, [statm as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_syscall_trace(records: &mut [SyscallRecord]) -> isize {
+    syscall(
+        SYSCALL_SYSCALL_TRACE,
+        [records.as_mut_ptr() as usize, records.len(), 0],
+    )
+}
+
+pub fn sys_syscall_time(time: &mut [usize]) -> isize {
+    syscall(
+        SYSCALL_SYSCALL_TIME,
+        [time.as_mut_ptr() as usize, time.len(), 0],
+    )
+}
+
+pub fn sys_cow_stats(stats: &mut CowStats) -> isize {
+    syscall(SYSCALL_COW_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_sched_debug(entries: &mut [SchedDebugEntry]) -> isize {
+    syscall(
+        SYSCALL_SCHED_DEBUG,
+        [entries.as_mut_ptr() as usize, entries.len(), 0],
+    )
+}
+
+pub fn sys_spawn(path: &str, args: &[*const u8]) -> isize {
+    syscall(
+        SYSCALL_SPAWN,
+        [path.as_ptr() as usize, args.as_ptr() as usize, 0],
+    )
 }
 
 pub fn sys_dup(fd: usize) -> isize {
     syscall(SYSCALL_DUP, [fd, 0, 0])
 }
 
+pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
+    syscall(SYSCALL_DUP2, [old_fd, new_fd, 0])
+}
+
 pub fn sys_pipe(pipe: &mut [usize]) -> isize {
     syscall(SYSCALL_PIPE, [pipe.as_mut_ptr() as usize, 0, 0])
 }
 
+pub fn sys_pipe2(pipe: &mut [usize], flags: u32) -> isize {
+    syscall(
+        SYSCALL_PIPE,
+        [pipe.as_mut_ptr() as usize, flags as usize, 0],
+    )
+}
+
+pub fn sys_process_madvise(pid: usize, start: usize, len: usize, advice: usize) -> isize {
+    syscall6(SYSCALL_PROCESS_MADVISE, [pid, start, len, advice, 0, 0])
+}
+
+pub fn sys_prefault(start: usize, len: usize) -> isize {
+    syscall(SYSCALL_PREFAULT, [start, len, 0])
+}
+
+pub fn sys_iosubmit(entries: &[IoEntry], completions: &mut [isize]) -> isize {
+    syscall(
+        SYSCALL_IOSUBMIT,
+        [
+            entries.as_ptr() as usize,
+            entries.len(),
+            completions.as_mut_ptr() as usize,
+        ],
+    )
+}
+
 pub fn sys_task_info(info: &TaskInfo) -> isize {
     syscall(SYSCALL_TASK_INFO, [info as *const _ as usize, 0, 0])
 }